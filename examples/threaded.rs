@@ -19,7 +19,7 @@ fn main() {
 					for i in 0..1000 {
 						// Reuse the same lock for creating and dropping the Box
 						let lock = alloc.acquire_locked();
-						total += *black_box(Box::new_in(i, &*lock));
+						total += *black_box(Box::new_in(i, &lock));
 					}
 					assert_eq!(total, 499500); // ensure no data races have occurred
 				});
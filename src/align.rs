@@ -21,12 +21,20 @@
 /// where
 ///     Align<B>: Alignment;
 /// ```
+///
+/// Rust doesn't allow types to be aligned to more than 2^29 bytes, so that is the largest block size.
+/// Allocations can still be aligned to anything up to `MAX_ALIGN`, since their alignment is worked out
+/// from their address.
 #[derive(Clone, Copy)]
 #[repr(transparent)]
 pub struct Align<const N: usize>(<Self as Alignment>::Inner)
 where
 	Self: Alignment;
 
+/// The largest alignment, in bytes, that an allocation can request. This is the largest alignment
+/// that a `Layout` with a nonzero size can have.
+pub const MAX_ALIGN: usize = 1 << (usize::BITS - 2);
+
 #[doc(hidden)]
 pub trait Alignment {
 	/// See the documentation for `Align`.
@@ -0,0 +1,227 @@
+use core::fmt::{self, Debug, Formatter};
+use core::ops::Deref;
+
+use crate::align::{Align, Alignment};
+use crate::{
+	AllocChain, AllocatorStats, ChainableAlloc, FirstFit, OwnedAllocChain, Stalloc, Strategy,
+};
+
+/// A `Stalloc` whose buffer is aligned to `A` bytes instead of `B`.
+///
+/// Normally, the buffer is aligned to the block size, so serving page-aligned allocations efficiently
+/// would take 4096-byte blocks, which wastes most of a block on every small allocation. An `AlignedStalloc`
+/// keeps the small blocks, but starts the buffer on an `A`-byte boundary, so an allocation aligned to
+/// `A` bytes can be placed at the start of the buffer without skipping any blocks.
+///
+/// It dereferences to the `Stalloc`, so it can be used like one.
+///
+/// # Examples
+/// ```
+/// use stalloc::AlignedStalloc;
+///
+/// // 16-byte blocks in a buffer that starts on a page boundary.
+/// let alloc = AlignedStalloc::<1024, 16, 4096>::new();
+///
+/// // An allocation of one page, aligned to a page (256 blocks of 16 bytes).
+/// let page = unsafe { alloc.allocate_blocks(256, 256) }.unwrap();
+/// assert_eq!(page.addr().get() % 4096, 0);
+///
+/// // Since the buffer is page-aligned, no blocks had to be skipped.
+/// let small = unsafe { alloc.allocate_blocks(1, 1) }.unwrap();
+/// assert_eq!(small, unsafe { page.add(4096) });
+///
+/// unsafe {
+///     alloc.deallocate_blocks(page, 256);
+///     alloc.deallocate_blocks(small, 1);
+/// }
+/// ```
+#[repr(C)]
+pub struct AlignedStalloc<const L: usize, const B: usize, const A: usize, S: Strategy = FirstFit>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	_align: [Align<A>; 0],
+	// The buffer is the first field of `Stalloc`, so it starts at the start of this struct.
+	inner: Stalloc<L, B, S>,
+}
+
+impl<const L: usize, const B: usize, const A: usize, S: Strategy> AlignedStalloc<L, B, A, S>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	/// Initializes a new empty `AlignedStalloc` instance.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::AlignedStalloc;
+	///
+	/// let alloc = AlignedStalloc::<200, 8, 64>::new();
+	/// ```
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			_align: [],
+			inner: Stalloc::new(),
+		}
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+}
+
+impl<const L: usize, const B: usize, const A: usize, S: Strategy> Deref
+	for AlignedStalloc<L, B, A, S>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	type Target = Stalloc<L, B, S>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl<const L: usize, const B: usize, const A: usize, S: Strategy> Default
+	for AlignedStalloc<L, B, A, S>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const L: usize, const B: usize, const A: usize, S: Strategy> Debug
+	for AlignedStalloc<L, B, A, S>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		Debug::fmt(&self.inner, f)
+	}
+}
+
+unsafe impl<const L: usize, const B: usize, const A: usize, S: Strategy> ChainableAlloc
+	for AlignedStalloc<L, B, A, S>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.inner.addr_in_bounds(addr)
+	}
+}
+
+impl<const L: usize, const B: usize, const A: usize, S: Strategy> AllocatorStats
+	for AlignedStalloc<L, B, A, S>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		self.inner.capacity_bytes()
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.inner.used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.inner.peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.inner.allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.inner.deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.inner.failed_allocations()
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::alloc::Layout,
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<const L: usize, const B: usize, const A: usize, S: Strategy> Allocator
+	for &AlignedStalloc<L, B, A, S>
+where
+	Align<B>: Alignment,
+	Align<A>: Alignment,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		(&self.inner).allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		(&self.inner).allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).deallocate(ptr, layout) };
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).grow(ptr, old_layout, new_layout) }
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).grow_zeroed(ptr, old_layout, new_layout) }
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).shrink(ptr, old_layout, new_layout) }
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
@@ -0,0 +1,242 @@
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy, UnsafeStalloc};
+
+/// A common interface for querying the memory usage of an allocator, so that monitoring code can be
+/// written generically over `Stalloc`, `UnsafeStalloc`, `SyncStalloc`, `SpinStalloc` and `AllocChain`.
+///
+/// Sizes are measured in bytes. The counters are only available with the `stats` feature, and are
+/// `None` otherwise.
+///
+/// # Examples
+/// ```
+/// use stalloc::{AllocatorStats, Stalloc};
+///
+/// fn usage_percent(alloc: &impl AllocatorStats) -> usize {
+///     alloc.used_bytes() * 100 / alloc.capacity_bytes()
+/// }
+///
+/// let alloc = Stalloc::<20, 4>::new();
+/// unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+/// assert_eq!(usage_percent(&alloc), 25);
+/// ```
+pub trait AllocatorStats {
+	/// Returns the total number of bytes managed by the allocator.
+	fn capacity_bytes(&self) -> usize;
+
+	/// Returns the number of bytes that are currently in use.
+	fn used_bytes(&self) -> usize;
+
+	/// Returns the number of bytes that are currently free.
+	fn free_bytes(&self) -> usize {
+		self.capacity_bytes() - self.used_bytes()
+	}
+
+	/// Returns the highest number of bytes that were ever in use at the same time.
+	fn peak_used_bytes(&self) -> Option<usize>;
+
+	/// Returns the number of successful allocations.
+	fn allocations(&self) -> Option<usize>;
+
+	/// Returns the number of deallocations.
+	fn deallocations(&self) -> Option<usize>;
+
+	/// Returns the number of allocations that failed.
+	fn failed_allocations(&self) -> Option<usize>;
+
+	/// Returns an adapter that renders these statistics in the Prometheus text exposition format.
+	/// This requires the `std` feature. For a `dyn AllocatorStats`, use `PrometheusMetrics::new()` instead.
+	#[cfg(feature = "std")]
+	fn metrics(&self) -> crate::PrometheusMetrics<'_, Self>
+	where
+		Self: Sized,
+	{
+		crate::PrometheusMetrics::new(self)
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> AllocatorStats for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		L * B
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.report().used_blocks * B
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.report().peak_used_blocks.map(|blocks| blocks * B)
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		#[cfg(feature = "stats")]
+		return Some(self.stats().allocations);
+		#[cfg(not(feature = "stats"))]
+		None
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		#[cfg(feature = "stats")]
+		return Some(self.stats().deallocations);
+		#[cfg(not(feature = "stats"))]
+		None
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		#[cfg(feature = "stats")]
+		return Some(self.stats().failed_allocations);
+		#[cfg(not(feature = "stats"))]
+		None
+	}
+}
+
+impl<const L: usize, const B: usize> AllocatorStats for UnsafeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		self.0.capacity_bytes()
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.0.used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.0.peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.0.allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.0.deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.0.failed_allocations()
+	}
+}
+
+impl<const L: usize, const B: usize> AllocatorStats for crate::SpinStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		L * B
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.acquire_locked().used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.acquire_locked().peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.acquire_locked().allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.acquire_locked().deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.acquire_locked().failed_allocations()
+	}
+}
+
+#[cfg(feature = "critical-section")]
+impl<const L: usize, const B: usize> AllocatorStats for crate::CriticalSectionStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		L * B
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.with_locked(AllocatorStats::used_bytes)
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.with_locked(AllocatorStats::peak_used_bytes)
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.with_locked(AllocatorStats::allocations)
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.with_locked(AllocatorStats::deallocations)
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.with_locked(AllocatorStats::failed_allocations)
+	}
+}
+
+#[cfg(feature = "lock_api")]
+impl<const L: usize, const B: usize, R: lock_api::RawMutex> AllocatorStats
+	for crate::LockedStalloc<L, B, R>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		L * B
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.acquire_locked().used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.acquire_locked().peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.acquire_locked().allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.acquire_locked().deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.acquire_locked().failed_allocations()
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> AllocatorStats for crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		L * B
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.acquire_locked().used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.acquire_locked().peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.acquire_locked().allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.acquire_locked().deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.acquire_locked().failed_allocations()
+	}
+}
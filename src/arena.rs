@@ -0,0 +1,90 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy};
+
+/// An arena-style interface, where values are allocated and then never freed individually.
+/// Their memory is reclaimed when the allocator is cleared or dropped. None of the values are ever dropped.
+///
+/// Every returned reference borrows the allocator, so it can't be cleared while the references are alive.
+#[allow(clippy::mut_from_ref)]
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Moves the value returned by `f` into the allocator, and returns a reference to it.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case `f` is not called.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let point = alloc.alloc_with(|| (1, 2)).unwrap();
+	/// point.0 += 10;
+	/// assert_eq!(*point, (11, 2));
+	/// ```
+	pub fn alloc_with<T>(&self, f: impl FnOnce() -> T) -> Result<&mut T, AllocError> {
+		let ptr = self.allocate_value::<T>()?;
+
+		// SAFETY: The memory is valid for a `T`, and is never handed out again while `self` is borrowed.
+		unsafe {
+			ptr.write(f());
+			Ok(&mut *ptr.as_ptr())
+		}
+	}
+
+	/// Copies `slice` into the allocator, and returns a reference to the copy.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let numbers = alloc.alloc_slice_copy(&[1u16, 2, 3]).unwrap();
+	/// numbers[0] = 4;
+	/// assert_eq!(numbers, [4, 2, 3]);
+	/// ```
+	pub fn alloc_slice_copy<T: Copy>(&self, slice: &[T]) -> Result<&mut [T], AllocError> {
+		let start = self.allocate_slice::<T>(slice.len())?.cast::<T>();
+
+		// SAFETY: The memory is valid for `slice.len()` values of type `T`, and is never handed out again
+		// while `self` is borrowed.
+		unsafe {
+			start.copy_from_nonoverlapping(NonNull::from(slice).cast(), slice.len());
+			Ok(core::slice::from_raw_parts_mut(start.as_ptr(), slice.len()))
+		}
+	}
+
+	/// Copies `s` into the allocator, and returns a reference to the copy.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let s = alloc.alloc_str("hello").unwrap();
+	/// s.make_ascii_uppercase();
+	/// assert_eq!(s, "HELLO");
+	/// ```
+	pub fn alloc_str(&self, s: &str) -> Result<&mut str, AllocError> {
+		let bytes = self.alloc_slice_copy(s.as_bytes())?;
+
+		// SAFETY: The bytes were copied from a `str`, so they are valid UTF-8.
+		Ok(unsafe { core::str::from_utf8_unchecked_mut(bytes) })
+	}
+}
@@ -0,0 +1,351 @@
+use core::alloc::Layout;
+use core::cell::{Cell, UnsafeCell};
+use core::fmt::{self, Debug, Formatter};
+use core::future::Future;
+use core::pin::Pin;
+use core::ptr::NonNull;
+use core::task::{Context, Poll, Waker};
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, AllocatorStats, SpinGuard, SpinStalloc};
+
+/// The number of tasks that can wait for memory from an `AsyncStalloc` at the same time.
+/// Any further tasks are woken up immediately, so they poll the allocator until a slot frees up.
+pub const MAX_WAITERS: usize = 8;
+
+/// A task that is waiting for memory, identified by the future it is polling.
+struct Waiter {
+	id: usize,
+	waker: Waker,
+}
+
+/// The tasks that are waiting for memory. This is only accessed while the allocator is locked.
+struct WaitList {
+	waiters: [Option<Waiter>; MAX_WAITERS],
+	next_id: Cell<usize>,
+}
+
+/// A wrapper around `SpinStalloc` whose allocations can wait for memory to become available,
+/// instead of failing when the allocator is out of memory.
+///
+/// `allocate()` returns a future that resolves once the allocation succeeds. Until then, the task is
+/// put on a wait list, and every deallocation wakes up the waiting tasks so they can try again. Since the
+/// allocator is a `SpinStalloc`, this works without `std` and with any executor.
+///
+/// An allocation that can never succeed, because it is larger than the whole allocator, fails immediately.
+/// Any other allocation waits until enough memory is freed, even if that never happens.
+///
+/// # Examples
+/// ```
+/// use core::alloc::Layout;
+/// use core::pin::pin;
+/// use core::task::{Context, Poll, Waker};
+/// use stalloc::AsyncStalloc;
+///
+/// let alloc = AsyncStalloc::<4, 8>::new();
+/// let layout = Layout::new::<[u64; 4]>();
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// let first = alloc.try_allocate(layout).unwrap();
+///
+/// // The allocator is full, so the second allocation has to wait.
+/// let mut second = pin!(alloc.allocate(layout));
+/// assert!(second.as_mut().poll(&mut cx).is_pending());
+///
+/// unsafe { alloc.deallocate(first.cast(), layout) };
+/// let Poll::Ready(Ok(second)) = second.poll(&mut cx) else {
+///     panic!("the allocation should have succeeded");
+/// };
+///
+/// unsafe { alloc.deallocate(second.cast(), layout) };
+/// ```
+pub struct AsyncStalloc<const L: usize, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	inner: SpinStalloc<L, B>,
+	wait_list: UnsafeCell<WaitList>,
+}
+
+// SAFETY: The wait list is only accessed while the spinlock of `inner` is held.
+unsafe impl<const L: usize, const B: usize> Sync for AsyncStalloc<L, B> where Align<B>: Alignment {}
+
+impl<const L: usize, const B: usize> AsyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new empty `AsyncStalloc` instance.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			inner: SpinStalloc::new(),
+			wait_list: UnsafeCell::new(WaitList {
+				waiters: [const { None }; MAX_WAITERS],
+				next_id: Cell::new(0),
+			}),
+		}
+	}
+
+	/// Returns the inner allocator. Memory that is freed directly through it doesn't wake up waiting tasks.
+	pub const fn inner(&self) -> &SpinStalloc<L, B> {
+		&self.inner
+	}
+
+	/// Returns a future that resolves to an allocation for `layout` once enough memory is available.
+	///
+	/// # Errors
+	///
+	/// The future resolves to `AllocError` if `layout` is larger than the whole allocator.
+	pub const fn allocate(&self, layout: Layout) -> AllocFuture<'_, L, B> {
+		AllocFuture {
+			alloc: self,
+			layout,
+			id: None,
+		}
+	}
+
+	/// Tries to allocate memory for `layout` without waiting.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub fn try_allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		Self::allocate_locked(&self.inner.acquire_locked(), layout)
+	}
+
+	/// Deallocates memory, and wakes up every task that is waiting for memory.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been allocated by this allocator with `layout`.
+	pub unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+		if size == 0 {
+			return;
+		}
+
+		let lock = self.inner.acquire_locked();
+		// SAFETY: Upheld by the caller.
+		unsafe { lock.deallocate_blocks(ptr, size) };
+
+		// SAFETY: We hold the lock.
+		let waiters = core::mem::replace(
+			unsafe { &mut (*self.wait_list.get()).waiters },
+			[const { None }; MAX_WAITERS],
+		);
+
+		// Wake the tasks after unlocking, in case a waker polls its task right away.
+		drop(lock);
+		for waiter in waiters.into_iter().flatten() {
+			waiter.waker.wake();
+		}
+	}
+
+	/// Returns the number of tasks that are waiting for memory.
+	pub fn waiting_tasks(&self) -> usize {
+		let _lock = self.inner.acquire_locked();
+
+		// SAFETY: We hold the lock.
+		unsafe { &(*self.wait_list.get()).waiters }
+			.iter()
+			.flatten()
+			.count()
+	}
+
+	/// Allocates memory for `layout` while the allocator is locked.
+	fn allocate_locked(
+		lock: &SpinGuard<'_, L, B>,
+		layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// We can only allocate memory in units of `B`, so round up.
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// If `size` is zero, give away a dangling pointer.
+		if size == 0 {
+			let dangling = NonNull::new(layout.align() as _).unwrap();
+			return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+		}
+
+		// SAFETY: We have made sure that `size` and `align` are valid.
+		unsafe { lock.allocate_blocks(size, align) }
+			.map(|p| NonNull::slice_from_raw_parts(p, size * B))
+	}
+}
+
+impl<const L: usize, const B: usize> Default for AsyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const L: usize, const B: usize> Debug for AsyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self.inner)
+	}
+}
+
+impl<const L: usize, const B: usize> AllocatorStats for AsyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		self.inner.capacity_bytes()
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.inner.used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.inner.peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.inner.allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.inner.deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.inner.failed_allocations()
+	}
+}
+
+/// The future returned by `AsyncStalloc::allocate()`.
+pub struct AllocFuture<'a, const L: usize, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a AsyncStalloc<L, B>,
+	layout: Layout,
+	/// The identifier of this future on the wait list, if it was ever put on it.
+	id: Option<usize>,
+}
+
+impl<const L: usize, const B: usize> AllocFuture<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Removes this future from the wait list. The allocator must be locked.
+	fn unregister(&self, wait_list: &mut WaitList) {
+		if let Some(id) = self.id {
+			for slot in &mut wait_list.waiters {
+				if slot.as_ref().is_some_and(|waiter| waiter.id == id) {
+					*slot = None;
+				}
+			}
+		}
+	}
+}
+
+impl<const L: usize, const B: usize> Future for AllocFuture<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	type Output = Result<NonNull<[u8]>, AllocError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let alloc = self.alloc;
+		let lock = alloc.inner.acquire_locked();
+
+		// SAFETY: We hold the lock.
+		let wait_list = unsafe { &mut *alloc.wait_list.get() };
+
+		if let Ok(ptr) = AsyncStalloc::allocate_locked(&lock, self.layout) {
+			self.unregister(wait_list);
+			return Poll::Ready(Ok(ptr));
+		}
+
+		// This allocation could never succeed, so don't wait for it.
+		if self.layout.size().div_ceil(B) > L {
+			self.unregister(wait_list);
+			return Poll::Ready(Err(AllocError));
+		}
+
+		let id = *self.id.get_or_insert_with(|| {
+			let id = wait_list.next_id.get();
+			wait_list.next_id.set(id.wrapping_add(1));
+			id
+		});
+
+		// Update our waker if we are still on the wait list, and otherwise take a free slot.
+		let slot = wait_list
+			.waiters
+			.iter()
+			.position(|w| w.as_ref().is_some_and(|w| w.id == id))
+			.or_else(|| wait_list.waiters.iter().position(Option::is_none));
+
+		match slot {
+			Some(i) => {
+				wait_list.waiters[i] = Some(Waiter {
+					id,
+					waker: cx.waker().clone(),
+				});
+			}
+			// The wait list is full, so poll again as soon as possible.
+			None => cx.waker().wake_by_ref(),
+		}
+
+		Poll::Pending
+	}
+}
+
+impl<const L: usize, const B: usize> Drop for AllocFuture<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		if self.id.is_some() {
+			let _lock = self.alloc.inner.acquire_locked();
+
+			// SAFETY: We hold the lock.
+			self.unregister(unsafe { &mut *self.alloc.wait_list.get() });
+		}
+	}
+}
+
+impl<const L: usize, const B: usize> Debug for AllocFuture<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AllocFuture")
+			.field("layout", &self.layout)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use crate::Allocator;
+
+/// Allocations made through the `Allocator` trait never wait, but deallocations wake up waiting tasks.
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<const L: usize, const B: usize> Allocator for &AsyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.try_allocate(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { AsyncStalloc::deallocate(self, ptr, layout) };
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
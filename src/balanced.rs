@@ -0,0 +1,91 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::align::{Align, Alignment};
+use crate::multiarena::multi_arena_api;
+use crate::{AllocatorStats, SpinStalloc};
+
+/// How a `BalancedStalloc` picks the arena that an allocation is tried in first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Balance {
+	/// Each allocation starts at the arena after the one the previous allocation started at.
+	#[default]
+	RoundRobin,
+	/// Each allocation starts at the arena with the fewest bytes in use.
+	LeastUsed,
+}
+
+/// An allocator that spreads its allocations over `N` arenas of `L` blocks, instead of filling one
+/// arena before moving on to the next.
+///
+/// Each allocation is first tried in the arena picked by the `Balance`, and then in the other arenas in
+/// order. Since each arena is a `SpinStalloc` with its own lock, threads that allocate at the same time
+/// usually don't contend, and fragmentation is spread out rather than concentrated in the first arena.
+/// Memory is returned to the arena that owns it, which is found by its address.
+///
+/// # Examples
+/// ```
+/// use stalloc::{Balance, BalancedStalloc};
+/// use std::alloc::{GlobalAlloc, Layout};
+///
+/// let alloc = BalancedStalloc::<100, 8, 4>::new().with_balance(Balance::RoundRobin);
+/// let layout = Layout::new::<u64>();
+///
+/// unsafe {
+///     let ptrs = [(); 4].map(|()| alloc.alloc(layout));
+///
+///     // Each allocation was made in a different arena.
+///     assert!(alloc.arenas().iter().all(|arena| !arena.is_empty()));
+///
+///     for ptr in ptrs {
+///         alloc.dealloc(ptr, layout);
+///     }
+/// }
+/// ```
+pub struct BalancedStalloc<const L: usize, const B: usize, const N: usize>
+where
+	Align<B>: Alignment,
+{
+	arenas: [SpinStalloc<L, B>; N],
+	balance: Balance,
+	next: AtomicUsize,
+}
+
+impl<const L: usize, const B: usize, const N: usize> BalancedStalloc<L, B, N>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new `BalancedStalloc` with `N` empty arenas, which picks arenas in round-robin order.
+	///
+	/// # Panics
+	///
+	/// Panics if `N` is zero.
+	#[must_use]
+	pub const fn new() -> Self {
+		assert!(N > 0, "a `BalancedStalloc` needs at least one arena");
+
+		Self {
+			arenas: [const { SpinStalloc::new() }; N],
+			balance: Balance::RoundRobin,
+			next: AtomicUsize::new(0),
+		}
+	}
+
+	/// Returns this allocator with `balance` deciding which arena each allocation is tried in first.
+	#[must_use]
+	pub const fn with_balance(mut self, balance: Balance) -> Self {
+		self.balance = balance;
+		self
+	}
+
+	/// Returns the index of the arena that the next allocation is tried in first.
+	fn first_arena(&self) -> usize {
+		match self.balance {
+			Balance::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % N,
+			Balance::LeastUsed => (0..N)
+				.min_by_key(|&i| self.arenas[i].used_bytes())
+				.unwrap_or(0),
+		}
+	}
+}
+
+multi_arena_api!(BalancedStalloc, "the arena picked by the `Balance`");
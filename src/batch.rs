@@ -0,0 +1,187 @@
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment, MAX_ALIGN};
+use crate::{AllocError, Stalloc, Strategy, precondition};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to make one allocation for every entry of `sizes` (in blocks), all aligned to `align` blocks,
+	/// using a single pass over the free list. If every allocation succeeds, the pointers are written to `out`
+	/// in the same order as `sizes`, and `out` is returned as an initialized slice.
+	///
+	/// The allocations are placed in order of increasing address, each one in the first free chunk
+	/// (after the previous allocation) that can hold it. Batch allocations don't use the cache of the
+	/// `free-cache` feature, and aren't affected by the `randomize` and `bounded-search` features.
+	///
+	/// # Safety
+	///
+	/// Every size must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if not all allocations could be made, in which case this function was a no-op.
+	///
+	/// # Panics
+	///
+	/// Panics if `sizes` and `out` have different lengths.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use std::mem::MaybeUninit;
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	/// let mut out = [MaybeUninit::uninit(); 3];
+	///
+	/// let ptrs = unsafe { alloc.allocate_many(&[2, 3, 4], 1, &mut out) }.unwrap();
+	/// assert_eq!(ptrs[1], unsafe { ptrs[0].add(8) });
+	///
+	/// // The batch is all-or-nothing.
+	/// assert!(unsafe { alloc.allocate_many(&[1, 2], 1, &mut out[..2]) }.is_err());
+	/// assert!(!alloc.is_oom());
+	/// ```
+	pub unsafe fn allocate_many<'a>(
+		&self,
+		sizes: &[usize],
+		align: usize,
+		out: &'a mut [MaybeUninit<NonNull<u8>>],
+	) -> Result<&'a mut [NonNull<u8>], AllocError> {
+		assert_eq!(
+			sizes.len(),
+			out.len(),
+			"`sizes` and `out` must have the same length"
+		);
+
+		// Assert unsafe preconditions.
+		precondition!(
+			sizes.iter().all(|&size| size >= 1)
+				&& align.is_power_of_two()
+				&& align <= MAX_ALIGN / B,
+			"every size must be nonzero and `align` must be a power of 2 in `1..=MAX_ALIGN / B`"
+		);
+
+		if !self.batch_fits(sizes, align) {
+			// Quarantined and cached blocks are released before giving up.
+			#[cfg(feature = "quarantine")]
+			if self.try_flush_quarantine() {
+				return unsafe { self.allocate_many(sizes, align, out) };
+			}
+			#[cfg(feature = "free-cache")]
+			if self.try_flush_free_cache() {
+				return unsafe { self.allocate_many(sizes, align, out) };
+			}
+
+			self.on_alloc_failed(sizes.iter().sum(), align);
+			return Err(AllocError);
+		}
+
+		unsafe {
+			// `prev` is the header that links to the free chunk where the search continues.
+			let mut prev = self.base.get();
+
+			for (&size, slot) in sizes.iter().zip(out.iter_mut()) {
+				let guarded_size = size + 2 * self.guard_blocks();
+
+				// The dry run guarantees that a large enough free chunk will be found.
+				loop {
+					let curr_idx = usize::from((*prev).next);
+					let curr = self.header_at(curr_idx);
+					let spare_front =
+						(curr.addr() / B + self.guard_blocks()).wrapping_neg() % align;
+
+					if spare_front + guarded_size <= usize::from((*curr).length) {
+						prev = self.split_chunk(prev, curr, spare_front, guarded_size);
+
+						let idx = curr_idx + spare_front + self.guard_blocks();
+						self.on_alloc(idx, size, align);
+						slot.write(NonNull::new_unchecked(self.block_at(idx).cast()));
+						break;
+					}
+
+					prev = curr;
+				}
+			}
+
+			// SAFETY: Every element of `out` was initialized.
+			Ok(&mut *(core::ptr::from_mut(out) as *mut [NonNull<u8>]))
+		}
+	}
+
+	/// Deallocates every `(ptr, size)` pair in `allocs` using a single pass over the free list.
+	/// The pairs are sorted by address first, so `allocs` is reordered. This function always succeeds.
+	///
+	/// Neighbouring allocations are merged into the same free chunk, so freeing many allocations at once
+	/// is much faster than freeing them one by one with `deallocate_blocks()`.
+	///
+	/// # Safety
+	///
+	/// Every `ptr` must point to a different allocation, and its `size` must be the number of blocks
+	/// in that allocation.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	///
+	/// let mut allocs: Vec<_> = (0..5)
+	///     .map(|_| (unsafe { alloc.allocate_blocks(2, 1) }.unwrap(), 2))
+	///     .collect();
+	/// allocs.reverse();
+	///
+	/// unsafe { alloc.deallocate_many(&mut allocs) };
+	/// assert!(alloc.is_empty());
+	/// ```
+	pub unsafe fn deallocate_many(&self, allocs: &mut [(NonNull<u8>, usize)]) {
+		// Assert unsafe preconditions.
+		precondition!(
+			allocs.iter().all(|&(_, size)| size >= 1 && size <= L),
+			"every size must be in `1..=L`"
+		);
+
+		allocs.sort_unstable_by_key(|&(ptr, _)| ptr);
+
+		// Every allocation comes after the free chunk that the previous one was released into.
+		let mut start = self.base.get();
+		for &(ptr, size) in &*allocs {
+			start = unsafe { self.free_allocation(start, ptr, size) };
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Checks whether `allocate_many()` can place every allocation, without modifying the free list.
+	fn batch_fits(&self, sizes: &[usize], align: usize) -> bool {
+		let mut chunks = self.free_chunks();
+		let (mut start, mut length) = chunks.next().unwrap_or((0, 0));
+
+		for &size in sizes {
+			let guarded_size = size + 2 * self.guard_blocks();
+
+			loop {
+				let addr = unsafe { self.block_at(start) }.addr();
+				let spare_front = (addr / B + self.guard_blocks()).wrapping_neg() % align;
+
+				if spare_front + guarded_size <= length {
+					start += spare_front + guarded_size;
+					length -= spare_front + guarded_size;
+					break;
+				}
+
+				match chunks.next() {
+					Some(chunk) => (start, length) = chunk,
+					None => return false,
+				}
+			}
+		}
+
+		true
+	}
+}
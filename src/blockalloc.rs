@@ -0,0 +1,509 @@
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{
+	AllocError, AsyncStalloc, LockFreeStalloc, SpinStalloc, Stalloc, Strategy, SubStalloc,
+	UnsafeStalloc,
+};
+
+/// A dyn-compatible interface to the allocators in this crate, so that libraries can accept a
+/// `&dyn BlockAlloc` without naming the const generics of a concrete allocator.
+///
+/// Unlike `Allocator`, this trait is available on stable Rust and without any features.
+/// Sizes are rounded up to whole blocks, like they are by the `Allocator` implementations.
+///
+/// # Safety
+///
+/// Every successful allocation must be valid for reads and writes of at least `layout.size()` bytes,
+/// aligned to `layout.align()`, and disjoint from all other live allocations, until it is deallocated.
+/// `grow()` and `shrink()` must preserve the contents of the allocation.
+///
+/// # Examples
+/// ```
+/// use core::alloc::Layout;
+/// use stalloc::{BlockAlloc, Stalloc, SyncStalloc};
+///
+/// fn fill(alloc: &dyn BlockAlloc, byte: u8) -> usize {
+///     let layout = Layout::new::<[u8; 32]>();
+///     let ptr = alloc.allocate(layout).unwrap();
+///     unsafe {
+///         ptr.cast::<u8>().write_bytes(byte, 32);
+///         alloc.deallocate(ptr.cast(), layout);
+///     }
+///     ptr.len()
+/// }
+///
+/// assert_eq!(fill(&Stalloc::<16, 4>::new(), 1), 32);
+/// assert_eq!(fill(&SyncStalloc::<8, 16>::new(), 2), 32);
+/// ```
+pub unsafe trait BlockAlloc {
+	/// Returns the size of a block in bytes. Every allocation is a multiple of this size.
+	fn block_size(&self) -> usize;
+
+	/// Tries to allocate memory for `layout`. A zero-sized layout gets a dangling pointer.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError>;
+
+	/// Tries to allocate zeroed memory for `layout`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let ptr = self.allocate(layout)?;
+
+		// SAFETY: The allocation is valid for `ptr.len()` bytes.
+		unsafe { ptr.cast::<u8>().write_bytes(0, ptr.len()) };
+		Ok(ptr)
+	}
+
+	/// Deallocates memory.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been allocated by this allocator with `layout`.
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+	/// Grows an allocation, moving it if necessary. See `reallocate()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been allocated by this allocator with `old_layout`,
+	/// and `new_layout.size()` must be at least `old_layout.size()`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+	/// in which case `ptr` is still valid.
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.reallocate(ptr, old_layout, new_layout) }
+	}
+
+	/// Shrinks an allocation, moving it if necessary. See `reallocate()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been allocated by this allocator with `old_layout`,
+	/// and `new_layout.size()` must be at most `old_layout.size()`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+	/// in which case `ptr` is still valid.
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.reallocate(ptr, old_layout, new_layout) }
+	}
+
+	/// Resizes an allocation to fit `new_layout`, which is what `grow()` and `shrink()` do. The allocators
+	/// in this crate resize the allocation in place if possible, but the default implementation always moves it.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been allocated by this allocator with `old_layout`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+	/// in which case `ptr` is still valid.
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		let new_ptr = self.allocate(new_layout)?;
+
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			ptr.copy_to_nonoverlapping(new_ptr.cast(), old_layout.size().min(new_layout.size()));
+			self.deallocate(ptr, old_layout);
+		}
+
+		Ok(new_ptr)
+	}
+}
+
+/// Allocates memory for `layout` with `allocate_blocks`, which is called with a nonzero size
+/// and a valid alignment, both measured in blocks of `B` bytes.
+fn allocate_with<const B: usize>(
+	layout: Layout,
+	allocate_blocks: impl FnOnce(usize, usize) -> Result<NonNull<u8>, AllocError>,
+) -> Result<NonNull<[u8]>, AllocError> {
+	// We can only allocate memory in units of `B`, so round up.
+	let size = layout.size().div_ceil(B);
+	let align = layout.align().div_ceil(B);
+
+	// If `size` is zero, give away a dangling pointer.
+	if size == 0 {
+		let dangling = NonNull::new(layout.align() as _).unwrap();
+		return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+	}
+
+	allocate_blocks(size, align).map(|p| NonNull::slice_from_raw_parts(p, size * B))
+}
+
+unsafe impl<const L: usize, const B: usize, S: Strategy> BlockAlloc for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: `allocate_with()` passes a valid size and alignment.
+		allocate_with::<B>(layout, |size, align| unsafe {
+			self.allocate_blocks(size, align)
+		})
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: `allocate_with()` passes a valid size and alignment.
+		allocate_with::<B>(layout, |size, align| unsafe {
+			self.allocate_blocks_zeroed(size, align)
+		})
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size == 0 {
+			return;
+		}
+
+		// SAFETY: We just made sure that size != 0. Everything else is upheld by the caller.
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		let old_size = old_layout.size().div_ceil(B);
+		let new_size = new_layout.size().div_ceil(B);
+
+		if old_size == 0 || new_size == 0 {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.deallocate(ptr, old_layout) };
+			return self.allocate(new_layout);
+		}
+
+		// This resizes the allocation in place if possible.
+		// SAFETY: `ptr` and `old_size` are upheld by the caller, and both sizes are nonzero.
+		unsafe { self.reallocate_blocks(ptr, old_size, new_size, new_layout.align().div_ceil(B)) }
+			.map(|p| NonNull::slice_from_raw_parts(p, new_size * B))
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> BlockAlloc for UnsafeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.0.allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.0.allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.deallocate(ptr, layout) };
+	}
+
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.reallocate(ptr, old_layout, new_layout) }
+	}
+}
+
+#[cfg(feature = "std")]
+unsafe impl<const L: usize, const B: usize> BlockAlloc for crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.acquire_locked().allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.acquire_locked().allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate(ptr, layout) };
+	}
+
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.reallocate(ptr, old_layout, new_layout)
+		}
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> BlockAlloc for SpinStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.acquire_locked().allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.acquire_locked().allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate(ptr, layout) };
+	}
+
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.reallocate(ptr, old_layout, new_layout)
+		}
+	}
+}
+
+#[cfg(feature = "lock_api")]
+unsafe impl<const L: usize, const B: usize, R: lock_api::RawMutex> BlockAlloc
+	for crate::LockedStalloc<L, B, R>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.acquire_locked().allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.acquire_locked().allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate(ptr, layout) };
+	}
+
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.reallocate(ptr, old_layout, new_layout)
+		}
+	}
+}
+
+#[cfg(feature = "critical-section")]
+unsafe impl<const L: usize, const B: usize> BlockAlloc for crate::CriticalSectionStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.with_locked(|alloc| alloc.allocate(layout))
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.with_locked(|alloc| alloc.allocate_zeroed(layout))
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		self.with_locked(|alloc| unsafe { alloc.deallocate(ptr, layout) });
+	}
+
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		self.with_locked(|alloc| unsafe { alloc.reallocate(ptr, old_layout, new_layout) })
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> BlockAlloc for LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: `allocate_with()` passes a valid size and alignment.
+		allocate_with::<B>(layout, |size, align| unsafe {
+			self.allocate_blocks(size, align)
+		})
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size != 0 {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.deallocate_blocks(ptr, size) };
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+unsafe impl<const L: usize, const B: usize, const N: usize> BlockAlloc
+	for crate::ThreadLocalStalloc<L, B, N>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: `allocate_with()` passes a valid size and alignment.
+		allocate_with::<B>(layout, |size, align| unsafe {
+			self.allocate_blocks(size, align)
+		})
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size != 0 {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.deallocate_blocks(ptr, size) };
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+unsafe impl<const L: usize, const B: usize> BlockAlloc for crate::ThreadCache<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: `allocate_with()` passes a valid size and alignment.
+		allocate_with::<B>(layout, |size, align| unsafe {
+			self.allocate_blocks(size, align)
+		})
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size != 0 {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.deallocate_blocks(ptr, size) };
+		}
+	}
+}
+
+unsafe impl<const B: usize> BlockAlloc for SubStalloc<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: `allocate_with()` passes a valid size and alignment.
+		allocate_with::<B>(layout, |size, align| unsafe {
+			self.allocate_blocks(size, align)
+		})
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size != 0 {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.deallocate_blocks(ptr, size) };
+		}
+	}
+}
+
+/// Allocations made through this trait never wait, but deallocations wake up waiting tasks.
+unsafe impl<const L: usize, const B: usize> BlockAlloc for AsyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_size(&self) -> usize {
+		B
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.try_allocate(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { Self::deallocate(self, ptr, layout) };
+	}
+}
@@ -0,0 +1,176 @@
+use core::fmt::{self, Debug, Formatter};
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment, MAX_ALIGN};
+use crate::{AllocError, CarveParent, Stalloc, Strategy};
+
+/// An owned allocation of raw blocks, returned by `try_alloc_blocks()`. The blocks are
+/// deallocated when the `BlockBox` is dropped, so their size doesn't have to be remembered.
+///
+/// A `BlockBox` dereferences to the bytes of the allocation, which start out uninitialized.
+///
+/// # Examples
+/// ```
+/// use stalloc::Stalloc;
+///
+/// let alloc = Stalloc::<16, 4>::new();
+///
+/// {
+///     let mut block = alloc.try_alloc_blocks(4, 1).unwrap();
+///     assert_eq!(block.len(), 16);
+///     block[0].write(42);
+/// }
+///
+/// assert!(alloc.is_empty());
+/// ```
+pub struct BlockBox<'a, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	parent: &'a dyn CarveParent,
+	ptr: NonNull<u8>,
+	blocks: usize,
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate `size` blocks, and wraps them in a `BlockBox` that deallocates them when it is dropped.
+	/// This is a safe version of `allocate_blocks()`. Note that `align` is measured in units of `B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `size` is zero, if `align` is not a power of 2 in the range `1..=MAX_ALIGN / B`,
+	/// or if the allocation was unsuccessful.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<16, 4>::new();
+	///
+	/// assert!(alloc.try_alloc_blocks(0, 1).is_err());
+	/// assert!(alloc.try_alloc_blocks(4, 3).is_err());
+	///
+	/// let block = alloc.try_alloc_blocks(4, 4).unwrap();
+	/// assert!(block.as_ptr().addr().get().is_multiple_of(16));
+	/// ```
+	pub fn try_alloc_blocks(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<BlockBox<'_, B>, AllocError> {
+		if size == 0 || !align.is_power_of_two() || align > MAX_ALIGN / B {
+			return Err(AllocError);
+		}
+
+		// SAFETY: We just checked `size` and `align`.
+		let ptr = unsafe { self.allocate_blocks(size, align) }?;
+		Ok(BlockBox {
+			parent: self,
+			ptr,
+			blocks: size,
+		})
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate `size` blocks, and wraps them in a `BlockBox` that deallocates them when it is dropped.
+	/// See `Stalloc::try_alloc_blocks()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `size` is zero, if `align` is not a power of 2 in the range `1..=MAX_ALIGN / B`,
+	/// or if the allocation was unsuccessful.
+	pub fn try_alloc_blocks(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<BlockBox<'_, B>, AllocError> {
+		if size == 0 || !align.is_power_of_two() || align > MAX_ALIGN / B {
+			return Err(AllocError);
+		}
+
+		// SAFETY: We just checked `size` and `align`.
+		let ptr = unsafe { self.allocate_blocks(size, align) }?;
+		Ok(BlockBox {
+			parent: self,
+			ptr,
+			blocks: size,
+		})
+	}
+}
+
+impl<const B: usize> BlockBox<'_, B>
+where
+	Align<B>: Alignment,
+{
+	/// Returns the number of blocks in the allocation.
+	#[must_use]
+	pub const fn blocks(&self) -> usize {
+		self.blocks
+	}
+
+	/// Returns a pointer to the start of the allocation.
+	#[must_use]
+	pub const fn as_ptr(&self) -> NonNull<u8> {
+		self.ptr
+	}
+
+	/// Consumes the `BlockBox` without deallocating the blocks, and returns a pointer to them along with
+	/// their size in blocks. The blocks must then be deallocated manually with `deallocate_blocks()`.
+	#[must_use]
+	pub fn into_raw(self) -> (NonNull<u8>, usize) {
+		let this = ManuallyDrop::new(self);
+		(this.ptr, this.blocks)
+	}
+}
+
+impl<const B: usize> Deref for BlockBox<'_, B>
+where
+	Align<B>: Alignment,
+{
+	type Target = [MaybeUninit<u8>];
+
+	fn deref(&self) -> &[MaybeUninit<u8>] {
+		unsafe { core::slice::from_raw_parts(self.ptr.as_ptr().cast(), self.blocks * B) }
+	}
+}
+
+impl<const B: usize> DerefMut for BlockBox<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn deref_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+		unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr().cast(), self.blocks * B) }
+	}
+}
+
+impl<const B: usize> Drop for BlockBox<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		// SAFETY: The blocks were allocated from the parent in `try_alloc_blocks()`.
+		unsafe { self.parent.release(self.ptr, self.blocks) };
+	}
+}
+
+impl<const B: usize> Debug for BlockBox<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("BlockBox")
+			.field("ptr", &self.ptr)
+			.field("blocks", &self.blocks)
+			.finish_non_exhaustive()
+	}
+}
@@ -0,0 +1,66 @@
+use core::num::NonZeroUsize;
+
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Limits how many free chunks an allocation may examine before giving up, or removes the limit
+	/// if `limit` is `None` (the initial state). This requires the `bounded-search` feature.
+	///
+	/// With a limit of `k`, an allocation fails as soon as the first `k` free chunks have turned out
+	/// to be too small, even if a later chunk would have been large enough. This bounds the latency of
+	/// `allocate_blocks()` on a fragmented heap, which makes it usable from hard real-time code.
+	/// To fall back to another allocator instead of failing, use an `AllocChain`.
+	///
+	/// Note that deallocation still walks the free list, and the `randomize` feature (once seeded)
+	/// examines every free chunk regardless of the limit. Blocks held back by the `quarantine` and
+	/// `free-cache` features are still released, and the search is retried, before an allocation fails.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use std::num::NonZeroUsize;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// // Fragment the heap, leaving free chunks of 1, 1 and 16 blocks.
+	/// let ptrs: Vec<_> = (0..4).map(|_| unsafe { alloc.allocate_blocks(1, 1) }.unwrap()).collect();
+	/// unsafe { alloc.deallocate_blocks(ptrs[0], 1) };
+	/// unsafe { alloc.deallocate_blocks(ptrs[2], 1) };
+	/// # #[cfg(feature = "quarantine")]
+	/// # alloc.flush_quarantine();
+	/// # #[cfg(feature = "free-cache")]
+	/// # alloc.flush_free_cache();
+	///
+	/// alloc.set_search_limit(NonZeroUsize::new(2));
+	/// assert!(unsafe { alloc.allocate_blocks(4, 1) }.is_err());
+	///
+	/// alloc.set_search_limit(None);
+	/// assert!(unsafe { alloc.allocate_blocks(4, 1) }.is_ok());
+	/// ```
+	pub fn set_search_limit(&self, limit: Option<NonZeroUsize>) {
+		self.search_limit.set(limit);
+	}
+
+	/// Returns the maximum number of free chunks that an allocation may examine, or `None` if there is no limit.
+	/// This requires the `bounded-search` feature.
+	pub const fn search_limit(&self) -> Option<NonZeroUsize> {
+		self.search_limit.get()
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns how many free chunks an allocation may examine.
+	pub(crate) fn search_budget(&self) -> usize {
+		self.search_limit
+			.get()
+			.map_or(usize::MAX, NonZeroUsize::get)
+	}
+}
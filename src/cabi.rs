@@ -0,0 +1,818 @@
+//! The C allocation functions `malloc`, `calloc`, `realloc`, `free`, `posix_memalign` and `aligned_alloc`, backed by a
+//! stalloc allocator. This requires the `c-abi` feature.
+//!
+//! C's `free` doesn't know the size of the allocation, so the allocator looks it up instead, which is
+//! why this feature enables `tracking`. The functions in this module are generic over the allocator, and
+//! `export_c_allocator!` exports them under their C names, so that C code linked into the same binary
+//! allocates from the same arena as Rust code.
+//!
+//! C code can also create arenas of its own, inside buffers that it provides, with `stalloc_create`.
+//! These are used with `stalloc_alloc`, `stalloc_free` and `stalloc_destroy`, which are always exported.
+//! When several arenas coexist, they can be registered with `stalloc_register`, so that `stalloc_free_any`
+//! can free a pointer without being told which arena it came from.
+//!
+//! # Examples
+//! ```
+//! use stalloc::SyncStalloc;
+//! use stalloc::cabi;
+//!
+//! let alloc = SyncStalloc::<100, 16>::new();
+//!
+//! unsafe {
+//!     let ptr = cabi::calloc(&alloc, 4, 8).cast::<u64>();
+//!     assert_eq!(*ptr.add(3), 0);
+//!
+//!     let ptr = cabi::realloc(&alloc, ptr.cast(), 64);
+//!     assert!(!ptr.is_null());
+//!
+//!     cabi::free(&alloc, ptr);
+//! }
+//!
+//! assert!(alloc.is_empty());
+//! ```
+
+use core::ffi::{c_int, c_void};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::align::{Align, Alignment, MAX_ALIGN};
+use crate::{
+	AllocChain, CarveParent, ChainableAlloc, OwnedAllocChain, SpinStalloc, Stalloc, SubStalloc,
+};
+
+/// The alignment of the memory returned by `malloc`, `calloc` and `realloc`,
+/// which is enough for any fundamental C type.
+pub const MALLOC_ALIGN: usize = 2 * size_of::<usize>();
+
+/// The error returned by `posix_memalign` when the alignment is invalid.
+pub const EINVAL: c_int = 22;
+
+/// The error returned by `posix_memalign` when there isn't enough memory.
+pub const ENOMEM: c_int = 12;
+
+/// An allocator that can back the C allocation functions, because it can free a pointer without
+/// being told its size.
+///
+/// # Safety
+///
+/// `c_alloc` must return null or a pointer to at least `size` bytes aligned to `align`, which is disjoint
+/// from all other live allocations. `c_usable_size` must return `Some` if and only if `ptr` points to a live
+/// allocation of this allocator, along with its size in bytes. `c_realloc` must preserve the contents of
+/// the allocation, and leave `ptr` valid if it fails.
+pub unsafe trait CAllocator: Sync {
+	/// Allocates `size` bytes aligned to `align`, or returns null.
+	/// `size` is nonzero, and `align` is a power of 2.
+	fn c_alloc(&self, size: usize, align: usize) -> *mut u8;
+
+	/// Returns the size in bytes of the live allocation that starts at `ptr`,
+	/// or `None` if it wasn't allocated by this allocator.
+	fn c_usable_size(&self, ptr: NonNull<u8>) -> Option<usize>;
+
+	/// Deallocates `ptr`.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a live allocation of this allocator.
+	unsafe fn c_free(&self, ptr: NonNull<u8>);
+
+	/// Resizes the allocation at `ptr` to `size` bytes aligned to `MALLOC_ALIGN`, moving it if necessary.
+	/// Returns null if that failed, in which case `ptr` is still valid.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a live allocation of this allocator, and `size` must be nonzero.
+	unsafe fn c_realloc(&self, ptr: NonNull<u8>, size: usize) -> *mut u8;
+}
+
+/// Allocates `size` bytes aligned to `align` from `alloc`, or returns null.
+fn alloc_in<const L: usize, const B: usize>(
+	alloc: &Stalloc<L, B>,
+	size: usize,
+	align: usize,
+) -> *mut u8
+where
+	Align<B>: Alignment,
+{
+	// SAFETY: `size` is nonzero, and `align` is a power of 2.
+	unsafe { alloc.allocate_blocks(size.div_ceil(B), align.div_ceil(B)) }
+		.map_or(ptr::null_mut(), NonNull::as_ptr)
+}
+
+/// Returns the size in bytes of the allocation at `ptr` in `alloc`.
+fn usable_size_in<const L: usize, const B: usize>(
+	alloc: &Stalloc<L, B>,
+	ptr: NonNull<u8>,
+) -> Option<usize>
+where
+	Align<B>: Alignment,
+{
+	alloc.allocation_size(ptr).map(|size| size * B)
+}
+
+/// Resizes the allocation at `ptr` in `alloc` to `size` bytes, or returns null.
+///
+/// # Safety
+///
+/// `ptr` must point to a live allocation of `alloc`, and `size` must be nonzero.
+unsafe fn realloc_in<const L: usize, const B: usize>(
+	alloc: &Stalloc<L, B>,
+	ptr: NonNull<u8>,
+	size: usize,
+) -> *mut u8
+where
+	Align<B>: Alignment,
+{
+	// SAFETY: Upheld by the caller.
+	let old_size = unsafe { alloc.allocation_size(ptr).unwrap_unchecked() };
+
+	// SAFETY: `ptr` points to an allocation of `old_size` blocks, and the new size is nonzero.
+	unsafe { alloc.reallocate_blocks(ptr, old_size, size.div_ceil(B), MALLOC_ALIGN.div_ceil(B)) }
+		.map_or(ptr::null_mut(), NonNull::as_ptr)
+}
+
+#[cfg(feature = "std")]
+unsafe impl<const L: usize, const B: usize> CAllocator for crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn c_alloc(&self, size: usize, align: usize) -> *mut u8 {
+		alloc_in(&self.acquire_locked(), size, align)
+	}
+
+	fn c_usable_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		usable_size_in(&self.acquire_locked(), ptr)
+	}
+
+	unsafe fn c_free(&self, ptr: NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.deallocate_tracked(ptr) };
+	}
+
+	unsafe fn c_realloc(&self, ptr: NonNull<u8>, size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { realloc_in(&self.acquire_locked(), ptr, size) }
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> CAllocator for SpinStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn c_alloc(&self, size: usize, align: usize) -> *mut u8 {
+		alloc_in(&self.acquire_locked(), size, align)
+	}
+
+	fn c_usable_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		usable_size_in(&self.acquire_locked(), ptr)
+	}
+
+	unsafe fn c_free(&self, ptr: NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate_tracked(ptr) };
+	}
+
+	unsafe fn c_realloc(&self, ptr: NonNull<u8>, size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { realloc_in(&self.acquire_locked(), ptr, size) }
+	}
+}
+
+#[cfg(feature = "critical-section")]
+unsafe impl<const L: usize, const B: usize> CAllocator for crate::CriticalSectionStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn c_alloc(&self, size: usize, align: usize) -> *mut u8 {
+		self.with_locked(|alloc| alloc_in(alloc, size, align))
+	}
+
+	fn c_usable_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		self.with_locked(|alloc| usable_size_in(alloc, ptr))
+	}
+
+	unsafe fn c_free(&self, ptr: NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		self.with_locked(|alloc| unsafe { alloc.deallocate_tracked(ptr) });
+	}
+
+	unsafe fn c_realloc(&self, ptr: NonNull<u8>, size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		self.with_locked(|alloc| unsafe { realloc_in(alloc, ptr, size) })
+	}
+}
+
+/// Both links of an `AllocChain` or `OwnedAllocChain`.
+///
+/// Allocates from the first allocator, and falls back to the second one. A pointer is freed by whichever
+/// allocator owns it, and an allocation that can't be resized by its owner is moved to the other one.
+struct Links<'a>(&'a dyn CAllocator, &'a dyn CAllocator);
+
+unsafe impl CAllocator for Links<'_> {
+	fn c_alloc(&self, size: usize, align: usize) -> *mut u8 {
+		let ptr = self.0.c_alloc(size, align);
+		if ptr.is_null() {
+			self.1.c_alloc(size, align)
+		} else {
+			ptr
+		}
+	}
+
+	fn c_usable_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		self.0
+			.c_usable_size(ptr)
+			.or_else(|| self.1.c_usable_size(ptr))
+	}
+
+	unsafe fn c_free(&self, ptr: NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			if self.0.c_usable_size(ptr).is_some() {
+				self.0.c_free(ptr);
+			} else {
+				self.1.c_free(ptr);
+			}
+		}
+	}
+
+	unsafe fn c_realloc(&self, ptr: NonNull<u8>, size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			let (owner, other) = if self.0.c_usable_size(ptr).is_some() {
+				(self.0, self.1)
+			} else {
+				(self.1, self.0)
+			};
+
+			let new_ptr = owner.c_realloc(ptr, size);
+			if !new_ptr.is_null() {
+				return new_ptr;
+			}
+
+			// The owner is out of memory, so move the allocation to the other allocator.
+			let new_ptr = other.c_alloc(size, MALLOC_ALIGN);
+			if !new_ptr.is_null() {
+				let old_size = owner.c_usable_size(ptr).unwrap_unchecked();
+				ptr.copy_to_nonoverlapping(NonNull::new_unchecked(new_ptr), old_size.min(size));
+				owner.c_free(ptr);
+			}
+			new_ptr
+		}
+	}
+}
+
+unsafe impl<A: CAllocator, B: CAllocator> CAllocator for AllocChain<'_, A, B> {
+	fn c_alloc(&self, size: usize, align: usize) -> *mut u8 {
+		Links(&self.0, self.1).c_alloc(size, align)
+	}
+
+	fn c_usable_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		Links(&self.0, self.1).c_usable_size(ptr)
+	}
+
+	unsafe fn c_free(&self, ptr: NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { Links(&self.0, self.1).c_free(ptr) };
+	}
+
+	unsafe fn c_realloc(&self, ptr: NonNull<u8>, size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { Links(&self.0, self.1).c_realloc(ptr, size) }
+	}
+}
+
+unsafe impl<A: CAllocator, B: CAllocator> CAllocator for OwnedAllocChain<A, B> {
+	fn c_alloc(&self, size: usize, align: usize) -> *mut u8 {
+		Links(&self.0, &self.1).c_alloc(size, align)
+	}
+
+	fn c_usable_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		Links(&self.0, &self.1).c_usable_size(ptr)
+	}
+
+	unsafe fn c_free(&self, ptr: NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { Links(&self.0, &self.1).c_free(ptr) };
+	}
+
+	unsafe fn c_realloc(&self, ptr: NonNull<u8>, size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { Links(&self.0, &self.1).c_realloc(ptr, size) }
+	}
+}
+
+/// Allocates `size` bytes aligned to `MALLOC_ALIGN`, or returns null. Like in glibc,
+/// `malloc(0)` returns a unique pointer that must be freed.
+pub fn malloc<A: CAllocator>(alloc: &A, size: usize) -> *mut c_void {
+	alloc.c_alloc(size.max(1), MALLOC_ALIGN).cast()
+}
+
+/// Allocates zeroed memory for an array of `count` elements of `size` bytes, or returns null.
+pub fn calloc<A: CAllocator>(alloc: &A, count: usize, size: usize) -> *mut c_void {
+	let Some(bytes) = count.checked_mul(size) else {
+		return ptr::null_mut();
+	};
+
+	let ptr = malloc(alloc, bytes);
+	if !ptr.is_null() {
+		// SAFETY: The allocation is valid for at least `bytes` bytes.
+		unsafe { ptr.write_bytes(0, bytes) };
+	}
+	ptr
+}
+
+/// Resizes an allocation to `size` bytes, moving it if necessary.
+///
+/// Returns null if that failed, in which case `ptr` is still valid. `realloc(null, size)` is `malloc(size)`, and `realloc(ptr, 0)`
+/// frees `ptr` and returns null.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a live allocation of `alloc`.
+pub unsafe fn realloc<A: CAllocator>(alloc: &A, ptr: *mut c_void, size: usize) -> *mut c_void {
+	let Some(ptr) = NonNull::new(ptr.cast()) else {
+		return malloc(alloc, size);
+	};
+
+	if size == 0 {
+		// SAFETY: Upheld by the caller.
+		unsafe { alloc.c_free(ptr) };
+		return ptr::null_mut();
+	}
+
+	// SAFETY: Upheld by the caller.
+	unsafe { alloc.c_realloc(ptr, size) }.cast()
+}
+
+/// Deallocates `ptr`. Does nothing if `ptr` is null.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a live allocation of `alloc`.
+pub unsafe fn free<A: CAllocator>(alloc: &A, ptr: *mut c_void) {
+	if let Some(ptr) = NonNull::new(ptr.cast()) {
+		// SAFETY: Upheld by the caller.
+		unsafe { alloc.c_free(ptr) };
+	}
+}
+
+/// Allocates `size` bytes aligned to `align`, and stores the pointer in `*memptr`.
+///
+/// Returns 0 on success,
+/// `EINVAL` if `align` isn't a power of 2 that is a multiple of the size of a pointer or is greater than
+/// `MAX_ALIGN`, and `ENOMEM` if the allocation failed. `*memptr` is only written on success.
+///
+/// # Safety
+///
+/// `memptr` must be valid for writes.
+pub unsafe fn posix_memalign<A: CAllocator>(
+	alloc: &A,
+	memptr: *mut *mut c_void,
+	align: usize,
+	size: usize,
+) -> c_int {
+	if !align.is_power_of_two() || align < size_of::<*mut c_void>() || align > MAX_ALIGN {
+		return EINVAL;
+	}
+
+	let ptr = alloc.c_alloc(size.max(1), align);
+	if ptr.is_null() {
+		return ENOMEM;
+	}
+
+	// SAFETY: Upheld by the caller.
+	unsafe { memptr.write(ptr.cast()) };
+	0
+}
+
+/// Allocates `size` bytes aligned to `align`, or returns null. Also returns null if `align` isn't a power
+/// of 2 or is greater than `MAX_ALIGN`.
+pub fn aligned_alloc<A: CAllocator>(alloc: &A, align: usize, size: usize) -> *mut c_void {
+	if !align.is_power_of_two() || align > MAX_ALIGN {
+		return ptr::null_mut();
+	}
+
+	alloc.c_alloc(size.max(1), align).cast()
+}
+
+/// Exports `malloc`, `calloc`, `realloc`, `free`, `posix_memalign` and `aligned_alloc` as C symbols, backed by the given
+/// allocator (usually a `static`). This requires the `c-abi` feature.
+///
+/// The allocator must implement `CAllocator`, and must not call `malloc` itself, so it can't be chained to the
+/// system allocator. These symbols replace the ones of the C library, so this should only be used once in a
+/// binary, usually together with `#[global_allocator]` so that Rust code uses the same arena.
+///
+/// # Examples
+/// ```no_run
+/// use stalloc::{SyncStalloc, export_c_allocator};
+///
+/// #[global_allocator]
+/// static GLOBAL: SyncStalloc<65535, 16> = SyncStalloc::new();
+///
+/// export_c_allocator!(GLOBAL);
+///
+/// fn main() {
+///     // Memory that C libraries allocate with `malloc` comes from `GLOBAL`.
+/// }
+/// ```
+#[macro_export]
+macro_rules! export_c_allocator {
+	($alloc:expr) => {
+		/// Allocates memory from the exported stalloc allocator.
+		#[unsafe(no_mangle)]
+		pub extern "C" fn malloc(size: usize) -> *mut ::core::ffi::c_void {
+			$crate::cabi::malloc(&$alloc, size)
+		}
+
+		/// Allocates zeroed memory from the exported stalloc allocator.
+		#[unsafe(no_mangle)]
+		pub extern "C" fn calloc(count: usize, size: usize) -> *mut ::core::ffi::c_void {
+			$crate::cabi::calloc(&$alloc, count, size)
+		}
+
+		/// Resizes memory of the exported stalloc allocator.
+		///
+		/// # Safety
+		///
+		/// `ptr` must be null or point to a live allocation.
+		#[unsafe(no_mangle)]
+		pub unsafe extern "C" fn realloc(
+			ptr: *mut ::core::ffi::c_void,
+			size: usize,
+		) -> *mut ::core::ffi::c_void {
+			// SAFETY: Upheld by the caller.
+			unsafe { $crate::cabi::realloc(&$alloc, ptr, size) }
+		}
+
+		/// Frees memory of the exported stalloc allocator.
+		///
+		/// # Safety
+		///
+		/// `ptr` must be null or point to a live allocation.
+		#[unsafe(no_mangle)]
+		pub unsafe extern "C" fn free(ptr: *mut ::core::ffi::c_void) {
+			// SAFETY: Upheld by the caller.
+			unsafe { $crate::cabi::free(&$alloc, ptr) }
+		}
+
+		/// Allocates aligned memory from the exported stalloc allocator.
+		///
+		/// # Safety
+		///
+		/// `memptr` must be valid for writes.
+		#[unsafe(no_mangle)]
+		pub unsafe extern "C" fn posix_memalign(
+			memptr: *mut *mut ::core::ffi::c_void,
+			align: usize,
+			size: usize,
+		) -> ::core::ffi::c_int {
+			// SAFETY: Upheld by the caller.
+			unsafe { $crate::cabi::posix_memalign(&$alloc, memptr, align, size) }
+		}
+
+		/// Allocates aligned memory from the exported stalloc allocator.
+		#[unsafe(no_mangle)]
+		pub extern "C" fn aligned_alloc(align: usize, size: usize) -> *mut ::core::ffi::c_void {
+			$crate::cabi::aligned_alloc(&$alloc, align, size)
+		}
+	};
+}
+
+/// The block size of the arenas created by `stalloc_create`.
+pub const HANDLE_BLOCK_SIZE: usize = 16;
+
+/// The parent of an arena created by `stalloc_create`. The buffer belongs to the caller,
+/// so nothing has to be returned when the arena is destroyed.
+struct CallerBuffer;
+
+impl CarveParent for CallerBuffer {
+	unsafe fn release(&self, _ptr: NonNull<u8>, _size: usize) {}
+}
+
+/// An arena inside a buffer provided by C code, created by `stalloc_create`. The handle is stored
+/// at the start of the buffer, so creating an arena never allocates.
+///
+/// Every allocation is preceded by a block that records its size, so that `stalloc_free` doesn't need to
+/// be told the size. A handle is not thread-safe, so it must not be used by several threads at the same time.
+pub struct StallocHandle {
+	arena: SubStalloc<'static, HANDLE_BLOCK_SIZE>,
+}
+
+/// The block that precedes every allocation of a `StallocHandle`.
+#[derive(Clone, Copy)]
+struct AllocHeader {
+	/// The total number of blocks in the allocation, including this header.
+	blocks: usize,
+	/// The number of blocks between the start of the allocation and the memory that was handed out.
+	front: usize,
+}
+
+/// Creates an arena in the buffer of `len` bytes at `buf`, and returns a handle to it. Returns null if the
+/// buffer is too small. Only the first 65535 blocks of a larger buffer are used.
+///
+/// # Safety
+///
+/// `buf` must be valid for reads and writes of `len` bytes until `stalloc_destroy` is called,
+/// and must not be accessed in any other way until then.
+///
+/// # Examples
+/// ```
+/// use core::mem::MaybeUninit;
+/// use stalloc::cabi::{stalloc_alloc, stalloc_create, stalloc_destroy, stalloc_free};
+///
+/// let mut buf = [MaybeUninit::<u8>::uninit(); 1024];
+///
+/// unsafe {
+///     let handle = stalloc_create(buf.as_mut_ptr().cast(), buf.len());
+///     assert!(!handle.is_null());
+///
+///     let ptr = stalloc_alloc(handle, 100, 8);
+///     assert!(!ptr.is_null());
+///     stalloc_free(handle, ptr);
+///
+///     stalloc_destroy(handle);
+/// }
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stalloc_create(buf: *mut c_void, len: usize) -> *mut StallocHandle {
+	let start = buf.addr();
+	let handle = start.next_multiple_of(HANDLE_BLOCK_SIZE);
+	let arena = handle + size_of::<StallocHandle>().next_multiple_of(HANDLE_BLOCK_SIZE);
+
+	let Some(available) = (start + len).checked_sub(arena) else {
+		return ptr::null_mut();
+	};
+
+	// An allocation needs at least one block besides its header.
+	let blocks = (available / HANDLE_BLOCK_SIZE).min(0xffff);
+	if buf.is_null() || blocks < 2 {
+		return ptr::null_mut();
+	}
+
+	// SAFETY: Both addresses are inside the buffer, and `blocks` is in `1..65536`.
+	unsafe {
+		let handle = buf.with_addr(handle).cast::<StallocHandle>();
+		let arena = NonNull::new_unchecked(buf.with_addr(arena).cast());
+
+		handle.write(StallocHandle {
+			arena: SubStalloc::new(&CallerBuffer, arena, blocks),
+		});
+		handle
+	}
+}
+
+/// Allocates `size` bytes aligned to `align` from the arena, or returns null. `align` must be a power of 2,
+/// or 0 for an alignment of `MALLOC_ALIGN`.
+///
+/// # Safety
+///
+/// `handle` must have been returned by `stalloc_create`, and must not have been destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stalloc_alloc(
+	handle: *mut StallocHandle,
+	size: usize,
+	align: usize,
+) -> *mut c_void {
+	let align = if align == 0 { MALLOC_ALIGN } else { align };
+	if !align.is_power_of_two() {
+		return ptr::null_mut();
+	}
+
+	// The header takes up one block, or more if the allocation needs a stricter alignment.
+	let front = align.div_ceil(HANDLE_BLOCK_SIZE);
+	let Some(blocks) = size.max(1).div_ceil(HANDLE_BLOCK_SIZE).checked_add(front) else {
+		return ptr::null_mut();
+	};
+
+	// SAFETY: Upheld by the caller.
+	let arena = unsafe { &(*handle).arena };
+
+	if blocks > arena.blocks() {
+		return ptr::null_mut();
+	}
+
+	// SAFETY: `blocks` is nonzero, and `front` is a power of 2 that is small enough.
+	let Ok(start) = (unsafe { arena.allocate_blocks(blocks, front) }) else {
+		return ptr::null_mut();
+	};
+
+	// SAFETY: The allocation is `blocks` blocks long, and `front < blocks`.
+	unsafe {
+		let ptr = start.add(front * HANDLE_BLOCK_SIZE);
+		ptr.cast::<AllocHeader>()
+			.sub(1)
+			.write_unaligned(AllocHeader { blocks, front });
+		ptr.as_ptr().cast()
+	}
+}
+
+/// Frees memory that was allocated from the arena. Does nothing if `ptr` is null.
+///
+/// # Safety
+///
+/// `handle` must have been returned by `stalloc_create`, and must not have been destroyed.
+/// `ptr` must be null or point to a live allocation of the arena.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stalloc_free(handle: *mut StallocHandle, ptr: *mut c_void) {
+	let Some(ptr) = NonNull::new(ptr.cast::<u8>()) else {
+		return;
+	};
+
+	// SAFETY: Upheld by the caller. The header was written by `stalloc_alloc`.
+	unsafe {
+		let AllocHeader { blocks, front } = ptr.cast::<AllocHeader>().sub(1).read_unaligned();
+		let start = ptr.sub(front * HANDLE_BLOCK_SIZE);
+		(*handle).arena.deallocate_blocks(start, blocks);
+	}
+}
+
+/// Destroys an arena. Afterwards, the buffer belongs to the caller again,
+/// and any memory that was allocated from the arena is invalid.
+///
+/// # Safety
+///
+/// `handle` must have been returned by `stalloc_create`, and must not have been destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stalloc_destroy(handle: *mut StallocHandle) {
+	REGISTRY.unregister(handle);
+
+	// SAFETY: Upheld by the caller.
+	unsafe { handle.drop_in_place() };
+}
+
+/// The number of arenas that can be registered with `stalloc_register` at the same time.
+pub const MAX_ARENAS: usize = 16;
+
+/// A C function that frees memory, like `free`.
+pub type FreeFn = unsafe extern "C" fn(*mut c_void);
+
+/// Records the address ranges of up to `N` arenas, so that a pointer can be freed without knowing
+/// which arena it came from. Pointers that don't belong to any arena are passed to a fallback function.
+///
+/// The C functions `stalloc_register`, `stalloc_unregister`, `stalloc_set_fallback_free` and
+/// `stalloc_free_any` use a global registry of `MAX_ARENAS` arenas.
+///
+/// # Examples
+/// ```
+/// use core::mem::MaybeUninit;
+/// use stalloc::cabi::{ArenaRegistry, stalloc_alloc, stalloc_create, stalloc_destroy};
+///
+/// let registry = ArenaRegistry::<4>::new();
+/// let mut bufs = [[MaybeUninit::<u8>::uninit(); 256]; 2];
+///
+/// unsafe {
+///     let [a, b] = bufs.each_mut().map(|buf| stalloc_create(buf.as_mut_ptr().cast(), buf.len()));
+///     assert!(registry.register(a) && registry.register(b));
+///
+///     let ptr = stalloc_alloc(b, 32, 0);
+///     assert_eq!(registry.owner(ptr), Some(b));
+///     registry.free(ptr);
+///
+///     registry.unregister(a);
+///     registry.unregister(b);
+///     stalloc_destroy(a);
+///     stalloc_destroy(b);
+/// }
+/// ```
+pub struct ArenaRegistry<const N: usize> {
+	arenas: [AtomicPtr<StallocHandle>; N],
+	fallback: AtomicPtr<()>,
+}
+
+impl<const N: usize> ArenaRegistry<N> {
+	/// Creates an empty registry, whose fallback is C's `free` with the `std` feature, and nothing otherwise.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			arenas: [const { AtomicPtr::new(ptr::null_mut()) }; N],
+			fallback: AtomicPtr::new(ptr::null_mut()),
+		}
+	}
+
+	/// Registers an arena. Returns `false` if the registry is full.
+	///
+	/// # Safety
+	///
+	/// `handle` must have been returned by `stalloc_create`, and must be unregistered before it is destroyed.
+	pub unsafe fn register(&self, handle: *mut StallocHandle) -> bool {
+		self.arenas.iter().any(|slot| {
+			slot.compare_exchange(ptr::null_mut(), handle, Ordering::AcqRel, Ordering::Relaxed)
+				.is_ok()
+		})
+	}
+
+	/// Unregisters an arena. Returns `false` if it wasn't registered.
+	pub fn unregister(&self, handle: *mut StallocHandle) -> bool {
+		self.arenas.iter().any(|slot| {
+			slot.compare_exchange(handle, ptr::null_mut(), Ordering::AcqRel, Ordering::Relaxed)
+				.is_ok()
+		})
+	}
+
+	/// Sets the function that frees pointers which don't belong to any registered arena.
+	/// With `None`, the default fallback is restored.
+	pub fn set_fallback(&self, free: Option<FreeFn>) {
+		self.fallback.store(
+			free.map_or(ptr::null_mut(), |f| f as *mut ()),
+			Ordering::Release,
+		);
+	}
+
+	/// Returns the registered arena that `ptr` points into, if there is one.
+	pub fn owner(&self, ptr: *const c_void) -> Option<*mut StallocHandle> {
+		self.arenas
+			.iter()
+			.map(|slot| slot.load(Ordering::Acquire))
+			// SAFETY: Registered handles are valid until they are unregistered.
+			.find(|&handle| {
+				!handle.is_null() && unsafe { (*handle).arena.addr_in_bounds(ptr.addr()) }
+			})
+	}
+
+	/// Frees `ptr` with the arena that owns it, or with the fallback function if no arena does.
+	/// Does nothing if `ptr` is null.
+	///
+	/// # Safety
+	///
+	/// `ptr` must be null, or point to a live allocation of a registered arena or of the fallback.
+	pub unsafe fn free(&self, ptr: *mut c_void) {
+		if ptr.is_null() {
+			return;
+		}
+
+		if let Some(handle) = self.owner(ptr) {
+			// SAFETY: `ptr` belongs to this arena.
+			unsafe { stalloc_free(handle, ptr) };
+			return;
+		}
+
+		let fallback = self.fallback.load(Ordering::Acquire);
+		if fallback.is_null() {
+			#[cfg(feature = "std")]
+			// SAFETY: Upheld by the caller.
+			unsafe {
+				system_free(ptr);
+			}
+		} else {
+			// SAFETY: `fallback` was stored by `set_fallback()`, and `ptr` is upheld by the caller.
+			unsafe { core::mem::transmute::<*mut (), FreeFn>(fallback)(ptr) };
+		}
+	}
+}
+
+impl<const N: usize> Default for ArenaRegistry<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(feature = "std")]
+unsafe extern "C" {
+	/// The `free` of the C library, which is the default fallback of an `ArenaRegistry`.
+	#[link_name = "free"]
+	fn system_free(ptr: *mut c_void);
+}
+
+/// The registry used by the C functions.
+static REGISTRY: ArenaRegistry<MAX_ARENAS> = ArenaRegistry::new();
+
+/// Registers an arena, so that `stalloc_free_any` can free its pointers. Returns 0 on success,
+/// or `ENOMEM` if `MAX_ARENAS` arenas are already registered. `stalloc_destroy` unregisters the arena.
+///
+/// # Safety
+///
+/// `handle` must have been returned by `stalloc_create`, and must not have been destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stalloc_register(handle: *mut StallocHandle) -> c_int {
+	// SAFETY: Upheld by the caller, and `stalloc_destroy` unregisters the handle.
+	if unsafe { REGISTRY.register(handle) } {
+		0
+	} else {
+		ENOMEM
+	}
+}
+
+/// Unregisters an arena. Does nothing if it wasn't registered.
+#[unsafe(no_mangle)]
+pub extern "C" fn stalloc_unregister(handle: *mut StallocHandle) {
+	REGISTRY.unregister(handle);
+}
+
+/// Sets the function that `stalloc_free_any` calls for pointers which don't belong to any registered arena.
+///
+/// Pass null to restore the default, which is C's `free` with the `std` feature, and nothing otherwise.
+#[unsafe(no_mangle)]
+pub extern "C" fn stalloc_set_fallback_free(free: Option<FreeFn>) {
+	REGISTRY.set_fallback(free);
+}
+
+/// Frees `ptr` with the registered arena that owns it, or with the fallback function if no arena does.
+/// Does nothing if `ptr` is null.
+///
+/// # Safety
+///
+/// `ptr` must be null, or point to a live allocation of a registered arena or of the fallback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stalloc_free_any(ptr: *mut c_void) {
+	// SAFETY: Upheld by the caller.
+	unsafe { REGISTRY.free(ptr) };
+}
@@ -0,0 +1,130 @@
+use crate::align::{Align, Alignment};
+use crate::{CorruptionError, Stalloc, Strategy};
+
+/// The byte that guard blocks are filled with. This requires the `canaries` feature.
+///
+/// In an allocator created with `with_canaries()`, every allocation is surrounded by a guard block on each
+/// side, which is filled with this byte. The guard blocks are checked whenever the allocation is freed or
+/// resized, and by `check_canaries()`. An allocation of `size` blocks therefore takes up `size + 2` blocks.
+///
+/// # Examples
+/// ```
+/// use stalloc::{CANARY_BYTE, Stalloc};
+///
+/// let alloc = Stalloc::<10, 4>::new().with_canaries();
+/// let ptr = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+///
+/// // The blocks right before and after the allocation are guard blocks.
+/// assert_eq!(unsafe { ptr.sub(1).read() }, CANARY_BYTE);
+/// assert_eq!(unsafe { ptr.add(8).read() }, CANARY_BYTE);
+/// ```
+pub const CANARY_BYTE: u8 = 0xCA;
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns this allocator with every allocation surrounded by guard blocks, which are filled with
+	/// `CANARY_BYTE` and checked for overflows. This requires the `canaries` feature.
+	///
+	/// Each allocation takes up two more blocks, so the allocator holds fewer of them. Allocators that
+	/// aren't created with this function are unaffected by the feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<10, 4>::new().with_canaries();
+	///
+	/// // 8 blocks are left for the allocation, after the guard blocks on each side.
+	/// assert!(unsafe { alloc.allocate_blocks(9, 1) }.is_err());
+	/// let ptr = unsafe { alloc.allocate_blocks(8, 1) }.unwrap();
+	/// assert!(alloc.is_oom());
+	/// ```
+	#[must_use]
+	pub const fn with_canaries(mut self) -> Self {
+		self.canaries = true;
+		self
+	}
+
+	/// Checks the guard blocks of every live allocation, if the allocator was created with `with_canaries()`.
+	/// This requires the `canaries` feature. It runs in O(L * B).
+	///
+	/// # Errors
+	///
+	/// Returns `CorruptionError::BrokenCanary` for the first allocation whose guard blocks were overwritten.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{CorruptionError, Stalloc};
+	///
+	/// let alloc = Stalloc::<10, 4>::new().with_canaries();
+	/// let ptr = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// assert_eq!(alloc.check_canaries(), Ok(()));
+	///
+	/// // Write one byte past the end of the allocation.
+	/// unsafe { ptr.add(8).write(0) };
+	/// assert_eq!(alloc.check_canaries(), Err(CorruptionError::BrokenCanary { index: 1 }));
+	/// ```
+	pub fn check_canaries(&self) -> Result<(), CorruptionError> {
+		if !self.canaries {
+			return Ok(());
+		}
+
+		match self
+			.iter_allocations()
+			.find(|&(idx, size)| !self.canaries_intact(idx, size))
+		{
+			Some((index, _)) => Err(CorruptionError::BrokenCanary { index }),
+			None => Ok(()),
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns the guard blocks in front of and behind the allocation of `size` blocks at `idx`.
+	const fn guard_blocks_around(&self, idx: usize, size: usize) -> [*mut u8; 2] {
+		unsafe {
+			[
+				self.block_at(idx - self.guard_blocks()).cast(),
+				self.block_at(idx + size).cast(),
+			]
+		}
+	}
+
+	/// Fills the guard blocks around the allocation of `size` blocks at `idx` with `CANARY_BYTE`.
+	pub(crate) fn write_canaries(&self, idx: usize, size: usize) {
+		if !self.canaries {
+			return;
+		}
+
+		for guard in self.guard_blocks_around(idx, size) {
+			unsafe { guard.write_bytes(CANARY_BYTE, self.guard_blocks() * B) };
+		}
+	}
+
+	/// Checks whether the guard blocks around the allocation of `size` blocks at `idx` are intact.
+	fn canaries_intact(&self, idx: usize, size: usize) -> bool {
+		!self.canaries
+			|| self
+				.guard_blocks_around(idx, size)
+				.into_iter()
+				.all(|guard| {
+					let bytes =
+						unsafe { core::slice::from_raw_parts(guard, self.guard_blocks() * B) };
+					bytes.iter().all(|&byte| byte == CANARY_BYTE)
+				})
+	}
+
+	/// Panics if the guard blocks around the allocation of `size` blocks at `idx` were overwritten.
+	pub(crate) fn assert_canaries(&self, idx: usize, size: usize) {
+		assert!(
+			self.canaries_intact(idx, size),
+			"stalloc: the guard blocks of the allocation at index {idx} were overwritten"
+		);
+	}
+}
@@ -1,4 +1,10 @@
 use core::alloc::{GlobalAlloc, Layout};
+use core::fmt::{self, Debug, Display, Formatter};
+#[cfg(feature = "stats")]
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::AllocatorStats;
 
 /// A trait representing an allocator that another allocator can be chained to.
 ///
@@ -6,13 +12,49 @@ use core::alloc::{GlobalAlloc, Layout};
 /// `addr_in_bounds` must return true if and only if the address could belong to
 /// a pointer which is valid for the allocator. This trait is used to decide
 /// which allocator to call when the user calls `deallocate()` and related functions.
+///
+/// If `chain_aware()` returns true, the other hidden methods must behave like their
+/// `GlobalAlloc` counterparts, except that a failed allocation returns a null pointer
+/// instead of running an OOM handler or aborting.
 pub unsafe trait ChainableAlloc {
 	/// Checks whether a certain address is contained within the allocator. This
 	/// is called when using `deallocate()` and related functions in order to
 	/// determine which allocator needs to free the pointer.
 	fn addr_in_bounds(&self, addr: usize) -> bool;
+
+	/// Returns true if a chain can use this allocator through the methods below instead of `GlobalAlloc`.
+	/// A chain does so for every link that it can fall back from, so that a link that reports OOM through
+	/// `GlobalAlloc` doesn't do it before the next link has been tried.
+	#[doc(hidden)]
+	fn chain_aware(&self) -> bool {
+		false
+	}
+
+	/// Like `GlobalAlloc::alloc()`, or `GlobalAlloc::alloc_zeroed()` if `zeroed` is true.
+	#[doc(hidden)]
+	unsafe fn alloc_in_chain(&self, _layout: Layout, _zeroed: bool) -> *mut u8 {
+		unreachable!("alloc_in_chain() called on an allocator that isn't chain-aware")
+	}
+
+	/// Like `GlobalAlloc::realloc()`.
+	#[doc(hidden)]
+	unsafe fn realloc_in_chain(&self, _ptr: *mut u8, _layout: Layout, _new_size: usize) -> *mut u8 {
+		unreachable!("realloc_in_chain() called on an allocator that isn't chain-aware")
+	}
+
+	/// Like `GlobalAlloc::dealloc()`.
+	#[doc(hidden)]
+	unsafe fn dealloc_in_chain(&self, _ptr: *mut u8, _layout: Layout) {
+		unreachable!("dealloc_in_chain() called on an allocator that isn't chain-aware")
+	}
 }
 
+/// A function that is called when an allocation falls through to the next link of a chain.
+///
+/// It receives the layout that the first link couldn't allocate. The handler runs in the middle of an
+/// allocation, so if the chain is the global allocator, it must not allocate.
+pub type FallbackHandler = fn(Layout);
+
 /// A chain of allocators. If the first allocator is exhuasted, the second one is used as a fallback.
 ///
 /// # Examples
@@ -28,12 +70,165 @@ pub unsafe trait ChainableAlloc {
 ///     .chain(&Stalloc::<8192, 16>::new())
 ///     .chain(&System);
 /// ```
-pub struct AllocChain<'a, A, B>(A, &'a B);
+pub struct AllocChain<'a, A, B>(pub(crate) A, pub(crate) &'a B, ChainState);
 
 impl<'a, A, B> AllocChain<'a, A, B> {
 	/// Initializes a new `AllocChain`.
 	pub const fn new(a: A, b: &'a B) -> Self {
-		Self(a, b)
+		Self(a, b, ChainState::new())
+	}
+
+	/// Creates a new `AllocChain` containing this chain and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this chain and `next`.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+
+	/// Sets whether an allocation in the fallback allocator is moved back into the first one when it is
+	/// reallocated, if it fits there again. This is off by default.
+	///
+	/// Without this, an allocation that spilled into the fallback stays there until it is freed, even after
+	/// the first allocator has room again. Enabling it repopulates the first allocator once the pressure
+	/// subsides, at the cost of trying the first allocator on every reallocation in the fallback.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	/// use std::alloc::{GlobalAlloc, Layout};
+	///
+	/// let second = SyncStalloc::<32, 4>::new();
+	/// let chain = SyncStalloc::<4, 4>::new().chain(&second).migrate_back(true);
+	/// let layout = Layout::new::<[u8; 64]>();
+	///
+	/// unsafe {
+	///     // This doesn't fit in the first allocator, so it spills into `second`.
+	///     let ptr = chain.alloc(layout);
+	///     assert!(!second.is_empty());
+	///
+	///     // After shrinking, the allocation is moved back.
+	///     let ptr = chain.realloc(ptr, layout, 8);
+	///     assert!(second.is_empty());
+	///
+	///     chain.dealloc(ptr, Layout::new::<[u8; 8]>());
+	/// }
+	/// ```
+	#[must_use]
+	pub const fn migrate_back(mut self, enabled: bool) -> Self {
+		self.2.migrate_back = enabled;
+		self
+	}
+
+	/// Returns this chain with `handler` registered as its fallback handler, which is called when an
+	/// allocation falls through to the second allocator. If `every_time` is false, it is only called the
+	/// first time that happens.
+	///
+	/// With the `log` feature, every fallback is also logged at the debug level.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	/// use std::alloc::{GlobalAlloc, Layout, System};
+	/// use std::sync::atomic::{AtomicUsize, Ordering};
+	///
+	/// static SPILLED: AtomicUsize = AtomicUsize::new(0);
+	///
+	/// fn on_fallback(layout: Layout) {
+	///     SPILLED.fetch_add(layout.size(), Ordering::Relaxed);
+	/// }
+	///
+	/// let chain = SyncStalloc::<4, 4>::new()
+	///     .chain(&System)
+	///     .with_fallback_handler(on_fallback, true);
+	/// let layout = Layout::new::<[u8; 64]>();
+	///
+	/// unsafe {
+	///     let ptr = chain.alloc(layout);
+	///     assert_eq!(SPILLED.load(Ordering::Relaxed), 64);
+	///     chain.dealloc(ptr, layout);
+	/// }
+	/// ```
+	#[must_use]
+	pub const fn with_fallback_handler(
+		mut self,
+		handler: FallbackHandler,
+		every_time: bool,
+	) -> Self {
+		self.2.fallback_handler = Some(handler);
+		self.2.every_fallback = every_time;
+		self
+	}
+
+	/// Returns how many allocations each link of the chain has served, and how many bytes are live in each.
+	/// This requires the `stats` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	/// use std::alloc::{GlobalAlloc, Layout, System};
+	///
+	/// let chain = SyncStalloc::<4, 4>::new().chain(&System);
+	/// let (small, large) = (Layout::new::<[u8; 8]>(), Layout::new::<[u8; 64]>());
+	///
+	/// unsafe {
+	///     let a = chain.alloc(small);
+	///     let b = chain.alloc(large);
+	///
+	///     let stats = chain.chain_stats();
+	///     assert_eq!((stats.first.allocations, stats.first.live_bytes), (1, 8));
+	///     assert_eq!((stats.second.allocations, stats.second.live_bytes), (1, 64));
+	///
+	///     chain.dealloc(a, small);
+	///     chain.dealloc(b, large);
+	/// }
+	///
+	/// assert_eq!(chain.chain_stats().second.live_bytes, 0);
+	/// ```
+	#[cfg(feature = "stats")]
+	pub fn chain_stats(&self) -> ChainStats {
+		self.2.snapshot()
+	}
+
+	/// Borrows both links of the chain.
+	const fn links(&self) -> Links<'_, A, B> {
+		Links(&self.0, self.1, &self.2)
+	}
+}
+
+/// A chain of allocators that owns its fallback allocator, unlike `AllocChain`, which borrows it.
+///
+/// Since nothing is borrowed, the whole chain can be built in a `static`, without declaring the
+/// fallback allocator in a separate one.
+///
+/// # Examples
+/// ```
+/// use stalloc::{OwnedAllocChain, SyncStalloc};
+/// use std::alloc::System;
+///
+/// // If the `SyncStalloc` is full, fall back to the system allocator.
+/// #[global_allocator]
+/// static GLOBAL: OwnedAllocChain<SyncStalloc<1024, 8>, System> = SyncStalloc::new().chain_owned(System);
+///
+/// fn main() {
+///     let v = vec![1, 2, 3];
+///     let big = vec![0u8; 100_000];
+/// }
+/// ```
+pub struct OwnedAllocChain<A, B>(pub(crate) A, pub(crate) B, ChainState);
+
+impl<A, B> OwnedAllocChain<A, B> {
+	/// Initializes a new `OwnedAllocChain`.
+	pub const fn new(a: A, b: B) -> Self {
+		Self(a, b, ChainState::new())
 	}
 
 	/// Creates a new `AllocChain` containing this chain and `next`.
@@ -43,49 +238,650 @@ impl<'a, A, B> AllocChain<'a, A, B> {
 	{
 		AllocChain::new(self, next)
 	}
+
+	/// Creates a new `OwnedAllocChain` containing this chain and `next`.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+
+	/// Sets whether an allocation in the fallback allocator is moved back into the first one when it is
+	/// reallocated. See `AllocChain::migrate_back()` for details.
+	#[must_use]
+	pub const fn migrate_back(mut self, enabled: bool) -> Self {
+		self.2.migrate_back = enabled;
+		self
+	}
+
+	/// Returns this chain with `handler` registered as its fallback handler.
+	/// See `AllocChain::with_fallback_handler()` for details.
+	#[must_use]
+	pub const fn with_fallback_handler(
+		mut self,
+		handler: FallbackHandler,
+		every_time: bool,
+	) -> Self {
+		self.2.fallback_handler = Some(handler);
+		self.2.every_fallback = every_time;
+		self
+	}
+
+	/// Returns how many allocations each link of the chain has served, and how many bytes are live in each.
+	/// This requires the `stats` feature. See `AllocChain::chain_stats()` for details.
+	#[cfg(feature = "stats")]
+	pub fn chain_stats(&self) -> ChainStats {
+		self.2.snapshot()
+	}
+
+	/// Borrows both links of the chain.
+	const fn links(&self) -> Links<'_, A, B> {
+		Links(&self.0, &self.1, &self.2)
+	}
+}
+
+/// Both links of a chain and their counters, borrowed. `AllocChain` and `OwnedAllocChain` only differ in
+/// how they store the second link, so they are both implemented in terms of this.
+struct Links<'a, A, B>(&'a A, &'a B, &'a ChainState);
+
+/// The traffic that went to one link of a chain. This requires the `stats` feature.
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkStats {
+	/// The number of allocations that this link has served, including allocations that were moved to it
+	/// by a reallocation.
+	pub allocations: usize,
+	/// The number of bytes that are currently allocated from this link, as requested by the layouts.
+	pub live_bytes: usize,
+}
+
+/// The traffic that went to each link of an `AllocChain` or `OwnedAllocChain`, as returned by
+/// `chain_stats()`. This requires the `stats` feature.
+///
+/// The first link of a longer chain is itself a chain, so its own `chain_stats()` breaks it down further.
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChainStats {
+	/// The traffic that went to the first allocator.
+	pub first: LinkStats,
+	/// The traffic that spilled into the fallback allocator.
+	pub second: LinkStats,
+}
+
+/// The settings of a chain, and the counters behind `ChainStats`, indexed by link.
+struct ChainState {
+	migrate_back: bool,
+	fallback_handler: Option<FallbackHandler>,
+	/// Whether the handler is called on every fallback, rather than only the first one.
+	every_fallback: bool,
+	fell_back: AtomicBool,
+	#[cfg(feature = "stats")]
+	allocations: [AtomicUsize; 2],
+	#[cfg(feature = "stats")]
+	live_bytes: [AtomicUsize; 2],
+}
+
+impl ChainState {
+	const fn new() -> Self {
+		Self {
+			migrate_back: false,
+			fallback_handler: None,
+			every_fallback: false,
+			fell_back: AtomicBool::new(false),
+			#[cfg(feature = "stats")]
+			allocations: [AtomicUsize::new(0), AtomicUsize::new(0)],
+			#[cfg(feature = "stats")]
+			live_bytes: [AtomicUsize::new(0), AtomicUsize::new(0)],
+		}
+	}
+
+	/// Reports that an allocation of `layout` is being passed on to the next allocator in the chain.
+	fn fallback(&self, layout: Layout) {
+		log_fallback(layout);
+
+		if let Some(handler) = self.fallback_handler
+			&& (self.every_fallback || !self.fell_back.swap(true, Ordering::Relaxed))
+		{
+			handler(layout);
+		}
+	}
+
+	/// Records a new allocation of `size` bytes in `link`.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn alloc(&self, link: usize, size: usize) {
+		#[cfg(feature = "stats")]
+		self.allocations[link].fetch_add(1, Ordering::Relaxed);
+		#[cfg(feature = "stats")]
+		self.live_bytes[link].fetch_add(size, Ordering::Relaxed);
+	}
+
+	/// Records that an allocation of `size` bytes in `link` was freed.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn dealloc(&self, link: usize, size: usize) {
+		#[cfg(feature = "stats")]
+		self.live_bytes[link].fetch_sub(size, Ordering::Relaxed);
+	}
+
+	/// Records that an allocation in `link` was resized in place from `old_size` to `new_size` bytes.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn resize(&self, link: usize, old_size: usize, new_size: usize) {
+		#[cfg(feature = "stats")]
+		self.live_bytes[link].fetch_sub(old_size, Ordering::Relaxed);
+		#[cfg(feature = "stats")]
+		self.live_bytes[link].fetch_add(new_size, Ordering::Relaxed);
+	}
+
+	#[cfg(feature = "stats")]
+	fn snapshot(&self) -> ChainStats {
+		let link = |i: usize| LinkStats {
+			allocations: self.allocations[i].load(Ordering::Relaxed),
+			live_bytes: self.live_bytes[i].load(Ordering::Relaxed),
+		};
+
+		ChainStats {
+			first: link(0),
+			second: link(1),
+		}
+	}
+}
+
+/// A chain contains an address if either of its links does, so it can be the first link of another chain.
+///
+/// # Examples
+/// ```
+/// use stalloc::SyncStalloc;
+/// use std::alloc::{GlobalAlloc, Layout, System};
+///
+/// let second = SyncStalloc::<16, 4>::new();
+/// let chain = SyncStalloc::<4, 4>::new().chain(&second).chain(&System);
+///
+/// let layout = Layout::new::<[u8; 32]>();
+/// unsafe {
+///     // This spills into `second`, and is freed there since the inner chain contains it.
+///     let ptr = chain.alloc(layout);
+///     assert!(!second.is_empty());
+///     chain.dealloc(ptr, layout);
+/// }
+///
+/// assert!(second.is_empty());
+/// ```
+unsafe impl<A: ChainableAlloc, B: ChainableAlloc> ChainableAlloc for AllocChain<'_, A, B> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.0.addr_in_bounds(addr) || self.1.addr_in_bounds(addr)
+	}
+
+	/// A chain used as the first link of another chain only reports a failure once both of its own links
+	/// have failed, and leaves it to the outer chain to fall back.
+	fn chain_aware(&self) -> bool {
+		self.0.chain_aware() && self.1.chain_aware()
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.links().alloc_in_chain(layout, zeroed) }
+	}
+
+	unsafe fn realloc_in_chain(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.links().realloc_in_chain(ptr, layout, new_size) }
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.links().dealloc_in_chain(ptr, layout) };
+	}
+}
+
+/// A chain contains an address if either of its links does, like `AllocChain`.
+unsafe impl<A: ChainableAlloc, B: ChainableAlloc> ChainableAlloc for OwnedAllocChain<A, B> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.0.addr_in_bounds(addr) || self.1.addr_in_bounds(addr)
+	}
+
+	/// A chain used as the first link of another chain only reports a failure once both of its own links
+	/// have failed, and leaves it to the outer chain to fall back.
+	fn chain_aware(&self) -> bool {
+		self.0.chain_aware() && self.1.chain_aware()
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.links().alloc_in_chain(layout, zeroed) }
+	}
+
+	unsafe fn realloc_in_chain(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.links().realloc_in_chain(ptr, layout, new_size) }
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.links().dealloc_in_chain(ptr, layout) };
+	}
+}
+
+/// Prints the summary of the first allocator. The fallback allocator is only mentioned, since it
+/// doesn't necessarily implement `Display` (the system allocator doesn't).
+impl<A: Display, B> Display for AllocChain<'_, A, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{} (with fallback)", self.0)
+	}
+}
+
+/// Prints the summary of the first allocator, like `AllocChain`.
+impl<A: Display, B> Display for OwnedAllocChain<A, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{} (with fallback)", self.0)
+	}
+}
+
+/// Prints both links, along with the `ChainStats` of the chain if the `stats` feature is enabled.
+///
+/// # Examples
+/// ```
+/// use stalloc::SyncStalloc;
+/// use std::alloc::System;
+///
+/// let chain = SyncStalloc::<4, 4>::new().chain(&System);
+/// assert!(format!("{chain:?}").starts_with("AllocChain { first: "));
+/// ```
+impl<A: Debug, B: Debug> Debug for AllocChain<'_, A, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		self.links().fmt_debug(f, "AllocChain")
+	}
+}
+
+/// Prints both links, along with the `ChainStats` of the chain, like `AllocChain`.
+impl<A: Debug, B: Debug> Debug for OwnedAllocChain<A, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		self.links().fmt_debug(f, "OwnedAllocChain")
+	}
+}
+
+impl<A: Debug, B: Debug> Links<'_, A, B> {
+	fn fmt_debug(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+		let mut s = f.debug_struct(name);
+		s.field("first", self.0).field("second", self.1);
+		#[cfg(feature = "stats")]
+		s.field("stats", &self.2.snapshot());
+		s.finish()
+	}
+}
+
+impl<A: AllocatorStats, B: AllocatorStats> AllocatorStats for Links<'_, A, B> {
+	fn capacity_bytes(&self) -> usize {
+		self.0
+			.capacity_bytes()
+			.saturating_add(self.1.capacity_bytes())
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.0.used_bytes() + self.1.used_bytes()
+	}
+
+	fn free_bytes(&self) -> usize {
+		self.0.free_bytes().saturating_add(self.1.free_bytes())
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		Some(self.0.peak_used_bytes()? + self.1.peak_used_bytes()?)
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		Some(self.0.allocations()?.wrapping_add(self.1.allocations()?))
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		Some(
+			self.0
+				.deallocations()?
+				.wrapping_add(self.1.deallocations()?),
+		)
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		Some(
+			self.0
+				.failed_allocations()?
+				.wrapping_add(self.1.failed_allocations()?),
+		)
+	}
+}
+
+/// Forwards every method of `AllocatorStats`, `GlobalAlloc` and `Allocator` for a chain type to `Links`.
+macro_rules! forward_to_links {
+	($($chain:tt)*) => {
+		/// Adds up the statistics of every allocator in the chain.
+		///
+		/// A failed allocation in one link is usually satisfied by the next one, so `failed_allocations()`
+		/// counts fallbacks as well as allocations that failed for the whole chain. Likewise,
+		/// `peak_used_bytes()` is the sum of the peaks of each link, which may be higher than the peak of
+		/// the chain as a whole.
+		impl<A: AllocatorStats, B: AllocatorStats> AllocatorStats for $($chain)* {
+			fn capacity_bytes(&self) -> usize {
+				self.links().capacity_bytes()
+			}
+
+			fn used_bytes(&self) -> usize {
+				self.links().used_bytes()
+			}
+
+			fn free_bytes(&self) -> usize {
+				self.links().free_bytes()
+			}
+
+			fn peak_used_bytes(&self) -> Option<usize> {
+				self.links().peak_used_bytes()
+			}
+
+			fn allocations(&self) -> Option<usize> {
+				self.links().allocations()
+			}
+
+			fn deallocations(&self) -> Option<usize> {
+				self.links().deallocations()
+			}
+
+			fn failed_allocations(&self) -> Option<usize> {
+				self.links().failed_allocations()
+			}
+		}
+
+		unsafe impl<A: GlobalAlloc + ChainableAlloc, B: GlobalAlloc> GlobalAlloc for $($chain)* {
+			unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().alloc(layout, false) }
+			}
+
+			unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().alloc(layout, true) }
+			}
+
+			unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().dealloc(ptr, layout) };
+			}
+
+			unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().realloc(ptr, layout, new_size) }
+			}
+		}
+
+		#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+		unsafe impl<A: ChainableAlloc, B> Allocator for &$($chain)*
+		where
+			for<'a> &'a A: Allocator,
+			for<'a> &'a B: Allocator,
+		{
+			fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+				self.links().allocate(layout, false)
+			}
+
+			fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+				self.links().allocate(layout, true)
+			}
+
+			unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().deallocate(ptr, layout) };
+			}
+
+			unsafe fn grow(
+				&self,
+				ptr: NonNull<u8>,
+				old_layout: Layout,
+				new_layout: Layout,
+			) -> Result<NonNull<[u8]>, AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().grow(ptr, old_layout, new_layout, false) }
+			}
+
+			unsafe fn grow_zeroed(
+				&self,
+				ptr: NonNull<u8>,
+				old_layout: Layout,
+				new_layout: Layout,
+			) -> Result<NonNull<[u8]>, AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().grow(ptr, old_layout, new_layout, true) }
+			}
+
+			unsafe fn shrink(
+				&self,
+				ptr: NonNull<u8>,
+				old_layout: Layout,
+				new_layout: Layout,
+			) -> Result<NonNull<[u8]>, AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.links().shrink(ptr, old_layout, new_layout) }
+			}
+
+			fn by_ref(&self) -> &Self
+			where
+				Self: Sized,
+			{
+				self
+			}
+		}
+	};
+}
+
+forward_to_links!(AllocChain<'_, A, B>);
+forward_to_links!(OwnedAllocChain<A, B>);
+
+/// Logs that an allocation of `layout` is being passed on to the next allocator in the chain.
+#[allow(unused_variables, clippy::missing_const_for_fn)]
+fn log_fallback(layout: Layout) {
+	#[cfg(feature = "log")]
+	log::debug!(
+		"stalloc: falling back to the next allocator for {} bytes aligned to {}",
+		layout.size(),
+		layout.align()
+	);
+}
+
+/// The operations that a chain needs from one of its links, without going through `Allocator`.
+trait RawLink {
+	unsafe fn alloc(&self, layout: Layout, zeroed: bool) -> *mut u8;
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8;
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout);
+}
+
+/// Uses a link through `GlobalAlloc`, which is how the last link of the outermost chain is used.
+struct Global<'a, T>(&'a T);
+
+/// Uses a link through its chain-aware methods if it has them, and through `GlobalAlloc` otherwise.
+struct Fallible<'a, T>(&'a T);
+
+/// Uses a link through its chain-aware methods only.
+struct InChain<'a, T>(&'a T);
+
+impl<T: GlobalAlloc> RawLink for Global<'_, T> {
+	unsafe fn alloc(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		unsafe {
+			if zeroed {
+				self.0.alloc_zeroed(layout)
+			} else {
+				self.0.alloc(layout)
+			}
+		}
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		unsafe { self.0.realloc(ptr, layout, new_size) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { self.0.dealloc(ptr, layout) };
+	}
 }
 
-unsafe impl<A: GlobalAlloc + ChainableAlloc, B: GlobalAlloc> GlobalAlloc for AllocChain<'_, A, B> {
-	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-		let ptr_a = unsafe { self.0.alloc(layout) };
+impl<T: GlobalAlloc + ChainableAlloc> RawLink for Fallible<'_, T> {
+	unsafe fn alloc(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		unsafe {
+			if self.0.chain_aware() {
+				self.0.alloc_in_chain(layout, zeroed)
+			} else {
+				Global(self.0).alloc(layout, zeroed)
+			}
+		}
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		unsafe {
+			if self.0.chain_aware() {
+				self.0.realloc_in_chain(ptr, layout, new_size)
+			} else {
+				self.0.realloc(ptr, layout, new_size)
+			}
+		}
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { self.0.dealloc(ptr, layout) };
+	}
+}
+
+impl<T: ChainableAlloc> RawLink for InChain<'_, T> {
+	unsafe fn alloc(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		unsafe { self.0.alloc_in_chain(layout, zeroed) }
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		unsafe { self.0.realloc_in_chain(ptr, layout, new_size) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { self.0.dealloc_in_chain(ptr, layout) };
+	}
+}
+
+impl<A: GlobalAlloc + ChainableAlloc, B: GlobalAlloc> Links<'_, A, B> {
+	/// Allocates from `A`, and falls back to `B`. If `zeroed` is true, each link is asked for zeroed memory,
+	/// so an allocator that gets zeroed pages for free doesn't have to clear them.
+	unsafe fn alloc(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		unsafe { self.alloc_via(&Fallible(self.0), &Global(self.1), layout, zeroed) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { self.dealloc_via(&Fallible(self.0), &Global(self.1), ptr, layout) };
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		unsafe { self.realloc_via(&Fallible(self.0), &Global(self.1), ptr, layout, new_size) }
+	}
+}
+
+impl<A: ChainableAlloc, B: ChainableAlloc> Links<'_, A, B> {
+	/// Like `alloc()`, but `B` is asked through `alloc_in_chain()` too, so that if both links fail, the chain
+	/// that contains this one can still fall back. Only called if both links are chain-aware.
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		unsafe { self.alloc_via(&InChain(self.0), &InChain(self.1), layout, zeroed) }
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { self.dealloc_via(&InChain(self.0), &InChain(self.1), ptr, layout) };
+	}
+
+	unsafe fn realloc_in_chain(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		unsafe { self.realloc_via(&InChain(self.0), &InChain(self.1), ptr, layout, new_size) }
+	}
+}
+
+impl<A: ChainableAlloc, B> Links<'_, A, B> {
+	/// Allocates from `A` through `a`, and falls back to `B` through `b`.
+	unsafe fn alloc_via(
+		&self,
+		a: &impl RawLink,
+		b: &impl RawLink,
+		layout: Layout,
+		zeroed: bool,
+	) -> *mut u8 {
+		let ptr_a = unsafe { a.alloc(layout, zeroed) };
+
 		if ptr_a.is_null() {
-			unsafe { self.1.alloc(layout) }
+			self.2.fallback(layout);
+			let ptr_b = unsafe { b.alloc(layout, zeroed) };
+			if !ptr_b.is_null() {
+				self.2.alloc(1, layout.size());
+			}
+			ptr_b
 		} else {
+			self.2.alloc(0, layout.size());
 			ptr_a
 		}
 	}
 
-	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+	unsafe fn dealloc_via(&self, a: &impl RawLink, b: &impl RawLink, ptr: *mut u8, layout: Layout) {
 		if self.0.addr_in_bounds(ptr.addr()) {
-			unsafe { self.0.dealloc(ptr, layout) };
+			unsafe { a.dealloc(ptr, layout) };
+			self.2.dealloc(0, layout.size());
 		} else {
-			unsafe { self.1.dealloc(ptr, layout) };
+			unsafe { b.dealloc(ptr, layout) };
+			self.2.dealloc(1, layout.size());
 		}
 	}
 
-	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+	/// Reallocates in the link that owns `ptr`, through `a` or `b`.
+	unsafe fn realloc_via(
+		&self,
+		a: &impl RawLink,
+		b: &impl RawLink,
+		ptr: *mut u8,
+		layout: Layout,
+		new_size: usize,
+	) -> *mut u8 {
 		if self.0.addr_in_bounds(ptr.addr()) {
-			let ptr_a = unsafe { self.0.realloc(ptr, layout, new_size) };
+			let ptr_a = unsafe { a.realloc(ptr, layout, new_size) };
 			if !ptr_a.is_null() {
+				self.2.resize(0, layout.size(), new_size);
 				return ptr_a;
 			}
 
 			let layout_b = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
-			let ptr_b = unsafe { self.1.alloc(layout_b) };
+			self.2.fallback(layout_b);
+			let ptr_b = unsafe { b.alloc(layout_b, false) };
 
 			if !ptr_b.is_null() {
 				// Copy the allocation from `A` to `B`.
 				unsafe {
 					ptr.copy_to_nonoverlapping(ptr_b, layout.size());
-					self.0.dealloc(ptr, layout);
+					a.dealloc(ptr, layout);
 				}
+				self.2.dealloc(0, layout.size());
+				self.2.alloc(1, new_size);
 			}
 
 			// This is either a valid pointer or null.
 			ptr_b
 		} else {
-			unsafe { self.1.realloc(ptr, layout, new_size) }
+			if self.2.migrate_back {
+				let layout_a =
+					unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+				let ptr_a = unsafe { a.alloc(layout_a, false) };
+
+				if !ptr_a.is_null() {
+					// Move the allocation back from `B` to `A`.
+					unsafe {
+						ptr.copy_to_nonoverlapping(ptr_a, layout.size().min(new_size));
+						b.dealloc(ptr, layout);
+					}
+					self.2.dealloc(1, layout.size());
+					self.2.alloc(0, new_size);
+					return ptr_a;
+				}
+			}
+
+			let ptr_b = unsafe { b.realloc(ptr, layout, new_size) };
+			if !ptr_b.is_null() {
+				self.2.resize(1, layout.size(), new_size);
+			}
+
 			// Don't fall back to `A`.
+			ptr_b
 		}
 	}
 }
@@ -97,72 +893,131 @@ use {
 };
 
 #[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
-unsafe impl<A: ChainableAlloc, B> Allocator for &AllocChain<'_, A, B>
+impl<A: ChainableAlloc, B> Links<'_, A, B>
 where
 	for<'a> &'a A: Allocator,
 	for<'a> &'a B: Allocator,
 {
-	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-		(&self.0)
-			.allocate(layout)
-			.or_else(|_| self.1.allocate(layout))
+	/// Allocates from `A`, and falls back to `B`. If `zeroed` is true, each link is asked for zeroed memory.
+	fn allocate(&self, layout: Layout, zeroed: bool) -> Result<NonNull<[u8]>, AllocError> {
+		let res_a = if zeroed {
+			self.0.allocate_zeroed(layout)
+		} else {
+			self.0.allocate(layout)
+		};
+		if res_a.is_ok() {
+			self.2.alloc(0, layout.size());
+			return res_a;
+		}
+
+		self.2.fallback(layout);
+		let res_b = if zeroed {
+			self.1.allocate_zeroed(layout)
+		} else {
+			self.1.allocate(layout)
+		};
+		if res_b.is_ok() {
+			self.2.alloc(1, layout.size());
+		}
+		res_b
 	}
 
 	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
 		if self.0.addr_in_bounds(ptr.addr().into()) {
-			unsafe { (&self.0).deallocate(ptr, layout) };
+			unsafe { self.0.deallocate(ptr, layout) };
+			self.2.dealloc(0, layout.size());
 		} else {
 			unsafe { self.1.deallocate(ptr, layout) }
+			self.2.dealloc(1, layout.size());
 		}
 	}
 
+	/// Grows an allocation in the link that owns it, or moves it to another link. If `zeroed` is true,
+	/// the new memory is zeroed by the link that provides it.
 	unsafe fn grow(
 		&self,
 		ptr: NonNull<u8>,
 		old_layout: Layout,
 		new_layout: Layout,
+		zeroed: bool,
 	) -> Result<NonNull<[u8]>, AllocError> {
 		if self.0.addr_in_bounds(ptr.addr().into()) {
-			let res_a = unsafe { (&self.0).grow(ptr, old_layout, new_layout) };
+			let res_a = unsafe {
+				if zeroed {
+					self.0.grow_zeroed(ptr, old_layout, new_layout)
+				} else {
+					self.0.grow(ptr, old_layout, new_layout)
+				}
+			};
 			if res_a.is_ok() {
+				self.2.resize(0, old_layout.size(), new_layout.size());
 				return res_a;
 			}
 
-			let res_b = self.1.allocate(new_layout);
+			self.2.fallback(new_layout);
+			let res_b = if zeroed {
+				self.1.allocate_zeroed(new_layout)
+			} else {
+				self.1.allocate(new_layout)
+			};
 			if let Ok(ptr_b) = res_b {
 				// Copy the allocation from `A` to `B`.
 				unsafe {
 					ptr.copy_to_nonoverlapping(ptr_b.cast(), old_layout.size());
-					(&self.0).deallocate(ptr, old_layout);
+					self.0.deallocate(ptr, old_layout);
 				}
+				self.2.dealloc(0, old_layout.size());
+				self.2.alloc(1, new_layout.size());
 			}
 
 			res_b
 		} else {
-			unsafe { self.1.grow(ptr, old_layout, new_layout) }
+			if let Some(ptr_a) = unsafe { self.migrate_back(ptr, old_layout, new_layout, zeroed) } {
+				return Ok(ptr_a);
+			}
+
+			let res_b = unsafe {
+				if zeroed {
+					self.1.grow_zeroed(ptr, old_layout, new_layout)
+				} else {
+					self.1.grow(ptr, old_layout, new_layout)
+				}
+			};
+			if res_b.is_ok() {
+				self.2.resize(1, old_layout.size(), new_layout.size());
+			}
+
 			// Don't fall back to `A`.
+			res_b
 		}
 	}
 
-	unsafe fn grow_zeroed(
+	/// Moves an allocation from `B` back into `A` if that is enabled and it fits, and otherwise returns
+	/// `None`. If `zeroed` is true, the memory after the old allocation is zeroed.
+	unsafe fn migrate_back(
 		&self,
 		ptr: NonNull<u8>,
 		old_layout: Layout,
 		new_layout: Layout,
-	) -> Result<NonNull<[u8]>, AllocError> {
-		unsafe {
-			// SAFETY: Upheld by the caller.
-			let new_ptr = self.grow(ptr, old_layout, new_layout)?;
-			let count = new_ptr.len() - old_layout.size();
-
-			// SAFETY: We are filling in the extra capacity with zeros.
-			new_ptr
-				.cast::<u8>()
-				.add(old_layout.size())
-				.write_bytes(0, count);
+		zeroed: bool,
+	) -> Option<NonNull<[u8]>> {
+		if !self.2.migrate_back {
+			return None;
+		}
 
-			Ok(new_ptr)
+		let ptr_a = if zeroed {
+			self.0.allocate_zeroed(new_layout)
+		} else {
+			self.0.allocate(new_layout)
 		}
+		.ok()?;
+		unsafe {
+			ptr.copy_to_nonoverlapping(ptr_a.cast(), old_layout.size().min(new_layout.size()));
+			self.1.deallocate(ptr, old_layout);
+		}
+		self.2.dealloc(1, old_layout.size());
+		self.2.alloc(0, new_layout.size());
+		Some(ptr_a)
 	}
 
 	unsafe fn shrink(
@@ -172,31 +1027,37 @@ where
 		new_layout: Layout,
 	) -> Result<NonNull<[u8]>, AllocError> {
 		if self.0.addr_in_bounds(ptr.addr().into()) {
-			let res_a = unsafe { (&self.0).shrink(ptr, old_layout, new_layout) };
+			let res_a = unsafe { self.0.shrink(ptr, old_layout, new_layout) };
 			if res_a.is_ok() {
+				self.2.resize(0, old_layout.size(), new_layout.size());
 				return res_a;
 			}
 
+			self.2.fallback(new_layout);
 			let res_b = self.1.allocate(new_layout);
 			if let Ok(ptr_b) = res_b {
 				// Copy the allocation from `A` to `B`.
 				unsafe {
-					ptr.copy_to_nonoverlapping(ptr_b.cast(), old_layout.size());
-					(&self.0).deallocate(ptr, old_layout);
+					ptr.copy_to_nonoverlapping(ptr_b.cast(), new_layout.size());
+					self.0.deallocate(ptr, old_layout);
 				}
+				self.2.dealloc(0, old_layout.size());
+				self.2.alloc(1, new_layout.size());
 			}
 
 			res_b
 		} else {
-			unsafe { self.1.shrink(ptr, old_layout, new_layout) }
+			if let Some(ptr_a) = unsafe { self.migrate_back(ptr, old_layout, new_layout, false) } {
+				return Ok(ptr_a);
+			}
+
+			let res_b = unsafe { self.1.shrink(ptr, old_layout, new_layout) };
+			if res_b.is_ok() {
+				self.2.resize(1, old_layout.size(), new_layout.size());
+			}
+
 			// Don't fall back to `A`.
+			res_b
 		}
 	}
-
-	fn by_ref(&self) -> &Self
-	where
-		Self: Sized,
-	{
-		self
-	}
 }
@@ -0,0 +1,1517 @@
+//! Collections that store their contents in a stalloc allocator, and work on stable Rust without
+//! the nightly allocator API.
+//!
+//! `Box`, `Vec` and `String` behave like their counterparts in the standard library, but borrow the
+//! allocator they were created with. Growing a `Vec` or a `String` goes through `reallocate_blocks()`,
+//! so it happens in place whenever the blocks after (or before) the buffer are free. `SmallVec` keeps
+//! its first few elements inline, and only uses the allocator once it grows beyond them. `RingBuffer`
+//! is a bounded queue that allocates its buffer once, and never allocates when elements are pushed.
+//!
+//! Methods that allocate panic if the allocator runs out of memory, like in the standard library,
+//! and each of them has a `try_` variant that returns `AllocError` instead.
+//!
+//! # Examples
+//! ```
+//! use stalloc::Stalloc;
+//! use stalloc::collections::{Box, String, Vec};
+//!
+//! let alloc = Stalloc::<64, 8>::new();
+//!
+//! let b = Box::new_in(42u64, &alloc);
+//! assert_eq!(*b, 42);
+//!
+//! let mut v = Vec::new_in(&alloc);
+//! v.extend([1, 2, 3]);
+//! assert_eq!(v, [1, 2, 3]);
+//!
+//! let mut s = String::new_in(&alloc);
+//! s.push_str("Hello, ");
+//! s.push_str("world!");
+//! assert_eq!(s, "Hello, world!");
+//! ```
+
+use core::borrow::{Borrow, BorrowMut};
+use core::fmt::{self, Debug, Display, Formatter};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy, SubStalloc, UnsafeStalloc};
+
+/// An allocator that the collections in this module can store their contents in.
+///
+/// # Safety
+///
+/// The methods must behave like the raw block API of `Stalloc`: allocations are made in units of
+/// `BLOCK_SIZE` bytes, every successful allocation is valid and disjoint from all other live allocations,
+/// and `reallocate_blocks()` preserves the contents of the allocation.
+pub unsafe trait CollectionAlloc {
+	/// The size of a block in bytes.
+	const BLOCK_SIZE: usize;
+
+	/// Tries to allocate `size` blocks. See `Stalloc::allocate_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / BLOCK_SIZE`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError>;
+
+	/// Deallocates a pointer. See `Stalloc::deallocate_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to an allocation of `size` blocks made by this allocator.
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize);
+
+	/// Resizes an allocation, moving it if necessary. See `Stalloc::reallocate_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to an allocation of `old_size` blocks made by this allocator. `new_size` must be nonzero,
+	/// and `align` must be a power of 2 in the range `1..=MAX_ALIGN / BLOCK_SIZE`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+	/// in which case `ptr` is still valid.
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError>;
+}
+
+unsafe impl<const L: usize, const B: usize, S: Strategy> CollectionAlloc for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+		unsafe { self.allocate_blocks(size, align) }
+	}
+
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		unsafe { self.reallocate_blocks(ptr, old_size, new_size, align) }
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> CollectionAlloc for UnsafeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+		unsafe { (**self).allocate_blocks(size, align) }
+	}
+
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		unsafe { (**self).deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		unsafe { (**self).reallocate_blocks(ptr, old_size, new_size, align) }
+	}
+}
+
+#[cfg(feature = "std")]
+unsafe impl<const L: usize, const B: usize> CollectionAlloc for crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+		unsafe { self.allocate_blocks(size, align) }
+	}
+
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		unsafe { self.reallocate_blocks(ptr, old_size, new_size, align) }
+	}
+}
+
+unsafe impl<const B: usize> CollectionAlloc for SubStalloc<'_, B>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+		unsafe { self.allocate_blocks(size, align) }
+	}
+
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// A `SubStalloc` can't resize in place, so always allocate and copy.
+		unsafe {
+			let new = self.allocate_blocks(new_size, align)?;
+			ptr.copy_to_nonoverlapping(new, old_size.min(new_size) * B);
+			self.deallocate_blocks(ptr, old_size);
+			Ok(new)
+		}
+	}
+}
+
+/// Returns the alignment of `T` in units of the block size of `A`.
+const fn align_in_blocks<T, A: CollectionAlloc>() -> usize {
+	mem::align_of::<T>().div_ceil(A::BLOCK_SIZE)
+}
+
+/// Panics because an allocator ran out of memory.
+#[cold]
+fn alloc_failed() -> ! {
+	panic!("memory allocation failed")
+}
+
+/// A pointer to a value that is stored in a stalloc allocator, like `std::boxed::Box`.
+/// The value is dropped and its memory is freed when the `Box` is dropped.
+pub struct Box<'a, T, A: CollectionAlloc> {
+	ptr: NonNull<T>,
+	alloc: &'a A,
+	_marker: PhantomData<T>,
+}
+
+impl<'a, T, A: CollectionAlloc> Box<'a, T, A> {
+	/// Moves `value` into `alloc`.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use stalloc::collections::Box;
+	///
+	/// let alloc = Stalloc::<8, 4>::new();
+	/// let b = Box::new_in([1u32, 2, 3], &alloc);
+	/// assert_eq!(b[1], 2);
+	/// ```
+	pub fn new_in(value: T, alloc: &'a A) -> Self {
+		Self::try_new_in(value, alloc).unwrap_or_else(|_| alloc_failed())
+	}
+
+	/// Tries to move `value` into `alloc`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case `value` is dropped.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use stalloc::collections::Box;
+	///
+	/// let alloc = Stalloc::<2, 8>::new();
+	/// assert!(Box::try_new_in(0u64, &alloc).is_ok());
+	/// assert!(Box::try_new_in([0u64; 3], &alloc).is_err());
+	/// ```
+	pub fn try_new_in(value: T, alloc: &'a A) -> Result<Self, AllocError> {
+		let ptr = if Self::BLOCKS == 0 {
+			NonNull::dangling()
+		} else {
+			// SAFETY: `BLOCKS` is nonzero, and the alignment of `T` is a valid alignment.
+			unsafe { alloc.allocate_blocks(Self::BLOCKS, align_in_blocks::<T, A>()) }?.cast()
+		};
+
+		// SAFETY: The memory is valid for a `T`.
+		unsafe { ptr.write(value) };
+
+		Ok(Self {
+			ptr,
+			alloc,
+			_marker: PhantomData,
+		})
+	}
+
+	/// Moves the value out of the box, and frees its memory.
+	#[must_use]
+	pub fn into_inner(b: Self) -> T {
+		let b = ManuallyDrop::new(b);
+
+		// SAFETY: The value is valid, and is never used again after the memory is freed.
+		unsafe {
+			let value = b.ptr.read();
+			b.free();
+			value
+		}
+	}
+
+	/// Consumes the box without dropping the value or freeing its memory, and returns a reference to the value.
+	/// The memory is only reclaimed when the allocator is cleared or dropped.
+	#[must_use]
+	pub fn leak(b: Self) -> &'a mut T {
+		let b = ManuallyDrop::new(b);
+
+		// SAFETY: The value is valid for as long as the allocator is borrowed.
+		unsafe { &mut *b.ptr.as_ptr() }
+	}
+
+	/// The number of blocks that are needed to store a `T`.
+	const BLOCKS: usize = mem::size_of::<T>().div_ceil(A::BLOCK_SIZE);
+
+	/// Frees the memory of the box without dropping the value.
+	unsafe fn free(&self) {
+		if Self::BLOCKS > 0 {
+			unsafe { self.alloc.deallocate_blocks(self.ptr.cast(), Self::BLOCKS) };
+		}
+	}
+}
+
+impl<T, A: CollectionAlloc> Drop for Box<'_, T, A> {
+	fn drop(&mut self) {
+		// SAFETY: The value is valid, and the memory was allocated in `try_new_in()`.
+		unsafe {
+			self.ptr.drop_in_place();
+			self.free();
+		}
+	}
+}
+
+impl<T, A: CollectionAlloc> Deref for Box<'_, T, A> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		unsafe { self.ptr.as_ref() }
+	}
+}
+
+impl<T, A: CollectionAlloc> DerefMut for Box<'_, T, A> {
+	fn deref_mut(&mut self) -> &mut T {
+		unsafe { self.ptr.as_mut() }
+	}
+}
+
+impl<T, A: CollectionAlloc> AsRef<T> for Box<'_, T, A> {
+	fn as_ref(&self) -> &T {
+		self
+	}
+}
+
+impl<T, A: CollectionAlloc> AsMut<T> for Box<'_, T, A> {
+	fn as_mut(&mut self) -> &mut T {
+		self
+	}
+}
+
+impl<T: Debug, A: CollectionAlloc> Debug for Box<'_, T, A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Debug::fmt(&**self, f)
+	}
+}
+
+impl<T: Display, A: CollectionAlloc> Display for Box<'_, T, A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Display::fmt(&**self, f)
+	}
+}
+
+impl<T: PartialEq, A: CollectionAlloc> PartialEq for Box<'_, T, A> {
+	fn eq(&self, other: &Self) -> bool {
+		**self == **other
+	}
+}
+
+impl<T: Eq, A: CollectionAlloc> Eq for Box<'_, T, A> {}
+
+/// A growable array that is stored in a stalloc allocator, like `std::vec::Vec`.
+pub struct Vec<'a, T, A: CollectionAlloc> {
+	ptr: NonNull<T>,
+	len: usize,
+	/// The size of the buffer in blocks, or zero if nothing has been allocated.
+	blocks: usize,
+	alloc: &'a A,
+	_marker: PhantomData<T>,
+}
+
+impl<'a, T, A: CollectionAlloc> Vec<'a, T, A> {
+	/// Creates an empty vector. Nothing is allocated until elements are pushed.
+	#[must_use]
+	pub const fn new_in(alloc: &'a A) -> Self {
+		Self {
+			ptr: NonNull::dangling(),
+			len: 0,
+			blocks: 0,
+			alloc,
+			_marker: PhantomData,
+		}
+	}
+
+	/// Creates an empty vector with room for at least `capacity` elements.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	#[must_use]
+	pub fn with_capacity_in(capacity: usize, alloc: &'a A) -> Self {
+		Self::try_with_capacity_in(capacity, alloc).unwrap_or_else(|_| alloc_failed())
+	}
+
+	/// Tries to create an empty vector with room for at least `capacity` elements.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use stalloc::collections::Vec;
+	///
+	/// let alloc = Stalloc::<8, 4>::new();
+	///
+	/// let v = Vec::<u32, _>::try_with_capacity_in(8, &alloc).unwrap();
+	/// assert_eq!(v.capacity(), 8);
+	/// assert!(Vec::<u32, _>::try_with_capacity_in(1, &alloc).is_err());
+	/// ```
+	pub fn try_with_capacity_in(capacity: usize, alloc: &'a A) -> Result<Self, AllocError> {
+		let mut v = Self::new_in(alloc);
+		v.try_reserve_exact(capacity)?;
+		Ok(v)
+	}
+
+	/// Returns the number of elements in the vector.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Checks whether the vector contains no elements.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Returns the number of elements that the vector can hold without growing.
+	/// Since the buffer is made of whole blocks, this can be more than was requested.
+	#[must_use]
+	pub const fn capacity(&self) -> usize {
+		match mem::size_of::<T>() {
+			0 => usize::MAX,
+			size => self.blocks * A::BLOCK_SIZE / size,
+		}
+	}
+
+	/// Returns the allocator that the vector is stored in.
+	#[must_use]
+	pub const fn allocator(&self) -> &'a A {
+		self.alloc
+	}
+
+	/// Returns a slice of the elements in the vector.
+	#[must_use]
+	pub const fn as_slice(&self) -> &[T] {
+		unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+	}
+
+	/// Returns a mutable slice of the elements in the vector.
+	#[must_use]
+	pub const fn as_mut_slice(&mut self) -> &mut [T] {
+		unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+	}
+
+	/// Returns a pointer to the buffer of the vector.
+	#[must_use]
+	pub const fn as_ptr(&self) -> *const T {
+		self.ptr.as_ptr()
+	}
+
+	/// Returns a mutable pointer to the buffer of the vector.
+	#[must_use]
+	pub const fn as_mut_ptr(&mut self) -> *mut T {
+		self.ptr.as_ptr()
+	}
+
+	/// Makes sure that the vector can hold at least `additional` more elements. The capacity is doubled
+	/// if possible, so that pushing elements one at a time doesn't grow the buffer every time.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	pub fn reserve(&mut self, additional: usize) {
+		self.try_reserve(additional)
+			.unwrap_or_else(|_| alloc_failed());
+	}
+
+	/// Tries to make sure that the vector can hold at least `additional` more elements.
+	/// See `reserve()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the buffer couldn't be grown, in which case the vector is unchanged.
+	pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+		let needed = self.needed_blocks(additional)?;
+		if needed <= self.blocks {
+			return Ok(());
+		}
+
+		// If doubling the buffer doesn't fit, fall back to the smallest buffer that does.
+		let doubled = (self.blocks * 2).max(needed);
+		self.resize_buffer(doubled)
+			.or_else(|_| self.resize_buffer(needed))
+	}
+
+	/// Makes sure that the vector can hold at least `additional` more elements, without growing the buffer
+	/// more than necessary.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	pub fn reserve_exact(&mut self, additional: usize) {
+		self.try_reserve_exact(additional)
+			.unwrap_or_else(|_| alloc_failed());
+	}
+
+	/// Tries to make sure that the vector can hold at least `additional` more elements, without growing the
+	/// buffer more than necessary.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the buffer couldn't be grown, in which case the vector is unchanged.
+	pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), AllocError> {
+		let needed = self.needed_blocks(additional)?;
+		if needed <= self.blocks {
+			return Ok(());
+		}
+
+		self.resize_buffer(needed)
+	}
+
+	/// Shrinks the buffer to the smallest number of blocks that can hold the elements.
+	/// The freed blocks are returned to the allocator. This never moves the buffer.
+	pub fn shrink_to_fit(&mut self) {
+		// `needed_blocks(0)` can't fail, because the elements already fit in memory.
+		if let Ok(needed) = self.needed_blocks(0)
+			&& needed < self.blocks
+		{
+			// Shrinking never fails, because the buffer is already aligned.
+			let _ = self.resize_buffer(needed);
+		}
+	}
+
+	/// Appends an element to the back of the vector.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use stalloc::collections::Vec;
+	///
+	/// let alloc = Stalloc::<8, 4>::new();
+	///
+	/// let mut v = Vec::new_in(&alloc);
+	/// for i in 0..8u32 {
+	///     v.push(i);
+	/// }
+	/// assert_eq!(v.len(), 8);
+	/// assert!(alloc.is_oom());
+	/// ```
+	pub fn push(&mut self, value: T) {
+		if self.try_push(value).is_err() {
+			alloc_failed();
+		}
+	}
+
+	/// Tries to append an element to the back of the vector.
+	///
+	/// # Errors
+	///
+	/// Will return the element if the buffer couldn't be grown.
+	pub fn try_push(&mut self, value: T) -> Result<(), T> {
+		if self.len == self.capacity() && self.try_reserve(1).is_err() {
+			return Err(value);
+		}
+
+		// SAFETY: There is room for the element.
+		unsafe { self.ptr.add(self.len).write(value) };
+		self.len += 1;
+		Ok(())
+	}
+
+	/// Removes the last element from the vector and returns it, or `None` if the vector is empty.
+	pub const fn pop(&mut self) -> Option<T> {
+		if self.len == 0 {
+			return None;
+		}
+
+		self.len -= 1;
+
+		// SAFETY: The element is initialized, and is no longer part of the vector.
+		Some(unsafe { self.ptr.add(self.len).read() })
+	}
+
+	/// Inserts an element at `index`, shifting all the elements after it to the right.
+	///
+	/// # Panics
+	///
+	/// Panics if `index > len`, or if the allocator runs out of memory.
+	pub fn insert(&mut self, index: usize, value: T) {
+		assert!(index <= self.len, "insertion index is out of bounds");
+		self.reserve(1);
+
+		// SAFETY: There is room for one more element, and `index` is in bounds.
+		unsafe {
+			let p = self.ptr.add(index);
+			p.copy_to(p.add(1), self.len - index);
+			p.write(value);
+		}
+		self.len += 1;
+	}
+
+	/// Removes and returns the element at `index`, shifting all the elements after it to the left.
+	///
+	/// # Panics
+	///
+	/// Panics if `index >= len`.
+	pub fn remove(&mut self, index: usize) -> T {
+		assert!(index < self.len, "removal index is out of bounds");
+
+		// SAFETY: `index` is in bounds, and the element is no longer part of the vector after the shift.
+		unsafe {
+			let p = self.ptr.add(index);
+			let value = p.read();
+			p.add(1).copy_to(p, self.len - index - 1);
+			self.len -= 1;
+			value
+		}
+	}
+
+	/// Removes and returns the element at `index`, replacing it with the last element.
+	///
+	/// # Panics
+	///
+	/// Panics if `index >= len`.
+	pub fn swap_remove(&mut self, index: usize) -> T {
+		assert!(index < self.len, "removal index is out of bounds");
+		let last = self.len - 1;
+		self.as_mut_slice().swap(index, last);
+
+		// SAFETY: The vector is not empty.
+		unsafe { self.pop().unwrap_unchecked() }
+	}
+
+	/// Shortens the vector to `len` elements, dropping the rest. Does nothing if the vector is already shorter.
+	pub fn truncate(&mut self, len: usize) {
+		if len >= self.len {
+			return;
+		}
+
+		let tail =
+			ptr::slice_from_raw_parts_mut(unsafe { self.ptr.as_ptr().add(len) }, self.len - len);
+
+		// The length is updated first, in case dropping an element panics.
+		self.len = len;
+		unsafe { tail.drop_in_place() };
+	}
+
+	/// Removes all elements from the vector. The buffer is kept.
+	pub fn clear(&mut self) {
+		self.truncate(0);
+	}
+
+	/// Consumes the vector without dropping its elements or freeing its buffer, and returns a slice of
+	/// the elements. The memory is only reclaimed when the allocator is cleared or dropped.
+	#[must_use]
+	pub fn leak(self) -> &'a mut [T] {
+		let v = ManuallyDrop::new(self);
+
+		// SAFETY: The elements are valid for as long as the allocator is borrowed.
+		unsafe { core::slice::from_raw_parts_mut(v.ptr.as_ptr(), v.len) }
+	}
+
+	/// Returns the number of blocks that are needed to hold `additional` more elements.
+	fn needed_blocks(&self, additional: usize) -> Result<usize, AllocError> {
+		self.len
+			.checked_add(additional)
+			.and_then(|len| len.checked_mul(mem::size_of::<T>()))
+			.map(|size| size.div_ceil(A::BLOCK_SIZE))
+			.ok_or(AllocError)
+	}
+
+	/// Allocates, resizes, or frees the buffer so that it is `blocks` blocks long.
+	/// The elements must fit in the new buffer.
+	fn resize_buffer(&mut self, blocks: usize) -> Result<(), AllocError> {
+		let align = align_in_blocks::<T, A>();
+
+		// SAFETY: `ptr` is an allocation of `self.blocks` blocks if `self.blocks` is nonzero,
+		// and the alignment of `T` is a valid alignment.
+		let ptr = unsafe {
+			match (self.blocks, blocks) {
+				(0, 0) => return Ok(()),
+				(0, _) => self.alloc.allocate_blocks(blocks, align)?,
+				(_, 0) => {
+					self.alloc.deallocate_blocks(self.ptr.cast(), self.blocks);
+					NonNull::<T>::dangling().cast()
+				}
+				(_, _) => {
+					self.alloc
+						.reallocate_blocks(self.ptr.cast(), self.blocks, blocks, align)?
+				}
+			}
+		};
+
+		self.ptr = ptr.cast();
+		self.blocks = blocks;
+		Ok(())
+	}
+}
+
+impl<T: Clone, A: CollectionAlloc> Vec<'_, T, A> {
+	/// Appends clones of all the elements in `other` to the vector.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	pub fn extend_from_slice(&mut self, other: &[T]) {
+		self.try_extend_from_slice(other)
+			.unwrap_or_else(|_| alloc_failed());
+	}
+
+	/// Tries to append clones of all the elements in `other` to the vector.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the buffer couldn't be grown, in which case the vector is unchanged.
+	pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), AllocError> {
+		self.try_reserve(other.len())?;
+		for value in other {
+			// SAFETY: There is room for all the elements.
+			unsafe { self.ptr.add(self.len).write(value.clone()) };
+			self.len += 1;
+		}
+		Ok(())
+	}
+
+	/// Resizes the vector to `len` elements, filling new slots with clones of `value`.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	pub fn resize(&mut self, len: usize, value: T) {
+		if len <= self.len {
+			self.truncate(len);
+			return;
+		}
+
+		self.reserve(len - self.len);
+		while self.len < len {
+			// SAFETY: There is room for all the elements.
+			unsafe { self.ptr.add(self.len).write(value.clone()) };
+			self.len += 1;
+		}
+	}
+}
+
+impl<T, A: CollectionAlloc> Drop for Vec<'_, T, A> {
+	fn drop(&mut self) {
+		unsafe { ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len).drop_in_place() };
+		let _ = self.resize_buffer(0);
+	}
+}
+
+impl<T, A: CollectionAlloc> Deref for Vec<'_, T, A> {
+	type Target = [T];
+
+	fn deref(&self) -> &[T] {
+		self.as_slice()
+	}
+}
+
+impl<T, A: CollectionAlloc> DerefMut for Vec<'_, T, A> {
+	fn deref_mut(&mut self) -> &mut [T] {
+		self.as_mut_slice()
+	}
+}
+
+impl<T, A: CollectionAlloc> AsRef<[T]> for Vec<'_, T, A> {
+	fn as_ref(&self) -> &[T] {
+		self
+	}
+}
+
+impl<T, A: CollectionAlloc> AsMut<[T]> for Vec<'_, T, A> {
+	fn as_mut(&mut self) -> &mut [T] {
+		self
+	}
+}
+
+impl<T, A: CollectionAlloc> Borrow<[T]> for Vec<'_, T, A> {
+	fn borrow(&self) -> &[T] {
+		self
+	}
+}
+
+impl<T, A: CollectionAlloc> BorrowMut<[T]> for Vec<'_, T, A> {
+	fn borrow_mut(&mut self) -> &mut [T] {
+		self
+	}
+}
+
+impl<T, A: CollectionAlloc> Extend<T> for Vec<'_, T, A> {
+	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+		let iter = iter.into_iter();
+		self.reserve(iter.size_hint().0);
+		for value in iter {
+			self.push(value);
+		}
+	}
+}
+
+impl<'b, T: Copy + 'b, A: CollectionAlloc> Extend<&'b T> for Vec<'_, T, A> {
+	fn extend<I: IntoIterator<Item = &'b T>>(&mut self, iter: I) {
+		self.extend(iter.into_iter().copied());
+	}
+}
+
+impl<T: Debug, A: CollectionAlloc> Debug for Vec<'_, T, A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Debug::fmt(self.as_slice(), f)
+	}
+}
+
+impl<T: PartialEq<U>, U, A: CollectionAlloc, A2: CollectionAlloc> PartialEq<Vec<'_, U, A2>>
+	for Vec<'_, T, A>
+{
+	fn eq(&self, other: &Vec<'_, U, A2>) -> bool {
+		self.as_slice() == other.as_slice()
+	}
+}
+
+impl<T: PartialEq<U>, U, A: CollectionAlloc> PartialEq<[U]> for Vec<'_, T, A> {
+	fn eq(&self, other: &[U]) -> bool {
+		self.as_slice() == other
+	}
+}
+
+impl<T: PartialEq<U>, U, A: CollectionAlloc, const N: usize> PartialEq<[U; N]> for Vec<'_, T, A> {
+	fn eq(&self, other: &[U; N]) -> bool {
+		self.as_slice() == other
+	}
+}
+
+impl<T: Eq, A: CollectionAlloc> Eq for Vec<'_, T, A> {}
+
+/// A growable UTF-8 string that is stored in a stalloc allocator, like `std::string::String`.
+pub struct String<'a, A: CollectionAlloc> {
+	vec: Vec<'a, u8, A>,
+}
+
+impl<'a, A: CollectionAlloc> String<'a, A> {
+	/// Creates an empty string. Nothing is allocated until text is pushed.
+	#[must_use]
+	pub const fn new_in(alloc: &'a A) -> Self {
+		Self {
+			vec: Vec::new_in(alloc),
+		}
+	}
+
+	/// Creates an empty string with room for at least `capacity` bytes.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	#[must_use]
+	pub fn with_capacity_in(capacity: usize, alloc: &'a A) -> Self {
+		Self {
+			vec: Vec::with_capacity_in(capacity, alloc),
+		}
+	}
+
+	/// Creates a string containing a copy of `s`.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use stalloc::collections::String;
+	///
+	/// let alloc = Stalloc::<8, 4>::new();
+	///
+	/// let s = String::from_str_in("hello", &alloc);
+	/// assert_eq!(s.as_str(), "hello");
+	/// assert_eq!(s.capacity(), 8);
+	/// ```
+	#[must_use]
+	pub fn from_str_in(s: &str, alloc: &'a A) -> Self {
+		let mut string = Self::new_in(alloc);
+		string.push_str(s);
+		string
+	}
+
+	/// Returns the length of the string in bytes.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.vec.len()
+	}
+
+	/// Checks whether the string is empty.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.vec.is_empty()
+	}
+
+	/// Returns the number of bytes that the string can hold without growing.
+	#[must_use]
+	pub const fn capacity(&self) -> usize {
+		self.vec.capacity()
+	}
+
+	/// Returns the contents of the string.
+	#[must_use]
+	pub const fn as_str(&self) -> &str {
+		// SAFETY: The bytes are always valid UTF-8.
+		unsafe { core::str::from_utf8_unchecked(self.vec.as_slice()) }
+	}
+
+	/// Returns the mutable contents of the string.
+	#[must_use]
+	pub const fn as_mut_str(&mut self) -> &mut str {
+		// SAFETY: The bytes are always valid UTF-8.
+		unsafe { core::str::from_utf8_unchecked_mut(self.vec.as_mut_slice()) }
+	}
+
+	/// Returns the bytes of the string.
+	#[must_use]
+	pub const fn as_bytes(&self) -> &[u8] {
+		self.vec.as_slice()
+	}
+
+	/// Makes sure that the string can hold at least `additional` more bytes.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	pub fn reserve(&mut self, additional: usize) {
+		self.vec.reserve(additional);
+	}
+
+	/// Tries to make sure that the string can hold at least `additional` more bytes.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the buffer couldn't be grown, in which case the string is unchanged.
+	pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+		self.vec.try_reserve(additional)
+	}
+
+	/// Shrinks the buffer to the smallest number of blocks that can hold the string.
+	pub fn shrink_to_fit(&mut self) {
+		self.vec.shrink_to_fit();
+	}
+
+	/// Appends `s` to the end of the string.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	pub fn push_str(&mut self, s: &str) {
+		self.vec.extend_from_slice(s.as_bytes());
+	}
+
+	/// Tries to append `s` to the end of the string.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the buffer couldn't be grown, in which case the string is unchanged.
+	pub fn try_push_str(&mut self, s: &str) -> Result<(), AllocError> {
+		self.vec.try_extend_from_slice(s.as_bytes())
+	}
+
+	/// Appends `c` to the end of the string.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	pub fn push(&mut self, c: char) {
+		self.push_str(c.encode_utf8(&mut [0; 4]));
+	}
+
+	/// Removes the last character from the string and returns it, or `None` if the string is empty.
+	pub fn pop(&mut self) -> Option<char> {
+		let c = self.as_str().chars().next_back()?;
+		self.vec.truncate(self.len() - c.len_utf8());
+		Some(c)
+	}
+
+	/// Shortens the string to `len` bytes. Does nothing if the string is already shorter.
+	///
+	/// # Panics
+	///
+	/// Panics if `len` is not on a character boundary.
+	pub fn truncate(&mut self, len: usize) {
+		if len < self.len() {
+			assert!(
+				self.as_str().is_char_boundary(len),
+				"new length is not on a character boundary"
+			);
+			self.vec.truncate(len);
+		}
+	}
+
+	/// Removes all text from the string. The buffer is kept.
+	pub fn clear(&mut self) {
+		self.vec.clear();
+	}
+
+	/// Converts the string into a vector of its bytes.
+	#[must_use]
+	pub fn into_bytes(self) -> Vec<'a, u8, A> {
+		self.vec
+	}
+
+	/// Consumes the string without freeing its buffer, and returns its contents.
+	/// The memory is only reclaimed when the allocator is cleared or dropped.
+	#[must_use]
+	pub fn leak(self) -> &'a mut str {
+		// SAFETY: The bytes are always valid UTF-8.
+		unsafe { core::str::from_utf8_unchecked_mut(self.vec.leak()) }
+	}
+}
+
+impl<A: CollectionAlloc> Deref for String<'_, A> {
+	type Target = str;
+
+	fn deref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl<A: CollectionAlloc> DerefMut for String<'_, A> {
+	fn deref_mut(&mut self) -> &mut str {
+		self.as_mut_str()
+	}
+}
+
+impl<A: CollectionAlloc> AsRef<str> for String<'_, A> {
+	fn as_ref(&self) -> &str {
+		self
+	}
+}
+
+impl<A: CollectionAlloc> AsRef<[u8]> for String<'_, A> {
+	fn as_ref(&self) -> &[u8] {
+		self.as_bytes()
+	}
+}
+
+impl<A: CollectionAlloc> Borrow<str> for String<'_, A> {
+	fn borrow(&self) -> &str {
+		self
+	}
+}
+
+impl<A: CollectionAlloc> fmt::Write for String<'_, A> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.try_push_str(s).map_err(|_| fmt::Error)
+	}
+}
+
+impl<A: CollectionAlloc> Extend<char> for String<'_, A> {
+	fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+		for c in iter {
+			self.push(c);
+		}
+	}
+}
+
+impl<'b, A: CollectionAlloc> Extend<&'b str> for String<'_, A> {
+	fn extend<I: IntoIterator<Item = &'b str>>(&mut self, iter: I) {
+		for s in iter {
+			self.push_str(s);
+		}
+	}
+}
+
+impl<A: CollectionAlloc> Debug for String<'_, A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Debug::fmt(self.as_str(), f)
+	}
+}
+
+impl<A: CollectionAlloc> Display for String<'_, A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Display::fmt(self.as_str(), f)
+	}
+}
+
+impl<A: CollectionAlloc, A2: CollectionAlloc> PartialEq<String<'_, A2>> for String<'_, A> {
+	fn eq(&self, other: &String<'_, A2>) -> bool {
+		self.as_str() == other.as_str()
+	}
+}
+
+impl<A: CollectionAlloc> PartialEq<str> for String<'_, A> {
+	fn eq(&self, other: &str) -> bool {
+		self.as_str() == other
+	}
+}
+
+impl<A: CollectionAlloc> PartialEq<&str> for String<'_, A> {
+	fn eq(&self, other: &&str) -> bool {
+		self.as_str() == *other
+	}
+}
+
+impl<A: CollectionAlloc> Eq for String<'_, A> {}
+
+/// A vector that stores its first `N` elements inline, and only moves them into the allocator once
+/// it grows beyond that. Pushing up to `N` elements does no allocator work at all.
+///
+/// Once the vector has spilled, it behaves like a `Vec` and stays in the allocator, even if elements
+/// are removed again.
+///
+/// # Examples
+/// ```
+/// use stalloc::Stalloc;
+/// use stalloc::collections::SmallVec;
+///
+/// let alloc = Stalloc::<16, 4>::new();
+///
+/// let mut v = SmallVec::<u32, _, 4>::new_in(&alloc);
+/// v.extend([1, 2, 3, 4]);
+/// assert!(!v.spilled());
+/// assert!(alloc.is_empty());
+///
+/// v.push(5);
+/// assert!(v.spilled());
+/// assert_eq!(v, [1, 2, 3, 4, 5]);
+/// ```
+pub struct SmallVec<'a, T, A: CollectionAlloc, const N: usize> {
+	inline: [MaybeUninit<T>; N],
+	/// The number of inline elements. This is always zero once the vector has spilled.
+	inline_len: usize,
+	heap: Vec<'a, T, A>,
+	spilled: bool,
+}
+
+impl<'a, T, A: CollectionAlloc, const N: usize> SmallVec<'a, T, A, N> {
+	/// Creates an empty vector. Nothing is allocated until more than `N` elements are pushed.
+	#[must_use]
+	pub const fn new_in(alloc: &'a A) -> Self {
+		Self {
+			inline: [const { MaybeUninit::uninit() }; N],
+			inline_len: 0,
+			heap: Vec::new_in(alloc),
+			spilled: false,
+		}
+	}
+
+	/// Checks whether the elements have been moved into the allocator.
+	#[must_use]
+	pub const fn spilled(&self) -> bool {
+		self.spilled
+	}
+
+	/// Returns the number of elements in the vector.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		if self.spilled {
+			self.heap.len()
+		} else {
+			self.inline_len
+		}
+	}
+
+	/// Checks whether the vector contains no elements.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the number of elements that the vector can hold without allocating or growing.
+	#[must_use]
+	pub const fn capacity(&self) -> usize {
+		if self.spilled {
+			self.heap.capacity()
+		} else {
+			N
+		}
+	}
+
+	/// Returns a slice of the elements in the vector.
+	#[must_use]
+	pub const fn as_slice(&self) -> &[T] {
+		if self.spilled {
+			self.heap.as_slice()
+		} else {
+			// SAFETY: The first `inline_len` inline elements are initialized.
+			unsafe { core::slice::from_raw_parts(self.inline.as_ptr().cast(), self.inline_len) }
+		}
+	}
+
+	/// Returns a mutable slice of the elements in the vector.
+	#[must_use]
+	pub const fn as_mut_slice(&mut self) -> &mut [T] {
+		if self.spilled {
+			self.heap.as_mut_slice()
+		} else {
+			// SAFETY: The first `inline_len` inline elements are initialized.
+			unsafe {
+				core::slice::from_raw_parts_mut(self.inline.as_mut_ptr().cast(), self.inline_len)
+			}
+		}
+	}
+
+	/// Appends an element to the back of the vector.
+	///
+	/// # Panics
+	///
+	/// Panics if the vector has to spill or grow, and the allocator runs out of memory.
+	pub fn push(&mut self, value: T) {
+		if self.try_push(value).is_err() {
+			alloc_failed();
+		}
+	}
+
+	/// Tries to append an element to the back of the vector.
+	///
+	/// # Errors
+	///
+	/// Will return the element if the vector had to spill or grow, and the allocation was unsuccessful.
+	pub fn try_push(&mut self, value: T) -> Result<(), T> {
+		if self.spilled {
+			return self.heap.try_push(value);
+		}
+
+		if self.inline_len < N {
+			self.inline[self.inline_len].write(value);
+			self.inline_len += 1;
+			return Ok(());
+		}
+
+		if self.spill(N + 1).is_err() {
+			return Err(value);
+		}
+		self.heap.try_push(value)
+	}
+
+	/// Removes the last element from the vector and returns it, or `None` if the vector is empty.
+	pub const fn pop(&mut self) -> Option<T> {
+		if self.spilled {
+			return self.heap.pop();
+		}
+
+		if self.inline_len == 0 {
+			return None;
+		}
+
+		self.inline_len -= 1;
+
+		// SAFETY: The element is initialized, and is no longer part of the vector.
+		Some(unsafe { self.inline[self.inline_len].assume_init_read() })
+	}
+
+	/// Shortens the vector to `len` elements, dropping the rest. Does nothing if the vector is already shorter.
+	pub fn truncate(&mut self, len: usize) {
+		if self.spilled {
+			self.heap.truncate(len);
+			return;
+		}
+
+		if len >= self.inline_len {
+			return;
+		}
+
+		let tail = ptr::slice_from_raw_parts_mut(
+			unsafe { self.inline.as_mut_ptr().add(len).cast::<T>() },
+			self.inline_len - len,
+		);
+
+		// The length is updated first, in case dropping an element panics.
+		self.inline_len = len;
+		unsafe { tail.drop_in_place() };
+	}
+
+	/// Removes all elements from the vector. If the vector has spilled, its buffer is kept.
+	pub fn clear(&mut self) {
+		self.truncate(0);
+	}
+
+	/// Moves the inline elements into a buffer in the allocator with room for `capacity` elements.
+	fn spill(&mut self, capacity: usize) -> Result<(), AllocError> {
+		self.heap.try_reserve_exact(capacity)?;
+
+		// SAFETY: The heap buffer has room for the inline elements, which are no longer used afterwards.
+		unsafe {
+			self.heap
+				.as_mut_ptr()
+				.copy_from_nonoverlapping(self.inline.as_ptr().cast(), self.inline_len);
+			self.heap.len = self.inline_len;
+		}
+
+		self.inline_len = 0;
+		self.spilled = true;
+		Ok(())
+	}
+}
+
+impl<T, A: CollectionAlloc, const N: usize> Drop for SmallVec<'_, T, A, N> {
+	fn drop(&mut self) {
+		// The heap elements are dropped by the `Vec`.
+		if !self.spilled {
+			self.clear();
+		}
+	}
+}
+
+impl<T, A: CollectionAlloc, const N: usize> Deref for SmallVec<'_, T, A, N> {
+	type Target = [T];
+
+	fn deref(&self) -> &[T] {
+		self.as_slice()
+	}
+}
+
+impl<T, A: CollectionAlloc, const N: usize> DerefMut for SmallVec<'_, T, A, N> {
+	fn deref_mut(&mut self) -> &mut [T] {
+		self.as_mut_slice()
+	}
+}
+
+impl<T, A: CollectionAlloc, const N: usize> Extend<T> for SmallVec<'_, T, A, N> {
+	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+		for value in iter {
+			self.push(value);
+		}
+	}
+}
+
+impl<T: Debug, A: CollectionAlloc, const N: usize> Debug for SmallVec<'_, T, A, N> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Debug::fmt(self.as_slice(), f)
+	}
+}
+
+impl<T: PartialEq<U>, U, A: CollectionAlloc, const N: usize> PartialEq<[U]>
+	for SmallVec<'_, T, A, N>
+{
+	fn eq(&self, other: &[U]) -> bool {
+		self.as_slice() == other
+	}
+}
+
+impl<T: PartialEq<U>, U, A: CollectionAlloc, const N: usize, const M: usize> PartialEq<[U; M]>
+	for SmallVec<'_, T, A, N>
+{
+	fn eq(&self, other: &[U; M]) -> bool {
+		self.as_slice() == other
+	}
+}
+
+/// A bounded first-in, first-out queue that is stored in a stalloc allocator.
+///
+/// The buffer is allocated once when the queue is created, and pushing or popping elements never
+/// allocates. Pushing to a full queue fails instead of growing it.
+///
+/// # Examples
+/// ```
+/// use stalloc::Stalloc;
+/// use stalloc::collections::RingBuffer;
+///
+/// let alloc = Stalloc::<16, 4>::new();
+///
+/// let mut queue = RingBuffer::with_capacity_in(3, &alloc);
+/// queue.push(1u32).unwrap();
+/// queue.push(2).unwrap();
+/// queue.push(3).unwrap();
+/// assert_eq!(queue.push(4), Err(4));
+///
+/// assert_eq!(queue.pop(), Some(1));
+/// queue.push(4).unwrap();
+/// assert!(queue.iter().eq(&[2, 3, 4]));
+/// ```
+pub struct RingBuffer<'a, T, A: CollectionAlloc> {
+	buf: Vec<'a, MaybeUninit<T>, A>,
+	capacity: usize,
+	/// The index of the first element.
+	head: usize,
+	len: usize,
+}
+
+impl<'a, T, A: CollectionAlloc> RingBuffer<'a, T, A> {
+	/// Creates an empty queue that can hold `capacity` elements.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator runs out of memory.
+	#[must_use]
+	pub fn with_capacity_in(capacity: usize, alloc: &'a A) -> Self {
+		Self::try_with_capacity_in(capacity, alloc).unwrap_or_else(|_| alloc_failed())
+	}
+
+	/// Tries to create an empty queue that can hold `capacity` elements.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	pub fn try_with_capacity_in(capacity: usize, alloc: &'a A) -> Result<Self, AllocError> {
+		Ok(Self {
+			buf: Vec::try_with_capacity_in(capacity, alloc)?,
+			capacity,
+			head: 0,
+			len: 0,
+		})
+	}
+
+	/// Returns the number of elements in the queue.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Checks whether the queue contains no elements.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Checks whether the queue can't hold any more elements.
+	#[must_use]
+	pub const fn is_full(&self) -> bool {
+		self.len == self.capacity
+	}
+
+	/// Returns the number of elements that the queue can hold.
+	#[must_use]
+	pub const fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Appends an element to the back of the queue.
+	///
+	/// # Errors
+	///
+	/// Will return the element if the queue is full.
+	pub const fn push(&mut self, value: T) -> Result<(), T> {
+		if self.is_full() {
+			return Err(value);
+		}
+
+		let idx = self.wrap(self.head + self.len);
+		// SAFETY: `idx` is in bounds, and the slot is not in use.
+		unsafe { self.slot(idx).write(value) };
+		self.len += 1;
+		Ok(())
+	}
+
+	/// Removes the element at the front of the queue and returns it, or `None` if the queue is empty.
+	pub const fn pop(&mut self) -> Option<T> {
+		if self.is_empty() {
+			return None;
+		}
+
+		// SAFETY: The first element is initialized, and is no longer part of the queue.
+		let value = unsafe { self.slot(self.head).read() };
+		self.head = self.wrap(self.head + 1);
+		self.len -= 1;
+		Some(value)
+	}
+
+	/// Returns a reference to the element at the front of the queue, or `None` if the queue is empty.
+	#[must_use]
+	pub fn front(&self) -> Option<&T> {
+		self.get(0)
+	}
+
+	/// Returns a reference to the element at the back of the queue, or `None` if the queue is empty.
+	#[must_use]
+	pub fn back(&self) -> Option<&T> {
+		self.len.checked_sub(1).and_then(|idx| self.get(idx))
+	}
+
+	/// Returns a reference to the element at position `idx` from the front, or `None` if it is out of bounds.
+	#[must_use]
+	pub fn get(&self, idx: usize) -> Option<&T> {
+		// SAFETY: The element is initialized.
+		(idx < self.len).then(|| unsafe { &*self.slot(self.wrap(self.head + idx)).cast_const() })
+	}
+
+	/// Returns an iterator over the elements, from front to back.
+	#[must_use]
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+		// SAFETY: Every element at position `0..len` is initialized.
+		(0..self.len).map(|idx| unsafe { &*self.slot(self.wrap(self.head + idx)).cast_const() })
+	}
+
+	/// Removes all elements from the queue.
+	pub fn clear(&mut self) {
+		while self.pop().is_some() {}
+	}
+
+	/// Returns a pointer to the slot at `idx`, which must be in `0..capacity`.
+	const unsafe fn slot(&self, idx: usize) -> *mut T {
+		unsafe { self.buf.ptr.as_ptr().add(idx).cast() }
+	}
+
+	/// Wraps an index in `0..2 * capacity` around to `0..capacity`.
+	const fn wrap(&self, idx: usize) -> usize {
+		if idx >= self.capacity {
+			idx - self.capacity
+		} else {
+			idx
+		}
+	}
+}
+
+impl<T, A: CollectionAlloc> Drop for RingBuffer<'_, T, A> {
+	fn drop(&mut self) {
+		// The buffer itself is freed by the `Vec`.
+		self.clear();
+	}
+}
+
+impl<T: Debug, A: CollectionAlloc> Debug for RingBuffer<'_, T, A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_list().entries(self.iter()).finish()
+	}
+}
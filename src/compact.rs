@@ -0,0 +1,164 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{Header, OOM_MARKER, Stalloc, Strategy, as_u16};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Slides every live allocation as far toward the start of the buffer as possible, so that all
+	/// free blocks end up in as few chunks as possible. This requires the `tracking` feature.
+	///
+	/// `on_move(old, new, size)` is called for every allocation of `size` blocks that was moved from
+	/// `old` to `new`, in order of increasing address, so that pointers into it can be fixed up.
+	/// A moved allocation is at least as aligned as it was before, up to an alignment of
+	/// `size.next_power_of_two()` blocks (see `duplicate()`). Reserved blocks are never moved, and
+	/// quarantined or cached blocks are returned to the free list first.
+	///
+	/// # Safety
+	///
+	/// Every moved allocation must only be accessed through the new pointer afterwards, so no references
+	/// into any allocation may be alive during the call. `on_move` must not use the allocator.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<12, 4>::new();
+	///
+	/// let a = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+	/// let mut b = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+	/// unsafe { b.cast::<u32>().write(42) };
+	/// unsafe { alloc.deallocate_blocks(a, 4) };
+	///
+	/// // There are 8 free blocks, but not in one piece.
+	/// assert!(unsafe { alloc.allocate_blocks(8, 1) }.is_err());
+	///
+	/// unsafe {
+	///     alloc.compact(|old, new, _| {
+	///         assert_eq!(old, b);
+	///         b = new;
+	///     })
+	/// };
+	/// assert_eq!(unsafe { b.cast::<u32>().read() }, 42);
+	/// assert!(unsafe { alloc.allocate_blocks(8, 1) }.is_ok());
+	/// ```
+	pub unsafe fn compact(&self, mut on_move: impl FnMut(NonNull<u8>, NonNull<u8>, usize)) {
+		#[cfg(feature = "quarantine")]
+		self.flush_quarantine();
+		#[cfg(feature = "free-cache")]
+		self.flush_free_cache();
+
+		unsafe {
+			let base = self.base.get();
+			let mut next_free: usize = if (*base).length == OOM_MARKER {
+				L
+			} else {
+				(*base).next.into()
+			};
+
+			// The free list is rebuilt from scratch, in order of increasing address.
+			(*base).next = 0;
+			(*base).length = OOM_MARKER;
+			let mut tail = base;
+
+			// Everything before `dest` has been compacted, and `idx` is the start of the next region.
+			let mut dest = 0;
+			let mut idx = 0;
+
+			while idx < L {
+				if idx == next_free {
+					// Skip the old free chunk. Its header is read before anything overwrites it.
+					let chunk = *self.header_at(idx);
+					next_free = if chunk.next == 0 {
+						L
+					} else {
+						chunk.next.into()
+					};
+					idx += usize::from(chunk.length);
+					continue;
+				}
+
+				let size = if idx + self.guard_blocks() < L {
+					self.tracked_length(idx + self.guard_blocks())
+				} else {
+					0
+				};
+
+				if size == 0 {
+					// This block is reserved, so it can't be moved.
+					tail = self.push_compacted_chunk(tail, dest, idx);
+					idx += 1;
+					dest = idx;
+					continue;
+				}
+
+				let old_idx = idx + self.guard_blocks();
+				let old_ptr = NonNull::new_unchecked(self.block_at(old_idx).cast::<u8>());
+				let align = Self::natural_align(old_ptr, size);
+
+				// Find the first suitably aligned index after `dest`.
+				let dest_addr = self.block_at(dest + self.guard_blocks()).addr();
+				let new_idx = dest
+					+ self.guard_blocks()
+					+ (dest_addr.next_multiple_of(align * B) - dest_addr) / B;
+
+				if new_idx < old_idx {
+					let new_ptr = NonNull::new_unchecked(self.block_at(new_idx).cast::<u8>());
+					old_ptr.copy_to(new_ptr, size * B);
+
+					self.track_dealloc(old_idx);
+					self.track_alloc(new_idx, size);
+					#[cfg(feature = "tags")]
+					self.set_tag_at(new_idx, self.tag_at(old_idx));
+					#[cfg(feature = "canaries")]
+					self.write_canaries(new_idx, size);
+
+					on_move(old_ptr, new_ptr, size);
+				}
+
+				tail = self.push_compacted_chunk(tail, dest, new_idx - self.guard_blocks());
+				dest = new_idx + size + self.guard_blocks();
+				idx = old_idx + size + self.guard_blocks();
+			}
+
+			self.push_compacted_chunk(tail, dest, L);
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Appends the blocks in `start..end` (if there are any) to the free list that is being rebuilt
+	/// by `compact()`, and returns its new last chunk.
+	unsafe fn push_compacted_chunk(
+		&self,
+		tail: *mut Header,
+		start: usize,
+		end: usize,
+	) -> *mut Header {
+		if start == end {
+			return tail;
+		}
+
+		#[cfg(feature = "zero-on-free")]
+		self.wipe_blocks(start, end - start);
+		#[cfg(feature = "poison")]
+		self.poison_blocks(start, end - start);
+
+		unsafe {
+			let chunk = self.header_at(start);
+			*chunk = Header {
+				next: 0,
+				length: as_u16(end - start),
+			};
+			(*tail).next = as_u16(start);
+			self.raise_largest_free(end - start);
+			chunk
+		}
+	}
+}
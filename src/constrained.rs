@@ -0,0 +1,167 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment, MAX_ALIGN};
+use crate::{AllocError, Stalloc, Strategy, precondition};
+
+/// Restrictions on the addresses of an allocation, as required by many DMA engines.
+/// Used with `allocate_blocks_constrained()`.
+///
+/// # Examples
+/// ```
+/// use stalloc::AddrConstraint;
+///
+/// // Below 16 MiB, and not crossing a 64 KiB boundary.
+/// let constraint = AddrConstraint::new().below(16 << 20).no_crossing(64 << 10);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AddrConstraint {
+	limit: usize,
+	boundary: usize,
+}
+
+impl AddrConstraint {
+	/// Returns a constraint that every address satisfies.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			limit: usize::MAX,
+			boundary: 0,
+		}
+	}
+
+	/// Requires the allocation to end at or below the address `limit`.
+	#[must_use]
+	pub const fn below(self, limit: usize) -> Self {
+		Self { limit, ..self }
+	}
+
+	/// Requires the allocation not to cross a multiple of `boundary` bytes.
+	///
+	/// # Panics
+	///
+	/// Panics if `boundary` is not a power of 2.
+	#[must_use]
+	pub const fn no_crossing(self, boundary: usize) -> Self {
+		assert!(
+			boundary.is_power_of_two(),
+			"`boundary` must be a power of 2"
+		);
+		Self { boundary, ..self }
+	}
+
+	/// Returns the lowest address at or after `addr` (which is aligned to `align` bytes) where an allocation
+	/// of `size` bytes satisfies the constraint, or `None` if there is none.
+	const fn place(&self, addr: usize, size: usize, align: usize) -> Option<usize> {
+		let mut addr = addr;
+
+		// If the allocation would cross a boundary, move it up to the boundary. Since it fits between two
+		// boundaries, `boundary > align` in that case, so the new address is still aligned.
+		if self.boundary != 0 {
+			if size > self.boundary {
+				return None;
+			}
+			if addr / self.boundary != (addr + size - 1) / self.boundary {
+				addr = (addr / self.boundary + 1) * self.boundary;
+			}
+		}
+
+		debug_assert!(addr.is_multiple_of(align));
+		if addr + size <= self.limit {
+			Some(addr)
+		} else {
+			None
+		}
+	}
+}
+
+impl Default for AddrConstraint {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate `size` blocks aligned to `align` blocks, at an address that satisfies `constraint`.
+	/// The allocation is placed at the lowest address that does. Note that `align` is measured in units of `B`.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{AddrConstraint, Stalloc};
+	///
+	/// let alloc = Stalloc::<64, 16>::new();
+	/// let constraint = AddrConstraint::new().no_crossing(256);
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks_constrained(10, 1, constraint) }.unwrap();
+	/// let start = ptr.addr().get();
+	/// assert_eq!(start / 256, (start + 10 * 16 - 1) / 256);
+	/// ```
+	pub unsafe fn allocate_blocks_constrained(
+		&self,
+		size: usize,
+		align: usize,
+		constraint: AddrConstraint,
+	) -> Result<NonNull<u8>, AllocError> {
+		// Assert unsafe preconditions.
+		precondition!(
+			size >= 1 && align.is_power_of_two() && align <= MAX_ALIGN / B,
+			"`size` must be nonzero and `align` must be a power of 2 in `1..=MAX_ALIGN / B`"
+		);
+
+		let guarded_size = size + 2 * self.guard_blocks();
+
+		unsafe {
+			// `prev` and `curr` are pointers that run through the free list.
+			let base = self.base.get();
+			let mut prev = base;
+
+			while !self.is_free_list_empty() {
+				let curr = self.header_at((*prev).next.into());
+				let chunk_end = curr.addr() + usize::from((*curr).length) * B;
+
+				// Find the first suitably aligned address in the chunk (after the guard blocks),
+				// then move it up until it satisfies the constraint.
+				let first = (curr.addr() + self.guard_blocks() * B).next_multiple_of(align * B);
+				if let Some(addr) = constraint.place(first, size * B, align * B)
+					&& addr + (size + self.guard_blocks()) * B <= chunk_end
+				{
+					let spare_front = (addr - curr.addr()) / B - self.guard_blocks();
+					self.split_chunk(prev, curr, spare_front, guarded_size);
+
+					let idx = self.index_of(curr) + spare_front + self.guard_blocks();
+					self.on_alloc(idx, size, align);
+					return Ok(NonNull::new_unchecked(self.block_at(idx).cast()));
+				}
+
+				// Stop at the end of the free list, or once the chunks are above the limit.
+				if (*curr).next == 0 || first >= constraint.limit {
+					break;
+				}
+				prev = curr;
+			}
+		}
+
+		// Quarantined and cached blocks are released before giving up.
+		#[cfg(feature = "quarantine")]
+		if self.try_flush_quarantine() {
+			return unsafe { self.allocate_blocks_constrained(size, align, constraint) };
+		}
+		#[cfg(feature = "free-cache")]
+		if self.try_flush_free_cache() {
+			return unsafe { self.allocate_blocks_constrained(size, align, constraint) };
+		}
+
+		self.on_alloc_failed(size, align);
+		Err(AllocError)
+	}
+}
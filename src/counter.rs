@@ -0,0 +1,368 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt::{self, Debug, Formatter};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{AllocChain, AllocatorStats, ChainableAlloc, OwnedAllocChain};
+
+/// A wrapper around any allocator that counts the bytes and allocations it hands out.
+///
+/// This gives allocators without statistics of their own, like the system allocator, the same accounting
+/// as a `Stalloc`. Sizes are taken from the layouts that were requested, so they don't include any
+/// rounding done by the inner allocator.
+///
+/// # Examples
+/// ```
+/// use stalloc::AllocCounter;
+/// use std::alloc::{GlobalAlloc, Layout, System};
+///
+/// let alloc = AllocCounter::new(System);
+/// let layout = Layout::new::<[u8; 100]>();
+///
+/// unsafe {
+///     let ptr = alloc.alloc(layout);
+///     assert_eq!(alloc.live_bytes(), 100);
+///     assert_eq!(alloc.live_allocations(), 1);
+///
+///     alloc.dealloc(ptr, layout);
+/// }
+///
+/// assert_eq!(alloc.live_bytes(), 0);
+/// assert_eq!(alloc.total_allocations(), 1);
+/// assert_eq!(alloc.total_bytes(), 100);
+/// ```
+pub struct AllocCounter<A> {
+	inner: A,
+	live_bytes: AtomicUsize,
+	live_allocations: AtomicUsize,
+	peak_bytes: AtomicUsize,
+	total_allocations: AtomicUsize,
+	total_bytes: AtomicUsize,
+	failed_allocations: AtomicUsize,
+}
+
+impl<A> AllocCounter<A> {
+	/// Wraps `inner`, with every counter set to zero.
+	pub const fn new(inner: A) -> Self {
+		Self {
+			inner,
+			live_bytes: AtomicUsize::new(0),
+			live_allocations: AtomicUsize::new(0),
+			peak_bytes: AtomicUsize::new(0),
+			total_allocations: AtomicUsize::new(0),
+			total_bytes: AtomicUsize::new(0),
+			failed_allocations: AtomicUsize::new(0),
+		}
+	}
+
+	/// Returns the number of bytes that are currently allocated.
+	pub fn live_bytes(&self) -> usize {
+		self.live_bytes.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of allocations that are currently live.
+	pub fn live_allocations(&self) -> usize {
+		self.live_allocations.load(Ordering::Relaxed)
+	}
+
+	/// Returns the highest number of bytes that were ever allocated at the same time.
+	pub fn peak_bytes(&self) -> usize {
+		self.peak_bytes.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of successful allocations that have been made so far.
+	pub fn total_allocations(&self) -> usize {
+		self.total_allocations.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of bytes that have been handed out so far, including growth of existing allocations.
+	pub fn total_bytes(&self) -> usize {
+		self.total_bytes.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of allocations (and grows) that the inner allocator failed.
+	pub fn failed_allocations(&self) -> usize {
+		self.failed_allocations.load(Ordering::Relaxed)
+	}
+
+	/// Resets the cumulative counters to zero, and the peak to the current number of live bytes.
+	pub fn reset_totals(&self) {
+		self.total_allocations.store(0, Ordering::Relaxed);
+		self.total_bytes.store(0, Ordering::Relaxed);
+		self.failed_allocations.store(0, Ordering::Relaxed);
+		self.peak_bytes.store(self.live_bytes(), Ordering::Relaxed);
+	}
+
+	/// Returns the wrapped allocator.
+	pub fn into_inner(self) -> A {
+		self.inner
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+
+	/// Records a new allocation of `size` bytes.
+	fn count_alloc(&self, size: usize) {
+		self.live_allocations.fetch_add(1, Ordering::Relaxed);
+		self.total_allocations.fetch_add(1, Ordering::Relaxed);
+		self.count_grow(size);
+	}
+
+	/// Records that `size` more bytes are allocated.
+	fn count_grow(&self, size: usize) {
+		let live = self.live_bytes.fetch_add(size, Ordering::Relaxed) + size;
+		self.peak_bytes.fetch_max(live, Ordering::Relaxed);
+		self.total_bytes.fetch_add(size, Ordering::Relaxed);
+	}
+
+	/// Records that `size` fewer bytes are allocated.
+	fn count_shrink(&self, size: usize) {
+		self.live_bytes.fetch_sub(size, Ordering::Relaxed);
+	}
+
+	/// Records that an allocation of `size` bytes was freed.
+	fn count_dealloc(&self, size: usize) {
+		self.live_allocations.fetch_sub(1, Ordering::Relaxed);
+		self.count_shrink(size);
+	}
+
+	/// Records that an allocation was resized from `old_size` to `new_size` bytes.
+	fn count_resize(&self, old_size: usize, new_size: usize) {
+		if new_size > old_size {
+			self.count_grow(new_size - old_size);
+		} else {
+			self.count_shrink(old_size - new_size);
+		}
+	}
+
+	/// Records that the inner allocator failed to allocate memory.
+	fn count_failure(&self) {
+		self.failed_allocations.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Counts the result of allocating `layout` from the inner allocator, and returns it.
+	fn counted_alloc(&self, ptr: *mut u8, layout: Layout) -> *mut u8 {
+		if ptr.is_null() {
+			self.count_failure();
+		} else {
+			self.count_alloc(layout.size());
+		}
+		ptr
+	}
+
+	/// Counts the result of resizing an allocation of `layout` to `new_size` bytes, and returns it.
+	fn counted_realloc(&self, new_ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		if new_ptr.is_null() {
+			self.count_failure();
+		} else {
+			self.count_resize(layout.size(), new_size);
+		}
+		new_ptr
+	}
+}
+
+impl<A> Deref for AllocCounter<A> {
+	type Target = A;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl<A> Debug for AllocCounter<A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AllocCounter")
+			.field("live_bytes", &self.live_bytes())
+			.field("live_allocations", &self.live_allocations())
+			.field("peak_bytes", &self.peak_bytes())
+			.field("total_allocations", &self.total_allocations())
+			.field("total_bytes", &self.total_bytes())
+			.field("failed_allocations", &self.failed_allocations())
+			.finish_non_exhaustive()
+	}
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for AllocCounter<A> {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		self.counted_alloc(unsafe { self.inner.alloc(layout) }, layout)
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		self.counted_alloc(unsafe { self.inner.alloc_zeroed(layout) }, layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.dealloc(ptr, layout) };
+		self.count_dealloc(layout.size());
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		self.counted_realloc(
+			unsafe { self.inner.realloc(ptr, layout, new_size) },
+			layout,
+			new_size,
+		)
+	}
+}
+
+/// Forwards the allocator's behavior in a chain, and counts like `GlobalAlloc`.
+unsafe impl<A: ChainableAlloc> ChainableAlloc for AllocCounter<A> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.inner.addr_in_bounds(addr)
+	}
+
+	fn chain_aware(&self) -> bool {
+		self.inner.chain_aware()
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		self.counted_alloc(unsafe { self.inner.alloc_in_chain(layout, zeroed) }, layout)
+	}
+
+	unsafe fn realloc_in_chain(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		self.counted_realloc(
+			unsafe { self.inner.realloc_in_chain(ptr, layout, new_size) },
+			layout,
+			new_size,
+		)
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.dealloc_in_chain(ptr, layout) };
+		self.count_dealloc(layout.size());
+	}
+}
+
+/// The capacity of the inner allocator is unknown, so `capacity_bytes()` returns `usize::MAX`.
+impl<A> AllocatorStats for AllocCounter<A> {
+	fn capacity_bytes(&self) -> usize {
+		usize::MAX
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.live_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		Some(self.peak_bytes())
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		Some(self.total_allocations())
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		Some(self.total_allocations() - self.live_allocations())
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		Some(self.failed_allocations())
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<A> Allocator for &AllocCounter<A>
+where
+	for<'a> &'a A: Allocator,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let res = (&self.inner).allocate(layout);
+		match res {
+			Ok(_) => self.count_alloc(layout.size()),
+			Err(_) => self.count_failure(),
+		}
+		res
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let res = (&self.inner).allocate_zeroed(layout);
+		match res {
+			Ok(_) => self.count_alloc(layout.size()),
+			Err(_) => self.count_failure(),
+		}
+		res
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).deallocate(ptr, layout) };
+		self.count_dealloc(layout.size());
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow(ptr, old_layout, new_layout) };
+		match res {
+			Ok(_) => self.count_resize(old_layout.size(), new_layout.size()),
+			Err(_) => self.count_failure(),
+		}
+		res
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow_zeroed(ptr, old_layout, new_layout) };
+		match res {
+			Ok(_) => self.count_resize(old_layout.size(), new_layout.size()),
+			Err(_) => self.count_failure(),
+		}
+		res
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).shrink(ptr, old_layout, new_layout) };
+		if res.is_ok() {
+			self.count_resize(old_layout.size(), new_layout.size());
+		}
+		res
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
@@ -0,0 +1,137 @@
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use critical_section::RestoreState;
+
+use crate::UnsafeStalloc;
+use crate::align::{Align, Alignment};
+use crate::lockwrap::locked_stalloc_api;
+
+/// A wrapper around `UnsafeStalloc` that accesses the allocator inside a critical section, using the
+/// `critical-section` crate. This requires the `critical-section` feature.
+///
+/// On single-core embedded targets, a critical section usually disables interrupts, which makes this
+/// allocator safe to use as a `#[global_allocator]` even when interrupt handlers allocate. Every
+/// operation keeps interrupts disabled for as long as it runs, so the interrupt latency grows with the
+/// number of free chunks that have to be searched.
+///
+/// The program must link in a `critical-section` implementation, which is usually provided by the
+/// crate for the target architecture (such as `cortex-m` or `riscv`).
+///
+/// The most common operations are available directly. Every other method of `Stalloc` can be called
+/// inside `with_locked()`.
+///
+/// # Examples
+/// ```
+/// use stalloc::CriticalSectionStalloc;
+///
+/// #[global_allocator]
+/// static GLOBAL: CriticalSectionStalloc<1000, 4> = CriticalSectionStalloc::new();
+///
+/// fn main() {
+///     let v = vec![1, 2, 3];
+///     assert!(!GLOBAL.is_empty());
+/// }
+/// ```
+pub struct CriticalSectionStalloc<const L: usize, const B: usize>(UnsafeStalloc<L, B>)
+where
+	Align<B>: Alignment;
+
+/// A critical section around `CriticalSectionStalloc`.
+///
+/// Constructing this type is proof that we have exclusive access to the inner `UnsafeStalloc`.
+/// When this falls out of scope, the critical section ends.
+///
+/// Critical sections must be released in the reverse order in which they were acquired, so this type
+/// is never handed out to users, and is only ever used as a temporary.
+struct CriticalSectionGuard<'a, const L: usize, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a CriticalSectionStalloc<L, B>,
+	state: RestoreState,
+	_not_sync: PhantomData<*const ()>,
+}
+
+impl<const L: usize, const B: usize> Deref for CriticalSectionGuard<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	type Target = UnsafeStalloc<L, B>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.alloc.0
+	}
+}
+
+impl<const L: usize, const B: usize> Drop for CriticalSectionGuard<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		// SAFETY: The critical section was acquired when this guard was created, and guards are only
+		// used as temporaries, so critical sections are released in reverse order.
+		unsafe { critical_section::release(self.state) };
+	}
+}
+
+impl<const L: usize, const B: usize> CriticalSectionStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new empty `CriticalSectionStalloc` instance.
+	#[must_use]
+	pub const fn new() -> Self {
+		// SAFETY: The `UnsafeStalloc` can only be accessed through `lock()`,
+		// which enters a critical section before proceeding.
+		Self(unsafe { UnsafeStalloc::new() })
+	}
+
+	/// Initializes a new `CriticalSectionStalloc` instance in which the `size` blocks starting at block index `idx`
+	/// are reserved. See `Stalloc::with_reserved()` for details.
+	///
+	/// # Panics
+	///
+	/// Panics if `size` is zero or the reserved blocks are out of bounds.
+	#[must_use]
+	pub const fn with_reserved(idx: usize, size: usize) -> Self {
+		// SAFETY: See `new()`.
+		Self(unsafe { UnsafeStalloc::with_reserved(idx, size) })
+	}
+
+	/// Runs `f` inside a critical section, giving it exclusive access to the allocator. This can be used
+	/// to chain multiple operations on the allocator, or to call methods that `CriticalSectionStalloc`
+	/// doesn't provide directly.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::CriticalSectionStalloc;
+	///
+	/// let alloc = CriticalSectionStalloc::<100, 4>::new();
+	///
+	/// alloc.with_locked(|alloc| {
+	///     for _ in 0..20 {
+	///         unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	///     }
+	/// });
+	///
+	/// assert!(alloc.is_oom());
+	/// ```
+	pub fn with_locked<R>(&self, f: impl FnOnce(&UnsafeStalloc<L, B>) -> R) -> R {
+		f(&self.lock())
+	}
+
+	/// Enters a critical section, which lasts until the returned guard is dropped.
+	fn lock(&self) -> CriticalSectionGuard<'_, L, B> {
+		// SAFETY: The critical section is released when the guard is dropped.
+		let state = unsafe { critical_section::acquire() };
+
+		CriticalSectionGuard {
+			alloc: self,
+			state,
+			_not_sync: PhantomData,
+		}
+	}
+}
+
+locked_stalloc_api!(CriticalSectionStalloc<L, B>, 0, lock);
@@ -0,0 +1,100 @@
+use core::fmt::{self, Write};
+
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Writes the state of the allocator as a Graphviz DOT graph.
+	///
+	/// Every region of the buffer becomes a node, laid out from left to right in order of increasing address.
+	/// Free chunks are linked together in the order of the free list, starting from the `base` node.
+	/// Used regions are shown as a single node, unless the `tracking` feature is enabled, in which case
+	/// every live allocation gets its own node.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing to `w` fails.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<12, 4>::new();
+	/// let ptr = unsafe { alloc.allocate_blocks(3, 1) }.unwrap();
+	///
+	/// let mut dot = String::new();
+	/// alloc.to_dot(&mut dot).unwrap();
+	/// assert!(dot.starts_with("digraph stalloc {"));
+	/// assert!(dot.contains("base -> free3"));
+	/// ```
+	pub fn to_dot(&self, w: &mut impl Write) -> fmt::Result {
+		writeln!(w, "digraph stalloc {{")?;
+		writeln!(w, "\trankdir=LR;")?;
+		writeln!(w, "\tnode [shape=record];")?;
+		writeln!(w, "\tbase [label=\"base\", shape=ellipse];")?;
+
+		// Write a node for every region, linking each one to the previous one to keep them in address order.
+		let mut prev = None;
+		let mut used_start = 0;
+
+		for (idx, length) in self.free_chunks().chain([(L, 0)]) {
+			// Everything between the previous free chunk and this one is in use.
+			while used_start < idx {
+				#[cfg(feature = "tracking")]
+				let length = match self.tracked_length(used_start) {
+					0 => idx - used_start,
+					length => length,
+				};
+				#[cfg(not(feature = "tracking"))]
+				let length = idx - used_start;
+
+				write_region(w, &mut prev, "used", used_start, length)?;
+				used_start += length;
+			}
+
+			if length > 0 {
+				write_region(w, &mut prev, "free", idx, length)?;
+			}
+			used_start = idx + length;
+		}
+
+		// Follow the free list.
+		let mut prev = None;
+		for (idx, _) in self.free_chunks() {
+			match prev.replace(idx) {
+				Some(prev_idx) => writeln!(w, "\tfree{prev_idx} -> free{idx};")?,
+				None => writeln!(w, "\tbase -> free{idx};")?,
+			}
+		}
+
+		writeln!(w, "}}")
+	}
+}
+
+/// Writes a node for a region of `length` blocks at `idx`, linking it invisibly to the previous region.
+fn write_region(
+	w: &mut impl Write,
+	prev: &mut Option<(&'static str, usize)>,
+	kind: &'static str,
+	idx: usize,
+	length: usize,
+) -> fmt::Result {
+	let color = if kind == "free" {
+		"palegreen"
+	} else {
+		"lightcoral"
+	};
+	writeln!(
+		w,
+		"\t{kind}{idx} [label=\"{kind} | index {idx} | {length} block(s)\", style=filled, fillcolor={color}];"
+	)?;
+
+	if let Some((prev_kind, prev_idx)) = prev.replace((kind, idx)) {
+		writeln!(w, "\t{prev_kind}{prev_idx} -> {kind}{idx} [style=invis];")?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,399 @@
+use core::alloc::Layout;
+use core::fmt::{self, Debug, Formatter};
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{
+	AllocError, AllocReport, AllocatorStats, BlockAlloc, CorruptionError, SpinStalloc, Stalloc,
+	Strategy, UnsafeStalloc,
+};
+
+/// The operations that a `StallocDyn` can perform on the allocator it refers to.
+trait ErasedStalloc: BlockAlloc + AllocatorStats {
+	fn block_count(&self) -> usize;
+	fn is_oom(&self) -> bool;
+	fn is_empty(&self) -> bool;
+	fn live_allocations(&self) -> usize;
+	fn report(&self) -> AllocReport;
+	fn validate(&self) -> Result<(), CorruptionError>;
+	#[cfg(feature = "stats")]
+	fn stats(&self) -> crate::Stats;
+}
+
+impl<const L: usize, const B: usize, S: Strategy> ErasedStalloc for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn block_count(&self) -> usize {
+		L
+	}
+
+	fn is_oom(&self) -> bool {
+		self.is_oom()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.is_empty()
+	}
+
+	fn live_allocations(&self) -> usize {
+		self.live_allocations()
+	}
+
+	fn report(&self) -> AllocReport {
+		self.report()
+	}
+
+	fn validate(&self) -> Result<(), CorruptionError> {
+		self.validate()
+	}
+
+	#[cfg(feature = "stats")]
+	fn stats(&self) -> crate::Stats {
+		self.stats()
+	}
+}
+
+impl<const L: usize, const B: usize> ErasedStalloc for UnsafeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_count(&self) -> usize {
+		L
+	}
+
+	fn is_oom(&self) -> bool {
+		self.0.is_oom()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	fn live_allocations(&self) -> usize {
+		self.0.live_allocations()
+	}
+
+	fn report(&self) -> AllocReport {
+		self.0.report()
+	}
+
+	fn validate(&self) -> Result<(), CorruptionError> {
+		self.0.validate()
+	}
+
+	#[cfg(feature = "stats")]
+	fn stats(&self) -> crate::Stats {
+		self.0.stats()
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> ErasedStalloc for crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_count(&self) -> usize {
+		L
+	}
+
+	fn is_oom(&self) -> bool {
+		self.is_oom()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.is_empty()
+	}
+
+	fn live_allocations(&self) -> usize {
+		self.live_allocations()
+	}
+
+	fn report(&self) -> AllocReport {
+		self.report()
+	}
+
+	fn validate(&self) -> Result<(), CorruptionError> {
+		self.validate()
+	}
+
+	#[cfg(feature = "stats")]
+	fn stats(&self) -> crate::Stats {
+		self.stats()
+	}
+}
+
+impl<const L: usize, const B: usize> ErasedStalloc for SpinStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn block_count(&self) -> usize {
+		L
+	}
+
+	fn is_oom(&self) -> bool {
+		self.is_oom()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.is_empty()
+	}
+
+	fn live_allocations(&self) -> usize {
+		self.live_allocations()
+	}
+
+	fn report(&self) -> AllocReport {
+		self.report()
+	}
+
+	fn validate(&self) -> Result<(), CorruptionError> {
+		self.validate()
+	}
+
+	#[cfg(feature = "stats")]
+	fn stats(&self) -> crate::Stats {
+		self.stats()
+	}
+}
+
+/// A type-erased reference to a `Stalloc`, `UnsafeStalloc`, `SyncStalloc` or `SpinStalloc`, which hides
+/// the const generics behind a vtable.
+///
+/// This lets code that can't name the concrete allocator type, like a plugin that is compiled separately
+/// from its host, use the allocator and query its state. A `StallocDyn` is created with `From`, and it is
+/// `Copy`, so it can be passed around freely. Since it is only a reference, it also implements `Allocator`
+/// by value.
+///
+/// # Examples
+/// ```
+/// use core::alloc::Layout;
+/// use stalloc::{Stalloc, StallocDyn};
+///
+/// fn plugin(alloc: StallocDyn<'_>) {
+///     let layout = Layout::new::<u64>();
+///     let ptr = alloc.allocate(layout).unwrap();
+///     assert!(!alloc.is_empty());
+///     unsafe { alloc.deallocate(ptr.cast(), layout) };
+/// }
+///
+/// let alloc = Stalloc::<100, 8>::new();
+/// plugin(StallocDyn::from(&alloc));
+///
+/// assert!(alloc.is_empty());
+/// ```
+#[derive(Clone, Copy)]
+pub struct StallocDyn<'a> {
+	inner: &'a dyn ErasedStalloc,
+}
+
+impl<'a, const L: usize, const B: usize, S: Strategy> From<&'a Stalloc<L, B, S>> for StallocDyn<'a>
+where
+	Align<B>: Alignment,
+{
+	fn from(alloc: &'a Stalloc<L, B, S>) -> Self {
+		Self { inner: alloc }
+	}
+}
+
+impl<'a, const L: usize, const B: usize> From<&'a UnsafeStalloc<L, B>> for StallocDyn<'a>
+where
+	Align<B>: Alignment,
+{
+	fn from(alloc: &'a UnsafeStalloc<L, B>) -> Self {
+		Self { inner: alloc }
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'a, const L: usize, const B: usize> From<&'a crate::SyncStalloc<L, B>> for StallocDyn<'a>
+where
+	Align<B>: Alignment,
+{
+	fn from(alloc: &'a crate::SyncStalloc<L, B>) -> Self {
+		Self { inner: alloc }
+	}
+}
+
+impl<'a, const L: usize, const B: usize> From<&'a SpinStalloc<L, B>> for StallocDyn<'a>
+where
+	Align<B>: Alignment,
+{
+	fn from(alloc: &'a SpinStalloc<L, B>) -> Self {
+		Self { inner: alloc }
+	}
+}
+
+impl StallocDyn<'_> {
+	/// Returns the size of a block in bytes.
+	#[must_use]
+	pub fn block_size(self) -> usize {
+		self.inner.block_size()
+	}
+
+	/// Returns the number of blocks in the allocator.
+	#[must_use]
+	pub fn block_count(self) -> usize {
+		self.inner.block_count()
+	}
+
+	/// Checks if the allocator is completely out of memory. See `Stalloc::is_oom()` for details.
+	#[must_use]
+	pub fn is_oom(self) -> bool {
+		self.inner.is_oom()
+	}
+
+	/// Checks if the allocator is empty. See `Stalloc::is_empty()` for details.
+	#[must_use]
+	pub fn is_empty(self) -> bool {
+		self.inner.is_empty()
+	}
+
+	/// Returns the number of live allocations. See `Stalloc::live_allocations()` for details.
+	#[must_use]
+	pub fn live_allocations(self) -> usize {
+		self.inner.live_allocations()
+	}
+
+	/// Returns a snapshot of the allocator's memory usage. See `Stalloc::report()` for details.
+	#[must_use]
+	pub fn report(self) -> AllocReport {
+		self.inner.report()
+	}
+
+	/// Walks the free list and checks that it is consistent. See `Stalloc::validate()` for details.
+	///
+	/// # Errors
+	///
+	/// Returns a `CorruptionError` describing the first inconsistency that was found.
+	pub fn validate(self) -> Result<(), CorruptionError> {
+		self.inner.validate()
+	}
+
+	/// Returns the allocator's statistics. This requires the `stats` feature.
+	#[cfg(feature = "stats")]
+	#[must_use]
+	pub fn stats(self) -> crate::Stats {
+		self.inner.stats()
+	}
+
+	/// Tries to allocate memory for `layout`. See `BlockAlloc::allocate()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	pub fn allocate(self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.inner.allocate(layout)
+	}
+
+	/// Deallocates memory. See `BlockAlloc::deallocate()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been allocated by this allocator with `layout`.
+	pub unsafe fn deallocate(self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.deallocate(ptr, layout) };
+	}
+}
+
+impl Debug for StallocDyn<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("StallocDyn")
+			.field("block_size", &self.block_size())
+			.field("block_count", &self.block_count())
+			.field("report", &self.report())
+			.finish()
+	}
+}
+
+unsafe impl BlockAlloc for StallocDyn<'_> {
+	fn block_size(&self) -> usize {
+		self.inner.block_size()
+	}
+
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.inner.allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.inner.allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.deallocate(ptr, layout) };
+	}
+
+	unsafe fn reallocate(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.reallocate(ptr, old_layout, new_layout) }
+	}
+}
+
+impl AllocatorStats for StallocDyn<'_> {
+	fn capacity_bytes(&self) -> usize {
+		self.inner.capacity_bytes()
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.inner.used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.inner.peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.inner.allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.inner.deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.inner.failed_allocations()
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl crate::Allocator for StallocDyn<'_> {
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.inner.allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.inner.allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.deallocate(ptr, layout) };
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.grow(ptr, old_layout, new_layout) }
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.shrink(ptr, old_layout, new_layout) }
+	}
+}
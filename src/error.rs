@@ -0,0 +1,172 @@
+use core::alloc::Layout;
+use core::fmt::{self, Display, Formatter};
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy};
+
+/// The reason why an allocation failed, as reported by `allocate_blocks_detailed()`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AllocErrorKind {
+	/// The allocation is larger than the whole allocator, so it can never succeed.
+	TooLarge,
+	/// No part of the allocator that is large enough is aligned to the requested alignment, so the
+	/// allocation can never succeed.
+	AlignTooLarge,
+	/// There are enough free blocks in total, but not enough contiguous (and suitably aligned) ones.
+	/// The allocation may succeed once neighboring allocations are freed.
+	Fragmented,
+	/// There aren't enough free blocks in total. The allocation may succeed once other allocations are freed.
+	Exhausted,
+}
+
+/// An allocation error that describes why the allocation failed, returned by `allocate_blocks_detailed()`.
+/// It can be converted into a plain `AllocError`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DetailedAllocError {
+	/// The reason why the allocation failed.
+	pub kind: AllocErrorKind,
+	/// The layout of the requested allocation, in bytes.
+	pub layout: Layout,
+}
+
+impl DetailedAllocError {
+	/// Checks whether the allocation can never succeed, no matter what is freed.
+	#[must_use]
+	pub const fn is_permanent(&self) -> bool {
+		matches!(
+			self.kind,
+			AllocErrorKind::TooLarge | AllocErrorKind::AlignTooLarge
+		)
+	}
+}
+
+impl Display for DetailedAllocError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let reason = match self.kind {
+			AllocErrorKind::TooLarge => "it is larger than the allocator",
+			AllocErrorKind::AlignTooLarge => {
+				"the allocator has no block that is aligned this strictly"
+			}
+			AllocErrorKind::Fragmented => "there is no large enough free chunk",
+			AllocErrorKind::Exhausted => "there aren't enough free blocks",
+		};
+		write!(
+			f,
+			"failed to allocate {} bytes aligned to {} bytes: {reason}",
+			self.layout.size(),
+			self.layout.align()
+		)
+	}
+}
+
+impl core::error::Error for DetailedAllocError {}
+
+impl From<DetailedAllocError> for AllocError {
+	fn from(_: DetailedAllocError) -> Self {
+		Self
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate `size` blocks like `allocate_blocks()`, but describes why the allocation failed.
+	/// Note that `align` is measured in units of `B`.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `DetailedAllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{AllocErrorKind, Stalloc};
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	///
+	/// let err = unsafe { alloc.allocate_blocks_detailed(20, 1) }.unwrap_err();
+	/// assert_eq!(err.kind, AllocErrorKind::TooLarge);
+	/// assert_eq!(err.layout.size(), 80);
+	///
+	/// let a = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+	/// let b = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(a, 4) };
+	///
+	/// // 8 blocks are free, but they are split into two chunks of 4.
+	/// let err = unsafe { alloc.allocate_blocks_detailed(6, 1) }.unwrap_err();
+	/// assert_eq!(err.kind, AllocErrorKind::Fragmented);
+	///
+	/// let err = unsafe { alloc.allocate_blocks_detailed(9, 1) }.unwrap_err();
+	/// assert_eq!(err.kind, AllocErrorKind::Exhausted);
+	///
+	/// // An alignment of 1 GiB is allowed, but the allocator is far too small to contain such an address.
+	/// let err = unsafe { alloc.allocate_blocks_detailed(1, (1 << 30) / 4) }.unwrap_err();
+	/// assert_eq!(err.kind, AllocErrorKind::AlignTooLarge);
+	/// assert!(err.is_permanent());
+	/// ```
+	pub unsafe fn allocate_blocks_detailed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, DetailedAllocError> {
+		unsafe { self.allocate_blocks(size, align) }.map_err(|_| DetailedAllocError {
+			kind: self.failure_kind(size, align),
+			// SAFETY: `align * B` is a power of two, and the caller upheld that `size` is reasonable.
+			layout: unsafe { Layout::from_size_align_unchecked(size * B, align * B) },
+		})
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate `size` blocks, and describes why the allocation failed.
+	/// See `Stalloc::allocate_blocks_detailed()` for details.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `DetailedAllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks_detailed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, DetailedAllocError> {
+		unsafe { self.acquire_locked().allocate_blocks_detailed(size, align) }
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Classifies why an allocation of `size` blocks aligned to `align` blocks failed.
+	fn failure_kind(&self, size: usize, align: usize) -> AllocErrorKind {
+		let guarded_size = size + 2 * self.guard_blocks();
+
+		// The number of blocks before the first one that the allocation could start at.
+		let spare_front =
+			(unsafe { self.block_at(0) }.addr() / B + self.guard_blocks()).wrapping_neg() % align;
+
+		if guarded_size > L {
+			AllocErrorKind::TooLarge
+		} else if spare_front + guarded_size > L {
+			AllocErrorKind::AlignTooLarge
+		} else if self.report().free_blocks < guarded_size {
+			AllocErrorKind::Exhausted
+		} else {
+			AllocErrorKind::Fragmented
+		}
+	}
+}
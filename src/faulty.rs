@@ -0,0 +1,244 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt::{self, Debug, Formatter};
+use core::ops::Deref;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{AllocChain, ChainableAlloc, OwnedAllocChain};
+
+/// Decides which allocations a `FaultyAlloc` fails.
+enum Policy<F> {
+	EveryNth(usize),
+	Custom(F),
+}
+
+/// A wrapper around an allocator that deliberately fails some allocations, in order to test how
+/// a program handles running out of memory. This requires the `fault-injection` feature.
+///
+/// Every request that needs new memory (allocating, and growing or reallocating an existing allocation)
+/// is numbered, starting from 0. Depending on how the wrapper was created, it fails either every `n`th request,
+/// or those for which a closure returns `true`. All other requests, as well as deallocations and shrinks,
+/// are passed on to the inner allocator.
+///
+/// # Examples
+/// ```
+/// use stalloc::{FaultyAlloc, SyncStalloc};
+/// use std::alloc::{GlobalAlloc, Layout};
+///
+/// // Fail every third allocation.
+/// let alloc = FaultyAlloc::every_nth(SyncStalloc::<100, 4>::new(), 3);
+/// let layout = Layout::new::<u32>();
+///
+/// unsafe {
+///     assert!(!alloc.alloc(layout).is_null());
+///     assert!(!alloc.alloc(layout).is_null());
+///     assert!(alloc.alloc(layout).is_null());
+/// }
+///
+/// // Fail every allocation that is larger than 16 bytes.
+/// let alloc = FaultyAlloc::new(SyncStalloc::<100, 4>::new(), |_, layout| layout.size() > 16);
+///
+/// unsafe {
+///     assert!(!alloc.alloc(Layout::new::<[u8; 16]>()).is_null());
+///     assert!(alloc.alloc(Layout::new::<[u8; 17]>()).is_null());
+/// }
+/// ```
+pub struct FaultyAlloc<A, F = fn(usize, Layout) -> bool> {
+	inner: A,
+	policy: Policy<F>,
+	count: AtomicUsize,
+}
+
+impl<A> FaultyAlloc<A> {
+	/// Wraps `inner`, failing every `n`th request for memory. If `n` is 0, no requests are failed.
+	pub const fn every_nth(inner: A, n: usize) -> Self {
+		Self {
+			inner,
+			policy: Policy::EveryNth(n),
+			count: AtomicUsize::new(0),
+		}
+	}
+}
+
+impl<A, F: Fn(usize, Layout) -> bool> FaultyAlloc<A, F> {
+	/// Wraps `inner`, failing the requests for which `should_fail` returns `true`. The closure is
+	/// passed the number of the request and the requested layout.
+	pub const fn new(inner: A, should_fail: F) -> Self {
+		Self {
+			inner,
+			policy: Policy::Custom(should_fail),
+			count: AtomicUsize::new(0),
+		}
+	}
+
+	/// Returns the number of requests for memory that have been made so far, including failed ones.
+	pub fn request_count(&self) -> usize {
+		self.count.load(Ordering::Relaxed)
+	}
+
+	/// Resets the request counter to zero.
+	pub fn reset_count(&self) {
+		self.count.store(0, Ordering::Relaxed);
+	}
+
+	/// Numbers a new request for `layout`, and decides whether it should fail.
+	fn should_fail(&self, layout: Layout) -> bool {
+		let n = self.count.fetch_add(1, Ordering::Relaxed);
+
+		match &self.policy {
+			// Note that nothing is a multiple of 0, except for 0 itself.
+			Policy::EveryNth(every) => (n + 1).is_multiple_of(*every),
+			Policy::Custom(should_fail) => should_fail(n, layout),
+		}
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+}
+
+impl<A, F> Deref for FaultyAlloc<A, F> {
+	type Target = A;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl<A: Debug, F> Debug for FaultyAlloc<A, F> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self.inner)
+	}
+}
+
+unsafe impl<A: GlobalAlloc, F: Fn(usize, Layout) -> bool> GlobalAlloc for FaultyAlloc<A, F> {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		if self.should_fail(layout) {
+			return ptr::null_mut();
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.alloc(layout) }
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		if self.should_fail(layout) {
+			return ptr::null_mut();
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.alloc_zeroed(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.dealloc(ptr, layout) }
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: `new_size` is valid for `layout.align()`, which is upheld by the caller.
+		let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+		if new_size > layout.size() && self.should_fail(new_layout) {
+			return ptr::null_mut();
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.realloc(ptr, layout, new_size) }
+	}
+}
+
+unsafe impl<A: ChainableAlloc, F> ChainableAlloc for FaultyAlloc<A, F> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.inner.addr_in_bounds(addr)
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<A, F: Fn(usize, Layout) -> bool> Allocator for &FaultyAlloc<A, F>
+where
+	for<'a> &'a A: Allocator,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		if self.should_fail(layout) {
+			return Err(AllocError);
+		}
+
+		(&self.inner).allocate(layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		if self.should_fail(layout) {
+			return Err(AllocError);
+		}
+
+		(&self.inner).allocate_zeroed(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).deallocate(ptr, layout) }
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		if self.should_fail(new_layout) {
+			return Err(AllocError);
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).grow(ptr, old_layout, new_layout) }
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		if self.should_fail(new_layout) {
+			return Err(AllocError);
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).grow_zeroed(ptr, old_layout, new_layout) }
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).shrink(ptr, old_layout, new_layout) }
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
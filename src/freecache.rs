@@ -0,0 +1,171 @@
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy, as_u16};
+
+/// The maximum number of freed regions that can be cached for reuse. This requires the `free-cache` feature.
+pub const FREE_CACHE_CAPACITY: usize = 8;
+
+/// A stack of recently freed regions that have not been returned to the free list, as `(index, length)` pairs.
+#[derive(Clone, Copy)]
+pub struct FreeCache {
+	regions: [(u16, u16); FREE_CACHE_CAPACITY],
+	len: usize,
+	depth: usize,
+}
+
+impl FreeCache {
+	pub const fn new() -> Self {
+		Self {
+			regions: [(0, 0); FREE_CACHE_CAPACITY],
+			len: 0,
+			depth: FREE_CACHE_CAPACITY,
+		}
+	}
+
+	/// Removes the oldest region from the bottom of the stack.
+	fn pop_oldest(&mut self) -> Option<(usize, usize)> {
+		if self.len == 0 {
+			return None;
+		}
+
+		let (idx, length) = self.regions[0];
+		self.regions.copy_within(1..self.len, 0);
+		self.len -= 1;
+		Some((idx.into(), length.into()))
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Sets how many recently freed regions are cached for reuse. This requires the `free-cache` feature.
+	///
+	/// With the `free-cache` feature, freed blocks are not returned to the free list immediately. Instead,
+	/// the last `depth` deallocations are kept on a stack, and an allocation of exactly the same size
+	/// (and a compatible alignment) reuses the most recently freed one without walking the free list.
+	/// This makes repeatedly allocating and freeing objects of the same size O(1). When the cache is full,
+	/// the oldest region is returned to the free list. If an allocation can't be satisfied otherwise,
+	/// the cache is flushed and the allocation is retried.
+	///
+	/// The depth starts out at `FREE_CACHE_CAPACITY`, and a depth of 0 turns the cache off.
+	/// Cached blocks count as free in `is_oom()` and `is_empty()`, and are reported separately by `report()`.
+	/// With the `quarantine` feature, freed blocks only enter the cache once they leave the quarantine.
+	///
+	/// # Panics
+	///
+	/// Panics if `depth` is greater than `FREE_CACHE_CAPACITY`.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// # if cfg!(feature = "quarantine") { return; }
+	/// let alloc = Stalloc::<20, 4>::new();
+	/// alloc.set_free_cache_depth(1);
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(ptr, 5) };
+	/// assert_eq!(alloc.cached_blocks(), 5);
+	///
+	/// // An allocation of the same size reuses the cached blocks.
+	/// assert_eq!(unsafe { alloc.allocate_blocks(5, 1) }, Ok(ptr));
+	/// assert_eq!(alloc.cached_blocks(), 0);
+	/// ```
+	pub fn set_free_cache_depth(&self, depth: usize) {
+		assert!(
+			depth <= FREE_CACHE_CAPACITY,
+			"the free cache depth must be at most {FREE_CACHE_CAPACITY}"
+		);
+
+		unsafe { (*self.free_cache.get()).depth = depth };
+		self.trim_free_cache();
+	}
+
+	/// Returns how many recently freed regions are cached for reuse. This requires the `free-cache` feature.
+	pub const fn free_cache_depth(&self) -> usize {
+		unsafe { (*self.free_cache.get()).depth }
+	}
+
+	/// Returns the number of blocks that were freed, but are being cached for reuse.
+	/// This requires the `free-cache` feature.
+	pub fn cached_blocks(&self) -> usize {
+		let cache = unsafe { &*self.free_cache.get() };
+		cache.regions[..cache.len]
+			.iter()
+			.map(|&(_, length)| usize::from(length))
+			.sum()
+	}
+
+	/// Returns every cached block to the free list. This requires the `free-cache` feature.
+	pub fn flush_free_cache(&self) {
+		while let Some((idx, length)) = unsafe { (*self.free_cache.get()).pop_oldest() } {
+			unsafe { self.release_blocks(self.base.get(), idx, length) };
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Caches `length` freed blocks at `idx` for reuse, releasing the oldest cached region if the cache is full.
+	pub(crate) fn cache_blocks(&self, idx: usize, length: usize) {
+		let cache = unsafe { &mut *self.free_cache.get() };
+		if cache.depth == 0 {
+			unsafe { self.release_blocks(self.base.get(), idx, length) };
+			return;
+		}
+
+		if cache.len == cache.depth
+			&& let Some((idx, length)) = cache.pop_oldest()
+		{
+			unsafe { self.release_blocks(self.base.get(), idx, length) };
+		}
+
+		cache.regions[cache.len] = unsafe { (as_u16(idx), as_u16(length)) };
+		cache.len += 1;
+	}
+
+	/// Removes the most recently cached region of exactly `length` blocks that can hold an allocation
+	/// aligned to `align` blocks after its guard blocks, and returns its index.
+	pub(crate) fn take_cached(&self, length: usize, align: usize) -> Option<usize> {
+		let cache = unsafe { &mut *self.free_cache.get() };
+
+		let i = cache.regions[..cache.len].iter().rposition(|&(idx, len)| {
+			let block = unsafe { self.block_at(usize::from(idx) + self.guard_blocks()) };
+			usize::from(len) == length && (block.addr() / B).is_multiple_of(align)
+		})?;
+
+		let idx = cache.regions[i].0.into();
+		cache.regions.copy_within(i + 1..cache.len, i);
+		cache.len -= 1;
+		Some(idx)
+	}
+
+	/// Releases the oldest cached regions until there are at most `depth` of them.
+	fn trim_free_cache(&self) {
+		while unsafe { (*self.free_cache.get()).len > (*self.free_cache.get()).depth } {
+			if let Some((idx, length)) = unsafe { (*self.free_cache.get()).pop_oldest() } {
+				unsafe { self.release_blocks(self.base.get(), idx, length) };
+			}
+		}
+	}
+
+	/// Empties the cache without releasing anything, after the allocator was cleared.
+	pub(crate) fn clear_free_cache(&self) {
+		unsafe { (*self.free_cache.get()).len = 0 };
+	}
+
+	/// Checks whether no freed regions are being cached.
+	pub(crate) const fn is_free_cache_empty(&self) -> bool {
+		unsafe { (*self.free_cache.get()).len == 0 }
+	}
+
+	/// Flushes the cache, returning whether any blocks were released.
+	pub(crate) fn try_flush_free_cache(&self) -> bool {
+		let was_empty = self.is_free_cache_empty();
+		self.flush_free_cache();
+		!was_empty
+	}
+}
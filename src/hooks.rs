@@ -0,0 +1,106 @@
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+/// Callbacks that are invoked whenever a `Stalloc` hands out, frees, or resizes memory.
+/// This requires the `hooks` feature.
+///
+/// Every layout passed to a hook describes the region actually used by the allocator, so its
+/// size is always a multiple of `B`. Because the allocator does not remember the alignment of
+/// an allocation, the layouts passed to `on_deallocate()` and `on_resize()` have an alignment of `B`.
+///
+/// Hooks run while the allocator is being modified (and, for `SyncStalloc`, while its lock is held),
+/// so they must not panic or use the allocator they are attached to.
+///
+/// # Examples
+/// ```
+/// use stalloc::{Stalloc, StallocHooks};
+/// use std::alloc::Layout;
+/// use std::ptr::NonNull;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// struct CountBytes(AtomicUsize);
+///
+/// impl StallocHooks for CountBytes {
+///     fn on_allocate(&self, _ptr: NonNull<u8>, layout: Layout) {
+///         self.0.fetch_add(layout.size(), Ordering::Relaxed);
+///     }
+/// }
+///
+/// static COUNTER: CountBytes = CountBytes(AtomicUsize::new(0));
+///
+/// let alloc = Stalloc::<20, 4>::new();
+/// alloc.set_hooks(Some(&COUNTER));
+///
+/// unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+/// assert_eq!(COUNTER.0.load(Ordering::Relaxed), 20);
+/// ```
+pub trait StallocHooks: Sync {
+	/// Called after a new allocation was made.
+	fn on_allocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let _ = (ptr, layout);
+	}
+
+	/// Called after an allocation was freed.
+	fn on_deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let _ = (ptr, layout);
+	}
+
+	/// Called after an allocation was grown or shrunk in place.
+	fn on_resize(&self, ptr: NonNull<u8>, old_layout: Layout, new_layout: Layout) {
+		let _ = (ptr, old_layout, new_layout);
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Registers a set of hooks that are called on every allocation, deallocation and in-place resize,
+	/// replacing any previously registered hooks. Pass `None` to remove them.
+	/// This requires the `hooks` feature.
+	pub fn set_hooks(&self, hooks: Option<&'static dyn StallocHooks>) {
+		self.hooks.set(hooks);
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns the layout of a region of `size` blocks aligned to `align` blocks.
+	const fn block_layout(size: usize, align: usize) -> Layout {
+		// SAFETY: `align * B` is a power of two, and the region fits inside the allocator.
+		unsafe { Layout::from_size_align_unchecked(size * B, align * B) }
+	}
+
+	/// Returns a pointer to the block at `idx`, which must be in `0..L`.
+	const fn block_ptr(&self, idx: usize) -> NonNull<u8> {
+		unsafe { NonNull::new_unchecked(self.block_at(idx).cast()) }
+	}
+
+	pub(crate) fn hook_alloc(&self, idx: usize, size: usize, align: usize) {
+		if let Some(hooks) = self.hooks.get() {
+			hooks.on_allocate(self.block_ptr(idx), Self::block_layout(size, align));
+		}
+	}
+
+	pub(crate) fn hook_dealloc(&self, idx: usize, size: usize) {
+		if let Some(hooks) = self.hooks.get() {
+			hooks.on_deallocate(self.block_ptr(idx), Self::block_layout(size, 1));
+		}
+	}
+
+	pub(crate) fn hook_resize(&self, idx: usize, old_size: usize, new_size: usize) {
+		if let Some(hooks) = self.hooks.get() {
+			hooks.on_resize(
+				self.block_ptr(idx),
+				Self::block_layout(old_size, 1),
+				Self::block_layout(new_size, 1),
+			);
+		}
+	}
+}
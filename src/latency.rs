@@ -0,0 +1,333 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt::{self, Debug, Formatter};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+extern crate std;
+use std::time::Instant;
+
+use crate::{AllocChain, ChainableAlloc, OwnedAllocChain};
+
+/// The number of buckets in a `LatencyHistogram`. This requires the `latency` feature.
+///
+/// Bucket 0 counts operations that took less than a nanosecond, and bucket `i` counts operations that
+/// took between `2^(i - 1)` and `2^i` nanoseconds. The last bucket also counts everything slower than that.
+pub const LATENCY_BUCKETS: usize = 40;
+
+/// A histogram of operation latencies, with exponentially growing buckets. This requires the `latency` feature.
+///
+/// Recording a latency is a single atomic increment, so the histogram can be shared between threads.
+pub struct LatencyHistogram {
+	buckets: [AtomicU64; LATENCY_BUCKETS],
+}
+
+impl LatencyHistogram {
+	/// Creates an empty histogram.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS],
+		}
+	}
+
+	/// Records an operation that took `latency`.
+	pub fn record(&self, latency: Duration) {
+		let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+		let bucket = (u64::BITS - nanos.leading_zeros()) as usize;
+		self.buckets[bucket.min(LATENCY_BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Returns the number of operations in each bucket.
+	pub fn buckets(&self) -> [u64; LATENCY_BUCKETS] {
+		core::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed))
+	}
+
+	/// Returns the number of recorded operations.
+	pub fn count(&self) -> u64 {
+		self.buckets().iter().sum()
+	}
+
+	/// Returns an upper bound for the latency below which a fraction `quantile` of the operations
+	/// completed, or `None` if nothing has been recorded. For example, `percentile(0.99)` is the p99 latency.
+	///
+	/// Since latencies are only recorded to the nearest power of two, the result is the upper end of the
+	/// bucket containing the requested percentile.
+	///
+	/// # Panics
+	///
+	/// Panics if `quantile` is not in the range `0.0..=1.0`.
+	#[allow(
+		clippy::cast_precision_loss,
+		clippy::cast_possible_truncation,
+		clippy::cast_sign_loss
+	)]
+	pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+		assert!(
+			(0.0..=1.0).contains(&quantile),
+			"`quantile` must be in the range `0.0..=1.0`"
+		);
+
+		let buckets = self.buckets();
+		let count: u64 = buckets.iter().sum();
+		if count == 0 {
+			return None;
+		}
+
+		// The rank of the operation at the requested percentile, counting from 1.
+		let rank = ((quantile * count as f64).ceil() as u64).max(1);
+
+		let mut seen = 0;
+		let bucket = buckets
+			.iter()
+			.position(|&n| {
+				seen += n;
+				seen >= rank
+			})
+			.unwrap_or(LATENCY_BUCKETS - 1);
+
+		Some(Duration::from_nanos(1 << bucket))
+	}
+
+	/// Resets every bucket to zero.
+	pub fn reset(&self) {
+		for bucket in &self.buckets {
+			bucket.store(0, Ordering::Relaxed);
+		}
+	}
+}
+
+impl Default for LatencyHistogram {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Debug for LatencyHistogram {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("LatencyHistogram")
+			.field("count", &self.count())
+			.field("p50", &self.percentile(0.5))
+			.field("p99", &self.percentile(0.99))
+			.field("p999", &self.percentile(0.999))
+			.field("max", &self.percentile(1.0))
+			.finish()
+	}
+}
+
+/// A wrapper around an allocator that measures how long each operation takes. This requires the `latency` feature.
+///
+/// Allocations, zeroed allocations, and resizes are recorded in `alloc_latencies()`, and deallocations
+/// are recorded in `dealloc_latencies()`. The measurements include the overhead of reading the clock,
+/// which is usually in the order of tens of nanoseconds.
+///
+/// # Examples
+/// ```
+/// use stalloc::{SyncStalloc, Timed};
+/// use std::alloc::{GlobalAlloc, Layout};
+///
+/// let alloc = Timed::new(SyncStalloc::<100, 4>::new());
+/// let layout = Layout::new::<u32>();
+///
+/// for _ in 0..10 {
+///     unsafe { alloc.dealloc(alloc.alloc(layout), layout) };
+/// }
+///
+/// assert_eq!(alloc.alloc_latencies().count(), 10);
+/// let p99 = alloc.alloc_latencies().percentile(0.99).unwrap();
+/// println!("p99 allocation latency: {p99:?}");
+/// ```
+pub struct Timed<A> {
+	inner: A,
+	alloc: LatencyHistogram,
+	dealloc: LatencyHistogram,
+}
+
+impl<A> Timed<A> {
+	/// Wraps `inner`, with empty histograms.
+	pub const fn new(inner: A) -> Self {
+		Self {
+			inner,
+			alloc: LatencyHistogram::new(),
+			dealloc: LatencyHistogram::new(),
+		}
+	}
+
+	/// Returns the latencies of allocations and resizes.
+	pub const fn alloc_latencies(&self) -> &LatencyHistogram {
+		&self.alloc
+	}
+
+	/// Returns the latencies of deallocations.
+	pub const fn dealloc_latencies(&self) -> &LatencyHistogram {
+		&self.dealloc
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+}
+
+/// Runs `f`, and records how long it took in `histogram`.
+fn timed<R>(histogram: &LatencyHistogram, f: impl FnOnce() -> R) -> R {
+	let start = Instant::now();
+	let result = f();
+	histogram.record(start.elapsed());
+	result
+}
+
+impl<A> Deref for Timed<A> {
+	type Target = A;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl<A: Debug> Debug for Timed<A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Timed")
+			.field("inner", &self.inner)
+			.field("alloc", &self.alloc)
+			.field("dealloc", &self.dealloc)
+			.finish()
+	}
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for Timed<A> {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe { self.inner.alloc(layout) })
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe { self.inner.alloc_zeroed(layout) })
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		timed(&self.dealloc, || unsafe { self.inner.dealloc(ptr, layout) });
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe {
+			self.inner.realloc(ptr, layout, new_size)
+		})
+	}
+}
+
+/// Forwards the allocator's behavior in a chain, and times it like `GlobalAlloc`.
+unsafe impl<A: ChainableAlloc> ChainableAlloc for Timed<A> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.inner.addr_in_bounds(addr)
+	}
+
+	fn chain_aware(&self) -> bool {
+		self.inner.chain_aware()
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe {
+			self.inner.alloc_in_chain(layout, zeroed)
+		})
+	}
+
+	unsafe fn realloc_in_chain(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe {
+			self.inner.realloc_in_chain(ptr, layout, new_size)
+		})
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		timed(&self.dealloc, || unsafe {
+			self.inner.dealloc_in_chain(ptr, layout);
+		});
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<A> Allocator for &Timed<A>
+where
+	for<'a> &'a A: Allocator,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		timed(&self.alloc, || (&self.inner).allocate(layout))
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		timed(&self.alloc, || (&self.inner).allocate_zeroed(layout))
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		timed(&self.dealloc, || unsafe {
+			(&self.inner).deallocate(ptr, layout);
+		});
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe {
+			(&self.inner).grow(ptr, old_layout, new_layout)
+		})
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe {
+			(&self.inner).grow_zeroed(ptr, old_layout, new_layout)
+		})
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		timed(&self.alloc, || unsafe {
+			(&self.inner).shrink(ptr, old_layout, new_layout)
+		})
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
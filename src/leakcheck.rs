@@ -0,0 +1,127 @@
+use core::fmt::{self, Display, Formatter};
+
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+/// The error returned by `check_leaks()` when an allocator still has live allocations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LeakError {
+	/// The number of live allocations, not counting permanent ones.
+	pub leaked: usize,
+}
+
+impl Display for LeakError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{} live allocation(s)", self.leaked)
+	}
+}
+
+impl core::error::Error for LeakError {}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Checks that every allocation has been freed, for example before the allocator goes out of scope.
+	/// Permanent allocations are not counted as leaks. This requires the `leak-check` feature.
+	///
+	/// # Errors
+	///
+	/// Returns a `LeakError` with the number of allocations that are still live.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{LeakError, Stalloc};
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	/// let ptr = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// assert_eq!(alloc.check_leaks(), Err(LeakError { leaked: 1 }));
+	///
+	/// unsafe { alloc.deallocate_blocks(ptr, 2) };
+	/// assert_eq!(alloc.check_leaks(), Ok(()));
+	/// ```
+	pub const fn check_leaks(&self) -> Result<(), LeakError> {
+		match self.live_allocations() - self.permanent.get() {
+			0 => Ok(()),
+			leaked => Err(LeakError { leaked }),
+		}
+	}
+
+	/// Fails a debug assertion if any allocation is still live, like `check_leaks()`. This requires the
+	/// `leak-check` feature, and does nothing in release builds.
+	///
+	/// # Panics
+	///
+	/// Panics in debug builds if `check_leaks()` fails. With the `tracking` feature, the panic message lists
+	/// every leaked allocation.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	/// let ptr = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(ptr, 2) };
+	///
+	/// // This would panic in a debug build if `ptr` hadn't been freed.
+	/// alloc.debug_assert_no_leaks();
+	/// ```
+	pub fn debug_assert_no_leaks(&self) {
+		if !cfg!(debug_assertions) {
+			return;
+		}
+
+		if let Err(err) = self.check_leaks() {
+			#[cfg(feature = "tracking")]
+			panic!("Stalloc has {err}: {}", Leaks(self));
+			#[cfg(not(feature = "tracking"))]
+			panic!("Stalloc has {err}");
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Checks that every allocation has been freed. See `Stalloc::check_leaks()` for details.
+	///
+	/// # Errors
+	///
+	/// Returns a `LeakError` with the number of allocations that are still live.
+	pub fn check_leaks(&self) -> Result<(), LeakError> {
+		self.acquire_locked().check_leaks()
+	}
+
+	/// Fails a debug assertion if any allocation is still live. See `Stalloc::debug_assert_no_leaks()` for details.
+	///
+	/// # Panics
+	///
+	/// Panics in debug builds if `check_leaks()` fails.
+	pub fn debug_assert_no_leaks(&self) {
+		self.acquire_locked().debug_assert_no_leaks();
+	}
+}
+
+/// Lists the live allocations of an allocator, for the panic message.
+#[cfg(feature = "tracking")]
+struct Leaks<'a, const L: usize, const B: usize, S: Strategy>(&'a Stalloc<L, B, S>)
+where
+	Align<B>: Alignment;
+
+#[cfg(feature = "tracking")]
+impl<const L: usize, const B: usize, S: Strategy> Display for Leaks<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		for (i, (idx, length)) in self.0.iter_allocations().enumerate() {
+			if i > 0 {
+				f.write_str(", ")?;
+			}
+			write!(f, "{length} block(s) at index {idx}")?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,318 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::ops::Deref;
+
+extern crate alloc;
+extern crate std;
+use alloc::collections::BTreeMap;
+use std::backtrace::Backtrace;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::{eprintln, thread_local};
+
+use crate::{AllocChain, ChainableAlloc, OwnedAllocChain};
+
+thread_local! {
+	/// Set while the current thread is recording or printing a backtrace. Any allocations made in the
+	/// meantime (for example by `Backtrace` itself) are passed through without being recorded.
+	static BUSY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` unless the current thread is already inside `with_guard()`.
+fn with_guard(f: impl FnOnce()) {
+	if BUSY.try_with(|busy| busy.replace(true)) == Ok(false) {
+		f();
+		BUSY.with(|busy| busy.set(false));
+	}
+}
+
+/// A wrapper around an allocator that captures a backtrace for every live allocation, so that
+/// allocations which are never freed can be found with `dump_leaks()`. This requires the `leak-debug` feature.
+///
+/// The backtraces are stored on the global allocator. Recording happens after the inner allocator has
+/// returned, so this can wrap the `#[global_allocator]` itself; allocations made while a backtrace is
+/// being captured are not recorded. Capturing a backtrace is slow, so this is intended for debugging only.
+///
+/// Capturing and printing backtraces takes a lot of memory. If the wrapped allocator is also the global
+/// allocator, chain it to the system allocator so that the backtraces don't exhaust it.
+///
+/// # Examples
+/// ```
+/// use stalloc::{AllocChain, LeakDebug, SyncStalloc};
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static GLOBAL: LeakDebug<AllocChain<SyncStalloc<16384, 4>, System>> =
+///     LeakDebug::new(SyncStalloc::new().chain(&System));
+///
+/// fn main() {
+///     let leaked = Box::leak(Box::new([0u8; 100]));
+///
+///     assert!(GLOBAL.leak_count() > 0);
+///     GLOBAL.dump_leaks(); // prints every live allocation along with where it was made
+/// }
+/// ```
+pub struct LeakDebug<A> {
+	inner: A,
+	live: Mutex<BTreeMap<usize, (Layout, Backtrace)>>,
+}
+
+impl<A> LeakDebug<A> {
+	/// Wraps `inner`, recording a backtrace for every allocation made through the wrapper.
+	pub const fn new(inner: A) -> Self {
+		Self {
+			inner,
+			live: Mutex::new(BTreeMap::new()),
+		}
+	}
+
+	/// Returns the number of allocations made through this wrapper that are still live.
+	pub fn leak_count(&self) -> usize {
+		let mut count = 0;
+		with_guard(|| count = self.live().len());
+		count
+	}
+
+	/// Prints every allocation that is still live to stderr, along with the backtrace of where it was made.
+	pub fn dump_leaks(&self) {
+		with_guard(|| {
+			let live = self.live();
+			eprintln!("{} live allocation(s)", live.len());
+
+			for (addr, (layout, backtrace)) in live.iter() {
+				eprintln!(
+					"\n{} bytes at {addr:#x} (align {}), allocated at:\n{backtrace}",
+					layout.size(),
+					layout.align()
+				);
+			}
+		});
+	}
+
+	/// Forgets every recorded allocation, for example after the inner allocator was cleared.
+	pub fn clear_leaks(&self) {
+		with_guard(|| self.live().clear());
+	}
+
+	/// Locks the map of live allocations. Poisoning is ignored, since the map is always left consistent.
+	fn live(&self) -> MutexGuard<'_, BTreeMap<usize, (Layout, Backtrace)>> {
+		self.live.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	fn record(&self, ptr: *mut u8, layout: Layout) {
+		// Zero-sized allocations are dangling pointers, which can't leak.
+		if !ptr.is_null() && layout.size() > 0 {
+			with_guard(|| {
+				let backtrace = Backtrace::force_capture();
+				let mut live = self.live();
+				live.insert(ptr.addr(), (layout, backtrace));
+			});
+		}
+	}
+
+	fn forget(&self, ptr: *mut u8) {
+		with_guard(|| {
+			self.live().remove(&ptr.addr());
+		});
+	}
+
+	/// Records the result of resizing `ptr` to `new_size` bytes, which keeps `ptr` alive if `new` is null.
+	///
+	/// # Safety
+	/// `new_size` must be valid for `layout.align()`.
+	unsafe fn record_realloc(&self, ptr: *mut u8, layout: Layout, new: *mut u8, new_size: usize) {
+		if new.is_null() {
+			self.record(ptr, layout);
+		} else {
+			// SAFETY: Upheld by the caller.
+			self.record(new, unsafe {
+				Layout::from_size_align_unchecked(new_size, layout.align())
+			});
+		}
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+}
+
+impl<A> Deref for LeakDebug<A> {
+	type Target = A;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl<A: Debug> Debug for LeakDebug<A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self.inner)
+	}
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for LeakDebug<A> {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.inner.alloc(layout) };
+		self.record(ptr, layout);
+		ptr
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.inner.alloc_zeroed(layout) };
+		self.record(ptr, layout);
+		ptr
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		self.forget(ptr);
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.dealloc(ptr, layout) };
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// Forget `ptr` first, since another thread may reuse its address as soon as it is freed.
+		self.forget(ptr);
+
+		// SAFETY: Upheld by the caller.
+		let new = unsafe { self.inner.realloc(ptr, layout, new_size) };
+		// SAFETY: Upheld by the caller.
+		unsafe { self.record_realloc(ptr, layout, new, new_size) };
+		new
+	}
+}
+
+/// Forwards the allocator's behavior in a chain, and records allocations like `GlobalAlloc`.
+unsafe impl<A: ChainableAlloc> ChainableAlloc for LeakDebug<A> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.inner.addr_in_bounds(addr)
+	}
+
+	fn chain_aware(&self) -> bool {
+		self.inner.chain_aware()
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.inner.alloc_in_chain(layout, zeroed) };
+		self.record(ptr, layout);
+		ptr
+	}
+
+	unsafe fn realloc_in_chain(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		self.forget(ptr);
+
+		// SAFETY: Upheld by the caller.
+		let new = unsafe { self.inner.realloc_in_chain(ptr, layout, new_size) };
+		// SAFETY: Upheld by the caller.
+		unsafe { self.record_realloc(ptr, layout, new, new_size) };
+		new
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		self.forget(ptr);
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.dealloc_in_chain(ptr, layout) };
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<A> Allocator for &LeakDebug<A>
+where
+	for<'a> &'a A: Allocator,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let ptr = (&self.inner).allocate(layout)?;
+		self.record(ptr.as_ptr().cast(), layout);
+		Ok(ptr)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let ptr = (&self.inner).allocate_zeroed(layout)?;
+		self.record(ptr.as_ptr().cast(), layout);
+		Ok(ptr)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		self.forget(ptr.as_ptr());
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).deallocate(ptr, layout) };
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.forget(ptr.as_ptr());
+
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow(ptr, old_layout, new_layout) };
+		match res {
+			Ok(new) => self.record(new.as_ptr().cast(), new_layout),
+			Err(_) => self.record(ptr.as_ptr(), old_layout),
+		}
+		res
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.forget(ptr.as_ptr());
+
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow_zeroed(ptr, old_layout, new_layout) };
+		match res {
+			Ok(new) => self.record(new.as_ptr().cast(), new_layout),
+			Err(_) => self.record(ptr.as_ptr(), old_layout),
+		}
+		res
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.forget(ptr.as_ptr());
+
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).shrink(ptr, old_layout, new_layout) };
+		match res {
+			Ok(new) => self.record(new.as_ptr().cast(), new_layout),
+			Err(_) => self.record(ptr.as_ptr(), old_layout),
+		}
+		res
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
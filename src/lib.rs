@@ -7,6 +7,10 @@
 //! it can be over 3x as fast as the default OS allocator! This is because all memory
 //! is allocated from the stack, which allows it to avoid all OS overhead. Since it
 //! doesn't rely on the OS (aside from `SyncStalloc`), this library is `no_std` compatible.
+//! On targets without `std`, `SpinStalloc` can be used as a global allocator instead, and on WebAssembly,
+//! it can be chained to a `WasmPageAlloc` that grows the linear memory once the `SpinStalloc` is full.
+//! When many threads allocate small objects at once, `LockFreeStalloc` avoids contention on the lock,
+//! and `ThreadLocalStalloc` gives each thread an arena of its own.
 //!
 //! ```
 //! use stalloc::SyncStalloc;
@@ -35,33 +39,250 @@
 //! static GLOBAL: AllocChain<SyncStalloc<1000, 8>, System> = SyncStalloc::new().chain(&System);
 //! ```
 //!
+//! The same setup is also available in one line:
+//! ```
+//! use stalloc::{SyncStalloc, SystemFallback};
+//!
+//! #[global_allocator]
+//! static GLOBAL: SystemFallback<1000, 8> = SyncStalloc::with_system_fallback();
+//! ```
+//!
 //! # Feature flags
-//! - `std` (on by default) — used in the implementation of `SyncStalloc`
+//! - `std` (on by default) — used in the implementation of `SyncStalloc` and `PrometheusMetrics`
+//! - `abort-on-oom` — makes `GlobalAlloc` dump the allocator's state (to stderr, or to a sink set with `set_oom_sink()`) and abort on OOM, unless the allocator is the first link of an `AllocChain`
 //! - `allocator-api` (requires nightly)
 //! - `allocator-api2` (pulls in the `allocator-api2` crate)
+//! - `bounded-search` — limits how many free chunks an allocation examines, with `set_search_limit()`
+//! - `c-abi` (requires `tracking`) — provides the `cabi` module and `export_c_allocator!`, which export `malloc`, `free` and friends backed by a stalloc allocator, and the `stalloc_*` C API for arenas in caller-provided buffers
+//! - `canaries` (requires `tracking`) — provides `with_canaries()`, which surrounds every allocation with guard blocks that are checked for overflows
+//! - `critical-section` — provides `CriticalSectionStalloc`, a global allocator for embedded targets that is safe to use from interrupt handlers
+//! - `fault-injection` — provides `FaultyAlloc`, which fails chosen allocations to help test out-of-memory handling
+//! - `free-cache` — reuses recently freed regions of the same size without walking the free list (with `quarantine`, once they leave the quarantine)
+//! - `hooks` — lets you register `StallocHooks` that are called on every allocation, deallocation and resize
+//! - `latency` (requires `std`) — provides `Timed`, which records allocation and deallocation latencies in a `LatencyHistogram`
+//! - `leak-check` — provides `check_leaks()` and `debug_assert_no_leaks()`, which check that no allocations are left, for example before an allocator is dropped
+//! - `leak-debug` (requires `std`) — provides `LeakDebug`, which captures a backtrace for every live allocation
+//! - `lock_api` — provides `LockedStalloc`, a thread-safe wrapper that is generic over any `lock_api::RawMutex`
+//! - `log` — emits `log` records for allocation failures, OOM transitions, and fallbacks in `AllocChain`
+//! - `oom-handler` — lets you register an `OomHandler` that is called when an allocation through `GlobalAlloc` fails (but not in the first link of an `AllocChain`), with `set_oom_handler()`
+//! - `poison` — fills freed blocks with `POISON_BYTE`, to make reads of freed memory easier to spot
+//! - `poison-check` (requires `poison`) — panics if poisoned memory was modified by the time it is reused
+//! - `quarantine` — delays the reuse of freed blocks by keeping the most recent deallocations in a queue (with `free-cache`, blocks leaving the queue are cached)
+//! - `randomize` — places allocations at random, with `set_seed()`, to make the heap layout unpredictable
+//! - `shadow` (requires `std`) — provides `Shadow`, which checks every operation against a model of the live allocations
+//! - `stats` — counts allocations, deallocations, grows, shrinks and failures, available through `stats()`, as well as lock contention in `SyncStalloc`
+//! - `strict-checks` — turns violated safety preconditions of the raw block API into panics instead of UB
+//! - `tags` (requires `tracking`) — labels allocations with a `u16` tag, and reports the usage of each tag with `tag_usage()`
+//! - `testing` — provides the `testing` module, a randomized test driver for allocators
+//! - `thresholds` (requires `stats`) — calls a function when the memory usage crosses a percentage, with `set_thresholds()`
+//! - `tracking` — records every live allocation, enabling `iter_allocations()` and `is_allocated()`
+//! - `zero-on-free` — wipes freed blocks with zeros (before poisoning them), and the whole buffer in `clear()`
 
 use core::cell::UnsafeCell;
-use core::fmt::{self, Debug, Formatter};
+use core::fmt::{self, Debug, Display, Formatter};
 use core::hint::assert_unchecked;
 use core::mem::MaybeUninit;
 use core::ptr::NonNull;
 
 mod align;
 pub use align::*;
+mod aligned;
+pub use aligned::*;
+mod allocstats;
+pub use allocstats::*;
+mod arena;
+mod asyncstalloc;
+pub use asyncstalloc::*;
 mod unsafestalloc;
 pub use unsafestalloc::*;
+mod balanced;
+pub use balanced::*;
+mod batch;
+mod blockalloc;
+pub use blockalloc::*;
+mod blockbox;
+pub use blockbox::*;
 mod chain;
 pub use chain::*;
+pub mod collections;
+#[cfg(feature = "tracking")]
+mod compact;
+mod constrained;
+pub use constrained::*;
+mod counter;
+pub use counter::*;
+mod dot;
+mod dynstalloc;
+pub use dynstalloc::*;
+mod error;
+pub use error::*;
+mod lockfree;
+pub use lockfree::*;
+mod lockwrap;
+mod macros;
+#[doc(hidden)]
+pub use macros::__private;
+mod memmap;
+pub use memmap::*;
+mod multiarena;
+mod oom;
+#[cfg(any(feature = "oom-handler", feature = "abort-on-oom"))]
+pub use oom::*;
+mod permanent;
+mod rawblock;
+pub use rawblock::*;
+mod report;
+pub use report::*;
+mod reserve;
+mod spinstalloc;
+pub use spinstalloc::*;
+mod split;
+mod strategy;
+pub use strategy::*;
+mod substalloc;
+pub use substalloc::*;
+mod taggedchain;
+pub use taggedchain::*;
+mod tracked;
+pub use tracked::*;
+mod transfer;
+mod typed;
+mod validate;
+pub use validate::*;
+mod wasmpage;
+pub use wasmpage::*;
+mod writer;
+pub use writer::*;
 
 mod alloc;
 #[allow(clippy::wildcard_imports)]
 use alloc::*;
 
+#[cfg(feature = "bounded-search")]
+mod bounded;
+
+#[cfg(feature = "c-abi")]
+pub mod cabi;
+
+#[cfg(feature = "canaries")]
+mod canary;
+#[cfg(feature = "canaries")]
+pub use canary::*;
+
+#[cfg(feature = "critical-section")]
+mod critsection;
+#[cfg(feature = "critical-section")]
+pub use critsection::*;
+
+#[cfg(feature = "fault-injection")]
+mod faulty;
+#[cfg(feature = "fault-injection")]
+pub use faulty::*;
+
+#[cfg(feature = "free-cache")]
+mod freecache;
+#[cfg(feature = "free-cache")]
+pub use freecache::FREE_CACHE_CAPACITY;
+#[cfg(feature = "free-cache")]
+use freecache::FreeCache;
+
+#[cfg(feature = "hooks")]
+mod hooks;
+#[cfg(feature = "hooks")]
+pub use hooks::*;
+
+#[cfg(feature = "latency")]
+mod latency;
+#[cfg(feature = "latency")]
+pub use latency::*;
+
+#[cfg(feature = "leak-debug")]
+mod leakdebug;
+#[cfg(feature = "leak-debug")]
+pub use leakdebug::*;
+
+#[cfg(feature = "leak-check")]
+mod leakcheck;
+#[cfg(feature = "leak-check")]
+pub use leakcheck::*;
+
+#[cfg(feature = "lock_api")]
+mod lockedstalloc;
+#[cfg(feature = "lock_api")]
+pub use lockedstalloc::*;
+
+#[cfg(feature = "log")]
+mod logging;
+
+#[cfg(feature = "poison")]
+mod poison;
+#[cfg(feature = "poison")]
+pub use poison::*;
+
+#[cfg(feature = "quarantine")]
+mod quarantine;
+#[cfg(feature = "quarantine")]
+pub use quarantine::QUARANTINE_CAPACITY;
+#[cfg(feature = "quarantine")]
+use quarantine::Quarantine;
+
+#[cfg(feature = "randomize")]
+mod randomize;
+
+mod scope;
+pub use scope::*;
+
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "stats")]
+pub use stats::*;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "thresholds")]
+mod thresholds;
+#[cfg(feature = "thresholds")]
+use thresholds::Thresholds;
+#[cfg(feature = "thresholds")]
+pub use thresholds::{Crossing, MAX_THRESHOLDS, ThresholdCallback, ThresholdEvent};
+
+#[cfg(feature = "tags")]
+mod tags;
+
+#[cfg(feature = "tracking")]
+mod tracking;
+#[cfg(feature = "tracking")]
+pub use tracking::*;
+
+#[cfg(feature = "zero-on-free")]
+mod wipe;
+
 #[cfg(feature = "std")]
 mod syncstalloc;
 #[cfg(feature = "std")]
 pub use syncstalloc::*;
 
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+pub use metrics::*;
+
+#[cfg(feature = "std")]
+mod threadcache;
+#[cfg(feature = "std")]
+pub use threadcache::*;
+
+#[cfg(feature = "std")]
+mod threadlocal;
+#[cfg(feature = "std")]
+pub use threadlocal::*;
+
+#[cfg(feature = "shadow")]
+mod shadow;
+#[cfg(feature = "shadow")]
+pub use shadow::*;
+
 #[cfg(test)]
 #[cfg(feature = "allocator-api")]
 mod tests;
@@ -103,9 +324,25 @@ const unsafe fn as_u16(val: usize) -> u16 {
 	val as u16
 }
 
-// The `base` Header has a unique meaning here. Because `base.length` is useless (always 0),
-// we use it as a special flag to check whether `data` is completely filled. Every call to
-// `allocate()` and related functions must verify that base.length != OOM_MARKER.
+/// Asserts a safety precondition of a public function. With the `strict-checks` feature, a violation
+/// causes a panic. Otherwise, it is undefined behavior, and the condition is only used as an optimization hint.
+macro_rules! precondition {
+	($cond:expr, $msg:literal) => {
+		#[cfg(feature = "strict-checks")]
+		assert!($cond, concat!("safety precondition violated: ", $msg));
+		#[cfg(not(feature = "strict-checks"))]
+		unsafe {
+			core::hint::assert_unchecked($cond);
+		}
+	};
+}
+pub(crate) use precondition;
+
+// The `base` Header has a unique meaning here. Because `base` is not a real free chunk, we use
+// `base.length` as a special flag to check whether `data` is completely filled. Every call to
+// `allocate()` and related functions must verify that base.length != OOM_MARKER. Otherwise, it holds an
+// upper bound on the length of the largest free chunk, minus 1, so that allocations that are too large
+// can be rejected without walking the free list.
 const OOM_MARKER: u16 = u16::MAX;
 
 /// A fast first-fit memory allocator.
@@ -113,6 +350,7 @@ const OOM_MARKER: u16 = u16::MAX;
 /// When you create an instance of this allocator, you pass in a value for `L` and `B`.
 /// `L` is the number of blocks, and `B` is the size of each block in bytes. The total size of this type
 /// comes out to `L * B + 4` bytes, of which `L * B` can be used (4 bytes are needed to hold some metadata).
+/// Some features, such as `stats` and `tracking`, store additional metadata alongside it.
 /// `B` must be a power of two from 4 and 2^29, and `L` must be a number in the range `1..65536`.
 ///
 /// `B` represents the smallest unit of memory that the allocator can manage. If `B == 16`, then asking
@@ -123,16 +361,59 @@ const OOM_MARKER: u16 = u16::MAX;
 ///
 /// Note that `Stalloc` cannot be used as a global allocator because it is not thread-safe. To switch out the global
 /// allocator, use `SyncStalloc` or `UnsafeStalloc`, which can be used concurrently.
+///
+/// The optional `S` parameter is the `Strategy` that picks the free chunk for each allocation, which is
+/// `FirstFit` by default. `BestFit` and `NextFit` are also provided.
 #[repr(C)]
-pub struct Stalloc<const L: usize, const B: usize>
+pub struct Stalloc<const L: usize, const B: usize, S: Strategy = FirstFit>
 where
 	Align<B>: Alignment,
 {
 	data: UnsafeCell<[Block<B>; L]>,
 	base: UnsafeCell<Header>,
+	/// The number of live allocations, which is at most `L` since each of them takes up at least one block.
+	live: core::cell::Cell<u16>,
+	/// The number of reserved blocks.
+	reserved: core::cell::Cell<u16>,
+	/// Whether every allocation is surrounded by guard blocks.
+	#[cfg(feature = "canaries")]
+	canaries: bool,
+	/// The function that is called when an allocation through `GlobalAlloc` fails.
+	#[cfg(feature = "oom-handler")]
+	oom_handler: core::cell::Cell<Option<OomHandler>>,
+	/// The function that receives the diagnostic dump before aborting on OOM.
+	#[cfg(feature = "abort-on-oom")]
+	oom_sink: core::cell::Cell<Option<OomSink>>,
+	/// The number of live allocations that were made permanent, which are not reported as leaks.
+	#[cfg(feature = "leak-check")]
+	permanent: core::cell::Cell<usize>,
+	/// The length of the allocation starting at each block, or 0 if no allocation starts there.
+	#[cfg(feature = "tracking")]
+	lengths: UnsafeCell<[u16; L]>,
+	/// The tag of the allocation starting at each block.
+	#[cfg(feature = "tags")]
+	tags: UnsafeCell<[u16; L]>,
+	#[cfg(feature = "quarantine")]
+	quarantine: UnsafeCell<Quarantine>,
+	#[cfg(feature = "free-cache")]
+	free_cache: UnsafeCell<FreeCache>,
+	/// The state of the random number generator, or 0 if randomized placement is turned off.
+	#[cfg(feature = "randomize")]
+	rng: core::cell::Cell<u64>,
+	/// The maximum number of free chunks that an allocation may examine.
+	#[cfg(feature = "bounded-search")]
+	search_limit: core::cell::Cell<Option<core::num::NonZeroUsize>>,
+	#[cfg(feature = "stats")]
+	stats: UnsafeCell<Stats>,
+	#[cfg(feature = "hooks")]
+	hooks: core::cell::Cell<Option<&'static dyn StallocHooks>>,
+	#[cfg(feature = "thresholds")]
+	thresholds: UnsafeCell<Thresholds>,
+	/// Decides which free chunk each allocation is placed in.
+	strategy: S,
 }
 
-impl<const L: usize, const B: usize> Stalloc<L, B>
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
 where
 	Align<B>: Alignment,
 {
@@ -152,9 +433,15 @@ where
 			assert!(B >= 4, "block size must be at least 4 bytes");
 		}
 
-		let mut blocks = [Block {
-			bytes: const { [MaybeUninit::uninit(); B] },
-		}; L];
+		// With `zero-on-free`, free blocks are kept zeroed (apart from free chunk headers) unless they are poisoned.
+		#[cfg(all(feature = "zero-on-free", not(feature = "poison")))]
+		let bytes = const { [MaybeUninit::zeroed(); B] };
+		#[cfg(not(any(feature = "zero-on-free", feature = "poison")))]
+		let bytes = const { [MaybeUninit::uninit(); B] };
+		#[cfg(feature = "poison")]
+		let bytes = const { [MaybeUninit::new(POISON_BYTE); B] };
+
+		let mut blocks = [Block { bytes }; L];
 
 		// Write the first header. SAFETY: we have already checked that `L <= 0xffff`.
 		blocks[0].header = Header {
@@ -163,15 +450,48 @@ where
 		};
 
 		Self {
-			base: UnsafeCell::new(Header { next: 0, length: 0 }),
+			base: UnsafeCell::new(Header {
+				next: 0,
+				length: unsafe { as_u16(L - 1) },
+			}),
 			data: UnsafeCell::new(blocks),
+			live: core::cell::Cell::new(0),
+			reserved: core::cell::Cell::new(0),
+			#[cfg(feature = "canaries")]
+			canaries: false,
+			#[cfg(feature = "oom-handler")]
+			oom_handler: core::cell::Cell::new(None),
+			#[cfg(feature = "abort-on-oom")]
+			oom_sink: core::cell::Cell::new(None),
+			#[cfg(feature = "leak-check")]
+			permanent: core::cell::Cell::new(0),
+			#[cfg(feature = "tracking")]
+			lengths: UnsafeCell::new([0; L]),
+			#[cfg(feature = "tags")]
+			tags: UnsafeCell::new([0; L]),
+			#[cfg(feature = "quarantine")]
+			quarantine: UnsafeCell::new(Quarantine::new()),
+			#[cfg(feature = "free-cache")]
+			free_cache: UnsafeCell::new(FreeCache::new()),
+			#[cfg(feature = "randomize")]
+			rng: core::cell::Cell::new(0),
+			#[cfg(feature = "bounded-search")]
+			search_limit: core::cell::Cell::new(None),
+			#[cfg(feature = "stats")]
+			stats: UnsafeCell::new(Stats::new()),
+			#[cfg(feature = "hooks")]
+			hooks: core::cell::Cell::new(None),
+			#[cfg(feature = "thresholds")]
+			thresholds: UnsafeCell::new(Thresholds::new()),
+			strategy: S::NEW,
 		}
 	}
 
 	/// Checks if the allocator is completely out of memory.
 	/// If this is false, then you are guaranteed to be able to allocate
 	/// a layout with a size and alignment of `B` bytes.
-	/// This runs in O(1).
+	/// Blocks held back by the `quarantine` or `free-cache` features count as free, since they are released
+	/// before an allocation fails. This runs in O(1).
 	///
 	/// # Examples
 	/// ```
@@ -183,13 +503,14 @@ where
 	/// assert!(alloc.is_oom());
 	/// ```
 	pub const fn is_oom(&self) -> bool {
-		unsafe { *self.base.get() }.length == OOM_MARKER
+		self.is_free_list_empty() && !self.holds_freed_blocks()
 	}
 
 	/// Checks if the allocator is empty.
 	/// If this is true, then you are guaranteed to be able to allocate
 	/// a layout with a size of `B * L` bytes and an alignment of `B` bytes.
 	/// If this is false, then this is guaranteed to be impossible.
+	/// Blocks that are held back by the `quarantine` or `free-cache` features count as free.
 	/// This runs in O(1).
 	///
 	/// # Examples
@@ -205,8 +526,62 @@ where
 	/// unsafe { alloc.deallocate_blocks(ptr, 60) };
 	/// assert!(alloc.is_empty());
 	/// ```
-	pub fn is_empty(&self) -> bool {
-		!self.is_oom() && unsafe { *self.base.get() }.next == 0
+	pub const fn is_empty(&self) -> bool {
+		// Freed blocks are always merged with their neighbors, so without any allocations or reserved
+		// blocks, the whole buffer is a single free chunk (or held back).
+		self.live.get() == 0 && self.reserved.get() == 0
+	}
+
+	/// Returns the number of bytes that can be used in an allocation at `ptr` that was requested with a
+	/// size of `size_hint` bytes. This is `size_hint` rounded up to a multiple of `B`, since memory is
+	/// always handed out in whole blocks. With the `tracking` feature, if `ptr` points to a live allocation,
+	/// its actual length is used instead, which may be larger (for example after `grow_up_to()`).
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 8>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// assert_eq!(alloc.usable_size(ptr, 13), 16);
+	/// ```
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	pub fn usable_size(&self, ptr: NonNull<u8>, size_hint: usize) -> usize {
+		#[cfg(feature = "tracking")]
+		if self.is_allocated(ptr) {
+			return self.tracked_length((ptr.addr().get() - self.data.get().addr()) / B) * B;
+		}
+
+		size_hint.div_ceil(B) * B
+	}
+
+	/// Checks whether the allocation of `size` blocks at `ptr` is the last one in the buffer, so that
+	/// every block after it is free. In that case, freeing or shrinking it returns its blocks to the free
+	/// space at the end of the buffer. This runs in O(n), where n is the number of free chunks.
+	///
+	/// Blocks that are reserved or held back by the `quarantine` or `free-cache` features count as used.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<40, 4>::new();
+	///
+	/// let ptr1 = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+	/// let ptr2 = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+	/// assert!(!alloc.is_last_allocation(ptr1, 4));
+	/// assert!(alloc.is_last_allocation(ptr2, 4));
+	/// ```
+	pub fn is_last_allocation(&self, ptr: NonNull<u8>, size: usize) -> bool {
+		let idx = ptr.addr().get().wrapping_sub(self.data.get().addr()) / B;
+		let end = idx.wrapping_add(size + self.guard_blocks());
+
+		end == L
+			|| self
+				.free_chunks()
+				.last()
+				.is_some_and(|(free_idx, length)| free_idx == end && free_idx + length == L)
 	}
 
 	/// # Safety
@@ -229,12 +604,67 @@ where
 	/// assert!(alloc.is_empty());
 	/// ```
 	pub unsafe fn clear(&self) {
+		#[cfg(feature = "zero-on-free")]
+		self.wipe_blocks(0, L);
+		#[cfg(feature = "poison")]
+		self.poison_blocks(0, L);
+
 		unsafe {
 			(*self.base.get()).next = 0;
-			(*self.base.get()).length = 0;
 			(*self.header_at(0)).next = 0;
 			(*self.header_at(0)).length = as_u16(L);
+			self.set_largest_free(L);
+		}
+
+		self.on_clear();
+	}
+
+	/// Resets the allocator like `clear()`, but only if it is empty, in which case no pointer can be
+	/// invalidated. Returns whether the allocator was reset.
+	///
+	/// This is useful with the `quarantine` and `free-cache` features, where freed blocks are not returned
+	/// to the free list right away. Reserved regions count as in use, so they are never released.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// assert!(!alloc.try_clear());
+	///
+	/// unsafe { alloc.deallocate_blocks(ptr, 5) };
+	/// assert!(alloc.try_clear());
+	/// assert!(alloc.is_empty());
+	/// ```
+	pub fn try_clear(&self) -> bool {
+		if !self.is_empty() {
+			return false;
 		}
+
+		// SAFETY: There are no live allocations or reserved regions that could be invalidated.
+		unsafe { self.clear() };
+		true
+	}
+
+	/// Returns the number of live allocations. Allocations made with `allocate_blocks_permanent()` or
+	/// given up with `leak_blocks()` count as live, while reserved regions don't count at all.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// assert_eq!(alloc.live_allocations(), 1);
+	///
+	/// unsafe { alloc.deallocate_blocks(ptr, 5) };
+	/// assert_eq!(alloc.live_allocations(), 0);
+	/// ```
+	pub const fn live_allocations(&self) -> usize {
+		self.live.get() as usize
 	}
 
 	/// Tries to allocate `count` blocks. If the allocation succeeds, a pointer is returned. This function
@@ -242,7 +672,7 @@ where
 	///
 	/// # Safety
 	///
-	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=2^29 / B`.
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
 	///
 	/// # Errors
 	///
@@ -266,76 +696,223 @@ where
 		align: usize,
 	) -> Result<NonNull<u8>, AllocError> {
 		// Assert unsafe preconditions.
-		unsafe {
-			assert_unchecked(size >= 1 && align.is_power_of_two() && align <= 2usize.pow(29) / B);
+		precondition!(
+			size >= 1 && align.is_power_of_two() && align <= MAX_ALIGN / B,
+			"`size` must be nonzero and `align` must be a power of 2 in `1..=MAX_ALIGN / B`"
+		);
+
+		// Every allocation is surrounded by guard blocks (if canaries are turned on).
+		let guarded_size = size + 2 * self.guard_blocks();
+
+		// With the `free-cache` feature, a recently freed region of the same size is reused if possible.
+		#[cfg(feature = "free-cache")]
+		if let Some(idx) = self.take_cached(guarded_size, align) {
+			self.on_alloc(idx + self.guard_blocks(), size, align);
+			return Ok(unsafe {
+				NonNull::new_unchecked(self.block_at(idx + self.guard_blocks()).cast())
+			});
 		}
 
-		if self.is_oom() {
+		// Fail early if the allocation can't fit in any free chunk.
+		if self.is_free_list_empty() || guarded_size > self.largest_free() {
+			// Quarantined and cached blocks are released before giving up.
+			#[cfg(feature = "quarantine")]
+			if self.try_flush_quarantine() {
+				return unsafe { self.allocate_blocks(size, align) };
+			}
+			#[cfg(feature = "free-cache")]
+			if self.try_flush_free_cache() {
+				return unsafe { self.allocate_blocks(size, align) };
+			}
+
+			self.on_alloc_failed(size, align);
 			return Err(AllocError);
 		}
 
-		// Loop through the free list, and find the first header whose length satisfies the layout.
+		// With the `randomize` feature, a random free chunk is picked out of the ones that satisfy the layout.
+		#[cfg(feature = "randomize")]
+		let mut fits_to_skip = self.random_fits_to_skip(guarded_size, align);
+
+		// With the `bounded-search` feature, only a limited number of free chunks are examined.
+		#[cfg(feature = "bounded-search")]
+		let mut chunks_left = self.search_budget();
+
+		// Loop through the free list, and let the strategy pick a header whose length satisfies the layout.
 		unsafe {
 			// `prev` and `curr` are pointers that run through the free list.
 			let base = self.base.get();
 			let mut prev = base;
 			let mut curr = self.header_at((*base).next.into());
 
+			// The length of the largest free chunk that was seen.
+			let mut largest_seen = 0;
+
+			// The chunk that the strategy remembered, along with the header before it.
+			let mut best: Option<(Candidate, *mut Header, *mut Header)> = None;
+
 			loop {
 				let curr_idx = usize::from((*prev).next);
 				let next_idx = (*curr).next.into();
 
 				// Check if the current free chunk satisfies the layout.
 				let curr_chunk_len = (*curr).length.into();
+				largest_seen = largest_seen.max(curr_chunk_len);
 
 				// If the alignment is more than 1, there might be spare blocks in front.
 				// If it is extremely large, there might have to be more spare blocks than are available.
-				let spare_front = (curr.addr() / B).wrapping_neg() % align;
-
-				if spare_front + size <= curr_chunk_len {
-					let avail_blocks = curr_chunk_len - spare_front;
-					let avail_blocks_ptr = self.block_at(curr_idx + spare_front);
-					let spare_back = avail_blocks - size;
-
-					// If there are spare blocks, add them to the free list.
-					if spare_back > 0 {
-						let spare_back_idx = curr_idx + spare_front + size;
-						let spare_back_ptr = self.header_at(spare_back_idx);
-						(*spare_back_ptr).next = as_u16(next_idx);
-						(*spare_back_ptr).length = as_u16(spare_back);
-
-						if spare_front > 0 {
-							(*curr).next = as_u16(spare_back_idx);
-							(*curr).length = as_u16(spare_front);
-						} else {
-							(*prev).next = as_u16(spare_back_idx);
-						}
-					} else if spare_front > 0 {
-						(*curr).next = as_u16(curr_idx + spare_front + size);
-						(*curr).length = as_u16(spare_front);
-						(*prev).next = as_u16(next_idx);
-					} else {
-						(*prev).next = as_u16(next_idx);
-						// If this is the last block of memory, set the OOM marker.
-						if next_idx == 0 {
-							(*base).length = OOM_MARKER;
-						}
+				// The guard blocks in front of the allocation must be taken into account.
+				let spare_front = (curr.addr() / B + self.guard_blocks()).wrapping_neg() % align;
+
+				let fits = spare_front + guarded_size <= curr_chunk_len;
+				#[cfg(feature = "randomize")]
+				let fits = fits && Self::take_fit(&mut fits_to_skip);
+
+				if fits {
+					let chunk = Candidate {
+						index: curr_idx,
+						length: curr_chunk_len,
+						needed: spare_front + guarded_size,
+					};
+
+					match self.strategy.select(chunk, best.map(|(best, ..)| best)) {
+						Fit::Take => return Ok(self.place(prev, curr, chunk, size, align)),
+						Fit::Remember => best = Some((chunk, prev, curr)),
+						Fit::Skip => {}
 					}
-
-					return Ok(NonNull::new_unchecked(avail_blocks_ptr.cast()));
 				}
 
 				// Check if we've already made a whole loop around without finding anything.
 				if next_idx == 0 {
+					// Every free chunk has been seen, so the upper bound can be tightened.
+					self.set_largest_free(largest_seen);
+
+					if let Some((chunk, prev, curr)) = best {
+						return Ok(self.place(prev, curr, chunk, size, align));
+					}
+
+					#[cfg(feature = "quarantine")]
+					if self.try_flush_quarantine() {
+						return self.allocate_blocks(size, align);
+					}
+					#[cfg(feature = "free-cache")]
+					if self.try_flush_free_cache() {
+						return self.allocate_blocks(size, align);
+					}
+
+					self.on_alloc_failed(size, align);
 					return Err(AllocError);
 				}
 
+				// Give up once the search limit is reached.
+				#[cfg(feature = "bounded-search")]
+				{
+					chunks_left -= 1;
+					if chunks_left == 0 {
+						if let Some((chunk, prev, curr)) = best {
+							return Ok(self.place(prev, curr, chunk, size, align));
+						}
+
+						#[cfg(feature = "quarantine")]
+						if self.try_flush_quarantine() {
+							return self.allocate_blocks(size, align);
+						}
+						#[cfg(feature = "free-cache")]
+						if self.try_flush_free_cache() {
+							return self.allocate_blocks(size, align);
+						}
+
+						self.on_alloc_failed(size, align);
+						return Err(AllocError);
+					}
+				}
+
 				prev = curr;
 				curr = self.header_at(next_idx);
 			}
 		}
 	}
 
+	/// Tries to allocate the `size` blocks starting at block index `idx`, counted from the start of the buffer.
+	/// This only succeeds if all of those blocks are free, which makes it possible to reserve a region
+	/// at a known offset while the rest of the buffer is used for general-purpose allocations.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `size` is zero, if the blocks are out of bounds, or if any of them
+	/// (or the guard blocks around them, with canaries) are in use. In that case,
+	/// this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<16, 4>::new();
+	///
+	/// let start = alloc.allocate_blocks_at(1, 2).unwrap();
+	/// let ring = alloc.allocate_blocks_at(8, 4).unwrap();
+	/// assert_eq!(ring, unsafe { start.add(7 * 4) });
+	///
+	/// // The blocks are no longer free.
+	/// assert!(alloc.allocate_blocks_at(10, 1).is_err());
+	/// ```
+	pub fn allocate_blocks_at(&self, idx: usize, size: usize) -> Result<NonNull<u8>, AllocError> {
+		// The guard blocks must be free as well.
+		let Some(start) = idx.checked_sub(self.guard_blocks()) else {
+			return Err(AllocError);
+		};
+		let guarded_size = size + 2 * self.guard_blocks();
+		if size == 0 || start + guarded_size > L {
+			return Err(AllocError);
+		}
+
+		if self.take_blocks_at(start, guarded_size) {
+			self.on_alloc(idx, size, 1);
+			return Ok(unsafe { NonNull::new_unchecked(self.block_at(idx).cast()) });
+		}
+
+		self.on_alloc_failed(size, 1);
+		Err(AllocError)
+	}
+
+	/// Tries to allocate `size` blocks, just like `allocate_blocks()`, and fills them with zeros.
+	/// Note that `align` is measured in units of `B`.
+	///
+	/// With the `zero-on-free` feature (and without the `poison` feature), free blocks are already known
+	/// to be zeroed apart from a few header bytes, so only those bytes are cleared.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks_zeroed(10, 1) }.unwrap();
+	/// let words = unsafe { std::slice::from_raw_parts(ptr.cast::<u32>().as_ptr(), 10) };
+	/// assert!(words.iter().all(|&w| w == 0));
+	/// ```
+	pub unsafe fn allocate_blocks_zeroed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		unsafe {
+			// SAFETY: Upheld by the caller.
+			let ptr = self.allocate_blocks(size, align)?;
+
+			// SAFETY: The blocks were just taken from the free list.
+			Self::zero_free_blocks(ptr, size);
+			Ok(ptr)
+		}
+	}
+
 	/// Deallocates a pointer. This function always succeeds.
 	///
 	/// # Safety
@@ -356,40 +933,10 @@ where
 	/// assert!(alloc.is_empty());
 	/// ```
 	pub unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
-		// Assert unsafe precondition.
-		unsafe {
-			assert_unchecked(size >= 1 && size <= L);
-		}
-
-		let freed_ptr = header_in_block(ptr.as_ptr().cast());
-		let freed_idx = self.index_of(freed_ptr);
-		let base = self.base.get();
-		let before = self.header_before(freed_idx);
-
-		unsafe {
-			let prev_next = (*before).next.into();
-			(*freed_ptr).next = as_u16(prev_next);
-			(*freed_ptr).length = as_u16(size);
-
-			// Try to merge with the next free block.
-			if freed_idx + size == prev_next {
-				let header_to_merge = self.header_at(prev_next);
-				(*freed_ptr).next = (*header_to_merge).next;
-				(*freed_ptr).length += (*header_to_merge).length;
-			}
+		// Assert unsafe preconditions.
+		precondition!(size >= 1 && size <= L, "`size` must be in `1..=L`");
 
-			// Try to merge with the previous free block.
-			if before.eq(&base) {
-				(*base).next = as_u16(freed_idx);
-				(*base).length = 0;
-			} else if self.index_of(before) + usize::from((*before).length) == freed_idx {
-				(*before).next = (*freed_ptr).next;
-				(*before).length += (*freed_ptr).length;
-			} else {
-				// No merge is possible.
-				(*before).next = as_u16(freed_idx);
-			}
-		}
+		unsafe { self.free_allocation(self.base.get(), ptr, size) };
 	}
 
 	/// Shrinks the allocation. This function always succeeds and never reallocates.
@@ -413,23 +960,30 @@ where
 	/// ```
 	pub unsafe fn shrink_in_place(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) {
 		// Assert unsafe preconditions.
-		unsafe {
-			assert_unchecked(new_size > 0 && new_size < old_size);
-		}
+		precondition!(
+			new_size > 0 && new_size < old_size,
+			"`new_size` must be in `1..old_size`"
+		);
+		self.check_allocation(ptr, old_size);
 
 		let curr_block: *mut Block<B> = ptr.as_ptr().cast();
 		let curr_idx = (curr_block.addr() - self.data.get().addr()) / B;
 
-		// A new chunk will be created in the gap.
-		let new_idx = curr_idx + new_size;
+		// A new chunk will be created in the gap, after the trailing guard blocks.
+		let new_idx = curr_idx + new_size + self.guard_blocks();
 		let spare_blocks = old_size - new_size;
 
+		#[cfg(feature = "zero-on-free")]
+		self.wipe_blocks(new_idx, spare_blocks);
+		#[cfg(feature = "poison")]
+		self.poison_blocks(new_idx, spare_blocks);
+
 		unsafe {
 			// Check if we can merge the block with a chunk immediately after.
 			let prev_free_chunk = self.header_before(curr_idx);
 
 			let next_free_idx = (*prev_free_chunk).next.into(); // possibly zero
-			let new_chunk = header_in_block(curr_block.add(new_size));
+			let new_chunk = self.header_at(new_idx);
 
 			(*prev_free_chunk).next = as_u16(new_idx);
 
@@ -437,13 +991,75 @@ where
 				let next_free_chunk = self.header_at(next_free_idx);
 				(*new_chunk).next = (*next_free_chunk).next;
 				(*new_chunk).length = as_u16(spare_blocks) + (*next_free_chunk).length;
+				#[cfg(feature = "poison")]
+				self.poison_header(next_free_idx);
 			} else {
 				(*new_chunk).next = as_u16(next_free_idx);
 				(*new_chunk).length = as_u16(spare_blocks);
 			}
 
 			// We are definitely no longer OOM.
-			(*self.base.get()).length = 0;
+			self.raise_largest_free((*new_chunk).length.into());
+		}
+
+		self.on_resize(curr_idx, old_size, new_size);
+	}
+
+	/// Shrinks the allocation by releasing its first `old_size - new_size` blocks, and returns a pointer to
+	/// the remaining blocks, which stay where they are. This function always succeeds and never reallocates.
+	///
+	/// The returned pointer is only aligned to `B`, since the allocator doesn't remember the alignment of
+	/// an allocation.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size` must be in `1..old_size`.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<100, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(100, 1) }.unwrap();
+	/// unsafe { ptr.add(360).cast::<u32>().write(42) };
+	///
+	/// // Release the first 90 blocks, keeping the last 10.
+	/// let rest = unsafe { alloc.shrink_in_place_front(ptr, 100, 10) };
+	/// assert_eq!(rest, unsafe { ptr.add(360) });
+	/// assert_eq!(unsafe { rest.cast::<u32>().read() }, 42);
+	/// assert!(unsafe { alloc.allocate_blocks(90, 1) }.is_ok());
+	/// ```
+	pub unsafe fn shrink_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> NonNull<u8> {
+		// Assert unsafe preconditions.
+		precondition!(
+			new_size > 0 && new_size < old_size,
+			"`new_size` must be in `1..old_size`"
+		);
+		self.check_allocation(ptr, old_size);
+
+		let curr_block: *mut Block<B> = ptr.as_ptr().cast();
+		let curr_idx = (curr_block.addr() - self.data.get().addr()) / B;
+
+		// The freed blocks start at the old leading guard blocks, and new ones are placed right before the rest.
+		let freed_idx = curr_idx - self.guard_blocks();
+		let spare_blocks = old_size - new_size;
+		let new_idx = curr_idx + spare_blocks;
+
+		#[cfg(feature = "zero-on-free")]
+		self.wipe_blocks(freed_idx, spare_blocks);
+		#[cfg(feature = "poison")]
+		self.poison_blocks(freed_idx, spare_blocks);
+
+		unsafe {
+			self.release_blocks(self.base.get(), freed_idx, spare_blocks);
+			self.on_resize_moved(curr_idx, new_idx, old_size, new_size);
+			ptr.add(spare_blocks * B)
 		}
 	}
 
@@ -477,9 +1093,11 @@ where
 		new_size: usize,
 	) -> Result<(), AllocError> {
 		// Assert unsafe preconditions.
-		unsafe {
-			assert_unchecked(old_size >= 1 && old_size <= L && new_size > old_size);
-		}
+		precondition!(
+			old_size >= 1 && old_size <= L && new_size > old_size,
+			"`old_size` must be in `1..=L` and `new_size` must be greater than `old_size`"
+		);
+		self.check_allocation(ptr, old_size);
 
 		let curr_block: *mut Block<B> = ptr.as_ptr().cast();
 		let curr_idx = (curr_block.addr() - self.data.get().addr()) / B;
@@ -488,8 +1106,8 @@ where
 		unsafe {
 			let next_free_idx = (*prev_free_chunk).next.into();
 
-			// The next free chunk must be directly adjacent to the current allocation.
-			if curr_idx + old_size != next_free_idx {
+			// The next free chunk must be directly adjacent to the current allocation (and its guard blocks).
+			if curr_idx + old_size + self.guard_blocks() != next_free_idx {
 				return Err(AllocError);
 			}
 
@@ -524,61 +1142,267 @@ where
 				}
 			}
 
+			self.on_resize(curr_idx, old_size, new_size);
 			Ok(())
 		}
 	}
 
-	/// Tries to grow the current allocation in-place. If that isn't possible, the allocator grows by as much
-	/// as it is able to, and the new length of the allocation is returned. The new length is guaranteed to be
-	/// in the range `old_size..=new_size`.
+	/// Tries to grow the current allocation into the free chunk right before it, moving its contents down.
+	/// If the allocation is also followed by a free chunk, that chunk is used as well. The allocation is
+	/// placed as high as possible, aligned to `align` blocks. If that isn't possible, this function is a no-op.
+	///
+	/// This succeeds in many cases where `grow_in_place()` fails, without needing a separate allocation
+	/// to copy into. Returns a pointer to the grown allocation, which is only equal to `ptr` if the allocation
+	/// could be grown forward.
+	///
 	/// # Safety
 	///
-	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size > old_size`.
+	/// `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
 	///
 	/// # Examples
 	/// ```
 	/// use stalloc::Stalloc;
 	///
-	/// let alloc1 = Stalloc::<7, 4>::new();
-	/// unsafe {
-	///     let ptr = alloc1.allocate_blocks(3, 1).unwrap(); // allocate 3 blocks
-	///     let new_size = alloc1.grow_up_to(ptr, 3, 9999); // try to grow to a ridiculous amount
-	///     assert_eq!(new_size, 7); // can only grow up to 7
-	/// }
+	/// let alloc = Stalloc::<100, 16>::new();
 	///
-	/// let alloc2 = Stalloc::<21, 16>::new();
-	/// unsafe {
-	///     let ptr = alloc2.allocate_blocks(9, 1).unwrap(); // allocate 9 blocks
-	///     let new_size = alloc2.grow_up_to(ptr, 9, 21);
-	///     assert_eq!(new_size, 21); // grow was successful
-	/// }
+	/// let ptr1 = unsafe { alloc.allocate_blocks(25, 1) }.unwrap();
+	/// let ptr2 = unsafe { alloc.allocate_blocks(25, 1) }.unwrap();
+	/// let ptr3 = unsafe { alloc.allocate_blocks(50, 1) }.unwrap();
+	/// unsafe { ptr2.write(42) };
+	/// unsafe { alloc.deallocate_blocks(ptr1, 25) };
+	/// # #[cfg(feature = "quarantine")]
+	/// # alloc.flush_quarantine();
+	/// # #[cfg(feature = "free-cache")]
+	/// # alloc.flush_free_cache();
+	///
+	/// // There is no room after `ptr2`, but there is room before it.
+	/// assert!(unsafe { alloc.grow_in_place(ptr2, 25, 40) }.is_err());
+	/// let ptr2 = unsafe { alloc.grow_in_place_front(ptr2, 25, 40, 1) }.unwrap();
+	/// assert_eq!(unsafe { ptr2.read() }, 42);
+	///
+	/// // The allocation was moved down by 15 blocks.
+	/// assert_eq!(ptr2, unsafe { ptr1.add(10 * 16) });
 	/// ```
-	pub unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize {
+	pub unsafe fn grow_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
 		// Assert unsafe preconditions.
-		unsafe {
-			assert_unchecked(old_size >= 1 && old_size <= L && new_size > old_size);
-		}
+		precondition!(
+			old_size >= 1
+				&& old_size <= L
+				&& new_size > old_size
+				&& align.is_power_of_two()
+				&& align <= MAX_ALIGN / B,
+			"`old_size` must be in `1..=L`, `new_size` must be greater than `old_size`, and `align` must be a power of 2 in `1..=MAX_ALIGN / B`"
+		);
+		self.check_allocation(ptr, old_size);
 
 		let curr_block: *mut Block<B> = ptr.as_ptr().cast();
 		let curr_idx = (curr_block.addr() - self.data.get().addr()) / B;
-		let prev_free_chunk = self.header_before(curr_idx);
+		let old_end = curr_idx + old_size + self.guard_blocks();
+		let base = self.base.get();
 
 		unsafe {
-			let next_free_idx = (*prev_free_chunk).next.into();
-
-			// The next free chunk must be directly adjacent to the current allocation.
-			if curr_idx + old_size != next_free_idx {
-				return old_size;
+			if (*base).length == OOM_MARKER {
+				return Err(AllocError);
 			}
 
-			let next_free_chunk = self.header_at(next_free_idx);
-			let room_to_grow = (*next_free_chunk).length.into();
+			// The previous free chunk must be directly adjacent to the allocation (and its guard blocks).
+			let prev_free_chunk = self.header_before(curr_idx);
+			if prev_free_chunk.eq(&base) {
+				return Err(AllocError);
+			}
 
-			// If there isn't enough room to grow, grow as much as possible.
-			let needed_blocks = (new_size - old_size).min(room_to_grow);
+			let prev_idx = self.index_of(prev_free_chunk);
+			let prev = *prev_free_chunk;
+			if prev_idx + usize::from(prev.length) != curr_idx - self.guard_blocks() {
+				return Err(AllocError);
+			}
 
-			// Check if there would be any blocks left over after growing into the next chunk.
-			let blocks_left_over = room_to_grow - needed_blocks;
+			// The next free chunk can be used too, if it is adjacent.
+			let next_idx = usize::from(prev.next);
+			let (back_end, back_next) = if next_idx == old_end {
+				let next = *self.header_at(next_idx);
+				(next_idx + usize::from(next.length), next.next)
+			} else {
+				(old_end, prev.next)
+			};
+
+			// Place the allocation as high as possible, so that the previous chunk keeps as many blocks as possible.
+			let Some(highest) = (back_end - self.guard_blocks()).checked_sub(new_size) else {
+				return Err(AllocError);
+			};
+			let highest = highest.min(curr_idx);
+			let misalignment = self.block_at(highest).addr() % (align * B) / B;
+			if highest < prev_idx + self.guard_blocks() + misalignment {
+				return Err(AllocError);
+			}
+			let new_idx = highest - misalignment;
+
+			if new_idx == curr_idx {
+				// The allocation doesn't have to move, so this is a regular grow.
+				return self.grow_in_place(ptr, old_size, new_size).map(|()| ptr);
+			}
+
+			// If the previous chunk is used up, the chunk before it has to be relinked instead.
+			let front_len = new_idx - self.guard_blocks() - prev_idx;
+			let link_from = if front_len > 0 {
+				prev_free_chunk
+			} else {
+				self.header_before(prev_idx)
+			};
+
+			let new_ptr = NonNull::new_unchecked(self.block_at(new_idx).cast::<u8>());
+			ptr.copy_to(new_ptr, old_size * B);
+
+			// If the allocation moved down by more than it grew, the end of the old allocation is freed.
+			let new_end = new_idx + new_size + self.guard_blocks();
+			if new_end < old_end {
+				#[cfg(feature = "zero-on-free")]
+				self.wipe_blocks(new_end, old_end - new_end);
+				#[cfg(feature = "poison")]
+				self.poison_blocks(new_end, old_end - new_end);
+			}
+			#[cfg(feature = "poison")]
+			if next_idx == old_end && new_end < next_idx {
+				self.poison_header(next_idx);
+			}
+
+			if front_len > 0 {
+				(*prev_free_chunk).length = as_u16(front_len);
+			}
+
+			let back_len = back_end - new_end;
+			if back_len > 0 {
+				*self.header_at(new_end) = Header {
+					next: back_next,
+					length: as_u16(back_len),
+				};
+				(*link_from).next = as_u16(new_end);
+				self.raise_largest_free(back_len);
+			} else {
+				(*link_from).next = back_next;
+
+				// If `link_from` is the base pointer and we just set it to 0, we are OOM.
+				if link_from.eq(&base) && back_next == 0 {
+					(*base).length = OOM_MARKER;
+				}
+			}
+
+			self.on_resize_moved(curr_idx, new_idx, old_size, new_size);
+			Ok(new_ptr)
+		}
+	}
+
+	/// Tries to grow the current allocation in-place, just like `grow_in_place()`, and fills the newly
+	/// acquired blocks with zeros. If that isn't possible, this function is a no-op.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// unsafe { ptr.write_bytes(0xff, 8) };
+	///
+	/// unsafe { alloc.grow_zeroed_in_place(ptr, 2, 4) }.unwrap();
+	/// let bytes = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), 16) };
+	/// assert_eq!(bytes, [[0xff; 8], [0; 8]].concat());
+	/// ```
+	pub unsafe fn grow_zeroed_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		unsafe {
+			// SAFETY: Upheld by the caller.
+			self.grow_in_place(ptr, old_size, new_size)?;
+
+			// The old trailing guard blocks are not free blocks, so they are zeroed completely.
+			let new_blocks = ptr.add(old_size * B);
+			new_blocks.write_bytes(0, self.guard_blocks() * B);
+
+			// SAFETY: The rest of the new blocks were just taken from the free list.
+			Self::zero_free_blocks(
+				new_blocks.add(self.guard_blocks() * B),
+				new_size - old_size - self.guard_blocks(),
+			);
+			Ok(())
+		}
+	}
+
+	/// Tries to grow the current allocation in-place. If that isn't possible, the allocator grows by as much
+	/// as it is able to, and the new length of the allocation is returned. The new length is guaranteed to be
+	/// in the range `old_size..=new_size`.
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc1 = Stalloc::<7, 4>::new();
+	/// unsafe {
+	///     let ptr = alloc1.allocate_blocks(3, 1).unwrap(); // allocate 3 blocks
+	///     let new_size = alloc1.grow_up_to(ptr, 3, 9999); // try to grow to a ridiculous amount
+	///     assert_eq!(new_size, 7); // can only grow up to 7
+	/// }
+	///
+	/// let alloc2 = Stalloc::<21, 16>::new();
+	/// unsafe {
+	///     let ptr = alloc2.allocate_blocks(9, 1).unwrap(); // allocate 9 blocks
+	///     let new_size = alloc2.grow_up_to(ptr, 9, 21);
+	///     assert_eq!(new_size, 21); // grow was successful
+	/// }
+	/// ```
+	pub unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize {
+		// Assert unsafe preconditions.
+		precondition!(
+			old_size >= 1 && old_size <= L && new_size > old_size,
+			"`old_size` must be in `1..=L` and `new_size` must be greater than `old_size`"
+		);
+		self.check_allocation(ptr, old_size);
+
+		let curr_block: *mut Block<B> = ptr.as_ptr().cast();
+		let curr_idx = (curr_block.addr() - self.data.get().addr()) / B;
+		let prev_free_chunk = self.header_before(curr_idx);
+
+		unsafe {
+			let next_free_idx = (*prev_free_chunk).next.into();
+
+			// The next free chunk must be directly adjacent to the current allocation (and its guard blocks).
+			if curr_idx + old_size + self.guard_blocks() != next_free_idx {
+				return old_size;
+			}
+
+			let next_free_chunk = self.header_at(next_free_idx);
+			let room_to_grow = (*next_free_chunk).length.into();
+
+			// If there isn't enough room to grow, grow as much as possible.
+			let needed_blocks = (new_size - old_size).min(room_to_grow);
+
+			// Check if there would be any blocks left over after growing into the next chunk.
+			let blocks_left_over = room_to_grow - needed_blocks;
 
 			if blocks_left_over > 0 {
 				let new_chunk_idx = next_free_idx + needed_blocks;
@@ -599,16 +1423,119 @@ where
 				}
 			}
 
+			self.on_resize(curr_idx, old_size, old_size + needed_blocks);
 			old_size + needed_blocks
 		}
 	}
+
+	/// Resizes an allocation to `new_size` blocks aligned to `align` blocks. The allocation is grown or
+	/// shrunk in place if possible; otherwise, a new allocation is made, the contents are copied over,
+	/// and the old allocation is freed. The returned pointer replaces `ptr`.
+	///
+	/// The allocation is only moved if it has to grow and there is no room after it, or if `ptr` isn't
+	/// aligned to `align` blocks.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. `new_size` must be nonzero,
+	/// and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+	/// in which case this function was a no-op and `ptr` is still valid.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<10, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// let blocker = unsafe { alloc.allocate_blocks(1, 1) }.unwrap();
+	/// unsafe { ptr.cast::<u32>().write(42) };
+	///
+	/// // The allocation can't grow in place, so it is moved.
+	/// let new = unsafe { alloc.reallocate_blocks(ptr, 2, 5, 1) }.unwrap();
+	/// assert_ne!(ptr, new);
+	/// assert_eq!(unsafe { new.cast::<u32>().read() }, 42);
+	/// ```
+	pub unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// Assert unsafe preconditions.
+		precondition!(
+			old_size >= 1 && new_size >= 1 && align.is_power_of_two() && align <= MAX_ALIGN / B,
+			"`old_size` and `new_size` must be nonzero and `align` must be a power of 2 in `1..=MAX_ALIGN / B`"
+		);
+
+		let aligned = ptr.addr().get().is_multiple_of(align * B);
+
+		unsafe {
+			if aligned && new_size < old_size {
+				self.shrink_in_place(ptr, old_size, new_size);
+				return Ok(ptr);
+			}
+
+			if aligned
+				&& (new_size == old_size || self.grow_in_place(ptr, old_size, new_size).is_ok())
+			{
+				return Ok(ptr);
+			}
+
+			// Growing into the free chunk before the allocation still avoids a separate allocation.
+			if new_size > old_size
+				&& let Ok(new) = self.grow_in_place_front(ptr, old_size, new_size, align)
+			{
+				return Ok(new);
+			}
+
+			// Otherwise just reallocate and copy. A failed allocation releases the blocks held back by the
+			// `quarantine` and `free-cache` features, which might make room to resize in place after all.
+			let held = self.holds_freed_blocks();
+			let new = match self.allocate_blocks(new_size, align) {
+				Ok(new) => new,
+				Err(_) if held => return self.reallocate_blocks(ptr, old_size, new_size, align),
+				Err(err) => return Err(err),
+			};
+
+			// SAFETY: Both allocations are at least `min(old_size, new_size)` blocks long.
+			ptr.copy_to_nonoverlapping(new, old_size.min(new_size) * B);
+			#[cfg(feature = "tags")]
+			self.set_tag_at(
+				self.index_of(new.as_ptr().cast()),
+				self.tag_at(self.index_of(ptr.as_ptr().cast())),
+			);
+			self.deallocate_blocks(ptr, old_size);
+
+			if new_size > old_size {
+				self.on_realloc();
+			}
+			Ok(new)
+		}
+	}
 }
 
 // Internal functions.
-impl<const L: usize, const B: usize> Stalloc<L, B>
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
 where
 	Align<B>: Alignment,
 {
+	/// The number of guard blocks on each side of every allocation: 1 if canaries were turned on with
+	/// `with_canaries()`, and 0 otherwise.
+	// Indices and sizes passed to the `on_*` functions never include the guard blocks.
+	#[allow(clippy::unused_self)]
+	const fn guard_blocks(&self) -> usize {
+		#[cfg(feature = "canaries")]
+		return self.canaries as usize;
+		#[cfg(not(feature = "canaries"))]
+		0
+	}
+
 	/// Get the index of a pointer to `data`. This function is always safe
 	/// to call, but the result may not be meaningful.
 	/// Even if the header is not at the start of the block (compiler's choice),
@@ -628,29 +1555,564 @@ where
 		header_in_block(unsafe { self.block_at(idx) })
 	}
 
+	/// Places an allocation of `size` blocks aligned to `align` blocks in `chunk`, whose header is `curr`.
+	/// Safety precondition: `curr` must be the free chunk after `prev`, and `chunk` must describe it.
+	unsafe fn place(
+		&self,
+		prev: *mut Header,
+		curr: *mut Header,
+		chunk: Candidate,
+		size: usize,
+		align: usize,
+	) -> NonNull<u8> {
+		let guarded_size = size + 2 * self.guard_blocks();
+		let slack = chunk.length - chunk.needed;
+
+		// With the `randomize` feature, a seeded allocator places the allocation at a random offset within
+		// the chunk. Otherwise, the strategy decides how the chunk is split.
+		#[cfg(feature = "randomize")]
+		let offset = if self.rng.get() == 0 {
+			self.strategy.placement(slack, align).min(slack) / align * align
+		} else {
+			self.random_offset(slack, align)
+		};
+		#[cfg(not(feature = "randomize"))]
+		let offset = self.strategy.placement(slack, align).min(slack) / align * align;
+
+		let spare_front = chunk.needed - guarded_size + offset;
+
+		unsafe {
+			self.split_chunk(prev, curr, spare_front, guarded_size);
+			self.strategy
+				.placed(chunk.index + spare_front, guarded_size);
+
+			let idx = chunk.index + spare_front + self.guard_blocks();
+			self.on_alloc(idx, size, align);
+			NonNull::new_unchecked(self.block_at(idx).cast())
+		}
+	}
+
+	/// Takes `size` blocks from the free chunk `curr`, after skipping `spare_front` blocks, and updates the
+	/// free list. Returns the header that now links to the free chunk following the taken blocks.
+	/// Safety precondition: `curr` must be the free chunk after `prev`, and it must be large enough.
+	unsafe fn split_chunk(
+		&self,
+		prev: *mut Header,
+		curr: *mut Header,
+		spare_front: usize,
+		size: usize,
+	) -> *mut Header {
+		unsafe {
+			let base = self.base.get();
+			let curr_idx = self.index_of(curr);
+			let next_idx = (*curr).next.into();
+			let spare_back = usize::from((*curr).length) - spare_front - size;
+
+			// If there are spare blocks, add them to the free list.
+			if spare_back > 0 {
+				let spare_back_idx = curr_idx + spare_front + size;
+				let spare_back_ptr = self.header_at(spare_back_idx);
+				(*spare_back_ptr).next = as_u16(next_idx);
+				(*spare_back_ptr).length = as_u16(spare_back);
+
+				if spare_front > 0 {
+					(*curr).next = as_u16(spare_back_idx);
+					(*curr).length = as_u16(spare_front);
+					curr
+				} else {
+					(*prev).next = as_u16(spare_back_idx);
+					prev
+				}
+			} else if spare_front > 0 {
+				// The spare blocks in front stay in the free list.
+				(*curr).length = as_u16(spare_front);
+				curr
+			} else {
+				(*prev).next = as_u16(next_idx);
+				// If this was the only free chunk, set the OOM marker.
+				if prev.eq(&base) && next_idx == 0 {
+					(*base).length = OOM_MARKER;
+				}
+				prev
+			}
+		}
+	}
+
+	/// Frees the allocation of `size` blocks at `ptr`, searching the free list from `start`, which must be
+	/// `base` or a free chunk before the allocation. Returns a header from which later searches can continue.
+	unsafe fn free_allocation(
+		&self,
+		start: *mut Header,
+		ptr: NonNull<u8>,
+		size: usize,
+	) -> *mut Header {
+		self.check_allocation(ptr, size);
+
+		// The guard blocks are freed together with the allocation.
+		let alloc_idx = self.index_of(header_in_block(ptr.as_ptr().cast()));
+		let freed_idx = alloc_idx - self.guard_blocks();
+		let freed_size = size + 2 * self.guard_blocks();
+
+		#[cfg(feature = "zero-on-free")]
+		self.wipe_blocks(freed_idx, freed_size);
+		#[cfg(feature = "poison")]
+		self.poison_blocks(freed_idx, freed_size);
+
+		#[cfg(feature = "quarantine")]
+		let end = {
+			self.quarantine_blocks(freed_idx, freed_size);
+			start
+		};
+		#[cfg(all(feature = "free-cache", not(feature = "quarantine")))]
+		let end = {
+			self.cache_blocks(freed_idx, freed_size);
+			start
+		};
+		#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+		let end = unsafe { self.release_blocks(start, freed_idx, freed_size) };
+
+		self.on_dealloc(alloc_idx, size);
+		end
+	}
+
+	/// Returns `freed_size` blocks at `freed_idx` to the free list, merging them with adjacent free chunks.
+	/// The free list is searched from `start`, which must be `base` or a free chunk before `freed_idx`.
+	/// Returns the free chunk that now contains the released blocks.
+	/// Safety precondition: the blocks must be in bounds, and must not be free or part of a live allocation.
+	unsafe fn release_blocks(
+		&self,
+		start: *mut Header,
+		freed_idx: usize,
+		freed_size: usize,
+	) -> *mut Header {
+		let freed_ptr = unsafe { self.header_at(freed_idx) };
+		let base = self.base.get();
+		let before = self.header_before_from(start, freed_idx);
+
+		unsafe {
+			let prev_next = (*before).next.into();
+			(*freed_ptr).next = as_u16(prev_next);
+			(*freed_ptr).length = as_u16(freed_size);
+
+			// Try to merge with the next free block.
+			if freed_idx + freed_size == prev_next {
+				let header_to_merge = self.header_at(prev_next);
+				(*freed_ptr).next = (*header_to_merge).next;
+				(*freed_ptr).length += (*header_to_merge).length;
+				#[cfg(feature = "poison")]
+				self.poison_header(prev_next);
+			}
+
+			// Try to merge with the previous free block.
+			if before.eq(&base) {
+				(*base).next = as_u16(freed_idx);
+			} else if self.index_of(before) + usize::from((*before).length) == freed_idx {
+				(*before).next = (*freed_ptr).next;
+				(*before).length += (*freed_ptr).length;
+				#[cfg(feature = "poison")]
+				self.poison_header(freed_idx);
+				self.raise_largest_free((*before).length.into());
+				return before;
+			} else {
+				// No merge is possible.
+				(*before).next = as_u16(freed_idx);
+			}
+
+			self.raise_largest_free((*freed_ptr).length.into());
+			freed_ptr
+		}
+	}
+
+	/// Removes the `size` blocks at `idx` from the free list, if they are all free, and returns whether
+	/// that succeeded. Quarantined and cached blocks are released first if necessary.
+	/// Safety precondition: the blocks must be in bounds.
+	fn take_blocks_at(&self, idx: usize, size: usize) -> bool {
+		unsafe {
+			// Find the last free chunk that starts at or before the blocks, and check if it contains them.
+			let curr = self.header_before(idx + 1);
+			if !curr.eq(&self.base.get()) {
+				let curr_idx = self.index_of(curr);
+				let prev = self.header_before(curr_idx);
+
+				if idx + size <= curr_idx + usize::from((*curr).length) {
+					self.split_chunk(prev, curr, idx - curr_idx, size);
+					return true;
+				}
+			}
+		}
+
+		// The blocks might be held back by the quarantine or the free cache.
+		#[cfg(feature = "quarantine")]
+		if self.try_flush_quarantine() {
+			return self.take_blocks_at(idx, size);
+		}
+		#[cfg(feature = "free-cache")]
+		if self.try_flush_free_cache() {
+			return self.take_blocks_at(idx, size);
+		}
+
+		false
+	}
+
+	/// Fills `size` blocks at `ptr`, which were free until now, with zeros.
+	/// With the `zero-on-free` feature, free blocks are zeroed apart from the headers of free chunks (which
+	/// are stored at the start of a block), so only those need to be cleared, unless the blocks were poisoned.
+	/// Safety precondition: the blocks must be in bounds.
+	#[allow(clippy::missing_const_for_fn)]
+	unsafe fn zero_free_blocks(ptr: NonNull<u8>, size: usize) {
+		#[cfg(all(feature = "zero-on-free", not(feature = "poison")))]
+		for i in 0..size {
+			unsafe {
+				ptr.add(i * B)
+					.cast::<Header>()
+					.write(Header { next: 0, length: 0 });
+			}
+		}
+		#[cfg(not(all(feature = "zero-on-free", not(feature = "poison"))))]
+		unsafe {
+			ptr.write_bytes(0, size * B);
+		}
+	}
+
+	/// Returns the alignment, in blocks, that a copy of an allocation of `size` blocks at `ptr` needs
+	/// to be at least as aligned as the original, up to an alignment of `size.next_power_of_two()`.
+	fn natural_align(ptr: NonNull<u8>, size: usize) -> usize {
+		// The buffer is always aligned to `B`, so this is at least 1.
+		let addr_align = 1 << (ptr.addr().get() / B).trailing_zeros();
+		addr_align.min(size.next_power_of_two()).min(MAX_ALIGN / B)
+	}
+
+	/// Checks whether there are no free chunks. Unlike `is_oom()`, this ignores held back blocks.
+	const fn is_free_list_empty(&self) -> bool {
+		unsafe { *self.base.get() }.length == OOM_MARKER
+	}
+
+	/// Checks whether any freed blocks are being held back by the `quarantine` or `free-cache` features.
+	#[allow(clippy::unused_self)]
+	const fn holds_freed_blocks(&self) -> bool {
+		#[cfg(feature = "quarantine")]
+		if !self.is_quarantine_empty() {
+			return true;
+		}
+		#[cfg(feature = "free-cache")]
+		if !self.is_free_cache_empty() {
+			return true;
+		}
+
+		false
+	}
+
+	/// Returns the number of freed blocks that are being held back by the `quarantine` or `free-cache` features.
+	#[allow(clippy::unused_self, clippy::missing_const_for_fn)]
+	pub(crate) fn held_blocks(&self) -> usize {
+		let held = 0;
+		#[cfg(feature = "quarantine")]
+		let held = held + self.quarantined_blocks();
+		#[cfg(feature = "free-cache")]
+		let held = held + self.cached_blocks();
+		held
+	}
+
+	/// Returns an upper bound on the length of the largest free chunk, or 0 if there are no free chunks.
+	const fn largest_free(&self) -> usize {
+		let length = unsafe { *self.base.get() }.length;
+		if length == OOM_MARKER {
+			0
+		} else {
+			length as usize + 1
+		}
+	}
+
+	/// Sets the upper bound on the length of the largest free chunk, which also clears the OOM marker.
+	/// Safety precondition: `length` must be in `1..=L`, and there must be free chunks.
+	const unsafe fn set_largest_free(&self, length: usize) {
+		unsafe { (*self.base.get()).length = as_u16(length - 1) };
+	}
+
+	/// Makes sure that the upper bound on the largest free chunk covers a free chunk of `length` blocks,
+	/// and clears the OOM marker. Safety precondition: the free chunk must be in the free list.
+	const fn raise_largest_free(&self, length: usize) {
+		if length > self.largest_free() {
+			unsafe { self.set_largest_free(length) };
+		}
+	}
+
+	/// With the `strict-checks` feature, panics unless `ptr` could point to an allocation of `size` blocks.
+	/// With the `tracking` feature as well, `ptr` must point to a live allocation of exactly `size` blocks.
+	/// With canaries, panics if the guard blocks around the allocation were overwritten.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn check_allocation(&self, ptr: NonNull<u8>, size: usize) {
+		#[cfg(feature = "strict-checks")]
+		{
+			let offset = ptr.addr().get().wrapping_sub(self.data.get().addr());
+			assert!(
+				offset.is_multiple_of(B) && offset / B < L && offset / B + size <= L,
+				"safety precondition violated: `ptr` does not point to an allocation of {size} blocks"
+			);
+
+			#[cfg(feature = "tracking")]
+			assert!(
+				self.tracked_length(offset / B) == size,
+				"safety precondition violated: `ptr` does not point to a live allocation of {size} blocks"
+			);
+		}
+
+		#[cfg(feature = "canaries")]
+		self.assert_canaries(self.index_of(header_in_block(ptr.as_ptr().cast())), size);
+	}
+
+	/// Called after `size` blocks starting at index `idx` have been allocated with an alignment of `align` blocks.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn on_alloc(&self, idx: usize, size: usize, align: usize) {
+		self.live.set(self.live.get() + 1);
+		#[cfg(feature = "poison-check")]
+		self.check_poison(idx, size);
+		#[cfg(feature = "tracking")]
+		self.track_alloc(idx, size);
+		#[cfg(feature = "tags")]
+		self.set_tag_at(idx, 0);
+		#[cfg(feature = "canaries")]
+		self.write_canaries(idx, size);
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| {
+			s.allocations = s.allocations.wrapping_add(1);
+			s.add_used_blocks(size + 2 * self.guard_blocks());
+		});
+		#[cfg(feature = "hooks")]
+		self.hook_alloc(idx, size, align);
+		#[cfg(feature = "log")]
+		self.log_alloc(size);
+	}
+
+	/// Called when an allocation of `size` blocks aligned to `align` blocks could not be satisfied.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn on_alloc_failed(&self, size: usize, align: usize) {
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| s.failed_allocations = s.failed_allocations.wrapping_add(1));
+		#[cfg(feature = "log")]
+		self.log_alloc_failed(size, align);
+	}
+
+	/// Called after `size` blocks starting at index `idx` have been deallocated.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn on_dealloc(&self, idx: usize, size: usize) {
+		self.live.set(self.live.get() - 1);
+		#[cfg(feature = "tracking")]
+		self.track_dealloc(idx);
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| {
+			s.deallocations = s.deallocations.wrapping_add(1);
+			s.used_blocks = s.used_blocks.saturating_sub(size + 2 * self.guard_blocks());
+		});
+		#[cfg(feature = "hooks")]
+		self.hook_dealloc(idx, size);
+		#[cfg(feature = "log")]
+		self.log_dealloc(idx, size);
+	}
+
+	/// Called after the allocation at index `idx` was resized in place.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn on_resize(&self, idx: usize, old_size: usize, new_size: usize) {
+		#[cfg(feature = "tracking")]
+		self.track_alloc(idx, new_size);
+		#[cfg(feature = "canaries")]
+		self.write_canaries(idx, new_size);
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| {
+			if new_size > old_size {
+				s.grows_in_place = s.grows_in_place.wrapping_add(1);
+				s.add_used_blocks(new_size - old_size);
+			} else if new_size < old_size {
+				s.shrinks = s.shrinks.wrapping_add(1);
+				s.used_blocks = s.used_blocks.saturating_sub(old_size - new_size);
+			}
+		});
+		#[cfg(feature = "hooks")]
+		self.hook_resize(idx, old_size, new_size);
+		#[cfg(feature = "log")]
+		self.log_resize(idx, old_size, new_size);
+	}
+
+	/// Called after the allocation at index `old_idx` was resized from `old_size` to `new_size` blocks
+	/// in place, in a way that moved its start to index `new_idx`.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn on_resize_moved(&self, old_idx: usize, new_idx: usize, old_size: usize, new_size: usize) {
+		#[cfg(feature = "tracking")]
+		{
+			self.track_dealloc(old_idx);
+			self.track_alloc(new_idx, new_size);
+		}
+		#[cfg(feature = "tags")]
+		self.set_tag_at(new_idx, self.tag_at(old_idx));
+		#[cfg(feature = "canaries")]
+		self.write_canaries(new_idx, new_size);
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| {
+			if new_size > old_size {
+				s.grows_in_place = s.grows_in_place.wrapping_add(1);
+				s.add_used_blocks(new_size - old_size);
+			} else {
+				s.shrinks = s.shrinks.wrapping_add(1);
+				s.used_blocks = s.used_blocks.saturating_sub(old_size - new_size);
+			}
+		});
+		#[cfg(feature = "hooks")]
+		{
+			self.hook_dealloc(old_idx, old_size);
+			self.hook_alloc(new_idx, new_size, 1);
+		}
+		#[cfg(feature = "log")]
+		if new_size > old_size {
+			self.log_resize(new_idx, old_size, new_size);
+		}
+	}
+
+	/// Called after the allocation of `size` blocks at index `idx` was split after its first `at` blocks.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn on_split(&self, idx: usize, size: usize, at: usize) {
+		let second_idx = idx + at + 2 * self.guard_blocks();
+		let second_size = size - at - 2 * self.guard_blocks();
+		self.live.set(self.live.get() + 1);
+
+		#[cfg(feature = "tracking")]
+		{
+			self.track_alloc(idx, at);
+			self.track_alloc(second_idx, second_size);
+		}
+		#[cfg(feature = "tags")]
+		self.set_tag_at(second_idx, self.tag_at(idx));
+		#[cfg(feature = "canaries")]
+		{
+			self.write_canaries(idx, at);
+			self.write_canaries(second_idx, second_size);
+		}
+		// The number of used blocks doesn't change, since the new guard blocks were already in use.
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| s.allocations = s.allocations.wrapping_add(1));
+		#[cfg(feature = "hooks")]
+		{
+			self.hook_resize(idx, size, at);
+			self.hook_alloc(second_idx, second_size, 1);
+		}
+	}
+
+	/// Called after the allocation of `first_size` blocks at index `idx` was merged with the allocation
+	/// of `second_size` blocks that follows it.
+	#[allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)]
+	fn on_merge(&self, idx: usize, first_size: usize, second_size: usize) {
+		let second_idx = idx + first_size + 2 * self.guard_blocks();
+		let size = first_size + second_size + 2 * self.guard_blocks();
+		self.live.set(self.live.get() - 1);
+
+		#[cfg(feature = "tracking")]
+		{
+			self.track_dealloc(second_idx);
+			self.track_alloc(idx, size);
+		}
+		#[cfg(feature = "canaries")]
+		self.write_canaries(idx, size);
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| s.deallocations = s.deallocations.wrapping_add(1));
+		#[cfg(feature = "hooks")]
+		{
+			self.hook_dealloc(second_idx, second_size);
+			self.hook_resize(idx, first_size, size);
+		}
+	}
+
+	/// Called after an allocation was moved to a new location in order to grow.
+	#[allow(clippy::unused_self, clippy::missing_const_for_fn)]
+	pub(crate) fn on_realloc(&self) {
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| s.reallocating_grows = s.reallocating_grows.wrapping_add(1));
+	}
+
+	/// Called after the allocator has been reset with `clear()`.
+	fn on_clear(&self) {
+		self.strategy.reset();
+		self.live.set(0);
+		self.reserved.set(0);
+		#[cfg(feature = "leak-check")]
+		self.permanent.set(0);
+		#[cfg(feature = "tracking")]
+		self.track_clear();
+		#[cfg(feature = "quarantine")]
+		self.clear_quarantine();
+		#[cfg(feature = "free-cache")]
+		self.clear_free_cache();
+		#[cfg(feature = "stats")]
+		self.update_stats(|s| s.used_blocks = 0);
+	}
+
+	/// Returns an iterator over the free chunks as `(index, length)` pairs, in order of increasing address.
+	pub(crate) fn free_chunks(&self) -> FreeChunks<'_, L, B, S> {
+		let base = unsafe { *self.base.get() };
+		FreeChunks {
+			alloc: self,
+			next: (base.length != OOM_MARKER).then_some(base.next.into()),
+		}
+	}
+
 	/// This function always is safe to call. If `idx` is very large,
 	/// the returned value will simply be the last header in the free list.
 	/// Note: this function may return a pointer to `base`.
 	fn header_before(&self, idx: usize) -> *mut Header {
-		let mut ptr = self.base.get();
+		self.header_before_from(self.base.get(), idx)
+	}
+
+	/// Like `header_before()`, but starts searching at `ptr`, which must be `base` or a free chunk before `idx`.
+	fn header_before_from(&self, mut ptr: *mut Header, idx: usize) -> *mut Header {
+		let base = self.base.get();
 
 		unsafe {
-			if (*ptr).length == OOM_MARKER || usize::from((*ptr).next) >= idx {
+			if ptr.eq(&base) && ((*ptr).length == OOM_MARKER || usize::from((*ptr).next) >= idx) {
 				return ptr;
 			}
 
 			loop {
-				ptr = self.header_at((*ptr).next.into());
 				let next_idx = usize::from((*ptr).next);
-				if next_idx == 0 || next_idx >= idx {
+				if !ptr.eq(&base) && (next_idx == 0 || next_idx >= idx) {
 					return ptr;
 				}
+				ptr = self.header_at(next_idx);
 			}
 		}
 	}
 }
 
-impl<const L: usize, const B: usize> Debug for Stalloc<L, B>
+/// An allocator that blocks can be carved from, and that owned allocations (like `SubStalloc` and `BlockBox`)
+/// return their blocks to.
+trait CarveParent {
+	/// Returns `size` blocks at `ptr` to the allocator.
+	unsafe fn release(&self, ptr: NonNull<u8>, size: usize);
+}
+
+/// An iterator over the free chunks of a `Stalloc`, created by `free_chunks()`.
+pub(crate) struct FreeChunks<'a, const L: usize, const B: usize, S: Strategy>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a Stalloc<L, B, S>,
+	next: Option<usize>,
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Iterator for FreeChunks<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	type Item = (usize, usize);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let idx = self.next?;
+		let header = unsafe { *self.alloc.header_at(idx) };
+
+		// The free list ends with a chunk whose `next` is 0.
+		self.next = (header.next != 0).then_some(header.next.into());
+		Some((idx, header.length.into()))
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Debug for Stalloc<L, B, S>
 where
 	Align<B>: Alignment,
 {
@@ -682,7 +2144,35 @@ where
 	}
 }
 
-impl<const L: usize, const B: usize> Default for Stalloc<L, B>
+/// Prints a one-line summary of the allocator's usage.
+///
+/// # Examples
+/// ```
+/// use stalloc::Stalloc;
+///
+/// let alloc = Stalloc::<200, 4>::new();
+/// unsafe { alloc.allocate_blocks(50, 1) }.unwrap();
+///
+/// assert_eq!(alloc.to_string(), "50/200 blocks used (25%), largest free run 150");
+/// ```
+impl<const L: usize, const B: usize, S: Strategy> Display for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let report = self.report();
+
+		write!(
+			f,
+			"{}/{L} blocks used ({}%), largest free run {}",
+			report.used_blocks,
+			report.used_blocks * 100 / L,
+			report.largest_free_chunk
+		)
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Default for Stalloc<L, B, S>
 where
 	Align<B>: Alignment,
 {
@@ -691,8 +2181,12 @@ where
 	}
 }
 
+/// `Allocator` is only implemented for references to the allocator types, and never for the types themselves.
+/// The memory of a `Stalloc` is stored inline, so moving it would move every live allocation along with it.
+/// The `Allocator` trait requires that moving an allocator doesn't invalidate its memory blocks, which means
+/// that a collection owning its allocator, like `Vec<T, Stalloc<L, B>>`, would be unsound.
 #[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
-unsafe impl<const L: usize, const B: usize> Allocator for &Stalloc<L, B>
+unsafe impl<const L: usize, const B: usize, S: Strategy> Allocator for &Stalloc<L, B, S>
 where
 	Align<B>: Alignment,
 {
@@ -713,14 +2207,17 @@ where
 	}
 
 	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-		let ptr = self.allocate(layout)?;
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
 
-		// We intentionally shorten the length of the allocated pointer and hence write fewer zeros.
-		let ptr = NonNull::slice_from_raw_parts(ptr.cast(), layout.size());
+		// A dangling pointer doesn't need to be zeroed.
+		if size == 0 {
+			return self.allocate(layout);
+		}
 
-		// SAFETY: We are filling in the entire allocated range with zeros.
-		unsafe { ptr.cast::<u8>().write_bytes(0, ptr.len()) }
-		Ok(ptr)
+		// SAFETY: We have made sure that `size` and `align` are valid.
+		unsafe { self.allocate_blocks_zeroed(size, align) }
+			.map(|p| NonNull::slice_from_raw_parts(p, size * B))
 	}
 
 	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
@@ -759,28 +2256,10 @@ where
 			};
 		}
 
-		unsafe {
-			// Try to grow in place.
-			// SAFETY: `ptr` and `old_size` are upheld by the caller. As for `new_size`,
-			// we have already made sure that `old_size != new_size`, and the fact that
-			// new_size >= old_size is upheld by the caller.
-			if self.grow_in_place(ptr, old_size, new_size).is_ok() {
-				Ok(NonNull::slice_from_raw_parts(ptr, new_size * B))
-			} else {
-				// Otherwise just reallocate and copy.
-				// SAFETY: We have made sure that `new_size > 0` and that `align` is valid.
-				let new = self.allocate_blocks(new_size, align)?;
-
-				// SAFETY: We are copying all the necessary bytes from `ptr` into `new`.
-				// `ptr` and `new` both point to an allocation of at least `old_layout.size()` bytes.
-				ptr.copy_to_nonoverlapping(new, old_layout.size());
-
-				// SAFETY: We already made sure that old_size > 0.
-				self.deallocate_blocks(ptr, old_size);
-
-				Ok(NonNull::slice_from_raw_parts(new, new_size * B))
-			}
-		}
+		// SAFETY: `ptr` and `old_size` are upheld by the caller. We have already made sure that `new_size`
+		// is nonzero, and that `align` is valid.
+		unsafe { self.reallocate_blocks(ptr, old_size, new_size, align) }
+			.map(|p| NonNull::slice_from_raw_parts(p, new_size * B))
 	}
 
 	unsafe fn grow_zeroed(
@@ -829,43 +2308,14 @@ where
 			}
 		}
 
-		// We have to reallocate only if the alignment isn't good enough anymore.
-		if ptr.as_ptr().addr() % new_layout.align() != 0 {
-			// Since the address of `ptr` must be a multiple of `B` (upheld by the caller),
-			// entering this branch means that `new_layout.align() > B`.
-			let align = new_layout.align() / B;
-
-			unsafe {
-				// SAFETY: We just made sure that `new_size > 0`, and `align` is always valid.
-				let new = self.allocate_blocks(new_size, align)?;
-
-				// SAFETY: We are copying all the necessary bytes from `ptr` into `new`.
-				// `ptr` and `new` both point to an allocation of at least `old_layout.size()` bytes.
-				ptr.copy_to_nonoverlapping(new, old_layout.size());
-
-				// SAFETY: We already made sure that old_size > 0.
-				self.deallocate_blocks(ptr, old_size);
-
-				return Ok(NonNull::slice_from_raw_parts(new, new_size * B));
-			}
-		}
-
-		// Check if the size hasn't changed.
-		if old_size == new_size {
-			return Ok(NonNull::slice_from_raw_parts(ptr, old_size * B));
-		}
-
-		// SAFETY: We just made sure that new_size > 0 and old_size > new_size,
-		// and `ptr` and `old_size` are valid (upheld by the caller).
-		unsafe {
-			self.shrink_in_place(ptr, old_size, new_size);
-		}
-
-		Ok(NonNull::slice_from_raw_parts(ptr, new_size * B))
+		// The allocation only has to move if the alignment isn't good enough anymore.
+		// SAFETY: `ptr` and `old_size` are upheld by the caller, and we just made sure that `new_size > 0`.
+		unsafe { self.reallocate_blocks(ptr, old_size, new_size, new_layout.align().div_ceil(B)) }
+			.map(|p| NonNull::slice_from_raw_parts(p, new_size * B))
 	}
 }
 
-unsafe impl<const L: usize, const B: usize> ChainableAlloc for Stalloc<L, B>
+unsafe impl<const L: usize, const B: usize, S: Strategy> ChainableAlloc for Stalloc<L, B, S>
 where
 	Align<B>: Alignment,
 {
@@ -874,7 +2324,7 @@ where
 	}
 }
 
-impl<const L: usize, const B: usize> Stalloc<L, B>
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
 where
 	Align<B>: Alignment,
 {
@@ -885,4 +2335,12 @@ where
 	{
 		AllocChain::new(self, next)
 	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
 }
@@ -0,0 +1,142 @@
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use lock_api::RawMutex;
+
+use crate::UnsafeStalloc;
+use crate::align::{Align, Alignment};
+use crate::lockwrap::locked_stalloc_api;
+
+/// A wrapper around `UnsafeStalloc` that prevents data races using any lock that implements
+/// `lock_api::RawMutex`. This requires the `lock_api` feature.
+///
+/// This makes it possible to use the locks of `parking_lot` or `spin`, an RTOS mutex, or a lock that masks
+/// interrupts, in places where `SyncStalloc` can't be used because it depends on `std::sync::Mutex`.
+///
+/// The most common operations are available directly. Every other method of `Stalloc` can be called
+/// through the guard returned by `acquire_locked()`.
+///
+/// # Examples
+/// ```
+/// use lock_api::{GuardSend, RawMutex};
+/// use stalloc::LockedStalloc;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+///
+/// // A simple spinlock. In practice, this could be `parking_lot::RawMutex`.
+/// struct RawSpinlock(AtomicBool);
+///
+/// unsafe impl RawMutex for RawSpinlock {
+///     const INIT: Self = Self(AtomicBool::new(false));
+///     type GuardMarker = GuardSend;
+///
+///     fn lock(&self) {
+///         while !self.try_lock() {
+///             std::hint::spin_loop();
+///         }
+///     }
+///
+///     fn try_lock(&self) -> bool {
+///         self.0.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok()
+///     }
+///
+///     unsafe fn unlock(&self) {
+///         self.0.store(false, Ordering::Release);
+///     }
+/// }
+///
+/// #[global_allocator]
+/// static GLOBAL: LockedStalloc<1000, 4, RawSpinlock> = LockedStalloc::new();
+///
+/// fn main() {
+///     let v = vec![1, 2, 3];
+///     assert!(!GLOBAL.is_empty());
+/// }
+/// ```
+pub struct LockedStalloc<const L: usize, const B: usize, R: RawMutex>
+where
+	Align<B>: Alignment,
+{
+	raw: R,
+	inner: UnsafeStalloc<L, B>,
+}
+
+/// A lock around `LockedStalloc`. Constructing this type is proof that the user holds an exclusive
+/// lock on the inner `UnsafeStalloc`. When this falls out of scope, the `LockedStalloc` is unlocked.
+pub struct LockedGuard<'a, const L: usize, const B: usize, R: RawMutex>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a LockedStalloc<L, B, R>,
+	_not_sync: PhantomData<*const ()>,
+}
+
+impl<const L: usize, const B: usize, R: RawMutex> Deref for LockedGuard<'_, L, B, R>
+where
+	Align<B>: Alignment,
+{
+	type Target = UnsafeStalloc<L, B>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.alloc.inner
+	}
+}
+
+impl<const L: usize, const B: usize, R: RawMutex> Drop for LockedGuard<'_, L, B, R>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		// SAFETY: The lock was acquired when this guard was created.
+		unsafe { self.alloc.raw.unlock() };
+	}
+}
+
+// SAFETY: The inner `UnsafeStalloc` is only accessed while the lock is held.
+unsafe impl<const L: usize, const B: usize, R: RawMutex + Sync> Sync for LockedStalloc<L, B, R> where
+	Align<B>: Alignment
+{
+}
+
+impl<const L: usize, const B: usize, R: RawMutex> LockedStalloc<L, B, R>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new empty `LockedStalloc` instance.
+	#[must_use]
+	pub const fn new() -> Self {
+		// SAFETY: The `UnsafeStalloc` can only be accessed through `acquire_locked()`,
+		// which guarantees that the lock is held before proceeding.
+		Self {
+			raw: R::INIT,
+			inner: unsafe { UnsafeStalloc::new() },
+		}
+	}
+
+	/// Initializes a new `LockedStalloc` instance in which the `size` blocks starting at block index `idx`
+	/// are reserved. See `Stalloc::with_reserved()` for details.
+	///
+	/// # Panics
+	///
+	/// Panics if `size` is zero or the reserved blocks are out of bounds.
+	#[must_use]
+	pub const fn with_reserved(idx: usize, size: usize) -> Self {
+		// SAFETY: See `new()`.
+		Self {
+			raw: R::INIT,
+			inner: unsafe { UnsafeStalloc::with_reserved(idx, size) },
+		}
+	}
+
+	/// Acquires an exclusive lock for the allocator. This can be used to chain multiple operations on the
+	/// allocator, or to call methods that `LockedStalloc` doesn't provide directly.
+	pub fn acquire_locked(&self) -> LockedGuard<'_, L, B, R> {
+		self.raw.lock();
+
+		LockedGuard {
+			alloc: self,
+			_not_sync: PhantomData,
+		}
+	}
+}
+
+locked_stalloc_api!(LockedStalloc<L, B, R: RawMutex>, inner, acquire_locked);
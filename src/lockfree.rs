@@ -0,0 +1,379 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt::{self, Debug, Formatter};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use crate::align::{Align, Alignment};
+use crate::{AllocChain, AllocError, AllocatorStats, ChainableAlloc, OwnedAllocChain, SpinStalloc};
+
+/// The number of size classes that a `LockFreeStalloc` caches. Allocations of up to this many blocks
+/// (with an alignment of at most `B`) are served without taking a lock.
+pub const SIZE_CLASSES: usize = 8;
+
+/// A thread-safe allocator whose common-case allocations and deallocations never take a lock.
+///
+/// Freed allocations of up to `SIZE_CLASSES` blocks are not returned to the free list. Instead, they are
+/// pushed onto a lock-free stack (a Treiber stack) for their size, and later allocations of the same size
+/// pop them off again with a single compare-and-swap. Only allocations that can't be served from these
+/// stacks fall back to an inner `SpinStalloc`, which takes a lock.
+///
+/// Cached blocks are still counted as allocated by the inner allocator. If an allocation fails, every
+/// cached block is returned to the free list and the allocation is retried, and `flush()` can be used
+/// to do the same by hand. The stacks are linked through a side table of `L` atomic indices, so this
+/// allocator is `4 * L` bytes larger than a `SpinStalloc`.
+///
+/// # Examples
+/// ```
+/// use stalloc::LockFreeStalloc;
+/// use std::alloc::{GlobalAlloc, Layout};
+///
+/// let alloc = LockFreeStalloc::<100, 8>::new();
+/// let layout = Layout::new::<[u64; 2]>();
+///
+/// unsafe {
+///     let first = alloc.alloc(layout);
+///     alloc.dealloc(first, layout);
+///
+///     // The freed block was cached, so this doesn't take the lock.
+///     let second = alloc.alloc(layout);
+///     assert_eq!(first, second);
+///     alloc.dealloc(second, layout);
+/// }
+/// ```
+pub struct LockFreeStalloc<const L: usize, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	inner: SpinStalloc<L, B>,
+	/// The top of the stack of each size class. The low 32 bits hold the index of the first cached block
+	/// plus one (or 0 if the stack is empty), and the high 32 bits hold a counter that is incremented
+	/// on every change, which prevents the ABA problem.
+	heads: [AtomicU64; SIZE_CLASSES],
+	/// For every cached block, the index of the next cached block in its stack, plus one.
+	next: [AtomicU32; L],
+}
+
+impl<const L: usize, const B: usize> LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new empty `LockFreeStalloc` instance.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			inner: SpinStalloc::new(),
+			heads: [const { AtomicU64::new(0) }; SIZE_CLASSES],
+			next: [const { AtomicU32::new(0) }; L],
+		}
+	}
+
+	/// Tries to allocate `size` blocks aligned to `align` blocks, using a cached allocation if possible.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		if align == 1
+			&& size <= SIZE_CLASSES
+			&& let Some(idx) = self.pop(size - 1)
+		{
+			return Ok(self.block_ptr(idx));
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.allocate_blocks(size, align) }.or_else(|_| {
+			if self.flush() == 0 {
+				return Err(AllocError);
+			}
+
+			// SAFETY: Upheld by the caller.
+			unsafe { self.inner.allocate_blocks(size, align) }
+		})
+	}
+
+	/// Deallocates a pointer. Allocations of up to `SIZE_CLASSES` blocks are cached for reuse.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to an allocation, and `size` must be the number of blocks
+	/// in the allocation. That is, `size` is always in `1..=L`.
+	pub unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		if size <= SIZE_CLASSES {
+			self.push(size - 1, self.block_idx(ptr));
+		} else {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.inner.deallocate_blocks(ptr, size) };
+		}
+	}
+
+	/// Returns every cached block to the free list of the inner allocator. Returns the number of blocks
+	/// that were released.
+	pub fn flush(&self) -> usize {
+		let mut released = 0;
+
+		for class in 0..SIZE_CLASSES {
+			while let Some(idx) = self.pop(class) {
+				// SAFETY: Cached blocks are allocations of `class + 1` blocks.
+				unsafe { self.inner.deallocate_blocks(self.block_ptr(idx), class + 1) };
+				released += class + 1;
+			}
+		}
+
+		released
+	}
+
+	/// Returns the inner allocator, which can be used to call the methods of `Stalloc` while holding its lock.
+	/// Cached blocks are counted as allocated by the inner allocator.
+	pub const fn inner(&self) -> &SpinStalloc<L, B> {
+		&self.inner
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+
+	/// Pushes the block at `idx` onto the stack of `class`.
+	#[allow(clippy::cast_possible_truncation)]
+	fn push(&self, class: usize, idx: usize) {
+		let head = &self.heads[class];
+		let mut old = head.load(Ordering::Relaxed);
+
+		loop {
+			self.next[idx].store(old as u32, Ordering::Relaxed);
+			let new = (old >> 32).wrapping_add(1) << 32 | (idx as u64 + 1);
+
+			match head.compare_exchange_weak(old, new, Ordering::Release, Ordering::Relaxed) {
+				Ok(_) => return,
+				Err(current) => old = current,
+			}
+		}
+	}
+
+	/// Pops a block off the stack of `class`, and returns its index.
+	#[allow(clippy::cast_possible_truncation)]
+	fn pop(&self, class: usize) -> Option<usize> {
+		let head = &self.heads[class];
+		let mut old = head.load(Ordering::Acquire);
+
+		loop {
+			let top = old as u32;
+			if top == 0 {
+				return None;
+			}
+
+			let idx = top as usize - 1;
+			let next = self.next[idx].load(Ordering::Relaxed);
+			let new = (old >> 32).wrapping_add(1) << 32 | u64::from(next);
+
+			match head.compare_exchange_weak(old, new, Ordering::Acquire, Ordering::Acquire) {
+				Ok(_) => return Some(idx),
+				Err(current) => old = current,
+			}
+		}
+	}
+
+	/// Returns a pointer to the block at `idx`, which must be in `0..L`.
+	const fn block_ptr(&self, idx: usize) -> NonNull<u8> {
+		// SAFETY: The block is inside the allocator.
+		unsafe { NonNull::new_unchecked(self.inner.base_ptr().add(idx * B)) }
+	}
+
+	/// Returns the index of the block that `ptr` points to.
+	fn block_idx(&self, ptr: NonNull<u8>) -> usize {
+		(ptr.addr().get() - self.inner.base_ptr().addr()) / B
+	}
+}
+
+impl<const L: usize, const B: usize> Default for LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const L: usize, const B: usize> Drop for LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		// Cached blocks aren't leaks, so return them before the inner allocator is dropped.
+		self.flush();
+	}
+}
+
+impl<const L: usize, const B: usize> Debug for LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self.inner)
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> GlobalAlloc for LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// SAFETY: `size` and `align` are valid.
+		unsafe { self.allocate_blocks(size, align) }.map_or(ptr::null_mut(), NonNull::as_ptr)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		// SAFETY: Upheld by the caller.
+		unsafe { self.deallocate_blocks(NonNull::new_unchecked(ptr), size) };
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
+		// The allocation already has room for `new_size` bytes.
+		if new_size.div_ceil(B) == old_layout.size().div_ceil(B) {
+			return ptr;
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			let new_layout = Layout::from_size_align_unchecked(new_size, old_layout.align());
+			let new_ptr = self.alloc(new_layout);
+			if !new_ptr.is_null() {
+				ptr.copy_to_nonoverlapping(new_ptr, old_layout.size().min(new_size));
+				self.dealloc(ptr, old_layout);
+			}
+			new_ptr
+		}
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> ChainableAlloc for LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.inner.addr_in_bounds(addr)
+	}
+
+	/// `GlobalAlloc` already returns a null pointer when this allocator is full.
+	fn chain_aware(&self) -> bool {
+		true
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			if zeroed {
+				self.alloc_zeroed(layout)
+			} else {
+				self.alloc(layout)
+			}
+		}
+	}
+
+	unsafe fn realloc_in_chain(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.realloc(ptr, layout, new_size) }
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.dealloc(ptr, layout) };
+	}
+}
+
+/// Cached blocks are counted as used.
+impl<const L: usize, const B: usize> AllocatorStats for LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn capacity_bytes(&self) -> usize {
+		self.inner.capacity_bytes()
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.inner.used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.inner.peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.inner.allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.inner.deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.inner.failed_allocations()
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use crate::Allocator;
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<const L: usize, const B: usize> Allocator for &LockFreeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// We can only allocate memory in units of `B`, so round up.
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// If `size` is zero, give away a dangling pointer.
+		if size == 0 {
+			let dangling = NonNull::new(layout.align() as _).unwrap();
+			return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+		}
+
+		// SAFETY: We have made sure that `size` and `align` are valid.
+		unsafe { self.allocate_blocks(size, align) }
+			.map(|p| NonNull::slice_from_raw_parts(p, size * B))
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size == 0 {
+			return;
+		}
+
+		// SAFETY: We just made sure that size != 0. Everything else is upheld by the caller.
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
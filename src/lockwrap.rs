@@ -0,0 +1,397 @@
+/// Implements the API that is shared by the wrappers that protect an `UnsafeStalloc` with a lock:
+/// `SpinStalloc`, `LockedStalloc` and `CriticalSectionStalloc`.
+///
+/// `$inner` is the field that holds the `UnsafeStalloc`, and `$lock` is the method that locks it, returning
+/// a guard that dereferences to it. Each wrapper still defines its own constructors and locking methods.
+macro_rules! locked_stalloc_api {
+	($name:ident<L, B $(, $param:ident: $bound:path)?>, $inner:tt, $lock:ident) => {
+		impl<const L: usize, const B: usize $(, $param: $bound)?> $name<L, B $(, $param)?>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			/// Returns this allocator with `handler` registered as its OOM handler, which is called when
+			/// `GlobalAlloc` is about to return a null pointer. This can be used to initialize a `static`.
+			/// This requires the `oom-handler` feature.
+			#[cfg(feature = "oom-handler")]
+			#[must_use]
+			pub const fn with_oom_handler(mut self, handler: $crate::OomHandler) -> Self {
+				self.$inner.0.oom_handler = core::cell::Cell::new(Some(handler));
+				self
+			}
+
+			/// Returns this allocator with every allocation surrounded by guard blocks. This requires the
+			/// `canaries` feature. See `Stalloc::with_canaries()` for details.
+			#[cfg(feature = "canaries")]
+			#[must_use]
+			pub const fn with_canaries(mut self) -> Self {
+				self.$inner.0.canaries = true;
+				self
+			}
+
+			/// Checks if the allocator is completely out of memory.
+			/// If this is false, then you are guaranteed to be able to allocate
+			/// a layout with a size and alignment of `B` bytes.
+			/// This runs in O(1).
+			pub fn is_oom(&self) -> bool {
+				self.$lock().is_oom()
+			}
+
+			/// Checks if the allocator is empty.
+			/// If this is true, then you are guaranteed to be able to allocate
+			/// a layout with a size of `B * L` bytes and an alignment of `B` bytes.
+			/// If this is false, then this is guaranteed to be impossible.
+			/// This runs in O(1).
+			pub fn is_empty(&self) -> bool {
+				self.$lock().is_empty()
+			}
+
+			/// # Safety
+			///
+			/// Calling this function immediately invalidates all pointers into the allocator. Calling
+			/// `deallocate_blocks()` with an invalidated pointer will result in the free list being corrupted.
+			pub unsafe fn clear(&self) {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().clear() }
+			}
+
+			/// Returns the number of live allocations. See `Stalloc::live_allocations()` for details.
+			pub fn live_allocations(&self) -> usize {
+				self.$lock().live_allocations()
+			}
+
+			/// Resets the allocator, but only if it is empty. Returns whether the allocator was reset.
+			/// See `Stalloc::try_clear()` for details.
+			pub fn try_clear(&self) -> bool {
+				self.$lock().try_clear()
+			}
+
+			/// Registers a function that is called whenever an allocation made through `GlobalAlloc` fails.
+			/// This requires the `oom-handler` feature. See `Stalloc::set_oom_handler()` for details.
+			#[cfg(feature = "oom-handler")]
+			pub fn set_oom_handler(&self, handler: Option<$crate::OomHandler>) {
+				self.$lock().set_oom_handler(handler);
+			}
+
+			/// Tries to allocate `count` blocks. If the allocation succeed, a pointer is returned. This function
+			/// never allocates more than necessary.
+			///
+			/// # Safety
+			///
+			/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+			///
+			/// # Errors
+			///
+			/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+			pub unsafe fn allocate_blocks(
+				&self,
+				size: usize,
+				align: usize,
+			) -> Result<core::ptr::NonNull<u8>, $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().allocate_blocks(size, align) }
+			}
+
+			/// Tries to allocate `size` blocks, and fills them with zeros.
+			/// See `Stalloc::allocate_blocks_zeroed()` for details.
+			///
+			/// # Safety
+			///
+			/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+			///
+			/// # Errors
+			///
+			/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+			pub unsafe fn allocate_blocks_zeroed(
+				&self,
+				size: usize,
+				align: usize,
+			) -> Result<core::ptr::NonNull<u8>, $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().allocate_blocks_zeroed(size, align) }
+			}
+
+			/// Deallocates a pointer.
+			///
+			/// # Safety
+			///
+			/// `ptr` must point to an allocation, and `size` must be the number of blocks
+			/// in the allocation. That is, `size` is always in `1..=L`.
+			pub unsafe fn deallocate_blocks(&self, ptr: core::ptr::NonNull<u8>, size: usize) {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().deallocate_blocks(ptr, size) }
+			}
+
+			/// Shrinks the allocation. This function always succeeds and never reallocates.
+			///
+			/// # Safety
+			///
+			/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size` must be in `1..old_size`.
+			pub unsafe fn shrink_in_place(
+				&self,
+				ptr: core::ptr::NonNull<u8>,
+				old_size: usize,
+				new_size: usize,
+			) {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().shrink_in_place(ptr, old_size, new_size) };
+			}
+
+			/// Tries to grow the current allocation in-place. If that isn't possible, this function is a no-op.
+			///
+			/// # Safety
+			///
+			/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+			///
+			/// # Errors
+			///
+			/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+			pub unsafe fn grow_in_place(
+				&self,
+				ptr: core::ptr::NonNull<u8>,
+				old_size: usize,
+				new_size: usize,
+			) -> Result<(), $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().grow_in_place(ptr, old_size, new_size) }
+			}
+
+			/// Resizes an allocation to `new_size` blocks aligned to `align` blocks, moving it if it can't be
+			/// resized in place. See `Stalloc::reallocate_blocks()` for details.
+			///
+			/// # Safety
+			///
+			/// `ptr` must point to a valid allocation of `old_size` blocks. `new_size` must be nonzero,
+			/// and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+			///
+			/// # Errors
+			///
+			/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+			/// in which case this function was a no-op and `ptr` is still valid.
+			pub unsafe fn reallocate_blocks(
+				&self,
+				ptr: core::ptr::NonNull<u8>,
+				old_size: usize,
+				new_size: usize,
+				align: usize,
+			) -> Result<core::ptr::NonNull<u8>, $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe {
+					self.$lock()
+						.reallocate_blocks(ptr, old_size, new_size, align)
+				}
+			}
+
+			/// Returns a snapshot of the allocator's usage counters. This requires the `stats` feature.
+			#[cfg(feature = "stats")]
+			pub fn stats(&self) -> $crate::Stats {
+				self.$lock().stats()
+			}
+
+			/// Returns a snapshot of the allocator's memory usage. This runs in O(n), where n is the number of free chunks.
+			pub fn report(&self) -> $crate::AllocReport {
+				self.$lock().report()
+			}
+
+			/// Walks the free list and checks that it is consistent. See `Stalloc::validate()` for details.
+			///
+			/// # Errors
+			///
+			/// Returns a `CorruptionError` describing the first inconsistency that was found.
+			pub fn validate(&self) -> Result<(), $crate::CorruptionError> {
+				self.$lock().validate()
+			}
+
+			/// Checks that every allocation has been freed. See `Stalloc::check_leaks()` for details.
+			///
+			/// # Errors
+			///
+			/// Returns a `LeakError` with the number of allocations that are still live.
+			#[cfg(feature = "leak-check")]
+			pub fn check_leaks(&self) -> Result<(), $crate::LeakError> {
+				self.$lock().check_leaks()
+			}
+
+			/// Fails a debug assertion if any allocation is still live. See `Stalloc::debug_assert_no_leaks()` for details.
+			///
+			/// # Panics
+			///
+			/// Panics in debug builds if `check_leaks()` fails.
+			#[cfg(feature = "leak-check")]
+			pub fn debug_assert_no_leaks(&self) {
+				self.$lock().debug_assert_no_leaks();
+			}
+
+			/// Creates a new `AllocChain` containing this allocator and `next`.
+			pub const fn chain<T>(self, next: &T) -> $crate::AllocChain<'_, Self, T>
+			where
+				Self: Sized,
+			{
+				$crate::AllocChain::new(self, next)
+			}
+
+			/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+			pub const fn chain_owned<T>(self, next: T) -> $crate::OwnedAllocChain<Self, T>
+			where
+				Self: Sized,
+			{
+				$crate::OwnedAllocChain::new(self, next)
+			}
+		}
+
+		impl<const L: usize, const B: usize $(, $param: $bound)?> Default for $name<L, B $(, $param)?>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<const L: usize, const B: usize $(, $param: $bound)?> core::fmt::Debug for $name<L, B $(, $param)?>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+				write!(f, "{:?}", *self.$lock())
+			}
+		}
+
+		impl<const L: usize, const B: usize $(, $param: $bound)?> core::fmt::Display for $name<L, B $(, $param)?>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+				write!(f, "{}", *self.$lock())
+			}
+		}
+
+		unsafe impl<const L: usize, const B: usize $(, $param: $bound)?> core::alloc::GlobalAlloc
+			for $name<L, B $(, $param)?>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().alloc(layout) }
+			}
+
+			unsafe fn alloc_zeroed(&self, layout: core::alloc::Layout) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().alloc_zeroed(layout) }
+			}
+
+			unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().dealloc(ptr, layout) }
+			}
+
+			unsafe fn realloc(
+				&self,
+				ptr: *mut u8,
+				old_layout: core::alloc::Layout,
+				new_size: usize,
+			) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().realloc(ptr, old_layout, new_size) }
+			}
+		}
+
+		#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+		unsafe impl<const L: usize, const B: usize $(, $param: $bound)?> $crate::Allocator
+			for &$name<L, B $(, $param)?>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn allocate(
+				&self,
+				layout: core::alloc::Layout,
+			) -> Result<core::ptr::NonNull<[u8]>, $crate::AllocError> {
+				(&*self.$lock()).allocate(layout)
+			}
+
+			unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+				// SAFETY: Upheld by the caller.
+				unsafe { (&*self.$lock()).deallocate(ptr, layout) };
+			}
+
+			fn allocate_zeroed(
+				&self,
+				layout: core::alloc::Layout,
+			) -> Result<core::ptr::NonNull<[u8]>, $crate::AllocError> {
+				(&*self.$lock()).allocate_zeroed(layout)
+			}
+
+			unsafe fn grow(
+				&self,
+				ptr: core::ptr::NonNull<u8>,
+				old_layout: core::alloc::Layout,
+				new_layout: core::alloc::Layout,
+			) -> Result<core::ptr::NonNull<[u8]>, $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { (&*self.$lock()).grow(ptr, old_layout, new_layout) }
+			}
+
+			unsafe fn grow_zeroed(
+				&self,
+				ptr: core::ptr::NonNull<u8>,
+				old_layout: core::alloc::Layout,
+				new_layout: core::alloc::Layout,
+			) -> Result<core::ptr::NonNull<[u8]>, $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { (&*self.$lock()).grow_zeroed(ptr, old_layout, new_layout) }
+			}
+
+			unsafe fn shrink(
+				&self,
+				ptr: core::ptr::NonNull<u8>,
+				old_layout: core::alloc::Layout,
+				new_layout: core::alloc::Layout,
+			) -> Result<core::ptr::NonNull<[u8]>, $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe { (&*self.$lock()).shrink(ptr, old_layout, new_layout) }
+			}
+
+			fn by_ref(&self) -> &Self
+			where
+				Self: Sized,
+			{
+				self
+			}
+		}
+
+		unsafe impl<const L: usize, const B: usize $(, $param: $bound)?> $crate::ChainableAlloc
+			for $name<L, B $(, $param)?>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn addr_in_bounds(&self, addr: usize) -> bool {
+				self.$inner.addr_in_bounds(addr)
+			}
+
+			fn chain_aware(&self) -> bool {
+				true
+			}
+
+			unsafe fn alloc_in_chain(&self, layout: core::alloc::Layout, zeroed: bool) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().alloc_in_chain(layout, zeroed) }
+			}
+
+			unsafe fn realloc_in_chain(
+				&self,
+				ptr: *mut u8,
+				old_layout: core::alloc::Layout,
+				new_size: usize,
+			) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.$lock().realloc_in_chain(ptr, old_layout, new_size) }
+			}
+
+			unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+				// SAFETY: Upheld by the caller.
+				unsafe { core::alloc::GlobalAlloc::dealloc(self, ptr, layout) };
+			}
+		}
+	};
+}
+pub(crate) use locked_stalloc_api;
@@ -0,0 +1,57 @@
+use crate::align::{Align, Alignment};
+use crate::{OOM_MARKER, Stalloc, Strategy};
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Checks whether the free list consists of a single chunk of `length` blocks at `idx`.
+	fn is_only_free_chunk(&self, idx: usize, length: usize) -> bool {
+		unsafe {
+			let base = *self.base.get();
+			let chunk = *self.header_at(idx);
+
+			base.length != OOM_MARKER
+				&& usize::from(base.next) == idx
+				&& chunk.next == 0
+				&& usize::from(chunk.length) == length
+		}
+	}
+
+	pub(crate) fn log_alloc(&self, size: usize) {
+		if self.is_oom() {
+			log::warn!("stalloc: out of memory after allocating {size} blocks of {B} bytes");
+		}
+	}
+
+	pub(crate) fn log_alloc_failed(&self, size: usize, align: usize) {
+		log::warn!(
+			"stalloc: failed to allocate {size} blocks of {B} bytes with an alignment of {align} blocks{}",
+			if self.is_oom() {
+				" (out of memory)"
+			} else {
+				""
+			}
+		);
+	}
+
+	pub(crate) fn log_dealloc(&self, idx: usize, size: usize) {
+		// If the freed blocks are the only free chunk, the allocator was out of memory before.
+		if self.is_only_free_chunk(idx - self.guard_blocks(), size + 2 * self.guard_blocks()) {
+			log::info!("stalloc: no longer out of memory after freeing {size} blocks");
+		}
+	}
+
+	pub(crate) fn log_resize(&self, idx: usize, old_size: usize, new_size: usize) {
+		if new_size > old_size && self.is_oom() {
+			log::warn!("stalloc: out of memory after growing an allocation to {new_size} blocks");
+		} else if new_size < old_size
+			&& self.is_only_free_chunk(idx + new_size + self.guard_blocks(), old_size - new_size)
+		{
+			log::info!(
+				"stalloc: no longer out of memory after shrinking an allocation to {new_size} blocks"
+			);
+		}
+	}
+}
@@ -0,0 +1,231 @@
+/// Formats a string into a `Stalloc`, like `format!`. This works on stable Rust and without `std`.
+///
+/// Evaluates to a `Result<&mut str, core::fmt::Error>`, which is an error if the allocator ran out of
+/// memory or if a formatting trait returned an error. The string is built with an `ArenaWriter`, so
+/// like the strings returned by `alloc_str()`, it lives until the allocator is cleared or dropped.
+///
+/// # Examples
+/// ```
+/// use stalloc::{Stalloc, format_in};
+///
+/// let alloc = Stalloc::<16, 4>::new();
+///
+/// let x = 42;
+/// let s = format_in!(&alloc, "x={}", x).unwrap();
+/// assert_eq!(s, "x=42");
+///
+/// // A string that doesn't fit is an error.
+/// assert!(format_in!(&alloc, "{:100}", x).is_err());
+/// ```
+#[macro_export]
+macro_rules! format_in {
+	($alloc:expr, $($arg:tt)*) => {{
+		let mut writer = $crate::ArenaWriter::new($alloc);
+		match ::core::fmt::Write::write_fmt(&mut writer, ::core::format_args!($($arg)*)) {
+			::core::result::Result::Ok(()) => ::core::result::Result::Ok(writer.finish()),
+			::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+		}
+	}};
+}
+
+/// Creates a `Vec` inside an allocator, like `vec!`. This requires the `allocator-api` or
+/// `allocator-api2` feature, and uses the `Vec` type of the corresponding API.
+///
+/// `vec_in!(alloc, a, b, c)` creates a `Vec` containing the given elements, and `vec_in!(alloc, elem; n)`
+/// creates a `Vec` containing `n` clones of `elem`. Like `vec!`, this panics (or aborts) if the allocator
+/// runs out of memory.
+///
+/// # Examples
+/// ```
+/// # #![feature(allocator_api)]
+/// use stalloc::{Stalloc, vec_in};
+///
+/// let alloc = Stalloc::<16, 4>::new();
+///
+/// let v = vec_in!(&alloc, 1u32, 2, 3);
+/// assert_eq!(v, [1, 2, 3]);
+///
+/// let zeros = vec_in!(&alloc, 0u8; 8);
+/// assert_eq!(zeros.len(), 8);
+/// ```
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+#[macro_export]
+macro_rules! vec_in {
+	($alloc:expr, $elem:expr; $n:expr) => {{
+		let n = $n;
+		let mut v = $crate::__private::Vec::with_capacity_in(n, $alloc);
+		v.resize(n, $elem);
+		v
+	}};
+	($alloc:expr $(, $x:expr)* $(,)?) => {{
+		let mut v = $crate::__private::Vec::with_capacity_in(0 $(+ { let _ = stringify!($x); 1 })*, $alloc);
+		$(v.push($x);)*
+		v
+	}};
+}
+
+/// Builds a chain of any number of allocators, each of which falls back to the next one. The chain owns
+/// every link, so it can be built in a `static` without declaring the links separately.
+///
+/// `alloc_chain!(a, b, c)` evaluates to a chain of the allocators `a`, `b` and `c`, which is a nested
+/// `OwnedAllocChain`. Since its type is hard to write out, `alloc_chain!(type Name = A, B, C)` declares
+/// a type alias for the chain of the allocator types `A`, `B` and `C`.
+///
+/// # Examples
+/// ```
+/// use stalloc::{SyncStalloc, alloc_chain};
+/// use std::alloc::System;
+///
+/// alloc_chain!(type Global = SyncStalloc<1024, 8>, SyncStalloc<8192, 16>, System);
+///
+/// #[global_allocator]
+/// static GLOBAL: Global = alloc_chain!(SyncStalloc::new(), SyncStalloc::new(), System);
+///
+/// fn main() {
+///     let v = vec![1, 2, 3];
+///     let big = vec![0u8; 1_000_000];
+/// }
+/// ```
+#[macro_export]
+macro_rules! alloc_chain {
+	($(#[$attr:meta])* $vis:vis type $name:ident = $first:ty $(, $rest:ty)+ $(,)?) => {
+		$(#[$attr])*
+		$vis type $name = $crate::alloc_chain!(@type $first $(, $rest)+);
+	};
+	(@type $chain:ty) => { $chain };
+	(@type $chain:ty, $next:ty $(, $rest:ty)*) => {
+		$crate::alloc_chain!(@type $crate::OwnedAllocChain<$chain, $next> $(, $rest)*)
+	};
+	(@expr $chain:expr) => { $chain };
+	(@expr $chain:expr, $next:expr $(, $rest:expr)*) => {
+		$crate::alloc_chain!(@expr $crate::OwnedAllocChain::new($chain, $next) $(, $rest)*)
+	};
+	($first:expr $(, $rest:expr)+ $(,)?) => {
+		$crate::alloc_chain!(@expr $first $(, $rest)+)
+	};
+}
+
+/// Names the type of a `Stalloc` that can hold a given number of bytes, working out the number of blocks.
+///
+/// `stalloc_for_bytes!(bytes, align = B)` is `Stalloc<L, B>`, where `L` is `bytes / B` rounded up, so the
+/// arena never holds less than `bytes`. The metadata is stored in addition to that. Another stalloc type
+/// can be named with `stalloc_for_bytes!(SyncStalloc, bytes, align = B)`. If `L` ends up out of range,
+/// creating the allocator fails to compile.
+///
+/// # Examples
+/// ```
+/// use stalloc::{AllocatorStats, SyncStalloc, stalloc_for_bytes};
+///
+/// let alloc = <stalloc_for_bytes!(64 * 1024, align = 8)>::new();
+/// assert_eq!(alloc.capacity_bytes(), 64 * 1024);
+///
+/// // 1000 bytes don't fill a whole number of blocks, so the arena is rounded up.
+/// static GLOBAL: stalloc_for_bytes!(SyncStalloc, 1000, align = 16) = SyncStalloc::new();
+/// assert_eq!(GLOBAL.capacity_bytes(), 1008);
+/// ```
+#[macro_export]
+macro_rules! stalloc_for_bytes {
+	($wrapper:ident, $bytes:expr, align = $b:expr $(,)?) => {
+		$crate::$wrapper<{ usize::div_ceil($bytes, $b) }, { $b }>
+	};
+	($bytes:expr, align = $b:expr $(,)?) => {
+		$crate::stalloc_for_bytes!(Stalloc, $bytes, align = $b)
+	};
+}
+
+/// Declares a `#[global_allocator]` static backed by a stalloc, without having to spell out its type.
+///
+/// `stalloc_global!(L blocks of B)` declares `static GLOBAL: SyncStalloc<L, B>`. The name and visibility can
+/// be chosen with `stalloc_global!(pub static NAME = L blocks of B)`. The following options can be added
+/// after the size, in this order:
+/// - `sync`, `spin` or `unsafe` — the wrapper to use: `SyncStalloc` (the default, which requires the `std`
+///   feature), `SpinStalloc` or `UnsafeStalloc`. With `unsafe`, the program must be single-threaded.
+/// - `fallback = Path` — an allocator to fall back to when the stalloc is full, such as `System`. `Path`
+///   must be a unit struct. Longer chains can be built with `alloc_chain!`.
+/// - `stats` — wraps the allocator in an `AllocCounter`.
+///
+/// # Examples
+/// ```
+/// use stalloc::stalloc_global;
+/// use std::alloc::System;
+///
+/// stalloc_global!(1000 blocks of 8, fallback = System, stats);
+///
+/// fn main() {
+///     let v = vec![0u8; 100_000];
+///     assert!(GLOBAL.live_bytes() >= 100_000);
+/// }
+/// ```
+///
+/// ```
+/// use stalloc::stalloc_global;
+///
+/// stalloc_global!(pub static ALLOC = 1024 blocks of 16, spin);
+///
+/// fn main() {
+///     let v = vec![1, 2, 3];
+///     assert!(!ALLOC.is_empty());
+/// }
+/// ```
+#[macro_export]
+macro_rules! stalloc_global {
+	(@parse $head:tt $size:tt $kind:tt $fb:tt $stats:tt $(,)?) => {
+		$crate::stalloc_global!(@emit $head $size $kind $fb $stats);
+	};
+	(@parse $head:tt $size:tt $kind:tt $fb:tt $stats:tt sync $(, $($rest:tt)*)?) => {
+		$crate::stalloc_global!(@parse $head $size [sync] $fb $stats $($($rest)*)?);
+	};
+	(@parse $head:tt $size:tt $kind:tt $fb:tt $stats:tt spin $(, $($rest:tt)*)?) => {
+		$crate::stalloc_global!(@parse $head $size [spin] $fb $stats $($($rest)*)?);
+	};
+	(@parse $head:tt $size:tt $kind:tt $fb:tt $stats:tt unsafe $(, $($rest:tt)*)?) => {
+		$crate::stalloc_global!(@parse $head $size [unsafe] $fb $stats $($($rest)*)?);
+	};
+	(@parse $head:tt $size:tt $kind:tt $fb:tt $stats:tt fallback = $fallback:path $(, $($rest:tt)*)?) => {
+		$crate::stalloc_global!(@parse $head $size $kind [$fallback] $stats $($($rest)*)?);
+	};
+	(@parse $head:tt $size:tt $kind:tt $fb:tt $stats:tt stats $(, $($rest:tt)*)?) => {
+		$crate::stalloc_global!(@parse $head $size $kind $fb [stats] $($($rest)*)?);
+	};
+	(@emit [$($head:tt)*] [$l:tt $b:tt] [$kind:tt] $fb:tt $stats:tt) => {
+		#[global_allocator]
+		$($head)*: $crate::stalloc_global!(@stats_ty $stats
+			$crate::stalloc_global!(@fb_ty $fb $crate::stalloc_global!(@base_ty $kind $l $b))
+		) = $crate::stalloc_global!(@stats_expr $stats
+			$crate::stalloc_global!(@fb_expr $fb $crate::stalloc_global!(@base_expr $kind))
+		);
+	};
+	(@base_ty sync $l:tt $b:tt) => { $crate::SyncStalloc<$l, $b> };
+	(@base_ty spin $l:tt $b:tt) => { $crate::SpinStalloc<$l, $b> };
+	(@base_ty unsafe $l:tt $b:tt) => { $crate::UnsafeStalloc<$l, $b> };
+	(@base_expr sync) => { $crate::SyncStalloc::new() };
+	(@base_expr spin) => { $crate::SpinStalloc::new() };
+	// SAFETY: The user promised that the program is single-threaded by choosing `unsafe`.
+	(@base_expr unsafe) => { unsafe { $crate::UnsafeStalloc::new() } };
+	(@fb_ty [] $t:ty) => { $t };
+	(@fb_ty [$fallback:path] $t:ty) => { $crate::OwnedAllocChain<$t, $fallback> };
+	(@fb_expr [] $e:expr) => { $e };
+	(@fb_expr [$fallback:path] $e:expr) => { $crate::OwnedAllocChain::new($e, $fallback) };
+	(@stats_ty [] $t:ty) => { $t };
+	(@stats_ty [stats] $t:ty) => { $crate::AllocCounter<$t> };
+	(@stats_expr [] $e:expr) => { $e };
+	(@stats_expr [stats] $e:expr) => { $crate::AllocCounter::new($e) };
+	($l:literal blocks of $b:literal $(, $($opt:tt)*)?) => {
+		$crate::stalloc_global!(static GLOBAL = $l blocks of $b $(, $($opt)*)?);
+	};
+	($(#[$attr:meta])* $vis:vis static $name:ident = $l:literal blocks of $b:literal $(, $($opt:tt)*)?) => {
+		$crate::stalloc_global!(@parse [$(#[$attr])* $vis static $name] [$l $b] [sync] [] [] $($($opt)*)?);
+	};
+}
+
+/// Items used by the exported macros. Not public API.
+#[doc(hidden)]
+pub mod __private {
+	#[cfg(feature = "allocator-api")]
+	extern crate alloc;
+	#[cfg(feature = "allocator-api")]
+	pub use alloc::vec::Vec;
+
+	#[cfg(feature = "allocator-api2")]
+	pub use allocator_api2::vec::Vec;
+}
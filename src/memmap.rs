@@ -0,0 +1,102 @@
+use core::fmt::{self, Display, Formatter, Write};
+
+use crate::align::{Align, Alignment};
+use crate::{FirstFit, Stalloc, Strategy};
+
+/// A compact rendering of which blocks of a `Stalloc` are in use, created by `memory_map()`.
+///
+/// When displayed, every block is drawn as `#` if it is in use or `.` if it is free. If the map is
+/// downsampled with `width()`, each character stands for several blocks and is drawn as `#` if all of
+/// them are in use, `.` if all of them are free, or `+` if only some of them are in use.
+pub struct MemoryMap<'a, const L: usize, const B: usize, S: Strategy = FirstFit>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a Stalloc<L, B, S>,
+	blocks_per_char: usize,
+}
+
+impl<const L: usize, const B: usize, S: Strategy> MemoryMap<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Downsamples the map so that it is at most `width` characters long.
+	///
+	/// # Panics
+	///
+	/// Panics if `width` is 0.
+	#[must_use]
+	pub const fn width(mut self, width: usize) -> Self {
+		assert!(width > 0, "memory map width must be nonzero");
+		self.blocks_per_char = L.div_ceil(width);
+		self
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Display for MemoryMap<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let mut free_chunks = self.alloc.free_chunks().peekable();
+
+		for start in (0..L).step_by(self.blocks_per_char) {
+			let end = (start + self.blocks_per_char).min(L);
+
+			// Count how many blocks in `start..end` are free.
+			let mut free = 0;
+			while let Some(&(idx, length)) = free_chunks.peek() {
+				free += (idx + length).min(end).saturating_sub(idx.max(start));
+
+				// Keep the chunk around if it extends into the next character.
+				if idx + length > end {
+					break;
+				}
+				free_chunks.next();
+			}
+
+			f.write_char(if free == 0 {
+				'#'
+			} else if free == end - start {
+				'.'
+			} else {
+				'+'
+			})?;
+		}
+
+		Ok(())
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns a compact map of which blocks are in use, which can be displayed as a line of text.
+	/// Use `width()` to downsample the map for large allocators.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<12, 4>::new();
+	///
+	/// let ptr1 = unsafe { alloc.allocate_blocks(3, 1) }.unwrap();
+	/// let ptr2 = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(ptr1, 3) };
+	/// # #[cfg(feature = "quarantine")]
+	/// # alloc.flush_quarantine();
+	/// # #[cfg(feature = "free-cache")]
+	/// # alloc.flush_free_cache();
+	///
+	/// assert_eq!(alloc.memory_map().to_string(), "...#####....");
+	/// assert_eq!(alloc.memory_map().width(4).to_string(), ".#+.");
+	/// assert_eq!(alloc.memory_map().width(3).to_string(), "+#.");
+	/// ```
+	pub const fn memory_map(&self) -> MemoryMap<'_, L, B, S> {
+		MemoryMap {
+			alloc: self,
+			blocks_per_char: 1,
+		}
+	}
+}
@@ -0,0 +1,122 @@
+use core::fmt::{self, Display, Formatter};
+
+use crate::AllocatorStats;
+
+/// Renders the statistics of an allocator in the Prometheus text exposition format, so that they can be
+/// served from a `/metrics` endpoint. This requires the `std` feature.
+///
+/// Every metric name starts with a prefix, which is `stalloc` by default. Counters that are only
+/// available with the `stats` feature are left out without it.
+///
+/// # Examples
+/// ```
+/// use stalloc::{AllocatorStats, Stalloc};
+///
+/// let alloc = Stalloc::<20, 4>::new();
+/// unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+///
+/// let text = alloc.metrics().with_prefix("arena").to_string();
+/// assert!(text.contains("# TYPE arena_used_bytes gauge\narena_used_bytes 20\n"));
+/// ```
+pub struct PrometheusMetrics<'a, A: ?Sized> {
+	alloc: &'a A,
+	prefix: &'a str,
+}
+
+impl<'a, A: AllocatorStats + ?Sized> PrometheusMetrics<'a, A> {
+	/// Creates an adapter that renders the statistics of `alloc`, with the prefix `stalloc`.
+	pub const fn new(alloc: &'a A) -> Self {
+		Self {
+			alloc,
+			prefix: "stalloc",
+		}
+	}
+
+	/// Sets the prefix of every metric name, which must be a valid Prometheus metric name.
+	#[must_use]
+	pub const fn with_prefix(self, prefix: &'a str) -> Self {
+		Self { prefix, ..self }
+	}
+
+	/// Writes a single metric, with its help text and type.
+	fn write_metric(
+		&self,
+		f: &mut Formatter<'_>,
+		name: &str,
+		kind: &str,
+		help: &str,
+		value: usize,
+	) -> fmt::Result {
+		let prefix = self.prefix;
+		writeln!(f, "# HELP {prefix}_{name} {help}")?;
+		writeln!(f, "# TYPE {prefix}_{name} {kind}")?;
+		writeln!(f, "{prefix}_{name} {value}")
+	}
+}
+
+impl<A: AllocatorStats + ?Sized> Display for PrometheusMetrics<'_, A> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let alloc = self.alloc;
+
+		self.write_metric(
+			f,
+			"capacity_bytes",
+			"gauge",
+			"The total number of bytes managed by the allocator.",
+			alloc.capacity_bytes(),
+		)?;
+		self.write_metric(
+			f,
+			"used_bytes",
+			"gauge",
+			"The number of bytes that are currently in use.",
+			alloc.used_bytes(),
+		)?;
+		self.write_metric(
+			f,
+			"free_bytes",
+			"gauge",
+			"The number of bytes that are currently free.",
+			alloc.free_bytes(),
+		)?;
+
+		if let Some(peak) = alloc.peak_used_bytes() {
+			self.write_metric(
+				f,
+				"peak_used_bytes",
+				"gauge",
+				"The highest number of bytes that were ever in use at the same time.",
+				peak,
+			)?;
+		}
+		if let Some(allocations) = alloc.allocations() {
+			self.write_metric(
+				f,
+				"allocations_total",
+				"counter",
+				"The number of successful allocations.",
+				allocations,
+			)?;
+		}
+		if let Some(deallocations) = alloc.deallocations() {
+			self.write_metric(
+				f,
+				"deallocations_total",
+				"counter",
+				"The number of deallocations.",
+				deallocations,
+			)?;
+		}
+		if let Some(failed) = alloc.failed_allocations() {
+			self.write_metric(
+				f,
+				"failed_allocations_total",
+				"counter",
+				"The number of allocations that failed.",
+				failed,
+			)?;
+		}
+
+		Ok(())
+	}
+}
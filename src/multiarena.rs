@@ -0,0 +1,330 @@
+/// Implements the API that is shared by the allocators that spread their allocations over `N` arenas:
+/// `ThreadLocalStalloc` and `BalancedStalloc`.
+///
+/// The allocator must have an `arenas: [SpinStalloc<L, B>; N]` field and a `first_arena()` method that
+/// returns the index of the arena that the next allocation is tried in first. `$first` describes that arena
+/// in the docs of `allocate_blocks()`. Each allocator still defines its own constructors.
+macro_rules! multi_arena_api {
+	($name:ident, $first:literal) => {
+		impl<const L: usize, const B: usize, const N: usize> $name<L, B, N>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			/// Returns the `N` arenas.
+			pub const fn arenas(&self) -> &[$crate::SpinStalloc<L, B>; N] {
+				&self.arenas
+			}
+
+			/// Returns the arena that owns `ptr`, or `None` if `ptr` doesn't point into any arena.
+			pub fn arena_of(&self, ptr: core::ptr::NonNull<u8>) -> Option<&$crate::SpinStalloc<L, B>> {
+				use $crate::ChainableAlloc;
+
+				self.arenas
+					.iter()
+					.find(|arena| arena.addr_in_bounds(ptr.addr().get()))
+			}
+
+			/// Checks if every arena is empty.
+			pub fn is_empty(&self) -> bool {
+				self.arenas.iter().all($crate::SpinStalloc::is_empty)
+			}
+
+			/// Returns the number of live allocations in all arenas.
+			pub fn live_allocations(&self) -> usize {
+				self.arenas
+					.iter()
+					.map($crate::SpinStalloc::live_allocations)
+					.sum()
+			}
+
+			#[doc = concat!("Tries to allocate `size` blocks from ", $first, ", and then from the other arenas.")]
+			///
+			/// # Safety
+			///
+			/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+			///
+			/// # Errors
+			///
+			/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+			pub unsafe fn allocate_blocks(
+				&self,
+				size: usize,
+				align: usize,
+			) -> Result<core::ptr::NonNull<u8>, $crate::AllocError> {
+				let first = self.first_arena();
+
+				(0..N)
+					.map(|i| &self.arenas[(first + i) % N])
+					// SAFETY: Upheld by the caller.
+					.find_map(|arena| unsafe { arena.allocate_blocks(size, align) }.ok())
+					.ok_or($crate::AllocError)
+			}
+
+			/// Deallocates a pointer, returning it to the arena that owns it.
+			///
+			/// # Safety
+			///
+			/// `ptr` must point to an allocation, and `size` must be the number of blocks
+			/// in the allocation. That is, `size` is always in `1..=L`.
+			pub unsafe fn deallocate_blocks(&self, ptr: core::ptr::NonNull<u8>, size: usize) {
+				// SAFETY: `ptr` was allocated by one of the arenas.
+				let arena = unsafe { self.owner(ptr) };
+
+				// SAFETY: Upheld by the caller.
+				unsafe { arena.deallocate_blocks(ptr, size) };
+			}
+
+			/// Creates a new `AllocChain` containing this allocator and `next`.
+			pub const fn chain<T>(self, next: &T) -> $crate::AllocChain<'_, Self, T>
+			where
+				Self: Sized,
+			{
+				$crate::AllocChain::new(self, next)
+			}
+
+			/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+			pub const fn chain_owned<T>(self, next: T) -> $crate::OwnedAllocChain<Self, T>
+			where
+				Self: Sized,
+			{
+				$crate::OwnedAllocChain::new(self, next)
+			}
+
+			/// Returns the arena that owns `ptr`.
+			///
+			/// # Safety
+			///
+			/// `ptr` must point into one of the arenas.
+			unsafe fn owner(&self, ptr: core::ptr::NonNull<u8>) -> &$crate::SpinStalloc<L, B> {
+				// SAFETY: Upheld by the caller.
+				unsafe { self.arena_of(ptr).unwrap_unchecked() }
+			}
+
+			/// Resizes an allocation from `old_size` to `new_size` blocks, moving it to any arena if necessary.
+			///
+			/// # Safety
+			///
+			/// `ptr` must point to a valid allocation of `old_size` blocks aligned to `align` blocks,
+			/// and `new_size` must be nonzero.
+			unsafe fn resize(
+				&self,
+				ptr: core::ptr::NonNull<u8>,
+				old_size: usize,
+				new_size: usize,
+				align: usize,
+			) -> Result<core::ptr::NonNull<u8>, $crate::AllocError> {
+				// SAFETY: Upheld by the caller.
+				unsafe {
+					let arena = self.owner(ptr);
+
+					if new_size < old_size {
+						arena.shrink_in_place(ptr, old_size, new_size);
+						return Ok(ptr);
+					}
+
+					if new_size == old_size || arena.grow_in_place(ptr, old_size, new_size).is_ok() {
+						return Ok(ptr);
+					}
+
+					let new_ptr = self.allocate_blocks(new_size, align)?;
+					ptr.copy_to_nonoverlapping(new_ptr, old_size * B);
+					arena.deallocate_blocks(ptr, old_size);
+					Ok(new_ptr)
+				}
+			}
+		}
+
+		impl<const L: usize, const B: usize, const N: usize> Default for $name<L, B, N>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<const L: usize, const B: usize, const N: usize> core::fmt::Debug for $name<L, B, N>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+				f.debug_list().entries(&self.arenas).finish()
+			}
+		}
+
+		unsafe impl<const L: usize, const B: usize, const N: usize> core::alloc::GlobalAlloc
+			for $name<L, B, N>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+				let size = layout.size().div_ceil(B);
+				let align = layout.align().div_ceil(B);
+
+				// SAFETY: `size` and `align` are valid.
+				unsafe { self.allocate_blocks(size, align) }
+					.map_or(core::ptr::null_mut(), core::ptr::NonNull::as_ptr)
+			}
+
+			unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+				let size = layout.size().div_ceil(B);
+
+				// SAFETY: Upheld by the caller.
+				unsafe { self.deallocate_blocks(core::ptr::NonNull::new_unchecked(ptr), size) };
+			}
+
+			unsafe fn realloc(
+				&self,
+				ptr: *mut u8,
+				old_layout: core::alloc::Layout,
+				new_size: usize,
+			) -> *mut u8 {
+				let old_size = old_layout.size().div_ceil(B);
+				let align = old_layout.align().div_ceil(B);
+
+				// SAFETY: Upheld by the caller.
+				unsafe {
+					self.resize(
+						core::ptr::NonNull::new_unchecked(ptr),
+						old_size,
+						new_size.div_ceil(B),
+						align,
+					)
+				}
+				.map_or(core::ptr::null_mut(), core::ptr::NonNull::as_ptr)
+			}
+		}
+
+		unsafe impl<const L: usize, const B: usize, const N: usize> $crate::ChainableAlloc
+			for $name<L, B, N>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn addr_in_bounds(&self, addr: usize) -> bool {
+				self.arenas
+					.iter()
+					.any(|arena| $crate::ChainableAlloc::addr_in_bounds(arena, addr))
+			}
+
+			/// `GlobalAlloc` already returns a null pointer when every arena is full.
+			fn chain_aware(&self) -> bool {
+				true
+			}
+
+			unsafe fn alloc_in_chain(&self, layout: core::alloc::Layout, zeroed: bool) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe {
+					if zeroed {
+						core::alloc::GlobalAlloc::alloc_zeroed(self, layout)
+					} else {
+						core::alloc::GlobalAlloc::alloc(self, layout)
+					}
+				}
+			}
+
+			unsafe fn realloc_in_chain(
+				&self,
+				ptr: *mut u8,
+				layout: core::alloc::Layout,
+				new_size: usize,
+			) -> *mut u8 {
+				// SAFETY: Upheld by the caller.
+				unsafe { core::alloc::GlobalAlloc::realloc(self, ptr, layout, new_size) }
+			}
+
+			unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+				// SAFETY: Upheld by the caller.
+				unsafe { core::alloc::GlobalAlloc::dealloc(self, ptr, layout) };
+			}
+		}
+
+		impl<const L: usize, const B: usize, const N: usize> $crate::AllocatorStats for $name<L, B, N>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn capacity_bytes(&self) -> usize {
+				L * B * N
+			}
+
+			fn used_bytes(&self) -> usize {
+				self.arenas
+					.iter()
+					.map($crate::AllocatorStats::used_bytes)
+					.sum()
+			}
+
+			fn peak_used_bytes(&self) -> Option<usize> {
+				self.arenas
+					.iter()
+					.map($crate::AllocatorStats::peak_used_bytes)
+					.sum()
+			}
+
+			fn allocations(&self) -> Option<usize> {
+				self.arenas
+					.iter()
+					.map($crate::AllocatorStats::allocations)
+					.sum()
+			}
+
+			fn deallocations(&self) -> Option<usize> {
+				self.arenas
+					.iter()
+					.map($crate::AllocatorStats::deallocations)
+					.sum()
+			}
+
+			fn failed_allocations(&self) -> Option<usize> {
+				self.arenas
+					.iter()
+					.map($crate::AllocatorStats::failed_allocations)
+					.sum()
+			}
+		}
+
+		#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+		unsafe impl<const L: usize, const B: usize, const N: usize> $crate::Allocator
+			for &$name<L, B, N>
+		where
+			$crate::Align<B>: $crate::Alignment,
+		{
+			fn allocate(
+				&self,
+				layout: core::alloc::Layout,
+			) -> Result<core::ptr::NonNull<[u8]>, $crate::AllocError> {
+				// We can only allocate memory in units of `B`, so round up.
+				let size = layout.size().div_ceil(B);
+				let align = layout.align().div_ceil(B);
+
+				// If `size` is zero, give away a dangling pointer.
+				if size == 0 {
+					let dangling = core::ptr::NonNull::new(layout.align() as _).unwrap();
+					return Ok(core::ptr::NonNull::slice_from_raw_parts(dangling, 0));
+				}
+
+				// SAFETY: We have made sure that `size` and `align` are valid.
+				unsafe { self.allocate_blocks(size, align) }
+					.map(|p| core::ptr::NonNull::slice_from_raw_parts(p, size * B))
+			}
+
+			unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+				let size = layout.size().div_ceil(B);
+
+				if size == 0 {
+					return;
+				}
+
+				// SAFETY: We just made sure that size != 0. Everything else is upheld by the caller.
+				unsafe { self.deallocate_blocks(ptr, size) };
+			}
+
+			fn by_ref(&self) -> &Self
+			where
+				Self: Sized,
+			{
+				self
+			}
+		}
+	};
+}
+pub(crate) use multi_arena_api;
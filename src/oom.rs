@@ -0,0 +1,141 @@
+use core::alloc::Layout;
+#[cfg(feature = "abort-on-oom")]
+use core::fmt;
+
+#[cfg(feature = "oom-handler")]
+use crate::AllocReport;
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+/// A function that is called when an allocator used through `GlobalAlloc` is about to return a null pointer.
+///
+/// It receives the layout that couldn't be allocated, and a report of the allocator's memory usage.
+///
+/// The handler runs while the allocator is in use (and, for `SyncStalloc`, while its lock is held),
+/// so it must not use the allocator it is attached to. If the allocator is the global allocator,
+/// this means that the handler must not allocate.
+///
+/// This requires the `oom-handler` feature.
+#[cfg(feature = "oom-handler")]
+pub type OomHandler = fn(Layout, &AllocReport);
+
+/// A function that receives the diagnostic dump that is written before aborting on OOM.
+/// This requires the `abort-on-oom` feature.
+///
+/// Like an `OomHandler`, the sink must not use the allocator it is attached to.
+#[cfg(feature = "abort-on-oom")]
+pub type OomSink = fn(fmt::Arguments);
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Registers a function that is called whenever an allocation made through `GlobalAlloc` fails,
+	/// replacing any previously registered handler. Pass `None` to remove it.
+	/// This only affects `UnsafeStalloc` and `SyncStalloc`, since `Stalloc` can't be used as a global allocator.
+	/// This requires the `oom-handler` feature.
+	#[cfg(feature = "oom-handler")]
+	pub fn set_oom_handler(&self, handler: Option<OomHandler>) {
+		self.oom_handler.set(handler);
+	}
+
+	/// Returns this allocator with `handler` registered as its OOM handler. See `set_oom_handler()` for details.
+	/// This requires the `oom-handler` feature.
+	#[cfg(feature = "oom-handler")]
+	#[must_use]
+	pub const fn with_oom_handler(mut self, handler: OomHandler) -> Self {
+		self.oom_handler = core::cell::Cell::new(Some(handler));
+		self
+	}
+
+	/// Registers a function that receives the diagnostic dump written when an allocation made through
+	/// `GlobalAlloc` fails, just before the process is aborted. Pass `None` to remove it.
+	/// This requires the `abort-on-oom` feature.
+	///
+	/// Without a sink, the dump is written to stderr if the `std` feature is enabled, and discarded otherwise.
+	#[cfg(feature = "abort-on-oom")]
+	pub fn set_oom_sink(&self, sink: Option<OomSink>) {
+		self.oom_sink.set(sink);
+	}
+
+	/// Returns this allocator with `sink` registered as its OOM sink. See `set_oom_sink()` for details.
+	/// This requires the `abort-on-oom` feature.
+	#[cfg(feature = "abort-on-oom")]
+	#[must_use]
+	pub const fn with_oom_sink(mut self, sink: OomSink) -> Self {
+		self.oom_sink = core::cell::Cell::new(Some(sink));
+		self
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Called when an allocation of `layout` through `GlobalAlloc` failed.
+	/// With the `abort-on-oom` feature, this never returns.
+	#[allow(clippy::unused_self, clippy::missing_const_for_fn)]
+	pub(crate) fn on_global_oom(&self, layout: Layout) {
+		#[cfg(feature = "oom-handler")]
+		if let Some(handler) = self.oom_handler.get() {
+			handler(layout, &self.report());
+		}
+
+		#[cfg(feature = "abort-on-oom")]
+		self.abort_on_oom(layout);
+
+		#[cfg(not(any(feature = "oom-handler", feature = "abort-on-oom")))]
+		let _ = layout;
+	}
+
+	/// Writes the state of the allocator to the OOM sink, and aborts.
+	#[cfg(feature = "abort-on-oom")]
+	fn abort_on_oom(&self, layout: Layout) -> ! {
+		let sink = self.oom_sink.get().unwrap_or(default_sink);
+		sink(format_args!(
+			"memory allocation of {} bytes (alignment {}) failed\n{self:?}\n{:?}",
+			layout.size(),
+			layout.align(),
+			self.report(),
+		));
+
+		#[cfg(feature = "stats")]
+		sink(format_args!("{:?}", self.stats()));
+
+		abort()
+	}
+}
+
+/// Writes the OOM dump to stderr, or discards it without the `std` feature.
+#[cfg(feature = "abort-on-oom")]
+#[allow(clippy::missing_const_for_fn)]
+fn default_sink(args: fmt::Arguments) {
+	#[cfg(feature = "std")]
+	{
+		extern crate std;
+		std::eprintln!("{args}");
+	}
+
+	#[cfg(not(feature = "std"))]
+	let _ = args;
+}
+
+/// Aborts the process.
+#[cfg(feature = "abort-on-oom")]
+fn abort() -> ! {
+	#[cfg(feature = "std")]
+	{
+		extern crate std;
+		std::process::abort()
+	}
+
+	// A panic can't unwind out of an `extern "C"` function, so this aborts even with `panic = "unwind"`.
+	#[cfg(not(feature = "std"))]
+	{
+		extern "C" fn abort_by_panic() -> ! {
+			panic!("aborting after an allocation failure")
+		}
+		abort_by_panic()
+	}
+}
@@ -0,0 +1,76 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate `size` blocks that will never be freed, such as the storage of a global singleton.
+	/// Note that `align` is measured in units of `B`. The allocation is made like `allocate_blocks()`,
+	/// and then passed to `leak_blocks()`.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let config = unsafe { alloc.allocate_blocks_permanent(4, 1) }.unwrap();
+	/// assert!(!alloc.is_empty());
+	/// # #[cfg(feature = "tracking")]
+	/// assert_eq!(alloc.iter_allocations().count(), 0);
+	/// ```
+	pub unsafe fn allocate_blocks_permanent(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		unsafe {
+			let ptr = self.allocate_blocks(size, align)?;
+			self.leak_blocks(ptr, size);
+			Ok(ptr)
+		}
+	}
+
+	/// Turns an allocation of `size` blocks into a permanent one, which must never be freed or resized.
+	///
+	/// A permanent allocation is not a live allocation as far as diagnostics are concerned: with the
+	/// `tracking` feature, it is skipped by `iter_allocations()`, `is_allocated()` and `validate()`,
+	/// and `compact()` leaves it where it is. Its blocks are still counted as used.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks. Afterwards, it must not be passed to any
+	/// function of this allocator, except for `clear()`, which frees permanent allocations as well.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+	/// unsafe { alloc.leak_blocks(ptr, 4) };
+	/// # #[cfg(feature = "tracking")]
+	/// assert!(!alloc.is_allocated(ptr));
+	/// ```
+	#[allow(unused_variables)]
+	pub unsafe fn leak_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		self.check_allocation(ptr, size);
+
+		#[cfg(feature = "tracking")]
+		self.track_dealloc((ptr.addr().get() - self.data.get().addr()) / B);
+		#[cfg(feature = "leak-check")]
+		self.permanent.set(self.permanent.get() + 1);
+	}
+}
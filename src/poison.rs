@@ -0,0 +1,65 @@
+use crate::align::{Align, Alignment};
+use crate::{Header, Stalloc, Strategy};
+
+/// The byte that freed blocks are filled with. This requires the `poison` feature.
+///
+/// Every free chunk is filled with this byte, except for the first few bytes of the chunk, which hold
+/// the allocator's metadata. With the `poison-check` feature, the allocator also checks that freed memory
+/// still holds this byte when it is handed out again, and panics if it was written to after being freed.
+///
+/// # Examples
+/// ```
+/// use stalloc::{POISON_BYTE, Stalloc};
+///
+/// let alloc = Stalloc::<10, 4>::new();
+///
+/// let ptr = unsafe { alloc.allocate_blocks(10, 1) }.unwrap();
+/// unsafe { ptr.write_bytes(0, 40) };
+/// unsafe { alloc.deallocate_blocks(ptr, 10) };
+///
+/// // Everything after the metadata in the first block has been poisoned.
+/// assert_eq!(unsafe { ptr.add(4).read() }, POISON_BYTE);
+/// assert_eq!(unsafe { ptr.add(39).read() }, POISON_BYTE);
+/// ```
+pub const POISON_BYTE: u8 = 0xDD;
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Fills `size` blocks starting at index `idx` with `POISON_BYTE`.
+	pub(crate) const fn poison_blocks(&self, idx: usize, size: usize) {
+		unsafe {
+			self.block_at(idx)
+				.cast::<u8>()
+				.write_bytes(POISON_BYTE, size * B);
+		}
+	}
+
+	/// Fills the header at `idx` with `POISON_BYTE`, after its chunk was merged into the one before it.
+	pub(crate) fn poison_header(&self, idx: usize) {
+		unsafe {
+			self.header_at(idx)
+				.cast::<u8>()
+				.write_bytes(POISON_BYTE, size_of::<Header>());
+		}
+	}
+
+	/// Panics unless the `size` blocks starting at index `idx`, which were just allocated, are still poisoned.
+	/// The first block is allowed to contain the header of the free chunk that the blocks were taken from.
+	#[cfg(feature = "poison-check")]
+	pub(crate) fn check_poison(&self, idx: usize, size: usize) {
+		let bytes = unsafe {
+			let start = self.block_at(idx).cast::<u8>().add(size_of::<Header>());
+			core::slice::from_raw_parts(start, size * B - size_of::<Header>())
+		};
+
+		if let Some(offset) = bytes.iter().position(|&byte| byte != POISON_BYTE) {
+			panic!(
+				"stalloc: freed memory at byte {} was written to after being freed",
+				idx * B + size_of::<Header>() + offset
+			);
+		}
+	}
+}
@@ -0,0 +1,136 @@
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy, as_u16};
+
+/// The maximum number of freed allocations that are held back from reuse. This requires the `quarantine` feature.
+pub const QUARANTINE_CAPACITY: usize = 16;
+
+/// A FIFO of freed regions that have not been returned to the free list yet, as `(index, length)` pairs.
+#[derive(Clone, Copy)]
+pub struct Quarantine {
+	regions: [(u16, u16); QUARANTINE_CAPACITY],
+	start: usize,
+	len: usize,
+}
+
+impl Quarantine {
+	pub const fn new() -> Self {
+		Self {
+			regions: [(0, 0); QUARANTINE_CAPACITY],
+			start: 0,
+			len: 0,
+		}
+	}
+
+	/// Adds a region to the back of the queue. If the queue was full, the oldest region is removed and returned.
+	fn push(&mut self, idx: usize, length: usize) -> Option<(usize, usize)> {
+		let oldest = if self.len == QUARANTINE_CAPACITY {
+			self.pop()
+		} else {
+			None
+		};
+
+		let slot = (self.start + self.len) % QUARANTINE_CAPACITY;
+		self.regions[slot] = unsafe { (as_u16(idx), as_u16(length)) };
+		self.len += 1;
+		oldest
+	}
+
+	/// Removes the oldest region from the front of the queue.
+	fn pop(&mut self) -> Option<(usize, usize)> {
+		if self.len == 0 {
+			return None;
+		}
+
+		let (idx, length) = self.regions[self.start];
+		self.start = (self.start + 1) % QUARANTINE_CAPACITY;
+		self.len -= 1;
+		Some((idx.into(), length.into()))
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns the number of blocks that were freed, but are being held back from reuse.
+	/// This requires the `quarantine` feature.
+	///
+	/// With the `quarantine` feature, freed blocks are not returned to the free list immediately. Instead,
+	/// the last `QUARANTINE_CAPACITY` deallocations are kept in a queue, and the oldest one is only
+	/// released when another allocation is freed. This makes it more likely that a use-after-free will
+	/// be caught (for example by the `poison-check` feature) instead of corrupting a newer allocation.
+	/// If an allocation can't be satisfied, the quarantine is flushed and the allocation is retried.
+	/// With the `free-cache` feature, regions that leave the quarantine go to the free cache.
+	///
+	/// Quarantined blocks count as free in `is_oom()` and `is_empty()`, and are reported separately by `report()`.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(ptr, 5) };
+	/// assert_eq!(alloc.quarantined_blocks(), 5);
+	///
+	/// // The freed blocks are not reused right away.
+	/// let ptr2 = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// assert_ne!(ptr, ptr2);
+	///
+	/// alloc.flush_quarantine();
+	/// assert_eq!(alloc.quarantined_blocks(), 0);
+	/// ```
+	pub fn quarantined_blocks(&self) -> usize {
+		let quarantine = unsafe { &*self.quarantine.get() };
+		(0..quarantine.len)
+			.map(|i| {
+				usize::from(quarantine.regions[(quarantine.start + i) % QUARANTINE_CAPACITY].1)
+			})
+			.sum()
+	}
+
+	/// Returns every quarantined block to the free list, making it available for reuse.
+	/// This requires the `quarantine` feature.
+	pub fn flush_quarantine(&self) {
+		while let Some((idx, length)) = unsafe { (*self.quarantine.get()).pop() } {
+			unsafe { self.release_blocks(self.base.get(), idx, length) };
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Puts `length` freed blocks at `idx` into quarantine. If it was full, the oldest quarantined region is
+	/// released, or cached with the `free-cache` feature.
+	pub(crate) fn quarantine_blocks(&self, idx: usize, length: usize) {
+		if let Some((idx, length)) = unsafe { (*self.quarantine.get()).push(idx, length) } {
+			#[cfg(feature = "free-cache")]
+			self.cache_blocks(idx, length);
+			#[cfg(not(feature = "free-cache"))]
+			unsafe {
+				self.release_blocks(self.base.get(), idx, length);
+			}
+		}
+	}
+
+	/// Empties the quarantine without releasing anything, after the allocator was cleared.
+	pub(crate) fn clear_quarantine(&self) {
+		unsafe { *self.quarantine.get() = Quarantine::new() };
+	}
+
+	/// Checks whether no freed blocks are being held back.
+	pub(crate) const fn is_quarantine_empty(&self) -> bool {
+		unsafe { (*self.quarantine.get()).len == 0 }
+	}
+
+	/// Flushes the quarantine, returning whether any blocks were released.
+	pub(crate) fn try_flush_quarantine(&self) -> bool {
+		let was_empty = self.is_quarantine_empty();
+		self.flush_quarantine();
+		!was_empty
+	}
+}
@@ -0,0 +1,92 @@
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Seeds the random number generator that is used to place allocations. This requires the `randomize` feature.
+	///
+	/// Once the allocator is seeded, every allocation is placed in a random free chunk that is large enough,
+	/// at a random (suitably aligned) offset within that chunk, which makes the layout of the heap hard to predict.
+	/// The seed should come from a good source of entropy, such as a hardware RNG. Passing a seed of 0 turns
+	/// randomization off, which is also the initial state, so that the allocator goes back to first-fit placement.
+	///
+	/// Randomized placement makes allocation O(n) even when the first free chunk is large enough,
+	/// and tends to fragment the heap more than first-fit placement.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<1000, 4>::new();
+	/// alloc.set_seed(0x2545_f491_4f6c_dd1d);
+	///
+	/// let ptrs: Vec<_> = (0..10).map(|_| unsafe { alloc.allocate_blocks(10, 1) }.unwrap()).collect();
+	///
+	/// // With first-fit placement, every allocation would directly follow the previous one.
+	/// assert!(ptrs.windows(2).any(|w| unsafe { w[0].add(40) } != w[1]));
+	/// ```
+	pub fn set_seed(&self, seed: u64) {
+		self.rng.set(seed);
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns a random number in `0..n`, or 0 if the allocator hasn't been seeded.
+	fn random_below(&self, n: usize) -> usize {
+		let mut state = self.rng.get();
+		if state == 0 || n <= 1 {
+			return 0;
+		}
+
+		// xorshift64
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		self.rng.set(state);
+
+		#[allow(clippy::cast_possible_truncation)]
+		let n = (state % n as u64) as usize;
+		n
+	}
+
+	/// Returns how many of the free chunks that could hold `size` blocks (including guard blocks)
+	/// aligned to `align` blocks should be skipped, chosen at random.
+	pub(crate) fn random_fits_to_skip(&self, size: usize, align: usize) -> usize {
+		if self.rng.get() == 0 {
+			return 0;
+		}
+
+		let fits = self
+			.free_chunks()
+			.filter(|&(idx, length)| {
+				let spare_front = (unsafe { self.block_at(idx) }.addr() / B + self.guard_blocks())
+					.wrapping_neg() % align;
+				spare_front + size <= length
+			})
+			.count();
+
+		self.random_below(fits)
+	}
+
+	/// Returns a random number of blocks to shift an allocation by, given that there are `slack` spare blocks
+	/// behind it. The result is a multiple of `align`.
+	pub(crate) fn random_offset(&self, slack: usize, align: usize) -> usize {
+		self.random_below(slack / align + 1) * align
+	}
+
+	/// Decides whether a fitting free chunk should be used, or skipped because `fits_to_skip` is nonzero.
+	pub(crate) const fn take_fit(fits_to_skip: &mut usize) -> bool {
+		if *fits_to_skip == 0 {
+			return true;
+		}
+
+		*fits_to_skip -= 1;
+		false
+	}
+}
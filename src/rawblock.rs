@@ -0,0 +1,472 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy, UnsafeStalloc};
+
+/// The raw block API that is shared by `Stalloc`, `UnsafeStalloc` and `SyncStalloc`, so that code which
+/// manages blocks directly can be written once for all of them.
+///
+/// Every method behaves like the inherent method of the same name on `Stalloc`. Since inherent methods
+/// take precedence, calling them on a concrete type is unaffected by this trait being in scope.
+///
+/// # Safety
+///
+/// The methods must behave like the raw block API of `Stalloc`: allocations are made in units of
+/// `BLOCK_SIZE` bytes, every successful allocation is valid and disjoint from all other live allocations,
+/// and resizing an allocation preserves its contents.
+///
+/// # Examples
+/// ```
+/// use stalloc::{RawBlockAllocator, Stalloc, SyncStalloc};
+///
+/// fn grow_or_move<A: RawBlockAllocator>(alloc: &A) -> bool {
+///     unsafe {
+///         let ptr = alloc.allocate_blocks(2, 1).unwrap();
+///         let grown = alloc.grow_in_place(ptr, 2, 4).is_ok();
+///         alloc.deallocate_blocks(ptr, if grown { 4 } else { 2 });
+///         grown
+///     }
+/// }
+///
+/// assert!(grow_or_move(&Stalloc::<8, 4>::new()));
+/// assert!(grow_or_move(&SyncStalloc::<8, 4>::new()));
+/// ```
+pub unsafe trait RawBlockAllocator {
+	/// The size of a block in bytes.
+	const BLOCK_SIZE: usize;
+
+	/// The number of blocks that the allocator manages.
+	const BLOCK_COUNT: usize;
+
+	/// Checks if the allocator is completely out of memory. See `Stalloc::is_oom()` for details.
+	fn is_oom(&self) -> bool;
+
+	/// Checks if the allocator is empty. See `Stalloc::is_empty()` for details.
+	fn is_empty(&self) -> bool;
+
+	/// Tries to allocate `size` blocks aligned to `align` blocks. See `Stalloc::allocate_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / BLOCK_SIZE`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError>;
+
+	/// Tries to allocate `size` blocks aligned to `align` blocks, and fills them with zeros.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / BLOCK_SIZE`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	unsafe fn allocate_blocks_zeroed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError>;
+
+	/// Deallocates a pointer. See `Stalloc::deallocate_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to an allocation, and `size` must be the number of blocks
+	/// in the allocation. That is, `size` is always in `1..=BLOCK_COUNT`.
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize);
+
+	/// Shrinks the allocation. This function always succeeds and never reallocates.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size` must be in `1..old_size`.
+	unsafe fn shrink_in_place(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize);
+
+	/// Shrinks the allocation by releasing its first `old_size - new_size` blocks.
+	/// See `Stalloc::shrink_in_place_front()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size` must be in `1..old_size`.
+	unsafe fn shrink_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> NonNull<u8>;
+
+	/// Tries to grow the current allocation in-place. If that isn't possible, this function is a no-op.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+	unsafe fn grow_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError>;
+
+	/// Tries to grow the current allocation into the free chunk right before it, moving its contents down.
+	/// See `Stalloc::grow_in_place_front()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size > old_size`.
+	/// `align` must be a power of 2 in the range `1..=MAX_ALIGN / BLOCK_SIZE`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+	unsafe fn grow_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError>;
+
+	/// Tries to grow the current allocation in-place, and fills the newly acquired blocks with zeros.
+	/// If that isn't possible, this function is a no-op.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+	unsafe fn grow_zeroed_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError>;
+
+	/// Grows the current allocation in-place by as much as possible, up to `new_size` blocks,
+	/// and returns its new length. See `Stalloc::grow_up_to()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize;
+
+	/// Resizes an allocation, moving it if it can't be resized in place.
+	/// See `Stalloc::reallocate_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. `new_size` must be nonzero,
+	/// and `align` must be a power of 2 in the range `1..=MAX_ALIGN / BLOCK_SIZE`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+	/// in which case this function was a no-op and `ptr` is still valid.
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError>;
+}
+
+unsafe impl<const L: usize, const B: usize, S: Strategy> RawBlockAllocator for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+	const BLOCK_COUNT: usize = L;
+
+	fn is_oom(&self) -> bool {
+		self.is_oom()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.is_empty()
+	}
+
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.allocate_blocks(size, align) }
+	}
+
+	unsafe fn allocate_blocks_zeroed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.allocate_blocks_zeroed(size, align) }
+	}
+
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn shrink_in_place(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.shrink_in_place(ptr, old_size, new_size) };
+	}
+
+	unsafe fn shrink_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> NonNull<u8> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.shrink_in_place_front(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_in_place(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_in_place_front(ptr, old_size, new_size, align) }
+	}
+
+	unsafe fn grow_zeroed_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_zeroed_in_place(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_up_to(ptr, old_size, new_size) }
+	}
+
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.reallocate_blocks(ptr, old_size, new_size, align) }
+	}
+}
+
+unsafe impl<const L: usize, const B: usize> RawBlockAllocator for UnsafeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+	const BLOCK_COUNT: usize = L;
+
+	fn is_oom(&self) -> bool {
+		self.0.is_oom()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.allocate_blocks(size, align) }
+	}
+
+	unsafe fn allocate_blocks_zeroed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.allocate_blocks_zeroed(size, align) }
+	}
+
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn shrink_in_place(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.shrink_in_place(ptr, old_size, new_size) };
+	}
+
+	unsafe fn shrink_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> NonNull<u8> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.shrink_in_place_front(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.grow_in_place(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.grow_in_place_front(ptr, old_size, new_size, align) }
+	}
+
+	unsafe fn grow_zeroed_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.grow_zeroed_in_place(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.grow_up_to(ptr, old_size, new_size) }
+	}
+
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.0.reallocate_blocks(ptr, old_size, new_size, align) }
+	}
+}
+
+#[cfg(feature = "std")]
+unsafe impl<const L: usize, const B: usize> RawBlockAllocator for crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+	const BLOCK_COUNT: usize = L;
+
+	fn is_oom(&self) -> bool {
+		self.is_oom()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.is_empty()
+	}
+
+	unsafe fn allocate_blocks(&self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.allocate_blocks(size, align) }
+	}
+
+	unsafe fn allocate_blocks_zeroed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.allocate_blocks_zeroed(size, align) }
+	}
+
+	unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	unsafe fn shrink_in_place(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.shrink_in_place(ptr, old_size, new_size) };
+	}
+
+	unsafe fn shrink_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> NonNull<u8> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.shrink_in_place_front(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_in_place(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_in_place_front(ptr, old_size, new_size, align) }
+	}
+
+	unsafe fn grow_zeroed_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_zeroed_in_place(ptr, old_size, new_size) }
+	}
+
+	unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.grow_up_to(ptr, old_size, new_size) }
+	}
+
+	unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.reallocate_blocks(ptr, old_size, new_size, align) }
+	}
+}
@@ -0,0 +1,73 @@
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+/// A snapshot of an allocator's memory usage, created by `report()`. All values are measured in blocks.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AllocReport {
+	/// The total number of blocks managed by the allocator (`L`).
+	pub total_blocks: usize,
+	/// The number of blocks that are currently allocated.
+	pub used_blocks: usize,
+	/// The number of blocks that are currently free.
+	pub free_blocks: usize,
+	/// The number of freed blocks that are held back from reuse by the `quarantine` or `free-cache`
+	/// features. They are released before an allocation fails.
+	pub held_blocks: usize,
+	/// The length of the largest free chunk, which is the largest allocation that could currently succeed.
+	pub largest_free_chunk: usize,
+	/// The number of separate free chunks. A high number relative to `free_blocks` indicates fragmentation.
+	pub free_chunks: usize,
+	/// The highest number of blocks that were ever in use at the same time.
+	/// This is only available with the `stats` feature.
+	pub peak_used_blocks: Option<usize>,
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns a snapshot of the allocator's memory usage. This runs in O(n), where n is the number of free chunks.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<100, 4>::new();
+	///
+	/// let ptr1 = unsafe { alloc.allocate_blocks(10, 1) }.unwrap();
+	/// let ptr2 = unsafe { alloc.allocate_blocks(20, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(ptr1, 10) };
+	/// # #[cfg(feature = "quarantine")]
+	/// # alloc.flush_quarantine();
+	/// # #[cfg(feature = "free-cache")]
+	/// # alloc.flush_free_cache();
+	///
+	/// let report = alloc.report();
+	/// assert_eq!(report.used_blocks, 20);
+	/// assert_eq!(report.free_blocks, 80);
+	/// assert_eq!(report.largest_free_chunk, 70);
+	/// assert_eq!(report.free_chunks, 2);
+	/// ```
+	pub fn report(&self) -> AllocReport {
+		let (free_blocks, largest_free_chunk, free_chunks) = self
+			.free_chunks()
+			.fold((0, 0, 0), |(free, largest, count), (_, length)| {
+				(free + length, largest.max(length), count + 1)
+			});
+
+		let held_blocks = self.held_blocks();
+
+		AllocReport {
+			total_blocks: L,
+			used_blocks: L - free_blocks - held_blocks,
+			free_blocks,
+			held_blocks,
+			largest_free_chunk,
+			free_chunks,
+			#[cfg(feature = "stats")]
+			peak_used_blocks: Some(self.stats().peak_used_blocks),
+			#[cfg(not(feature = "stats"))]
+			peak_used_blocks: None,
+		}
+	}
+}
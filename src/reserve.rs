@@ -0,0 +1,128 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Header, OOM_MARKER, Stalloc, Strategy, as_u16, precondition};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new `Stalloc` instance in which the `size` blocks starting at block index `idx`
+	/// are reserved, so they are never handed out. The reserved blocks can be managed by hand (see
+	/// `reserve_blocks()`), and later returned to the allocator with `release_reserved()`.
+	///
+	/// # Panics
+	///
+	/// Panics if `size` is zero or the reserved blocks are out of bounds.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<16, 4>::with_reserved(4, 8);
+	///
+	/// // Only the 8 unreserved blocks are available.
+	/// assert!(unsafe { alloc.allocate_blocks(5, 1) }.is_err());
+	/// assert!(unsafe { alloc.allocate_blocks(4, 1) }.is_ok());
+	/// ```
+	#[must_use]
+	pub const fn with_reserved(idx: usize, size: usize) -> Self {
+		assert!(
+			size >= 1 && size <= L && idx <= L - size,
+			"the reserved blocks must be in bounds"
+		);
+
+		let mut alloc = Self::new();
+		// SAFETY: `size` is at most `L`, which is at most `0xffff`.
+		alloc.reserved = core::cell::Cell::new(unsafe { as_u16(size) });
+		let blocks = alloc.data.get_mut();
+		let base = alloc.base.get_mut();
+		let after = idx + size;
+
+		// SAFETY: Every index and length is at most `L`, which is at most `0xffff`.
+		unsafe {
+			// The blocks before and after the reserved region are free (if there are any).
+			if after < L {
+				blocks[after].header = Header {
+					next: 0,
+					length: as_u16(L - after),
+				};
+			}
+
+			if idx > 0 {
+				blocks[0].header = Header {
+					next: if after < L { as_u16(after) } else { 0 },
+					length: as_u16(idx),
+				};
+			} else if after < L {
+				base.next = as_u16(after);
+			}
+
+			// `base.length` holds the length of the largest free chunk minus 1, or the OOM marker.
+			let largest_free = if idx > L - after { idx } else { L - after };
+			base.length = if largest_free == 0 {
+				OOM_MARKER
+			} else {
+				as_u16(largest_free - 1)
+			};
+		}
+
+		alloc
+	}
+
+	/// Reserves the `size` blocks starting at block index `idx`, if they are all free. Unlike an allocation,
+	/// a reserved region is not tracked, counted, or reported to hooks, and has no guard blocks.
+	/// It stays reserved until it is passed to `release_reserved()`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `size` is zero, if the blocks are out of bounds, or if any of them are in use.
+	/// In that case, this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<16, 4>::new();
+	///
+	/// let region = alloc.reserve_blocks(0, 8).unwrap();
+	/// assert!(unsafe { alloc.allocate_blocks(9, 1) }.is_err());
+	///
+	/// unsafe { alloc.release_reserved(0, 8) };
+	/// assert!(alloc.is_empty());
+	/// ```
+	pub fn reserve_blocks(&self, idx: usize, size: usize) -> Result<NonNull<u8>, AllocError> {
+		if size == 0 || idx + size > L || !self.take_blocks_at(idx, size) {
+			return Err(AllocError);
+		}
+		// SAFETY: `size` is at most `L`, which is at most `0xffff`.
+		self.reserved
+			.set(self.reserved.get() + unsafe { as_u16(size) });
+
+		Ok(unsafe { NonNull::new_unchecked(self.block_at(idx).cast()) })
+	}
+
+	/// Returns the `size` reserved blocks starting at block index `idx` to the allocator.
+	///
+	/// # Safety
+	///
+	/// The blocks must be reserved, either with `with_reserved()` or `reserve_blocks()`. Part of a
+	/// reserved region may be released, as long as every block is only released once.
+	pub unsafe fn release_reserved(&self, idx: usize, size: usize) {
+		// Assert unsafe preconditions.
+		precondition!(
+			size >= 1 && idx + size <= L,
+			"the released blocks must be in bounds"
+		);
+
+		#[cfg(feature = "zero-on-free")]
+		self.wipe_blocks(idx, size);
+		#[cfg(feature = "poison")]
+		self.poison_blocks(idx, size);
+
+		unsafe { self.release_blocks(self.base.get(), idx, size) };
+		// SAFETY: Upheld by the caller.
+		self.reserved
+			.set(self.reserved.get() - unsafe { as_u16(size) });
+	}
+}
@@ -0,0 +1,129 @@
+use core::fmt::{self, Debug, Display, Formatter};
+
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+/// The error returned by `run_scope()` when the number of live allocations changed during the scope.
+///
+/// This means that an allocation made in the scope is still alive (or that one made before it was freed).
+/// In that case, the allocator was not reset.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScopeLeak<R> {
+	/// The value returned by the closure.
+	pub result: R,
+	/// The number of live allocations before the scope.
+	pub live_before: usize,
+	/// The number of live allocations after the scope.
+	pub live_after: usize,
+}
+
+impl<R> Display for ScopeLeak<R> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"scope changed the number of live allocations from {} to {}",
+			self.live_before, self.live_after
+		)
+	}
+}
+
+impl<R: Debug> core::error::Error for ScopeLeak<R> {}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Runs `f`, and then checks that it freed everything it allocated, by comparing the number of live
+	/// allocations before and after. If so, the allocator is reset to its state from before the scope:
+	/// quarantined and cached blocks are returned to the free list, and if the allocator is empty, it is
+	/// cleared. Reserved regions are never released.
+	///
+	/// # Errors
+	///
+	/// Will return `ScopeLeak`, containing the value returned by `f`, if the number of live allocations changed.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let sum = alloc.run_scope(|| {
+	///     let ptr = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	///     unsafe { alloc.deallocate_blocks(ptr, 5) };
+	///     42
+	/// });
+	/// assert_eq!(sum, Ok(42));
+	///
+	/// let leak = alloc.run_scope(|| unsafe { alloc.allocate_blocks(5, 1) }.unwrap());
+	/// assert_eq!(leak.unwrap_err().live_after, 1);
+	/// ```
+	pub fn run_scope<R>(&self, f: impl FnOnce() -> R) -> Result<R, ScopeLeak<R>> {
+		let live_before = self.live_allocations();
+		let result = f();
+		self.end_scope(live_before, result)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Runs `f`, and then checks that it freed everything it allocated. If so, the allocator is reset to its
+	/// state from before the scope. See `Stalloc::run_scope()` for details.
+	///
+	/// The allocator is not locked while `f` runs, so allocations made by other threads in the meantime
+	/// count towards the scope.
+	///
+	/// # Errors
+	///
+	/// Will return `ScopeLeak`, containing the value returned by `f`, if the number of live allocations changed.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	///
+	/// #[global_allocator]
+	/// static GLOBAL: SyncStalloc<1000, 8> = SyncStalloc::new();
+	///
+	/// fn main() {
+	///     for i in 0..100 {
+	///         let len = GLOBAL.run_scope(|| format!("item {i}").len()).unwrap();
+	///         assert!(len >= 6);
+	///     }
+	/// }
+	/// ```
+	pub fn run_scope<R>(&self, f: impl FnOnce() -> R) -> Result<R, ScopeLeak<R>> {
+		let live_before = self.live_allocations();
+		let result = f();
+		self.acquire_locked().end_scope(live_before, result)
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Finishes a scope that started with `live_before` live allocations.
+	fn end_scope<R>(&self, live_before: usize, result: R) -> Result<R, ScopeLeak<R>> {
+		let live_after = self.live_allocations();
+		if live_after != live_before {
+			return Err(ScopeLeak {
+				result,
+				live_before,
+				live_after,
+			});
+		}
+
+		if !self.try_clear() {
+			#[cfg(feature = "quarantine")]
+			self.flush_quarantine();
+			#[cfg(feature = "free-cache")]
+			self.flush_free_cache();
+		}
+
+		Ok(result)
+	}
+}
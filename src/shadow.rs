@@ -0,0 +1,425 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt::{self, Debug, Formatter};
+use core::iter;
+use core::ops::{Deref, Range};
+
+extern crate std;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::align::{Align, Alignment};
+use crate::{
+	AllocChain, ChainableAlloc, OwnedAllocChain, Stalloc, Strategy, SyncStalloc, UnsafeStalloc,
+};
+
+/// An allocator that hands out memory from a single contiguous arena, in units of `BLOCK_SIZE` bytes.
+/// This lets `Shadow` predict whether an allocation should succeed. This requires the `shadow` feature.
+pub trait ArenaAlloc {
+	/// The size of the smallest unit of memory that the allocator hands out.
+	const BLOCK_SIZE: usize;
+
+	/// Returns the range of addresses managed by the allocator.
+	fn arena(&self) -> Range<usize>;
+
+	/// Returns the number of bytes on each side of every allocation that the allocator keeps for itself,
+	/// such as the guard blocks of a `Stalloc` created with `with_canaries()`.
+	fn guard_size(&self) -> usize {
+		0
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> ArenaAlloc for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+
+	fn arena(&self) -> Range<usize> {
+		let start = self.data.get().addr();
+		start..start + L * B
+	}
+
+	fn guard_size(&self) -> usize {
+		self.guard_blocks() * B
+	}
+}
+
+impl<const L: usize, const B: usize> ArenaAlloc for UnsafeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+
+	fn arena(&self) -> Range<usize> {
+		(**self).arena()
+	}
+
+	fn guard_size(&self) -> usize {
+		(**self).guard_size()
+	}
+}
+
+impl<const L: usize, const B: usize> ArenaAlloc for SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	const BLOCK_SIZE: usize = B;
+
+	fn arena(&self) -> Range<usize> {
+		self.acquire_locked().arena()
+	}
+
+	fn guard_size(&self) -> usize {
+		self.acquire_locked().guard_size()
+	}
+}
+
+/// A debugging wrapper that mirrors every operation on an allocator into a simple reference model,
+/// which records the live allocations. This requires the `shadow` feature.
+///
+/// The wrapper panics as soon as the allocator and the model disagree, or the allocator is misused:
+/// - when a returned allocation is misaligned, out of bounds, or overlaps another live allocation,
+/// - when an allocation fails even though the model has a large enough free region,
+/// - when memory that isn't a live allocation is freed or resized, or the wrong layout is passed.
+///
+/// Every allocation in the inner allocator must be made through the wrapper, or the model will be wrong.
+/// The model doesn't allocate, and holds at most `N` live allocations (panicking if there are more).
+/// Each operation takes O(N) time, so this is intended for debugging only.
+///
+/// # Examples
+/// ```
+/// use stalloc::{Shadow, SyncStalloc};
+///
+/// #[global_allocator]
+/// static GLOBAL: Shadow<SyncStalloc<4096, 8>> = Shadow::new(SyncStalloc::new());
+///
+/// fn main() {
+///     let v: Vec<u32> = (0..100).collect();
+///     assert!(GLOBAL.live_allocations() > 0);
+/// }
+/// ```
+pub struct Shadow<A, const N: usize = 1024> {
+	inner: A,
+	model: Mutex<Model<N>>,
+}
+
+/// The live allocations, sorted by address.
+struct Model<const N: usize> {
+	live: [(usize, Layout); N],
+	len: usize,
+}
+
+impl<A, const N: usize> Shadow<A, N> {
+	/// Wraps `inner`, checking every operation against a model of its live allocations.
+	pub const fn new(inner: A) -> Self {
+		Self {
+			inner,
+			model: Mutex::new(Model {
+				live: [(0, Layout::new::<u8>()); N],
+				len: 0,
+			}),
+		}
+	}
+
+	/// Returns the number of live allocations made through this wrapper.
+	pub fn live_allocations(&self) -> usize {
+		self.model().len
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+
+	/// Locks the model. Poisoning is ignored, since every panic happens before the model is modified.
+	fn model(&self) -> MutexGuard<'_, Model<N>> {
+		self.model.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+impl<A: ArenaAlloc, const N: usize> Shadow<A, N> {
+	/// Returns the range of addresses occupied by an allocation of `layout` at `addr`, including padding
+	/// and guard blocks.
+	fn footprint(&self, addr: usize, layout: Layout) -> Range<usize> {
+		let guard = self.inner.guard_size();
+		let size = layout.size().div_ceil(A::BLOCK_SIZE) * A::BLOCK_SIZE;
+		addr - guard..addr + size + guard
+	}
+
+	/// Checks whether the model has room for `layout`.
+	fn has_room(&self, model: &Model<N>, layout: Layout) -> bool {
+		let arena = self.inner.arena();
+		let align = layout.align().max(A::BLOCK_SIZE);
+		let guard = self.inner.guard_size();
+
+		let gaps = model.live[..model.len]
+			.iter()
+			.map(|&(addr, layout)| self.footprint(addr, layout))
+			.chain(iter::once(arena.end..arena.end));
+
+		let mut gap_start = arena.start;
+		for used in gaps {
+			let addr = (gap_start + guard).next_multiple_of(align);
+			if self.footprint(addr, layout).end <= used.start {
+				return true;
+			}
+			gap_start = used.end;
+		}
+
+		false
+	}
+
+	/// Checks a new allocation of `layout` at `addr` against the model, then records it.
+	fn record(&self, addr: usize, layout: Layout) {
+		if layout.size() == 0 {
+			return;
+		}
+
+		let mut model = self.model();
+		let footprint = self.footprint(addr, layout);
+		let arena = self.inner.arena();
+
+		assert!(
+			addr.is_multiple_of(layout.align()),
+			"shadow: allocation at {addr:#x} is not aligned to {} bytes",
+			layout.align()
+		);
+		assert!(
+			arena.start <= footprint.start && footprint.end <= arena.end,
+			"shadow: allocation at {addr:#x} is out of bounds"
+		);
+
+		let i = model.live[..model.len].partition_point(|&(other, _)| other < addr);
+		for &(other, other_layout) in &model.live[i.saturating_sub(1)..(i + 1).min(model.len)] {
+			let other_footprint = self.footprint(other, other_layout);
+			assert!(
+				footprint.end <= other_footprint.start || other_footprint.end <= footprint.start,
+				"shadow: allocation at {addr:#x} overlaps the live allocation at {other:#x}"
+			);
+		}
+
+		assert!(model.len < N, "shadow: more than {N} live allocations");
+		let len = model.len;
+		model.live.copy_within(i..len, i + 1);
+		model.live[i] = (addr, layout);
+		model.len += 1;
+	}
+
+	/// Panics if an allocation of `layout` failed even though the model has room for it.
+	/// This isn't checked with the `quarantine` feature, since quarantined blocks can't be reused right away,
+	/// or with the `bounded-search` feature, since the search may give up early.
+	fn check_failure(&self, layout: Layout) {
+		if cfg!(not(any(feature = "quarantine", feature = "bounded-search"))) {
+			let model = self.model();
+			assert!(
+				!self.has_room(&model, layout),
+				"shadow: failed to allocate {} bytes aligned to {}, but there is a large enough free region",
+				layout.size(),
+				layout.align()
+			);
+		}
+	}
+
+	/// Checks that `addr` is a live allocation of `layout`, and removes it from the model.
+	fn forget(&self, addr: usize, layout: Layout) {
+		if layout.size() == 0 {
+			return;
+		}
+
+		let mut model = self.model();
+		let len = model.len;
+		let i = model.live[..len].partition_point(|&(other, _)| other < addr);
+
+		match model.live[..len].get(i) {
+			Some(&(other, other_layout)) if other == addr => assert!(
+				other_layout == layout,
+				"shadow: freed the allocation at {addr:#x} with {layout:?}, but it was allocated with {other_layout:?}"
+			),
+			_ => panic!("shadow: freed {addr:#x}, which is not a live allocation"),
+		}
+
+		model.live.copy_within(i + 1..len, i);
+		model.len -= 1;
+	}
+
+	/// Handles the result of resizing the allocation of `old_layout` at `addr`, which has been forgotten.
+	fn resized(
+		&self,
+		addr: usize,
+		old_layout: Layout,
+		new_addr: Option<usize>,
+		new_layout: Layout,
+	) {
+		if let Some(new_addr) = new_addr {
+			self.record(new_addr, new_layout);
+		} else {
+			// The old allocation is still live.
+			self.record(addr, old_layout);
+			self.check_failure(new_layout);
+		}
+	}
+}
+
+impl<A, const N: usize> Deref for Shadow<A, N> {
+	type Target = A;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl<A: Debug, const N: usize> Debug for Shadow<A, N> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self.inner)
+	}
+}
+
+unsafe impl<A: GlobalAlloc + ArenaAlloc, const N: usize> GlobalAlloc for Shadow<A, N> {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.inner.alloc(layout) };
+		if ptr.is_null() {
+			self.check_failure(layout);
+		} else {
+			self.record(ptr.addr(), layout);
+		}
+		ptr
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.inner.alloc_zeroed(layout) };
+		if ptr.is_null() {
+			self.check_failure(layout);
+		} else {
+			self.record(ptr.addr(), layout);
+		}
+		ptr
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		self.forget(ptr.addr(), layout);
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.dealloc(ptr, layout) };
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		self.forget(ptr.addr(), layout);
+
+		// SAFETY: Upheld by the caller.
+		let new = unsafe { self.inner.realloc(ptr, layout, new_size) };
+		// SAFETY: `new_size` is valid for `layout.align()`, which is upheld by the caller.
+		let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+		self.resized(
+			ptr.addr(),
+			layout,
+			(!new.is_null()).then_some(new.addr()),
+			new_layout,
+		);
+		new
+	}
+}
+
+unsafe impl<A: ChainableAlloc, const N: usize> ChainableAlloc for Shadow<A, N> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.inner.addr_in_bounds(addr)
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<A: ArenaAlloc, const N: usize> Allocator for &Shadow<A, N>
+where
+	for<'a> &'a A: Allocator,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let res = (&self.inner).allocate(layout);
+		match res {
+			Ok(ptr) => self.record(ptr.addr().get(), layout),
+			Err(_) => self.check_failure(layout),
+		}
+		res
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let res = (&self.inner).allocate_zeroed(layout);
+		match res {
+			Ok(ptr) => self.record(ptr.addr().get(), layout),
+			Err(_) => self.check_failure(layout),
+		}
+		res
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		self.forget(ptr.addr().get(), layout);
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).deallocate(ptr, layout) };
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.forget(ptr.addr().get(), old_layout);
+
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow(ptr, old_layout, new_layout) };
+		let new_addr = res.ok().map(|new| new.addr().get());
+		self.resized(ptr.addr().get(), old_layout, new_addr, new_layout);
+		res
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.forget(ptr.addr().get(), old_layout);
+
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow_zeroed(ptr, old_layout, new_layout) };
+		let new_addr = res.ok().map(|new| new.addr().get());
+		self.resized(ptr.addr().get(), old_layout, new_addr, new_layout);
+		res
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.forget(ptr.addr().get(), old_layout);
+
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).shrink(ptr, old_layout, new_layout) };
+		let new_addr = res.ok().map(|new| new.addr().get());
+		self.resized(ptr.addr().get(), old_layout, new_addr, new_layout);
+		res
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
@@ -0,0 +1,161 @@
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::UnsafeStalloc;
+use crate::align::{Align, Alignment};
+use crate::lockwrap::locked_stalloc_api;
+
+/// The highest number of times `spin_loop()` is called between two attempts to take the lock.
+const MAX_BACKOFF: u32 = 64;
+
+/// A wrapper around `UnsafeStalloc` that prevents data races using a spinlock. Unlike `SyncStalloc`,
+/// it doesn't need `std`, so it can be used as a safe global allocator on bare-metal targets.
+///
+/// A thread that finds the lock taken spins with exponential backoff until it is released. This is
+/// efficient when the lock is only held briefly, as it is by every allocator operation, but a thread that
+/// is preempted while holding the lock makes all other threads spin. Never use a `SpinStalloc` from an
+/// interrupt handler that could interrupt a thread holding the lock, since this would deadlock.
+///
+/// The most common operations are available directly. Every other method of `Stalloc` can be called
+/// through the guard returned by `acquire_locked()`.
+///
+/// # Examples
+/// ```
+/// use stalloc::SpinStalloc;
+///
+/// #[global_allocator]
+/// static GLOBAL: SpinStalloc<1000, 4> = SpinStalloc::new();
+///
+/// fn main() {
+///     let v = vec![1, 2, 3];
+///     assert!(!GLOBAL.is_empty());
+/// }
+/// ```
+pub struct SpinStalloc<const L: usize, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	locked: AtomicBool,
+	inner: UnsafeStalloc<L, B>,
+}
+
+/// A lock around `SpinStalloc`. Constructing this type is proof that the user holds an exclusive
+/// lock on the inner `UnsafeStalloc`. When this falls out of scope, the `SpinStalloc` is unlocked.
+pub struct SpinGuard<'a, const L: usize, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a SpinStalloc<L, B>,
+	_not_sync: PhantomData<*const ()>,
+}
+
+impl<const L: usize, const B: usize> Deref for SpinGuard<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	type Target = UnsafeStalloc<L, B>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.alloc.inner
+	}
+}
+
+impl<const L: usize, const B: usize> Drop for SpinGuard<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		self.alloc.locked.store(false, Ordering::Release);
+	}
+}
+
+// SAFETY: The inner `UnsafeStalloc` is only accessed while the spinlock is held.
+unsafe impl<const L: usize, const B: usize> Sync for SpinStalloc<L, B> where Align<B>: Alignment {}
+
+impl<const L: usize, const B: usize> SpinStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new empty `SpinStalloc` instance.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SpinStalloc;
+	///
+	/// let alloc = SpinStalloc::<200, 8>::new();
+	/// ```
+	#[must_use]
+	pub const fn new() -> Self {
+		// SAFETY: The `UnsafeStalloc` can only be accessed through `acquire_locked()`,
+		// which guarantees that the spinlock is held before proceeding.
+		Self {
+			locked: AtomicBool::new(false),
+			inner: unsafe { UnsafeStalloc::new() },
+		}
+	}
+
+	/// Initializes a new `SpinStalloc` instance in which the `size` blocks starting at block index `idx`
+	/// are reserved. See `Stalloc::with_reserved()` for details.
+	///
+	/// # Panics
+	///
+	/// Panics if `size` is zero or the reserved blocks are out of bounds.
+	#[must_use]
+	pub const fn with_reserved(idx: usize, size: usize) -> Self {
+		// SAFETY: See `new()`.
+		Self {
+			locked: AtomicBool::new(false),
+			inner: unsafe { UnsafeStalloc::with_reserved(idx, size) },
+		}
+	}
+
+	/// Acquires an exclusive lock for the allocator, spinning until it is available. This can be used
+	/// to chain multiple operations on the allocator, or to call methods that `SpinStalloc` doesn't
+	/// provide directly.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SpinStalloc;
+	///
+	/// let alloc = SpinStalloc::<100, 4>::new();
+	///
+	/// let lock = alloc.acquire_locked();
+	/// for _ in 0..20 {
+	///     unsafe { lock.allocate_blocks(5, 1) }.unwrap();
+	/// }
+	/// drop(lock);
+	///
+	/// assert!(alloc.is_oom());
+	/// ```
+	pub fn acquire_locked(&self) -> SpinGuard<'_, L, B> {
+		let mut backoff = 1;
+
+		while self
+			.locked
+			.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+			.is_err()
+		{
+			// Wait until the lock looks free before trying again, so that waiting threads
+			// don't keep invalidating the cache line that holds the lock.
+			while self.locked.load(Ordering::Relaxed) {
+				for _ in 0..backoff {
+					core::hint::spin_loop();
+				}
+				backoff = (backoff * 2).min(MAX_BACKOFF);
+			}
+		}
+
+		SpinGuard {
+			alloc: self,
+			_not_sync: PhantomData,
+		}
+	}
+
+	/// Returns a pointer to the first block. This doesn't need the lock, since the buffer never moves.
+	pub(crate) const fn base_ptr(&self) -> *mut u8 {
+		self.inner.0.data.get().cast()
+	}
+}
+
+locked_stalloc_api!(SpinStalloc<L, B>, inner, acquire_locked);
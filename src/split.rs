@@ -0,0 +1,107 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{Block, Stalloc, Strategy, precondition};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Splits an allocation of `size` blocks in two, so that each part can be resized or deallocated
+	/// on its own. The first part keeps the first `at` blocks, and the second part gets the rest.
+	/// Returns pointers to both parts; the first one is always equal to `ptr`.
+	///
+	/// If the allocator was created with `with_canaries()`, the two parts need guard blocks between them, so the 2 blocks
+	/// after the first part are taken from the second one.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks, and `at` must be in `1..size`
+	/// (or `1..size - 2` with canaries).
+	/// Afterwards, the first part is an allocation of `at` blocks, and the second part is an allocation
+	/// of `size - at` blocks (or `size - at - 2` blocks with canaries).
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(20, 1) }.unwrap();
+	/// let (first, _second) = unsafe { alloc.split_allocation(ptr, 20, 5) };
+	/// assert_eq!(first, ptr);
+	///
+	/// // The parts can be freed separately.
+	/// unsafe { alloc.deallocate_blocks(first, 5) };
+	/// assert!(unsafe { alloc.allocate_blocks(5, 1) }.is_ok());
+	/// ```
+	pub unsafe fn split_allocation(
+		&self,
+		ptr: NonNull<u8>,
+		size: usize,
+		at: usize,
+	) -> (NonNull<u8>, NonNull<u8>) {
+		// Assert unsafe preconditions.
+		precondition!(
+			at >= 1 && at + 2 * self.guard_blocks() < size,
+			"`at` must be in `1..size - 2 * self.guard_blocks()`"
+		);
+		self.check_allocation(ptr, size);
+
+		let curr_block: *mut Block<B> = ptr.as_ptr().cast();
+		let curr_idx = (curr_block.addr() - self.data.get().addr()) / B;
+		self.on_split(curr_idx, size, at);
+
+		// SAFETY: The second part starts inside the allocation.
+		(ptr, unsafe { ptr.add((at + 2 * self.guard_blocks()) * B) })
+	}
+
+	/// Merges two adjacent allocations into a single one, which can then be resized or deallocated as
+	/// a unit. This is the inverse of `split_allocation()`. Returns the size of the merged allocation
+	/// in blocks, which starts at `first`.
+	///
+	/// With canaries, the guard blocks between the two allocations become part of the
+	/// merged allocation, so it is 2 blocks larger than `first_size + second_size`.
+	///
+	/// # Safety
+	///
+	/// `first` must point to a valid allocation of `first_size` blocks, and `second` must point to a valid
+	/// allocation of `second_size` blocks that starts right after it (after the guard blocks of `first`,
+	/// with canaries). Both pointers must be invalidated and replaced by `first`.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let first = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// let second = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// let size = unsafe { alloc.merge_allocations(first, 5, second, 5) };
+	///
+	/// // The merged allocation can be freed at once.
+	/// unsafe { alloc.deallocate_blocks(first, size) };
+	/// assert!(alloc.is_empty());
+	/// ```
+	pub unsafe fn merge_allocations(
+		&self,
+		first: NonNull<u8>,
+		first_size: usize,
+		second: NonNull<u8>,
+		second_size: usize,
+	) -> usize {
+		// Assert unsafe preconditions.
+		precondition!(
+			second.addr().get() == first.addr().get() + (first_size + 2 * self.guard_blocks()) * B,
+			"`second` must start right after `first`"
+		);
+		self.check_allocation(first, first_size);
+		self.check_allocation(second, second_size);
+
+		let curr_block: *mut Block<B> = first.as_ptr().cast();
+		let curr_idx = (curr_block.addr() - self.data.get().addr()) / B;
+		self.on_merge(curr_idx, first_size, second_size);
+
+		first_size + second_size + 2 * self.guard_blocks()
+	}
+}
@@ -0,0 +1,105 @@
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+/// Counters describing how an allocator has been used. This requires the `stats` feature.
+///
+/// All counters start at zero when the allocator is created and wrap around on overflow.
+/// `used_blocks` and `peak_used_blocks` describe the current and highest memory usage instead.
+/// A grow that has to reallocate is counted in `reallocating_grows`, as well as in
+/// `allocations` and `deallocations` for the underlying allocate and free.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Stats {
+	/// The number of successful allocations.
+	pub allocations: usize,
+	/// The number of deallocations.
+	pub deallocations: usize,
+	/// The number of allocations that were grown in place.
+	pub grows_in_place: usize,
+	/// The number of allocations that had to be moved in order to grow.
+	pub reallocating_grows: usize,
+	/// The number of allocations that were shrunk in place.
+	pub shrinks: usize,
+	/// The number of allocations that failed, through any of the allocation functions. This includes
+	/// calls to `allocate_blocks_at()` whose blocks weren't free (but not ones that were out of bounds),
+	/// and a batch from `allocate_many()` that didn't fit counts as one failure.
+	pub failed_allocations: usize,
+	/// The number of blocks that are currently allocated.
+	pub used_blocks: usize,
+	/// The highest number of blocks that were ever allocated at the same time.
+	pub peak_used_blocks: usize,
+}
+
+impl Stats {
+	/// Returns a `Stats` with every counter set to zero.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			allocations: 0,
+			deallocations: 0,
+			grows_in_place: 0,
+			reallocating_grows: 0,
+			shrinks: 0,
+			failed_allocations: 0,
+			used_blocks: 0,
+			peak_used_blocks: 0,
+		}
+	}
+
+	/// Records that `size` more blocks are in use.
+	pub(crate) fn add_used_blocks(&mut self, size: usize) {
+		self.used_blocks += size;
+		self.peak_used_blocks = self.peak_used_blocks.max(self.used_blocks);
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns a snapshot of the allocator's usage counters. This requires the `stats` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// unsafe { alloc.grow_in_place(ptr, 5, 10) }.unwrap();
+	/// assert!(unsafe { alloc.allocate_blocks(11, 1) }.is_err());
+	///
+	/// let stats = alloc.stats();
+	/// assert_eq!(stats.allocations, 1);
+	/// assert_eq!(stats.grows_in_place, 1);
+	/// assert_eq!(stats.failed_allocations, 1);
+	/// ```
+	pub const fn stats(&self) -> Stats {
+		unsafe { *self.stats.get() }
+	}
+
+	/// Resets all usage counters to zero, and the peak usage to the current usage.
+	/// This requires the `stats` feature.
+	pub const fn reset_stats(&self) {
+		unsafe {
+			let used_blocks = (*self.stats.get()).used_blocks;
+			*self.stats.get() = Stats {
+				used_blocks,
+				peak_used_blocks: used_blocks,
+				..Stats::new()
+			};
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Applies `f` to the usage counters.
+	pub(crate) fn update_stats(&self, f: impl FnOnce(&mut Stats)) {
+		f(unsafe { &mut *self.stats.get() });
+		#[cfg(feature = "thresholds")]
+		self.check_thresholds();
+	}
+}
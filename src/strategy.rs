@@ -0,0 +1,155 @@
+use core::cell::Cell;
+
+/// A free chunk that can hold an allocation, as seen by a `Strategy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Candidate {
+	/// The index of the first block of the chunk.
+	pub index: usize,
+	/// The length of the chunk in blocks.
+	pub length: usize,
+	/// The number of blocks that the allocation takes up at the start of the chunk, including padding
+	/// for alignment and guard blocks.
+	pub needed: usize,
+}
+
+/// What a `Strategy` does with a free chunk that can hold an allocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fit {
+	/// Place the allocation in this chunk, and stop searching.
+	Take,
+	/// Remember this chunk instead of the one remembered so far, and keep searching. The remembered
+	/// chunk is used once the free list has been searched.
+	Remember,
+	/// Keep searching.
+	Skip,
+}
+
+/// Decides which free chunk an allocation is placed in, and where in the chunk it goes.
+///
+/// A `Stalloc` walks its free list in address order and asks its strategy about every chunk that can hold
+/// the allocation. Every strategy shares the same free list, so they differ only in which chunk they pick.
+/// `FirstFit` is the default. Allocations that are placed by hand, like `allocate_blocks_at()` and
+/// batches, don't go through the strategy.
+///
+/// # Examples
+/// ```
+/// use stalloc::{Candidate, Fit, Stalloc, Strategy};
+///
+/// /// Places every allocation in the last free chunk that can hold it.
+/// struct LastFit;
+///
+/// impl Strategy for LastFit {
+///     const NEW: Self = LastFit;
+///
+///     fn select(&self, _chunk: Candidate, _best: Option<Candidate>) -> Fit {
+///         Fit::Remember
+///     }
+/// }
+///
+/// let alloc = Stalloc::<16, 4, LastFit>::new();
+/// let a = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+/// let b = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+/// unsafe { alloc.deallocate_blocks(a, 4) };
+/// # #[cfg(feature = "quarantine")]
+/// # alloc.flush_quarantine();
+/// # #[cfg(feature = "free-cache")]
+/// # alloc.flush_free_cache();
+///
+/// // The freed chunk comes first, but the one after `b` is used.
+/// let c = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+/// assert_eq!(c, unsafe { b.add(4 * 4) });
+/// ```
+pub trait Strategy {
+	/// The strategy of a newly created allocator.
+	const NEW: Self;
+
+	/// Decides what to do with `chunk`, given the chunk that was remembered so far.
+	///
+	/// If the end of the free list is reached and no chunk was remembered, the allocation fails.
+	fn select(&self, chunk: Candidate, best: Option<Candidate>) -> Fit;
+
+	/// Returns how many of the `slack` blocks that the allocation doesn't need are placed in front of it,
+	/// which decides how the chunk is split. The result is rounded down to a multiple of `align` and capped
+	/// at `slack`. By default, the allocation goes at the start of the chunk.
+	fn placement(&self, slack: usize, align: usize) -> usize {
+		let _ = (slack, align);
+		0
+	}
+
+	/// Called after an allocation was placed in the `size` blocks starting at block index `index`,
+	/// including its guard blocks.
+	fn placed(&self, index: usize, size: usize) {
+		let _ = (index, size);
+	}
+
+	/// Called after the allocator was emptied with `clear()`, so that the strategy can forget the
+	/// allocations that it has seen.
+	fn reset(&self) {}
+}
+
+/// Places every allocation in the first free chunk that can hold it. This is the default strategy, and
+/// usually the fastest one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FirstFit;
+
+impl Strategy for FirstFit {
+	const NEW: Self = Self;
+
+	fn select(&self, _chunk: Candidate, _best: Option<Candidate>) -> Fit {
+		Fit::Take
+	}
+}
+
+/// Places every allocation in the smallest free chunk that can hold it, which keeps large chunks
+/// available for large allocations. Unless a chunk fits exactly, the whole free list is searched.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BestFit;
+
+impl Strategy for BestFit {
+	const NEW: Self = Self;
+
+	fn select(&self, chunk: Candidate, best: Option<Candidate>) -> Fit {
+		if chunk.length == chunk.needed {
+			Fit::Take
+		} else if best.is_none_or(|best| chunk.length < best.length) {
+			Fit::Remember
+		} else {
+			Fit::Skip
+		}
+	}
+}
+
+/// Places every allocation in the first free chunk that can hold it after the previous allocation.
+///
+/// The search wraps around to the start of the buffer. This spreads allocations over the whole buffer,
+/// instead of crowding the small chunks at its start.
+#[derive(Debug, Default)]
+pub struct NextFit {
+	/// The block index right after the previous allocation.
+	rover: Cell<usize>,
+}
+
+impl Strategy for NextFit {
+	const NEW: Self = Self {
+		rover: Cell::new(0),
+	};
+
+	fn select(&self, chunk: Candidate, best: Option<Candidate>) -> Fit {
+		if chunk.index >= self.rover.get() {
+			Fit::Take
+		} else if best.is_none() {
+			// Chunks before the rover are only used if nothing after it fits.
+			Fit::Remember
+		} else {
+			Fit::Skip
+		}
+	}
+
+	fn placed(&self, index: usize, size: usize) {
+		self.rover.set(index + size);
+	}
+
+	fn reset(&self) {
+		self.rover.set(0);
+	}
+}
@@ -0,0 +1,387 @@
+use core::cell::UnsafeCell;
+use core::fmt::{self, Debug, Formatter};
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment, MAX_ALIGN};
+use crate::{
+	AllocError, CarveParent, ChainableAlloc, Header, OOM_MARKER, Stalloc, Strategy, as_u16,
+	precondition,
+};
+
+/// An allocator whose blocks were carved out of a parent allocator with `carve()`.
+/// When it is dropped, its blocks are returned to the parent.
+///
+/// A `SubStalloc` manages its blocks with the same first-fit algorithm as `Stalloc`, but its size is
+/// chosen at runtime. It doesn't support the debugging features of `Stalloc` (such as `canaries`
+/// or `tracking`), and like `Stalloc`, it is not thread-safe.
+///
+/// # Examples
+/// ```
+/// use stalloc::Stalloc;
+///
+/// let connection = Stalloc::<64, 8>::new();
+///
+/// {
+///     let request = connection.carve(16).unwrap();
+///     let ptr = unsafe { request.allocate_blocks(16, 1) }.unwrap();
+///     assert!(request.is_oom());
+///     assert!(!connection.is_oom());
+/// }
+///
+/// // The carved blocks were returned when `request` was dropped.
+/// assert!(connection.is_empty());
+/// ```
+pub struct SubStalloc<'a, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	parent: &'a dyn CarveParent,
+	start: NonNull<u8>,
+	blocks: usize,
+	base: UnsafeCell<Header>,
+}
+
+impl<const L: usize, const B: usize, S: Strategy> CarveParent for Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	unsafe fn release(&self, ptr: NonNull<u8>, size: usize) {
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> CarveParent for crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	unsafe fn release(&self, ptr: NonNull<u8>, size: usize) {
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Allocates `blocks` contiguous blocks, and wraps them in a `SubStalloc` that can be used
+	/// as an allocator of its own. The blocks are returned to this allocator when the `SubStalloc`
+	/// is dropped.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `blocks` is zero, or if the blocks couldn't be allocated.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<32, 4>::new();
+	/// let sub = alloc.carve(8).unwrap();
+	///
+	/// let ptr = unsafe { sub.allocate_blocks(3, 1) }.unwrap();
+	/// assert!(alloc.carve(32).is_err());
+	/// ```
+	pub fn carve(&self, blocks: usize) -> Result<SubStalloc<'_, B>, AllocError> {
+		if blocks == 0 {
+			return Err(AllocError);
+		}
+
+		// SAFETY: `blocks` is nonzero, and an alignment of 1 is always valid.
+		let start = unsafe { self.allocate_blocks(blocks, 1) }?;
+		Ok(unsafe { SubStalloc::new(self, start, blocks) })
+	}
+}
+
+#[cfg(feature = "std")]
+impl<const L: usize, const B: usize> crate::SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Allocates `blocks` contiguous blocks, and wraps them in a `SubStalloc` that can be used
+	/// as an allocator of its own. See `Stalloc::carve()` for details.
+	///
+	/// The `SubStalloc` is not thread-safe, and only locks this allocator when it is dropped.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `blocks` is zero, or if the blocks couldn't be allocated.
+	pub fn carve(&self, blocks: usize) -> Result<SubStalloc<'_, B>, AllocError> {
+		if blocks == 0 {
+			return Err(AllocError);
+		}
+
+		// SAFETY: `blocks` is nonzero, and an alignment of 1 is always valid.
+		let start = unsafe { self.allocate_blocks(blocks, 1) }?;
+		Ok(unsafe { SubStalloc::new(self, start, blocks) })
+	}
+}
+
+impl<'a, const B: usize> SubStalloc<'a, B>
+where
+	Align<B>: Alignment,
+{
+	/// Safety precondition: `start` must point to an allocation of `blocks` blocks in `parent`,
+	/// where `blocks` is in `1..65536`.
+	pub(crate) unsafe fn new(
+		parent: &'a dyn CarveParent,
+		start: NonNull<u8>,
+		blocks: usize,
+	) -> Self {
+		unsafe {
+			start.cast::<Header>().write(Header {
+				next: 0,
+				length: as_u16(blocks),
+			});
+		}
+
+		Self {
+			parent,
+			start,
+			blocks,
+			base: UnsafeCell::new(Header { next: 0, length: 0 }),
+		}
+	}
+
+	/// Returns the number of blocks that were carved out of the parent allocator.
+	#[must_use]
+	pub const fn blocks(&self) -> usize {
+		self.blocks
+	}
+
+	/// Checks if the allocator is completely out of memory.
+	pub const fn is_oom(&self) -> bool {
+		unsafe { (*self.base.get()).length == OOM_MARKER }
+	}
+
+	/// Checks if the allocator is empty.
+	pub fn is_empty(&self) -> bool {
+		!self.is_oom()
+			&& unsafe {
+				(*self.base.get()).next == 0
+					&& usize::from((*self.header_at(0)).length) == self.blocks
+			}
+	}
+
+	/// Tries to allocate `size` blocks. Note that `align` is measured in units of `B`.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// Assert unsafe preconditions.
+		precondition!(size >= 1, "`size` must be nonzero");
+		precondition!(
+			align.is_power_of_two() && align <= MAX_ALIGN / B,
+			"`align` must be a power of 2 in the range `1..=MAX_ALIGN / B`"
+		);
+
+		if self.is_oom() {
+			return Err(AllocError);
+		}
+
+		unsafe {
+			let mut prev = self.base.get();
+			let mut curr_idx = usize::from((*prev).next);
+
+			loop {
+				let curr = self.header_at(curr_idx);
+				let length = usize::from((*curr).length);
+				let next_idx = usize::from((*curr).next);
+
+				// The number of blocks that have to be skipped for the allocation to be aligned.
+				let addr = self.start.addr().get() + curr_idx * B;
+				let spare_front = (addr.next_multiple_of(align * B) - addr) / B;
+
+				if spare_front + size <= length {
+					let alloc_idx = curr_idx + spare_front;
+					let spare_back = length - spare_front - size;
+
+					// Link the blocks after the allocation (if there are any) in place of the chunk.
+					let following = if spare_back > 0 {
+						let back_idx = alloc_idx + size;
+						*self.header_at(back_idx) = Header {
+							next: as_u16(next_idx),
+							length: as_u16(spare_back),
+						};
+						back_idx
+					} else {
+						next_idx
+					};
+
+					if spare_front > 0 {
+						(*curr).length = as_u16(spare_front);
+						(*curr).next = as_u16(following);
+					} else if spare_back == 0 && following == 0 && prev == self.base.get() {
+						// That was the last free chunk.
+						(*prev).length = OOM_MARKER;
+					} else {
+						(*prev).next = as_u16(following);
+					}
+
+					return Ok(self.start.add(alloc_idx * B));
+				}
+
+				if next_idx == 0 {
+					return Err(AllocError);
+				}
+
+				prev = curr;
+				curr_idx = next_idx;
+			}
+		}
+	}
+
+	/// Deallocates a pointer.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to an allocation of `size` blocks made by this allocator.
+	pub unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		// Assert unsafe preconditions.
+		precondition!(
+			size >= 1 && size <= self.blocks,
+			"`size` must be in `1..=blocks()`"
+		);
+
+		let freed_idx = (ptr.addr().get() - self.start.addr().get()) / B;
+
+		unsafe {
+			let base = self.base.get();
+			let freed = self.header_at(freed_idx);
+
+			if (*base).length == OOM_MARKER {
+				(*base).length = 0;
+				(*base).next = as_u16(freed_idx);
+				*freed = Header {
+					next: 0,
+					length: as_u16(size),
+				};
+				return;
+			}
+
+			// Find the last free chunk before the freed blocks, or `base` if there is none.
+			let mut prev = base;
+			let mut prev_idx = None;
+			let mut next_idx = Some(usize::from((*base).next));
+
+			while let Some(idx) = next_idx.filter(|&idx| idx < freed_idx) {
+				prev = self.header_at(idx);
+				prev_idx = Some(idx);
+				next_idx = match (*prev).next {
+					0 => None,
+					next => Some(next.into()),
+				};
+			}
+
+			// Merge the freed blocks with the chunk after them, if they touch.
+			*freed = match next_idx {
+				Some(idx) if idx == freed_idx + size => {
+					let next = *self.header_at(idx);
+					Header {
+						next: next.next,
+						length: as_u16(size) + next.length,
+					}
+				}
+				Some(idx) => Header {
+					next: as_u16(idx),
+					length: as_u16(size),
+				},
+				None => Header {
+					next: 0,
+					length: as_u16(size),
+				},
+			};
+
+			// Merge the freed blocks with the chunk before them, if they touch.
+			match prev_idx {
+				Some(idx) if idx + usize::from((*prev).length) == freed_idx => {
+					(*prev).length += (*freed).length;
+					(*prev).next = (*freed).next;
+				}
+				_ => (*prev).next = as_u16(freed_idx),
+			}
+		}
+	}
+
+	/// Safety precondition: idx must be in `0..blocks`.
+	const unsafe fn header_at(&self, idx: usize) -> *mut Header {
+		unsafe { self.start.as_ptr().add(idx * B).cast() }
+	}
+}
+
+impl<const B: usize> Drop for SubStalloc<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		// SAFETY: The blocks were allocated from the parent in `new()`.
+		unsafe { self.parent.release(self.start, self.blocks) };
+	}
+}
+
+impl<const B: usize> Debug for SubStalloc<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SubStalloc")
+			.field("start", &self.start)
+			.field("blocks", &self.blocks)
+			.field("is_oom", &self.is_oom())
+			.finish_non_exhaustive()
+	}
+}
+
+unsafe impl<const B: usize> ChainableAlloc for SubStalloc<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		addr >= self.start.addr().get() && addr < self.start.addr().get() + B * self.blocks
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use crate::{Allocator, Layout};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<const B: usize> Allocator for &SubStalloc<'_, B>
+where
+	Align<B>: Alignment,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// We can only allocate memory in units of `B`, so round up.
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// If `size` is zero, give away a dangling pointer.
+		if size == 0 {
+			let dangling = NonNull::new(layout.align() as _).unwrap();
+			return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+		}
+
+		// SAFETY: We have made sure that `size` and `align` are valid.
+		unsafe { self.allocate_blocks(size, align) }
+			.map(|p| NonNull::slice_from_raw_parts(p, size * B))
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size == 0 {
+			return;
+		}
+
+		// SAFETY: We just made sure that size != 0. Everything else is upheld by the caller.
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+}
@@ -1,22 +1,94 @@
 use core::alloc::{GlobalAlloc, Layout};
-use core::fmt::{self, Debug, Formatter};
+use core::fmt::{self, Debug, Display, Formatter};
 use core::marker::PhantomData;
 use core::ops::Deref;
 use core::ptr::NonNull;
+use core::time::Duration;
 
 extern crate std;
-use std::sync::{Mutex, MutexGuard};
+use std::alloc::System;
+use std::sync::{LockResult, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::time::Instant;
 
 use crate::align::{Align, Alignment};
-use crate::{AllocChain, AllocError, ChainableAlloc, UnsafeStalloc};
+use crate::{AllocChain, AllocError, ChainableAlloc, OwnedAllocChain, Stalloc, UnsafeStalloc};
 
 /// A wrapper around `UnsafeStalloc` that is safe to create because it prevents data races using a Mutex.
 /// In comparison to `UnsafeStalloc`, the mutex may cause a slight overhead.
 #[repr(C)]
-pub struct SyncStalloc<const L: usize, const B: usize>(Mutex<()>, UnsafeStalloc<L, B>)
+pub struct SyncStalloc<const L: usize, const B: usize>(
+	Mutex<()>,
+	UnsafeStalloc<L, B>,
+	#[cfg(feature = "stats")] LockCounters,
+)
 where
 	Align<B>: Alignment;
 
+/// Counters describing how often the lock of a `SyncStalloc` was contended. This requires the `stats` feature.
+///
+/// An acquisition is contended if the lock was already held by another thread, so that the caller had to wait.
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct LockStats {
+	/// The number of times the lock was acquired.
+	pub acquisitions: u64,
+	/// The number of acquisitions that had to wait for another thread to release the lock.
+	pub contended_acquisitions: u64,
+	/// The total time spent waiting for the lock in contended acquisitions.
+	pub total_wait: Duration,
+}
+
+/// The lock counters of a `SyncStalloc`, which are updated atomically, since they are written without holding the lock.
+#[cfg(feature = "stats")]
+struct LockCounters {
+	acquisitions: std::sync::atomic::AtomicU64,
+	contended_acquisitions: std::sync::atomic::AtomicU64,
+	wait_nanos: std::sync::atomic::AtomicU64,
+}
+
+#[cfg(feature = "stats")]
+impl LockCounters {
+	const fn new() -> Self {
+		use std::sync::atomic::AtomicU64;
+
+		Self {
+			acquisitions: AtomicU64::new(0),
+			contended_acquisitions: AtomicU64::new(0),
+			wait_nanos: AtomicU64::new(0),
+		}
+	}
+
+	/// Records an acquisition of the lock, which had to wait for `wait` if it was contended.
+	fn record(&self, wait: Option<Duration>) {
+		use std::sync::atomic::Ordering::Relaxed;
+
+		self.acquisitions.fetch_add(1, Relaxed);
+		if let Some(wait) = wait {
+			let nanos = u64::try_from(wait.as_nanos()).unwrap_or(u64::MAX);
+			self.contended_acquisitions.fetch_add(1, Relaxed);
+			self.wait_nanos.fetch_add(nanos, Relaxed);
+		}
+	}
+
+	fn snapshot(&self) -> LockStats {
+		use std::sync::atomic::Ordering::Relaxed;
+
+		LockStats {
+			acquisitions: self.acquisitions.load(Relaxed),
+			contended_acquisitions: self.contended_acquisitions.load(Relaxed),
+			total_wait: Duration::from_nanos(self.wait_nanos.load(Relaxed)),
+		}
+	}
+
+	fn reset(&self) {
+		use std::sync::atomic::Ordering::Relaxed;
+
+		self.acquisitions.store(0, Relaxed);
+		self.contended_acquisitions.store(0, Relaxed);
+		self.wait_nanos.store(0, Relaxed);
+	}
+}
+
 /// A lock around `SyncStalloc`. Constructing this type is proof that the user holds an exclusive
 /// lock on the inner `UnsafeStalloc`. When this falls out of scope, the `SyncStalloc` is unlocked.
 ///
@@ -57,111 +129,996 @@ where
 	pub const fn new() -> Self {
 		// SAFETY: The `UnsafeStalloc` can only be accessed through `acquire_locked()`,
 		// which guarantees that the mutex is locked before proceeding.
-		Self(Mutex::new(()), unsafe { UnsafeStalloc::<L, B>::new() })
+		Self(
+			Mutex::new(()),
+			unsafe { UnsafeStalloc::<L, B>::new() },
+			#[cfg(feature = "stats")]
+			LockCounters::new(),
+		)
+	}
+
+	/// Initializes a new `SyncStalloc` instance in which the `size` blocks starting at block index `idx`
+	/// are reserved. See `Stalloc::with_reserved()` for details.
+	///
+	/// # Panics
+	///
+	/// Panics if `size` is zero or the reserved blocks are out of bounds.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	///
+	/// // The first 64 blocks are managed by hand.
+	/// static ALLOC: SyncStalloc<256, 8> = SyncStalloc::with_reserved(0, 64);
+	/// ```
+	#[must_use]
+	pub const fn with_reserved(idx: usize, size: usize) -> Self {
+		// SAFETY: See `new()`.
+		Self(
+			Mutex::new(()),
+			unsafe { UnsafeStalloc::<L, B>::with_reserved(idx, size) },
+			#[cfg(feature = "stats")]
+			LockCounters::new(),
+		)
+	}
+
+	/// Returns this allocator with `handler` registered as its OOM handler, which is called when
+	/// `GlobalAlloc` is about to return a null pointer. This can be used to initialize a `static`.
+	/// This requires the `oom-handler` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{AllocReport, SyncStalloc};
+	/// use std::alloc::Layout;
+	///
+	/// fn on_oom(layout: Layout, report: &AllocReport) {
+	///     // Log the state of the allocator, without allocating.
+	/// }
+	///
+	/// #[global_allocator]
+	/// static GLOBAL: SyncStalloc<1000, 4> = SyncStalloc::new().with_oom_handler(on_oom);
+	/// # fn main() {}
+	/// ```
+	#[cfg(feature = "oom-handler")]
+	#[must_use]
+	pub const fn with_oom_handler(mut self, handler: crate::OomHandler) -> Self {
+		self.1.0.oom_handler = core::cell::Cell::new(Some(handler));
+		self
+	}
+
+	/// Returns this allocator with `sink` registered as its OOM sink. This requires the `abort-on-oom` feature.
+	/// See `Stalloc::set_oom_sink()` for details.
+	#[cfg(feature = "abort-on-oom")]
+	#[must_use]
+	pub const fn with_oom_sink(mut self, sink: crate::OomSink) -> Self {
+		self.1.0.oom_sink = core::cell::Cell::new(Some(sink));
+		self
+	}
+
+	/// Returns this allocator with every allocation surrounded by guard blocks. This requires the `canaries`
+	/// feature. See `Stalloc::with_canaries()` for details.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	///
+	/// #[global_allocator]
+	/// static GLOBAL: SyncStalloc<1000, 4> = SyncStalloc::new().with_canaries();
+	/// # fn main() {}
+	/// ```
+	#[cfg(feature = "canaries")]
+	#[must_use]
+	pub const fn with_canaries(mut self) -> Self {
+		self.1.0.canaries = true;
+		self
+	}
+
+	/// Checks if the allocator is completely out of memory.
+	/// If this is false, then you are guaranteed to be able to allocate
+	/// a layout with a size and alignment of `B` bytes.
+	/// This runs in O(1).
+	pub fn is_oom(&self) -> bool {
+		self.acquire_locked().is_oom()
+	}
+
+	/// Checks if the allocator is empty.
+	/// If this is true, then you are guaranteed to be able to allocate
+	/// a layout with a size of `B * L` bytes and an alignment of `B` bytes.
+	/// If this is false, then this is guaranteed to be impossible.
+	/// This runs in O(1).
+	pub fn is_empty(&self) -> bool {
+		self.acquire_locked().is_empty()
+	}
+
+	/// # Safety
+	///
+	/// Calling this function immediately invalidates all pointers into the allocator. Calling
+	/// `deallocate_blocks()` with an invalidated pointer will result in the free list being corrupted.
+	pub unsafe fn clear(&self) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().clear() }
+	}
+
+	/// Returns the number of live allocations. See `Stalloc::live_allocations()` for details.
+	pub fn live_allocations(&self) -> usize {
+		self.acquire_locked().live_allocations()
+	}
+
+	/// Resets the allocator, but only if it is empty. Returns whether the allocator was reset.
+	/// See `Stalloc::try_clear()` for details.
+	pub fn try_clear(&self) -> bool {
+		self.acquire_locked().try_clear()
+	}
+
+	/// Registers a function that is called whenever an allocation made through `GlobalAlloc` fails.
+	/// This requires the `oom-handler` feature. See `Stalloc::set_oom_handler()` for details.
+	#[cfg(feature = "oom-handler")]
+	pub fn set_oom_handler(&self, handler: Option<crate::OomHandler>) {
+		self.acquire_locked().set_oom_handler(handler);
+	}
+
+	/// Registers a function that receives the diagnostic dump written before aborting on OOM.
+	/// See `Stalloc::set_oom_sink()` for details.
+	#[cfg(feature = "abort-on-oom")]
+	pub fn set_oom_sink(&self, sink: Option<crate::OomSink>) {
+		self.acquire_locked().set_oom_sink(sink);
+	}
+
+	/// Tries to allocate `count` blocks. If the allocation succeed, a pointer is returned. This function
+	/// never allocates more than necessary.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().allocate_blocks(size, align) }
+	}
+
+	/// Tries to allocate `size` blocks aligned to `align` blocks, at an address that satisfies `constraint`.
+	/// See `Stalloc::allocate_blocks_constrained()` for details.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks_constrained(
+		&self,
+		size: usize,
+		align: usize,
+		constraint: crate::AddrConstraint,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.allocate_blocks_constrained(size, align, constraint)
+		}
+	}
+
+	/// Tries to allocate the `size` blocks starting at block index `idx`, counted from the start of the buffer.
+	/// See `Stalloc::allocate_blocks_at()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `size` is zero, if the blocks are out of bounds, or if any of them
+	/// are in use. In that case, this function was a no-op.
+	pub fn allocate_blocks_at(&self, idx: usize, size: usize) -> Result<NonNull<u8>, AllocError> {
+		self.acquire_locked().allocate_blocks_at(idx, size)
+	}
+
+	/// Reserves the `size` blocks starting at block index `idx`, if they are all free.
+	/// See `Stalloc::reserve_blocks()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `size` is zero, if the blocks are out of bounds, or if any of them are in use.
+	/// In that case, this function was a no-op.
+	pub fn reserve_blocks(&self, idx: usize, size: usize) -> Result<NonNull<u8>, AllocError> {
+		self.acquire_locked().reserve_blocks(idx, size)
+	}
+
+	/// Returns the `size` reserved blocks starting at block index `idx` to the allocator.
+	///
+	/// # Safety
+	///
+	/// The blocks must be reserved, either with `with_reserved()` or `reserve_blocks()`. Part of a
+	/// reserved region may be released, as long as every block is only released once.
+	pub unsafe fn release_reserved(&self, idx: usize, size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().release_reserved(idx, size) }
+	}
+
+	/// Splits an allocation of `size` blocks in two after its first `at` blocks, so that each part
+	/// can be resized or deallocated on its own. See `Stalloc::split_allocation()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks, and `at` must be in `1..size`
+	/// (or `1..size - 2` with canaries).
+	pub unsafe fn split_allocation(
+		&self,
+		ptr: NonNull<u8>,
+		size: usize,
+		at: usize,
+	) -> (NonNull<u8>, NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().split_allocation(ptr, size, at) }
+	}
+
+	/// Moves an allocation of `size` blocks from this allocator into `dest`, where it is aligned to
+	/// `align` blocks of `dest`. See `Stalloc::transfer_to()` for details.
+	///
+	/// The two allocators are never locked at the same time, so transfers in opposite directions
+	/// can't deadlock, and `dest` may be the same allocator as `self`.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks, and `align` must be a power of 2
+	/// in the range `1..=MAX_ALIGN / B2`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `dest` couldn't make the new allocation, in which case this function
+	/// was a no-op and `ptr` is still valid.
+	pub unsafe fn transfer_to<const L2: usize, const B2: usize>(
+		&self,
+		ptr: NonNull<u8>,
+		size: usize,
+		dest: &SyncStalloc<L2, B2>,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError>
+	where
+		Align<B2>: Alignment,
+	{
+		// SAFETY: Upheld by the caller. The old allocation belongs to the caller, so it can be
+		// copied without holding either lock.
+		unsafe {
+			let new_ptr = dest.allocate_blocks((size * B).div_ceil(B2), align)?;
+			ptr.copy_to_nonoverlapping(new_ptr, size * B);
+			self.deallocate_blocks(ptr, size);
+			Ok(new_ptr)
+		}
+	}
+
+	/// Makes a copy of an allocation of `size` blocks in this allocator, and returns a pointer to it.
+	/// See `Stalloc::duplicate()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the copy couldn't be allocated, in which case this function was a no-op.
+	pub unsafe fn duplicate(
+		&self,
+		ptr: NonNull<u8>,
+		size: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().duplicate(ptr, size) }
+	}
+
+	/// Slides every live allocation as far toward the start of the buffer as possible, calling
+	/// `on_move(old, new, size)` for every allocation that was moved. See `Stalloc::compact()` for details.
+	/// This requires the `tracking` feature.
+	///
+	/// # Safety
+	///
+	/// Every moved allocation must only be accessed through the new pointer afterwards, so no references
+	/// into any allocation may be alive during the call. `on_move` is called while the allocator is locked,
+	/// so it must not use the allocator.
+	#[cfg(feature = "tracking")]
+	pub unsafe fn compact(&self, on_move: impl FnMut(NonNull<u8>, NonNull<u8>, usize)) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().compact(on_move) }
+	}
+
+	/// Tries to allocate `size` blocks that will never be freed. Note that `align` is measured in units of `B`.
+	/// See `Stalloc::allocate_blocks_permanent()` for details.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks_permanent(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().allocate_blocks_permanent(size, align) }
+	}
+
+	/// Turns an allocation of `size` blocks into a permanent one, which must never be freed or resized.
+	/// See `Stalloc::leak_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks. Afterwards, it must not be passed to any
+	/// function of this allocator, except for `clear()`, which frees permanent allocations as well.
+	pub unsafe fn leak_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().leak_blocks(ptr, size) }
+	}
+
+	/// Returns the number of bytes that can be used in an allocation at `ptr` that was requested with a
+	/// size of `size_hint` bytes. See `Stalloc::usable_size()` for details.
+	pub fn usable_size(&self, ptr: NonNull<u8>, size_hint: usize) -> usize {
+		self.acquire_locked().usable_size(ptr, size_hint)
+	}
+
+	/// Deallocates a pointer without knowing its size, which is looked up instead.
+	/// This requires the `tracking` feature.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation.
+	#[cfg(feature = "tracking")]
+	pub unsafe fn deallocate_tracked(&self, ptr: NonNull<u8>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate_tracked(ptr) }
+	}
+
+	/// Checks whether the allocation of `size` blocks at `ptr` is the last one in the buffer, so that
+	/// every block after it is free. See `Stalloc::is_last_allocation()` for details.
+	pub fn is_last_allocation(&self, ptr: NonNull<u8>, size: usize) -> bool {
+		self.acquire_locked().is_last_allocation(ptr, size)
+	}
+
+	/// Tries to allocate uninitialized memory for a value of type `T`.
+	/// See `Stalloc::allocate_value()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub fn allocate_value<T>(&self) -> Result<NonNull<T>, AllocError> {
+		self.acquire_locked().allocate_value()
+	}
+
+	/// Tries to allocate uninitialized memory for `len` values of type `T`.
+	/// See `Stalloc::allocate_slice()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the size of the slice overflows, or if the allocation was unsuccessful.
+	/// In that case, this function was a no-op.
+	pub fn allocate_slice<T>(&self, len: usize) -> Result<NonNull<[T]>, AllocError> {
+		self.acquire_locked().allocate_slice(len)
+	}
+
+	/// Deallocates memory that was allocated with `allocate_value::<T>()`.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been returned by `allocate_value::<T>()` on this allocator, and must not have been freed.
+	/// The value is not dropped.
+	pub unsafe fn deallocate_value<T>(&self, ptr: NonNull<T>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate_value(ptr) }
+	}
+
+	/// Deallocates memory that was allocated with `allocate_slice::<T>()`.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been returned by `allocate_slice::<T>()` on this allocator, and must not have been freed.
+	/// The values are not dropped.
+	pub unsafe fn deallocate_slice<T>(&self, ptr: NonNull<[T]>) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate_slice(ptr) }
+	}
+
+	/// Moves the value returned by `f` into the allocator, and returns a reference to it. The value is
+	/// never dropped, and its memory is only reclaimed when the allocator is cleared or dropped.
+	///
+	/// `f` is called after the allocator is unlocked, so it may use the allocator itself.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case `f` is not called.
+	#[allow(clippy::mut_from_ref)]
+	pub fn alloc_with<T>(&self, f: impl FnOnce() -> T) -> Result<&mut T, AllocError> {
+		let ptr = self.allocate_value::<T>()?;
+
+		// SAFETY: The memory is valid for a `T`, and is never handed out again while `self` is borrowed.
+		unsafe {
+			ptr.write(f());
+			Ok(&mut *ptr.as_ptr())
+		}
+	}
+
+	/// Copies `slice` into the allocator, and returns a reference to the copy.
+	/// See `Stalloc::alloc_slice_copy()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	#[allow(clippy::mut_from_ref)]
+	pub fn alloc_slice_copy<T: Copy>(&self, slice: &[T]) -> Result<&mut [T], AllocError> {
+		let start = self.allocate_slice::<T>(slice.len())?.cast::<T>();
+
+		// SAFETY: The memory is valid for `slice.len()` values of type `T`, and is never handed out again
+		// while `self` is borrowed.
+		unsafe {
+			start.copy_from_nonoverlapping(NonNull::from(slice).cast(), slice.len());
+			Ok(core::slice::from_raw_parts_mut(start.as_ptr(), slice.len()))
+		}
+	}
+
+	/// Copies `s` into the allocator, and returns a reference to the copy.
+	/// See `Stalloc::alloc_str()` for details.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful.
+	#[allow(clippy::mut_from_ref)]
+	pub fn alloc_str(&self, s: &str) -> Result<&mut str, AllocError> {
+		let bytes = self.alloc_slice_copy(s.as_bytes())?;
+
+		// SAFETY: The bytes were copied from a `str`, so they are valid UTF-8.
+		Ok(unsafe { core::str::from_utf8_unchecked_mut(bytes) })
+	}
+
+	/// Merges two adjacent allocations into a single one, and returns its size in blocks.
+	/// See `Stalloc::merge_allocations()` for details.
+	///
+	/// # Safety
+	///
+	/// `first` must point to a valid allocation of `first_size` blocks, and `second` must point to a valid
+	/// allocation of `second_size` blocks that starts right after it (after the guard blocks of `first`,
+	/// with canaries). Both pointers must be invalidated and replaced by `first`.
+	pub unsafe fn merge_allocations(
+		&self,
+		first: NonNull<u8>,
+		first_size: usize,
+		second: NonNull<u8>,
+		second_size: usize,
+	) -> usize {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.merge_allocations(first, first_size, second, second_size)
+		}
+	}
+
+	/// Tries to allocate `size` blocks, and fills them with zeros. Note that `align` is measured in units of `B`.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks_zeroed(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().allocate_blocks_zeroed(size, align) }
+	}
+
+	/// Tries to make one allocation for every entry of `sizes`, all aligned to `align` blocks, while only
+	/// acquiring the lock once. See `Stalloc::allocate_many()` for details.
+	///
+	/// # Safety
+	///
+	/// Every size must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if not all allocations could be made, in which case this function was a no-op.
+	///
+	/// # Panics
+	///
+	/// Panics if `sizes` and `out` have different lengths.
+	pub unsafe fn allocate_many<'a>(
+		&self,
+		sizes: &[usize],
+		align: usize,
+		out: &'a mut [core::mem::MaybeUninit<NonNull<u8>>],
+	) -> Result<&'a mut [NonNull<u8>], AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().allocate_many(sizes, align, out) }
+	}
+
+	/// Deallocates a pointer.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to an allocation, and `size` must be the number of blocks
+	/// in the allocation. That is, `size` is always in `1..=L`.
+	pub unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate_blocks(ptr, size) }
+	}
+
+	/// Deallocates every `(ptr, size)` pair in `allocs` while only acquiring the lock once.
+	/// See `Stalloc::deallocate_many()` for details.
+	///
+	/// # Safety
+	///
+	/// Every `ptr` must point to a different allocation, and its `size` must be the number of blocks
+	/// in that allocation.
+	pub unsafe fn deallocate_many(&self, allocs: &mut [(NonNull<u8>, usize)]) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().deallocate_many(allocs) }
+	}
+
+	/// Shrinks the allocation. This function always succeeds and never reallocates.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size` must be in `1..old_size`.
+	pub unsafe fn shrink_in_place(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.shrink_in_place(ptr, old_size, new_size);
+		}
+	}
+
+	/// Shrinks the allocation by releasing its first `old_size - new_size` blocks, and returns a pointer to
+	/// the remaining blocks, which stay where they are. This function always succeeds and never reallocates.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size` must be in `1..old_size`.
+	pub unsafe fn shrink_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> NonNull<u8> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.shrink_in_place_front(ptr, old_size, new_size)
+		}
+	}
+
+	/// Tries to grow the current allocation in-place. If that isn't possible, this function is a no-op.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn grow_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().grow_in_place(ptr, old_size, new_size) }
+	}
+
+	/// Tries to grow the current allocation into the free chunk right before it, moving its contents down.
+	/// See `Stalloc::grow_in_place_front()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size > old_size`.
+	/// `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn grow_in_place_front(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.grow_in_place_front(ptr, old_size, new_size, align)
+		}
+	}
+
+	/// Tries to grow the current allocation in-place, and fills the newly acquired blocks with zeros.
+	/// If that isn't possible, this function is a no-op.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn grow_zeroed_in_place(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+	) -> Result<(), AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.grow_zeroed_in_place(ptr, old_size, new_size)
+		}
+	}
+
+	/// Tries to grow the current allocation in-place. If that isn't possible, the allocator grows by as much
+	/// as it is able to, and the new length of the allocation is returned. The new length is guaranteed to be
+	/// in the range `old_size..=new_size`.
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	pub unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().grow_up_to(ptr, old_size, new_size) }
+	}
+
+	/// Resizes an allocation to `new_size` blocks aligned to `align` blocks, moving it if it can't be
+	/// resized in place. See `Stalloc::reallocate_blocks()` for details.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `old_size` blocks. `new_size` must be nonzero,
+	/// and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation had to be moved and the new allocation failed,
+	/// in which case this function was a no-op and `ptr` is still valid.
+	pub unsafe fn reallocate_blocks(
+		&self,
+		ptr: NonNull<u8>,
+		old_size: usize,
+		new_size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.reallocate_blocks(ptr, old_size, new_size, align)
+		}
+	}
+
+	/// Registers a set of hooks that are called on every allocation, deallocation and in-place resize,
+	/// replacing any previously registered hooks. Pass `None` to remove them.
+	/// This requires the `hooks` feature.
+	///
+	/// The hooks are called while the allocator is locked, so they must not use this allocator.
+	#[cfg(feature = "hooks")]
+	pub fn set_hooks(&self, hooks: Option<&'static dyn crate::StallocHooks>) {
+		self.acquire_locked().set_hooks(hooks);
+	}
+
+	/// Seeds the random number generator that is used to place allocations, or turns randomized
+	/// placement off if `seed` is 0. See `Stalloc::set_seed()` for details.
+	/// This requires the `randomize` feature.
+	#[cfg(feature = "randomize")]
+	pub fn set_seed(&self, seed: u64) {
+		self.acquire_locked().set_seed(seed);
+	}
+
+	/// Limits how many free chunks an allocation may examine before giving up, or removes the limit
+	/// if `limit` is `None`. See `Stalloc::set_search_limit()` for details.
+	/// This requires the `bounded-search` feature.
+	#[cfg(feature = "bounded-search")]
+	pub fn set_search_limit(&self, limit: Option<core::num::NonZeroUsize>) {
+		self.acquire_locked().set_search_limit(limit);
+	}
+
+	/// Returns the maximum number of free chunks that an allocation may examine, or `None` if there is no limit.
+	/// This requires the `bounded-search` feature.
+	#[cfg(feature = "bounded-search")]
+	pub fn search_limit(&self) -> Option<core::num::NonZeroUsize> {
+		self.acquire_locked().search_limit()
+	}
+
+	/// Returns a snapshot of the allocator's usage counters. This requires the `stats` feature.
+	#[cfg(feature = "stats")]
+	pub fn stats(&self) -> crate::Stats {
+		self.acquire_locked().stats()
+	}
+
+	/// Resets all usage counters to zero, and the peak usage to the current usage.
+	/// This requires the `stats` feature.
+	#[cfg(feature = "stats")]
+	pub fn reset_stats(&self) {
+		self.acquire_locked().reset_stats();
+		self.2.reset();
+	}
+
+	/// Returns how often the lock of this allocator was acquired, and how much of that was contended.
+	/// Resetting the stats with `reset_stats()` also resets these counters. This requires the `stats` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	///
+	/// let alloc = SyncStalloc::<100, 4>::new();
+	/// unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	///
+	/// let lock_stats = alloc.lock_stats();
+	/// assert_eq!(lock_stats.acquisitions, 1);
+	/// assert_eq!(lock_stats.contended_acquisitions, 0);
+	/// ```
+	#[cfg(feature = "stats")]
+	pub fn lock_stats(&self) -> LockStats {
+		self.2.snapshot()
+	}
+
+	/// Registers a callback that is called whenever the memory usage crosses one of `percents`.
+	/// See `Stalloc::set_thresholds()` for details.
+	///
+	/// The callback is called while the allocator is locked, so it must not use this allocator.
+	///
+	/// # Panics
+	///
+	/// Panics if more than `MAX_THRESHOLDS` thresholds are given, or if a threshold is greater than 100.
+	#[cfg(feature = "thresholds")]
+	pub fn set_thresholds(&self, percents: &[u8], callback: crate::ThresholdCallback) {
+		self.acquire_locked().set_thresholds(percents, callback);
 	}
 
-	/// Checks if the allocator is completely out of memory.
-	/// If this is false, then you are guaranteed to be able to allocate
-	/// a layout with a size and alignment of `B` bytes.
-	/// This runs in O(1).
-	pub fn is_oom(&self) -> bool {
-		self.acquire_locked().is_oom()
+	/// Removes the registered thresholds and their callback. This requires the `thresholds` feature.
+	#[cfg(feature = "thresholds")]
+	pub fn clear_thresholds(&self) {
+		self.acquire_locked().clear_thresholds();
 	}
 
-	/// Checks if the allocator is empty.
-	/// If this is true, then you are guaranteed to be able to allocate
-	/// a layout with a size of `B * L` bytes and an alignment of `B` bytes.
-	/// If this is false, then this is guaranteed to be impossible.
-	/// This runs in O(1).
-	pub fn is_empty(&self) -> bool {
-		self.acquire_locked().is_empty()
+	/// Checks whether `ptr` points to the start of a live allocation.
+	/// This runs in O(1) and requires the `tracking` feature.
+	///
+	/// To iterate over all live allocations, use `acquire_locked().iter_allocations()`.
+	#[cfg(feature = "tracking")]
+	pub fn is_allocated(&self, ptr: NonNull<u8>) -> bool {
+		self.acquire_locked().is_allocated(ptr)
 	}
 
-	/// # Safety
-	///
-	/// Calling this function immediately invalidates all pointers into the allocator. Calling
-	/// `deallocate_blocks()` with an invalidated pointer will result in the free list being corrupted.
-	pub unsafe fn clear(&self) {
-		// SAFETY: Upheld by the caller.
-		unsafe { self.acquire_locked().clear() }
+	/// Returns the length in blocks of the live allocation that starts at `ptr`, or `None` if there is none.
+	/// This runs in O(1) and requires the `tracking` feature.
+	#[cfg(feature = "tracking")]
+	pub fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		self.acquire_locked().allocation_size(ptr)
 	}
 
-	/// Tries to allocate `count` blocks. If the allocation succeed, a pointer is returned. This function
-	/// never allocates more than necessary.
+	/// Tries to allocate `size` blocks, and labels the allocation with `tag`. Note that `align` is measured
+	/// in units of `B`. See `Stalloc::allocate_blocks_tagged()` for details. This requires the `tags` feature.
 	///
 	/// # Safety
 	///
-	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=2^29 / B`.
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
 	///
 	/// # Errors
 	///
 	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
-	pub unsafe fn allocate_blocks(
+	#[cfg(feature = "tags")]
+	pub unsafe fn allocate_blocks_tagged(
 		&self,
 		size: usize,
 		align: usize,
+		tag: u16,
 	) -> Result<NonNull<u8>, AllocError> {
 		// SAFETY: Upheld by the caller.
-		unsafe { self.acquire_locked().allocate_blocks(size, align) }
+		unsafe {
+			self.acquire_locked()
+				.allocate_blocks_tagged(size, align, tag)
+		}
 	}
 
-	/// Deallocates a pointer.
+	/// Tries to allocate memory for `layout`, and labels the allocation with `tag`.
+	/// This requires the `tags` feature.
 	///
-	/// # Safety
+	/// # Errors
 	///
-	/// `ptr` must point to an allocation, and `size` must be the number of blocks
-	/// in the allocation. That is, `size` is always in `1..=L`.
-	pub unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
-		// SAFETY: Upheld by the caller.
-		unsafe { self.acquire_locked().deallocate_blocks(ptr, size) }
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	#[cfg(feature = "tags")]
+	pub fn allocate_tagged(
+		&self,
+		layout: core::alloc::Layout,
+		tag: u16,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.acquire_locked().allocate_tagged(layout, tag)
 	}
 
-	/// Shrinks the allocation. This function always succeeds and never reallocates.
+	/// Returns the tag of the live allocation that starts at `ptr`, or `None` if there is none.
+	/// This requires the `tags` feature.
+	#[cfg(feature = "tags")]
+	pub fn tag(&self, ptr: NonNull<u8>) -> Option<u16> {
+		self.acquire_locked().tag(ptr)
+	}
+
+	/// Changes the tag of the live allocation that starts at `ptr`. Returns `false` (and does nothing)
+	/// if there is no such allocation. This requires the `tags` feature.
+	#[cfg(feature = "tags")]
+	pub fn set_tag(&self, ptr: NonNull<u8>, tag: u16) -> bool {
+		self.acquire_locked().set_tag(ptr, tag)
+	}
+
+	/// Returns the total number of blocks in the live allocations labeled with `tag`.
+	/// This runs in O(L) and requires the `tags` feature.
+	#[cfg(feature = "tags")]
+	pub fn tag_usage(&self, tag: u16) -> usize {
+		self.acquire_locked().tag_usage(tag)
+	}
+
+	/// Deallocates every live allocation labeled with `tag`, and returns how many there were.
+	/// This runs in O(L) and requires the `tags` feature.
 	///
 	/// # Safety
 	///
-	/// `ptr` must point to a valid allocation of `old_size` blocks, and `new_size` must be in `1..old_size`.
-	pub unsafe fn shrink_in_place(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) {
+	/// Every pointer to an allocation labeled with `tag` is invalidated.
+	#[cfg(feature = "tags")]
+	pub unsafe fn free_all_with_tag(&self, tag: u16) -> usize {
 		// SAFETY: Upheld by the caller.
-		unsafe {
-			self.acquire_locked()
-				.shrink_in_place(ptr, old_size, new_size);
-		}
+		unsafe { self.acquire_locked().free_all_with_tag(tag) }
 	}
 
-	/// Tries to grow the current allocation in-place. If that isn't possible, this function is a no-op.
+	/// Returns the number of blocks that were freed, but are being held back from reuse.
+	/// This requires the `quarantine` feature.
+	#[cfg(feature = "quarantine")]
+	pub fn quarantined_blocks(&self) -> usize {
+		self.acquire_locked().quarantined_blocks()
+	}
+
+	/// Returns every quarantined block to the free list, making it available for reuse.
+	/// This requires the `quarantine` feature.
+	#[cfg(feature = "quarantine")]
+	pub fn flush_quarantine(&self) {
+		self.acquire_locked().flush_quarantine();
+	}
+
+	/// Sets how many recently freed regions are cached for reuse. See `Stalloc::set_free_cache_depth()` for details.
+	/// This requires the `free-cache` feature.
 	///
-	/// # Safety
+	/// # Panics
 	///
-	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
+	/// Panics if `depth` is greater than `FREE_CACHE_CAPACITY`.
+	#[cfg(feature = "free-cache")]
+	pub fn set_free_cache_depth(&self, depth: usize) {
+		self.acquire_locked().set_free_cache_depth(depth);
+	}
+
+	/// Returns how many recently freed regions are cached for reuse. This requires the `free-cache` feature.
+	#[cfg(feature = "free-cache")]
+	pub fn free_cache_depth(&self) -> usize {
+		self.acquire_locked().free_cache_depth()
+	}
+
+	/// Returns the number of blocks that were freed, but are being cached for reuse.
+	/// This requires the `free-cache` feature.
+	#[cfg(feature = "free-cache")]
+	pub fn cached_blocks(&self) -> usize {
+		self.acquire_locked().cached_blocks()
+	}
+
+	/// Returns every cached block to the free list. This requires the `free-cache` feature.
+	#[cfg(feature = "free-cache")]
+	pub fn flush_free_cache(&self) {
+		self.acquire_locked().flush_free_cache();
+	}
+
+	/// Returns a snapshot of the allocator's memory usage. This runs in O(n), where n is the number of free chunks.
+	pub fn report(&self) -> crate::AllocReport {
+		self.acquire_locked().report()
+	}
+
+	/// Walks the free list and checks that it is consistent. See `Stalloc::validate()` for details.
 	///
 	/// # Errors
 	///
-	/// Will return `AllocError` if the grow was unsuccessful, in which case this function was a no-op.
-	pub unsafe fn grow_in_place(
-		&self,
-		ptr: NonNull<u8>,
-		old_size: usize,
-		new_size: usize,
-	) -> Result<(), AllocError> {
-		// SAFETY: Upheld by the caller.
-		unsafe { self.acquire_locked().grow_in_place(ptr, old_size, new_size) }
+	/// Returns a `CorruptionError` describing the first inconsistency that was found.
+	pub fn validate(&self) -> Result<(), crate::CorruptionError> {
+		self.acquire_locked().validate()
 	}
 
-	/// Tries to grow the current allocation in-place. If that isn't possible, the allocator grows by as much
-	/// as it is able to, and the new length of the allocation is returned. The new length is guaranteed to be
-	/// in the range `old_size..=new_size`.
-	/// # Safety
+	/// Checks if a thread panicked while holding the lock, for example in a hook or an OOM handler.
 	///
-	/// `ptr` must point to a valid allocation of `old_size` blocks. Also, `new_size > old_size`.
-	pub unsafe fn grow_up_to(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> usize {
-		// SAFETY: Upheld by the caller.
-		unsafe { self.acquire_locked().grow_up_to(ptr, old_size, new_size) }
+	/// Locking never fails because of this, but the allocator may have been left in an inconsistent state.
+	/// Use `recover()` to check the allocator and clear the flag.
+	pub fn is_poisoned(&self) -> bool {
+		self.0.is_poisoned()
+	}
+
+	/// Recovers from a panic that happened while the lock was held. This walks the free list, and if it
+	/// is consistent, clears the poison flag so that `is_poisoned()` returns false again.
+	///
+	/// If the free list is corrupted, the flag is left set, and the allocator should no longer be used for
+	/// allocating. Once every pointer into it has been forgotten, `clear()` resets it to a usable state.
+	///
+	/// # Errors
+	///
+	/// Returns a `CorruptionError` describing the first inconsistency that was found.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	/// use std::panic::{AssertUnwindSafe, catch_unwind};
+	///
+	/// let alloc = SyncStalloc::<100, 4>::new();
+	///
+	/// let _ = catch_unwind(AssertUnwindSafe(|| {
+	///     let _lock = alloc.acquire_locked();
+	///     panic!("a hook panicked");
+	/// }));
+	/// assert!(alloc.is_poisoned());
+	///
+	/// // The allocator is still usable, and it wasn't corrupted.
+	/// assert_eq!(alloc.recover(), Ok(()));
+	/// assert!(!alloc.is_poisoned());
+	/// ```
+	pub fn recover(&self) -> Result<(), crate::CorruptionError> {
+		let lock = self.acquire_locked();
+		lock.validate()?;
+		self.0.clear_poison();
+		drop(lock);
+		Ok(())
+	}
+
+	/// Returns a mutable reference to the inner `Stalloc`. Since the `SyncStalloc` is borrowed mutably,
+	/// no other thread can hold the lock, so this doesn't need to acquire it.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	///
+	/// let mut alloc = SyncStalloc::<100, 4>::new();
+	///
+	/// // Set up the arena before sharing it with other threads.
+	/// let setup = alloc.get_mut();
+	/// let ptr = unsafe { setup.allocate_blocks(10, 1) }.unwrap();
+	///
+	/// std::thread::scope(|s| {
+	///     s.spawn(|| assert!(!alloc.is_empty()));
+	/// });
+	///
+	/// unsafe { alloc.deallocate_blocks(ptr, 10) };
+	/// ```
+	pub const fn get_mut(&mut self) -> &mut Stalloc<L, B> {
+		&mut self.1.0
+	}
+
+	/// Consumes the `SyncStalloc`, and returns the inner `Stalloc`.
+	///
+	/// Since the memory of the allocator is stored inline, moving it invalidates every pointer into it.
+	/// Use `get_mut()` instead to access an allocator that has live allocations.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::SyncStalloc;
+	///
+	/// let alloc = SyncStalloc::<100, 4>::with_reserved(0, 10);
+	///
+	/// let alloc = alloc.into_inner();
+	/// assert!(!alloc.is_empty());
+	/// ```
+	// Whether dropping the mutex is possible in a `const fn` depends on the platform.
+	#[allow(clippy::missing_const_for_fn)]
+	pub fn into_inner(self) -> Stalloc<L, B> {
+		self.1.0
 	}
 
 	/// Acquires an exclusive lock for the allocator. This can be used to chain multiple
 	/// operations on the allocator without having to repeatedly acquire locks for each one.
 	///
+	/// If another thread panicked while holding the lock, the lock is acquired anyway. See `recover()`.
+	///
 	/// # Example
 	/// ```
 	/// use stalloc::SyncStalloc;
@@ -177,15 +1134,131 @@ where
 	///
 	/// assert!(alloc.is_oom());
 	/// ```
-	pub fn acquire_locked(&self) -> StallocGuard<L, B> {
-		// SAFETY: if this Mutex is poisoned, it means that one of the allocator functions panicked,
-		// which is already declared to be UB. Therefore, we can assume that this is never poisoned.
+	pub fn acquire_locked(&self) -> StallocGuard<'_, L, B> {
+		self.guard(self.lock())
+	}
+
+	/// Acquires an exclusive lock for the allocator if no other thread holds it, without blocking.
+	/// Returns `None` if the lock is currently held.
+	///
+	/// This lets latency-sensitive threads skip optional allocation work instead of waiting.
+	///
+	/// # Example
+	/// ```
+	/// use stalloc::SyncStalloc;
+	///
+	/// let alloc = SyncStalloc::<100, 4>::new();
+	///
+	/// let lock = alloc.try_acquire_locked().unwrap();
+	/// assert!(alloc.try_acquire_locked().is_none()); // the lock is already held
+	/// drop(lock);
+	///
+	/// assert!(alloc.try_acquire_locked().is_some());
+	/// ```
+	pub fn try_acquire_locked(&self) -> Option<StallocGuard<'_, L, B>> {
+		let result = self.try_lock()?;
+
+		#[cfg(feature = "stats")]
+		self.2.record(None);
+
+		Some(self.guard(result))
+	}
+
+	/// Acquires an exclusive lock for the allocator, waiting for at most `timeout`.
+	/// Returns `None` if the lock couldn't be acquired in time.
+	///
+	/// While the lock is held by another thread, this spins briefly, and then sleeps between attempts
+	/// for a period that doubles up to 1 ms (but never past the timeout).
+	///
+	/// # Example
+	/// ```
+	/// use stalloc::SyncStalloc;
+	/// use std::time::Duration;
+	///
+	/// let alloc = SyncStalloc::<100, 4>::new();
+	///
+	/// let lock = alloc.acquire_locked();
+	/// std::thread::scope(|s| {
+	///     s.spawn(|| assert!(alloc.try_acquire_locked_for(Duration::from_millis(10)).is_none()));
+	/// });
+	/// drop(lock);
+	///
+	/// assert!(alloc.try_acquire_locked_for(Duration::from_millis(10)).is_some());
+	/// ```
+	pub fn try_acquire_locked_for(&self, timeout: Duration) -> Option<StallocGuard<'_, L, B>> {
+		const MAX_SPINS: u32 = 64;
+		const MAX_SLEEP: Duration = Duration::from_millis(1);
+
+		let start = Instant::now();
+		let mut spins = 1;
+		let mut sleep = Duration::from_micros(1);
+
+		loop {
+			if let Some(result) = self.try_lock() {
+				#[cfg(feature = "stats")]
+				self.2
+					.record(Some(start.elapsed()).filter(|wait| !wait.is_zero()));
+
+				return Some(self.guard(result));
+			}
+
+			let left = timeout
+				.checked_sub(start.elapsed())
+				.filter(|left| !left.is_zero())?;
+
+			if spins <= MAX_SPINS {
+				for _ in 0..spins {
+					core::hint::spin_loop();
+				}
+				spins *= 2;
+			} else {
+				std::thread::sleep(sleep.min(left));
+				sleep = (sleep * 2).min(MAX_SLEEP);
+			}
+		}
+	}
+
+	/// Turns the result of locking the mutex into a `StallocGuard`.
+	fn guard<'a>(&'a self, result: LockResult<MutexGuard<'a, ()>>) -> StallocGuard<'a, L, B> {
+		// A poisoned mutex means that a thread panicked while holding the lock, for example in a hook.
+		// This doesn't make the lock itself unusable, so the poison is ignored here, and `recover()`
+		// can be used to check whether the allocator was left in a consistent state.
+		let guard = result.unwrap_or_else(PoisonError::into_inner);
+
 		StallocGuard {
-			_guard: unsafe { self.0.lock().unwrap_unchecked() },
+			_guard: guard,
 			inner: &self.1,
 			_not_sync: PhantomData,
 		}
 	}
+
+	/// Tries to lock the mutex without blocking. Returns `None` if it is held by another thread.
+	fn try_lock(&self) -> Option<LockResult<MutexGuard<'_, ()>>> {
+		match self.0.try_lock() {
+			Ok(guard) => Some(Ok(guard)),
+			Err(TryLockError::Poisoned(err)) => Some(Err(err)),
+			Err(TryLockError::WouldBlock) => None,
+		}
+	}
+
+	/// Locks the mutex. With the `stats` feature, the acquisition is recorded in the lock counters.
+	fn lock(&self) -> LockResult<MutexGuard<'_, ()>> {
+		#[cfg(feature = "stats")]
+		{
+			if let Some(result) = self.try_lock() {
+				self.2.record(None);
+				return result;
+			}
+
+			let start = Instant::now();
+			let result = self.0.lock();
+			self.2.record(Some(start.elapsed()));
+			result
+		}
+
+		#[cfg(not(feature = "stats"))]
+		self.0.lock()
+	}
 }
 
 impl<const L: usize, const B: usize> Default for SyncStalloc<L, B>
@@ -206,6 +1279,15 @@ where
 	}
 }
 
+impl<const L: usize, const B: usize> Display for SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.acquire_locked().inner)
+	}
+}
+
 unsafe impl<const L: usize, const B: usize> GlobalAlloc for SyncStalloc<L, B>
 where
 	Align<B>: Alignment,
@@ -292,6 +1374,64 @@ where
 	}
 }
 
+/// Allocating through a guard reuses the lock that it holds, so a collection like `Vec::new_in(&guard)`
+/// never has to acquire the lock again.
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<const L: usize, const B: usize> Allocator for &StallocGuard<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.inner.allocate(layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.deallocate(ptr, layout) }
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.inner.allocate_zeroed(layout)
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.grow(ptr, old_layout, new_layout) }
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.grow_zeroed(ptr, old_layout, new_layout) }
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.shrink(ptr, old_layout, new_layout) }
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
+
 unsafe impl<const L: usize, const B: usize> ChainableAlloc for SyncStalloc<L, B>
 where
 	Align<B>: Alignment,
@@ -299,6 +1439,33 @@ where
 	fn addr_in_bounds(&self, addr: usize) -> bool {
 		self.1.addr_in_bounds(addr)
 	}
+
+	fn chain_aware(&self) -> bool {
+		true
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.acquire_locked().alloc_in_chain(layout, zeroed) }
+	}
+
+	unsafe fn realloc_in_chain(
+		&self,
+		ptr: *mut u8,
+		old_layout: Layout,
+		new_size: usize,
+	) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		unsafe {
+			self.acquire_locked()
+				.realloc_in_chain(ptr, old_layout, new_size)
+		}
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.dealloc(ptr, layout) };
+	}
 }
 
 impl<const L: usize, const B: usize> SyncStalloc<L, B>
@@ -312,4 +1479,36 @@ where
 	{
 		AllocChain::new(self, next)
 	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+
+	/// Creates a new `SyncStalloc` that falls back to the system allocator when it is full.
+	/// The result can be used directly as a `#[global_allocator]`.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{SyncStalloc, SystemFallback};
+	///
+	/// #[global_allocator]
+	/// static GLOBAL: SystemFallback<1024, 8> = SyncStalloc::with_system_fallback();
+	///
+	/// fn main() {
+	///     let small = vec![1, 2, 3];
+	///     let big = vec![0u8; 100_000];
+	/// }
+	/// ```
+	#[must_use]
+	pub const fn with_system_fallback() -> SystemFallback<L, B> {
+		OwnedAllocChain::new(Self::new(), System)
+	}
 }
+
+/// A `SyncStalloc` that falls back to the system allocator, as created by `SyncStalloc::with_system_fallback()`.
+pub type SystemFallback<const L: usize, const B: usize> =
+	OwnedAllocChain<SyncStalloc<L, B>, System>;
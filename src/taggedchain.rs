@@ -0,0 +1,327 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt::{self, Debug, Formatter};
+use core::ptr;
+
+use crate::{ChainableAlloc, precondition};
+
+/// The size of the tag that is stored before every allocation.
+const TAG_SIZE: usize = size_of::<usize>();
+
+/// The tag of an allocation served by the first allocator.
+const FIRST: usize = 0;
+
+/// The tag of an allocation served by the second allocator.
+const SECOND: usize = 1;
+
+/// Returns the layout of the allocation that holds `layout` behind its tag, along with the offset of the
+/// memory that is handed out. The offset only depends on the alignment, so it doesn't change on resizes.
+fn tagged_layout(layout: Layout) -> Option<(Layout, usize)> {
+	let offset = layout.align().max(TAG_SIZE);
+	let size = layout.size().checked_add(offset)?;
+	Some((Layout::from_size_align(size, offset).ok()?, offset))
+}
+
+/// Writes `tag` in front of the memory at `offset` in the allocation at `base`, and returns that memory.
+///
+/// # Safety
+///
+/// `base` must point to an allocation of at least `offset` bytes, aligned to `offset`.
+const unsafe fn write_tag(base: *mut u8, offset: usize, tag: usize) -> *mut u8 {
+	unsafe {
+		let ptr = base.add(offset);
+		ptr.sub(TAG_SIZE).cast::<usize>().write_unaligned(tag);
+		ptr
+	}
+}
+
+/// Reads the tag in front of `ptr`, which was handed out for `layout`, and returns it along with the
+/// allocation that holds `ptr`, its layout, and the offset of `ptr` in it.
+///
+/// # Safety
+///
+/// `ptr` must have been handed out by a `TaggedAllocChain` for `layout`.
+unsafe fn read_tag(ptr: *mut u8, layout: Layout) -> (usize, *mut u8, Layout, usize) {
+	// SAFETY: This succeeded when the allocation was made.
+	let (inner, offset) = unsafe { tagged_layout(layout).unwrap_unchecked() };
+	let tag = unsafe { ptr.sub(TAG_SIZE).cast::<usize>().read_unaligned() };
+	precondition!(
+		tag == FIRST || tag == SECOND,
+		"the pointer was not allocated by this TaggedAllocChain"
+	);
+	(tag, unsafe { ptr.sub(offset) }, inner, offset)
+}
+
+/// A chain of two allocators that records which one served each allocation, instead of asking the
+/// first allocator whether it contains a pointer.
+///
+/// Every allocation is preceded by a tag of one word, which is padded to the alignment of the allocation.
+/// `dealloc()` and `realloc()` read the tag, so they never ask the first allocator whether it contains a
+/// pointer, and zero-sized or dangling allocations can't be sent to the wrong allocator, since every
+/// allocation is a real one. In exchange, each allocation takes up more memory.
+///
+/// Through `GlobalAlloc`, the first allocator is used like the first link of an `AllocChain`, so it has to
+/// implement `ChainableAlloc`. Through `Allocator`, it can be any allocator.
+///
+/// # Examples
+/// ```
+/// use stalloc::{SyncStalloc, TaggedAllocChain};
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static GLOBAL: TaggedAllocChain<SyncStalloc<1024, 8>, System> =
+///     TaggedAllocChain::new(SyncStalloc::new(), System);
+///
+/// fn main() {
+///     let v = vec![1, 2, 3];
+///     let big = vec![0u8; 100_000];
+/// }
+/// ```
+pub struct TaggedAllocChain<A, B>(pub(crate) A, pub(crate) B);
+
+impl<A, B> TaggedAllocChain<A, B> {
+	/// Initializes a new `TaggedAllocChain`.
+	pub const fn new(a: A, b: B) -> Self {
+		Self(a, b)
+	}
+}
+
+impl<A: Debug, B: Debug> Debug for TaggedAllocChain<A, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TaggedAllocChain")
+			.field("first", &self.0)
+			.field("second", &self.1)
+			.finish()
+	}
+}
+
+impl<A: GlobalAlloc + ChainableAlloc, B: GlobalAlloc> TaggedAllocChain<A, B> {
+	/// Allocates from `A`, and falls back to `B`.
+	unsafe fn alloc_tagged(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		let Some((inner, offset)) = tagged_layout(layout) else {
+			return ptr::null_mut();
+		};
+
+		unsafe {
+			let base = if self.0.chain_aware() {
+				self.0.alloc_in_chain(inner, zeroed)
+			} else if zeroed {
+				self.0.alloc_zeroed(inner)
+			} else {
+				self.0.alloc(inner)
+			};
+			if !base.is_null() {
+				return write_tag(base, offset, FIRST);
+			}
+
+			let base = if zeroed {
+				self.1.alloc_zeroed(inner)
+			} else {
+				self.1.alloc(inner)
+			};
+			if base.is_null() {
+				return base;
+			}
+			write_tag(base, offset, SECOND)
+		}
+	}
+}
+
+unsafe impl<A: GlobalAlloc + ChainableAlloc, B: GlobalAlloc> GlobalAlloc
+	for TaggedAllocChain<A, B>
+{
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		unsafe { self.alloc_tagged(layout, false) }
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		unsafe { self.alloc_tagged(layout, true) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe {
+			let (tag, base, inner, _) = read_tag(ptr, layout);
+			if tag == FIRST {
+				self.0.dealloc(base, inner);
+			} else {
+				self.1.dealloc(base, inner);
+			}
+		}
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		// SAFETY: The caller guarantees that the new layout is valid.
+		let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+		let Some((new_inner, _)) = tagged_layout(new_layout) else {
+			return ptr::null_mut();
+		};
+
+		unsafe {
+			let (tag, base, inner, offset) = read_tag(ptr, layout);
+
+			// The tag is copied along with the allocation, so it doesn't need to be written again.
+			if tag == SECOND {
+				// Don't fall back to `A`.
+				let new_base = self.1.realloc(base, inner, new_inner.size());
+				return if new_base.is_null() {
+					new_base
+				} else {
+					new_base.add(offset)
+				};
+			}
+
+			let new_base = if self.0.chain_aware() {
+				self.0.realloc_in_chain(base, inner, new_inner.size())
+			} else {
+				self.0.realloc(base, inner, new_inner.size())
+			};
+			if !new_base.is_null() {
+				return new_base.add(offset);
+			}
+
+			// Move the allocation from `A` to `B`.
+			let new_base = self.1.alloc(new_inner);
+			if new_base.is_null() {
+				return new_base;
+			}
+			let new_ptr = write_tag(new_base, offset, SECOND);
+			ptr.copy_to_nonoverlapping(new_ptr, layout.size().min(new_size));
+			self.0.dealloc(base, inner);
+			new_ptr
+		}
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+impl<A, B> TaggedAllocChain<A, B>
+where
+	for<'a> &'a A: Allocator,
+	for<'a> &'a B: Allocator,
+{
+	/// Moves the allocation at `base` in `A` to `B`, resizing it from `old_layout` to `new_layout`.
+	unsafe fn move_to_second(
+		&self,
+		ptr: NonNull<u8>,
+		(base, inner, offset): (*mut u8, Layout, usize),
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		let (new_inner, _) = tagged_layout(new_layout).ok_or(AllocError)?;
+		let new_base = (&self.1).allocate(new_inner)?;
+
+		unsafe {
+			let new_ptr = write_tag(new_base.cast().as_ptr(), offset, SECOND);
+			ptr.as_ptr()
+				.copy_to_nonoverlapping(new_ptr, old_layout.size().min(new_layout.size()));
+			(&self.0).deallocate(NonNull::new_unchecked(base), inner);
+			Ok(NonNull::slice_from_raw_parts(
+				NonNull::new_unchecked(new_ptr),
+				new_base.len() - offset,
+			))
+		}
+	}
+
+	/// Grows or shrinks the allocation at `ptr` in the allocator that owns it. If `A` fails, the allocation
+	/// is moved to `B`.
+	unsafe fn resize_tagged(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+		grow: bool,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		let (new_inner, _) = tagged_layout(new_layout).ok_or(AllocError)?;
+
+		unsafe {
+			let (tag, base, inner, offset) = read_tag(ptr.as_ptr(), old_layout);
+			let owner: &dyn Allocator = if tag == FIRST { &&self.0 } else { &&self.1 };
+
+			// The tag is copied along with the allocation, so it doesn't need to be written again.
+			let base_ptr = NonNull::new_unchecked(base);
+			let res = if grow {
+				owner.grow(base_ptr, inner, new_inner)
+			} else {
+				owner.shrink(base_ptr, inner, new_inner)
+			};
+
+			match res {
+				Ok(new_base) => Ok(NonNull::slice_from_raw_parts(
+					new_base.cast().add(offset),
+					new_base.len() - offset,
+				)),
+				// Don't fall back to `A`.
+				Err(e) if tag == SECOND => Err(e),
+				Err(_) => self.move_to_second(ptr, (base, inner, offset), old_layout, new_layout),
+			}
+		}
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<A, B> Allocator for &TaggedAllocChain<A, B>
+where
+	for<'a> &'a A: Allocator,
+	for<'a> &'a B: Allocator,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		let (inner, offset) = tagged_layout(layout).ok_or(AllocError)?;
+
+		let (tag, base) = match (&self.0).allocate(inner) {
+			Ok(base) => (FIRST, base),
+			Err(_) => (SECOND, (&self.1).allocate(inner)?),
+		};
+
+		// SAFETY: `base` is valid for `inner`, which has room for the tag.
+		unsafe {
+			let ptr = write_tag(base.cast().as_ptr(), offset, tag);
+			Ok(NonNull::slice_from_raw_parts(
+				NonNull::new_unchecked(ptr),
+				base.len() - offset,
+			))
+		}
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		unsafe {
+			let (tag, base, inner, _) = read_tag(ptr.as_ptr(), layout);
+			let base = NonNull::new_unchecked(base);
+			if tag == FIRST {
+				(&self.0).deallocate(base, inner);
+			} else {
+				(&self.1).deallocate(base, inner);
+			}
+		}
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.resize_tagged(ptr, old_layout, new_layout, true) }
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.resize_tagged(ptr, old_layout, new_layout, false) }
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
@@ -0,0 +1,195 @@
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate `size` blocks, and labels the allocation with `tag`, for example to attribute it
+	/// to a subsystem. Note that `align` is measured in units of `B`. This requires the `tags` feature.
+	///
+	/// Allocations made in any other way have a tag of 0. The tag of an allocation is kept when it is
+	/// resized, split (both parts keep it), merged (the first allocation's tag is kept), or moved.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// const NETWORK: u16 = 1;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks_tagged(3, 1, NETWORK) }.unwrap();
+	/// assert_eq!(alloc.tag(ptr), Some(NETWORK));
+	/// ```
+	pub unsafe fn allocate_blocks_tagged(
+		&self,
+		size: usize,
+		align: usize,
+		tag: u16,
+	) -> Result<NonNull<u8>, AllocError> {
+		let ptr = unsafe { self.allocate_blocks(size, align) }?;
+		self.set_tag_at(self.block_index(ptr), tag);
+		Ok(ptr)
+	}
+
+	/// Tries to allocate memory for `layout`, and labels the allocation with `tag`.
+	/// See `allocate_blocks_tagged()` for details. This requires the `tags` feature.
+	///
+	/// Like `Allocator::allocate()`, a zero-sized allocation gives away a dangling pointer, which has no tag.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	/// use std::alloc::Layout;
+	///
+	/// const RENDERER: u16 = 2;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// alloc.allocate_tagged(Layout::new::<[u32; 4]>(), RENDERER).unwrap();
+	/// assert_eq!(alloc.tag_usage(RENDERER), 4);
+	/// ```
+	pub fn allocate_tagged(&self, layout: Layout, tag: u16) -> Result<NonNull<[u8]>, AllocError> {
+		// We can only allocate memory in units of `B`, so round up.
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// If `size` is zero, give away a dangling pointer.
+		if size == 0 {
+			// SAFETY: Alignment is always nonzero.
+			let dangling = unsafe { NonNull::new_unchecked(layout.align() as _) };
+			return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+		}
+
+		// SAFETY: We have made sure that `size` and `align` are valid.
+		unsafe { self.allocate_blocks_tagged(size, align, tag) }
+			.map(|p| NonNull::slice_from_raw_parts(p, size * B))
+	}
+
+	/// Returns the tag of the live allocation that starts at `ptr`, or `None` if there is none.
+	/// This requires the `tags` feature.
+	pub fn tag(&self, ptr: NonNull<u8>) -> Option<u16> {
+		self.allocation_size(ptr)
+			.map(|_| self.tag_at(self.block_index(ptr)))
+	}
+
+	/// Changes the tag of the live allocation that starts at `ptr`. Returns `false` (and does nothing)
+	/// if there is no such allocation. This requires the `tags` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(3, 1) }.unwrap();
+	/// assert_eq!(alloc.tag(ptr), Some(0));
+	///
+	/// assert!(alloc.set_tag(ptr, 7));
+	/// assert_eq!(alloc.tag_usage(7), 3);
+	/// ```
+	pub fn set_tag(&self, ptr: NonNull<u8>, tag: u16) -> bool {
+		let live = self.allocation_size(ptr).is_some();
+		if live {
+			self.set_tag_at(self.block_index(ptr), tag);
+		}
+		live
+	}
+
+	/// Returns the total number of blocks in the live allocations labeled with `tag`, not counting guard blocks.
+	/// This runs in O(L) and requires the `tags` feature.
+	pub fn tag_usage(&self, tag: u16) -> usize {
+		self.iter_allocations()
+			.filter(|&(idx, _)| self.tag_at(idx) == tag)
+			.map(|(_, length)| length)
+			.sum()
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Deallocates every live allocation labeled with `tag`, and returns how many there were.
+	/// This runs in O(L) and requires the `tags` feature.
+	///
+	/// Permanent allocations (see `leak_blocks()`) are never freed.
+	///
+	/// # Safety
+	///
+	/// Every pointer to an allocation labeled with `tag` is invalidated.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// const LEVEL_DATA: u16 = 3;
+	///
+	/// let alloc = Stalloc::<64, 4>::new();
+	///
+	/// for _ in 0..4 {
+	///     unsafe { alloc.allocate_blocks_tagged(4, 1, LEVEL_DATA) }.unwrap();
+	///     unsafe { alloc.allocate_blocks(2, 1) }.unwrap();
+	/// }
+	///
+	/// assert_eq!(unsafe { alloc.free_all_with_tag(LEVEL_DATA) }, 4);
+	/// assert_eq!(alloc.tag_usage(LEVEL_DATA), 0);
+	/// assert_eq!(alloc.tag_usage(0), 8);
+	/// ```
+	pub unsafe fn free_all_with_tag(&self, tag: u16) -> usize {
+		let mut freed = 0;
+
+		// The allocations are visited in order of increasing address, so every one of them comes after
+		// the free chunk that the previous one was released into. Freeing an allocation doesn't affect
+		// the iterator, which has already moved past it.
+		let mut start = self.base.get();
+		for (idx, length) in self.iter_allocations() {
+			if self.tag_at(idx) == tag {
+				unsafe {
+					let ptr = NonNull::new_unchecked(self.block_at(idx).cast());
+					start = self.free_allocation(start, ptr, length);
+				}
+				freed += 1;
+			}
+		}
+
+		freed
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns the index of the block that `ptr` points to.
+	fn block_index(&self, ptr: NonNull<u8>) -> usize {
+		(ptr.addr().get() - self.data.get().addr()) / B
+	}
+
+	/// Returns the tag of the allocation starting at `idx`.
+	pub(crate) fn tag_at(&self, idx: usize) -> u16 {
+		unsafe { (*self.tags.get())[idx] }
+	}
+
+	/// Sets the tag of the allocation starting at `idx`.
+	pub(crate) fn set_tag_at(&self, idx: usize, tag: u16) {
+		unsafe { (*self.tags.get())[idx] = tag };
+	}
+}
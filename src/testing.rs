@@ -0,0 +1,357 @@
+//! A randomized test driver for allocators. This requires the `testing` feature.
+//!
+//! A `Harness` performs a random sequence of allocations, deallocations, grows and shrinks on an allocator,
+//! and panics as soon as the allocator misbehaves. After every operation, it checks that:
+//! - every allocation is suitably aligned and doesn't overlap any other live allocation,
+//! - the contents of every allocation are preserved, including across grows and shrinks,
+//! - the allocator is empty again once everything has been freed.
+//!
+//! Allocation failures are allowed, so the allocator under test may run out of memory.
+//!
+//! # Examples
+//! ```
+//! use stalloc::SyncStalloc;
+//! use stalloc::testing::Harness;
+//!
+//! # if cfg!(feature = "abort-on-oom") { return; }
+//! let alloc = SyncStalloc::<256, 8>::new();
+//!
+//! let summary = Harness::new(42).steps(1000).run_global(&alloc, |a| a.is_empty());
+//! assert!(summary.allocations > 0);
+//! ```
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::NonNull;
+
+/// The maximum number of allocations that a `Harness` keeps alive at the same time.
+pub const MAX_LIVE_ALLOCATIONS: usize = 64;
+
+/// A randomized test driver for allocators. See the [module-level documentation](self) for details.
+#[derive(Clone, Copy, Debug)]
+pub struct Harness {
+	seed: u64,
+	steps: usize,
+	max_size: usize,
+	max_align: usize,
+}
+
+/// Counts of the operations that were performed by a `Harness`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Summary {
+	/// The number of successful allocations.
+	pub allocations: usize,
+	/// The number of allocations that failed.
+	pub failed_allocations: usize,
+	/// The number of successful grows.
+	pub grows: usize,
+	/// The number of successful shrinks.
+	pub shrinks: usize,
+	/// The number of grows or shrinks that failed.
+	pub failed_resizes: usize,
+	/// The number of deallocations.
+	pub deallocations: usize,
+}
+
+impl Harness {
+	/// Creates a harness that performs 1000 operations, with sizes of up to 256 bytes and alignments of
+	/// up to 16 bytes. The sequence of operations is fully determined by `seed`.
+	#[must_use]
+	pub const fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			steps: 1000,
+			max_size: 256,
+			max_align: 16,
+		}
+	}
+
+	/// Sets the number of operations to perform.
+	#[must_use]
+	pub const fn steps(mut self, steps: usize) -> Self {
+		self.steps = steps;
+		self
+	}
+
+	/// Sets the largest size of an allocation, in bytes.
+	///
+	/// # Panics
+	///
+	/// Panics if `max_size` is 0.
+	#[must_use]
+	pub const fn max_size(mut self, max_size: usize) -> Self {
+		assert!(max_size > 0, "maximum size must be nonzero");
+		self.max_size = max_size;
+		self
+	}
+
+	/// Sets the largest alignment of an allocation, in bytes.
+	///
+	/// # Panics
+	///
+	/// Panics if `max_align` is not a power of two.
+	#[must_use]
+	pub const fn max_align(mut self, max_align: usize) -> Self {
+		assert!(
+			max_align.is_power_of_two(),
+			"maximum alignment must be a power of two"
+		);
+		self.max_align = max_align;
+		self
+	}
+
+	/// Runs the harness on a `GlobalAlloc`. Once everything has been freed, `is_empty` is called to
+	/// check that the allocator is empty again; pass `|_| true` to skip this check.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator misbehaves.
+	pub fn run_global<A: GlobalAlloc>(
+		&self,
+		alloc: &A,
+		is_empty: impl FnOnce(&A) -> bool,
+	) -> Summary {
+		let summary = self.run(&Global(alloc));
+		assert!(
+			is_empty(alloc),
+			"allocator is not empty after freeing everything"
+		);
+		summary
+	}
+
+	/// Runs the harness on an `Allocator`. Once everything has been freed, `is_empty` is called to
+	/// check that the allocator is empty again; pass `|_| true` to skip this check.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocator misbehaves.
+	#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+	pub fn run_allocator<A: crate::Allocator>(
+		&self,
+		alloc: A,
+		is_empty: impl FnOnce(&A) -> bool,
+	) -> Summary {
+		let alloc = ByAllocator(alloc);
+		let summary = self.run(&alloc);
+		assert!(
+			is_empty(&alloc.0),
+			"allocator is not empty after freeing everything"
+		);
+		summary
+	}
+
+	fn run(&self, alloc: &impl Backend) -> Summary {
+		let mut rng = Rng(self.seed | 1);
+		let mut live = Live::new();
+		let mut summary = Summary::default();
+
+		for step in 0..self.steps {
+			// Fill every allocation with a different byte, so that overlaps and lost writes can be detected.
+			#[allow(clippy::cast_possible_truncation)]
+			let fill = step as u8;
+
+			match rng.below(4) {
+				0 | 1 if live.len < MAX_LIVE_ALLOCATIONS => {
+					let layout = self.random_layout(&mut rng, None);
+					match unsafe { alloc.allocate(layout) } {
+						Some(ptr) => {
+							live.insert(ptr, layout, fill);
+							summary.allocations += 1;
+						}
+						None => summary.failed_allocations += 1,
+					}
+				}
+				2 if live.len > 0 => {
+					let (ptr, layout) = live.remove(rng.below(live.len));
+					unsafe { alloc.deallocate(ptr, layout) };
+					summary.deallocations += 1;
+				}
+				_ if live.len > 0 => {
+					let i = rng.below(live.len);
+					let (ptr, old_layout, old_fill) = live.slots[i];
+					let new_layout = self.random_layout(&mut rng, Some(old_layout.align()));
+					check_contents(ptr, old_layout.size(), old_fill);
+
+					match unsafe { alloc.resize(ptr, old_layout, new_layout) } {
+						Some(new_ptr) => {
+							// The contents must have been moved over.
+							check_contents(
+								new_ptr,
+								old_layout.size().min(new_layout.size()),
+								old_fill,
+							);
+							live.forget(i);
+							live.insert(new_ptr, new_layout, fill);
+
+							if new_layout.size() >= old_layout.size() {
+								summary.grows += 1;
+							} else {
+								summary.shrinks += 1;
+							}
+						}
+						None => summary.failed_resizes += 1,
+					}
+				}
+				_ => {}
+			}
+		}
+
+		while live.len > 0 {
+			let (ptr, layout) = live.remove(live.len - 1);
+			unsafe { alloc.deallocate(ptr, layout) };
+			summary.deallocations += 1;
+		}
+
+		summary
+	}
+
+	/// Picks a random layout. If `align` is given, it is used instead of a random alignment.
+	fn random_layout(&self, rng: &mut Rng, align: Option<usize>) -> Layout {
+		let size = rng.below(self.max_size) + 1;
+		let align =
+			align.unwrap_or_else(|| 1 << rng.below(self.max_align.trailing_zeros() as usize + 1));
+		Layout::from_size_align(size, align).unwrap()
+	}
+}
+
+impl Default for Harness {
+	fn default() -> Self {
+		Self::new(0)
+	}
+}
+
+/// A xorshift64 random number generator.
+struct Rng(u64);
+
+impl Rng {
+	/// Returns a random number in `0..n`.
+	#[allow(clippy::cast_possible_truncation)]
+	const fn below(&mut self, n: usize) -> usize {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		(self.0 % n as u64) as usize
+	}
+}
+
+/// The live allocations, along with the byte that each one is filled with.
+struct Live {
+	slots: [(NonNull<u8>, Layout, u8); MAX_LIVE_ALLOCATIONS],
+	len: usize,
+}
+
+impl Live {
+	const fn new() -> Self {
+		Self {
+			slots: [(NonNull::dangling(), Layout::new::<u8>(), 0); MAX_LIVE_ALLOCATIONS],
+			len: 0,
+		}
+	}
+
+	/// Checks a new allocation against the live ones, then fills it with `fill` and records it.
+	/// If the allocation was resized, its old contents must have been preserved.
+	fn insert(&mut self, ptr: NonNull<u8>, layout: Layout, fill: u8) {
+		assert!(
+			ptr.addr().get().is_multiple_of(layout.align()),
+			"allocation at {ptr:p} is not aligned to {} bytes",
+			layout.align()
+		);
+
+		let start = ptr.addr().get();
+		for &(other, other_layout, _) in &self.slots[..self.len] {
+			let other_start = other.addr().get();
+			assert!(
+				start + layout.size() <= other_start || other_start + other_layout.size() <= start,
+				"allocation at {ptr:p} overlaps the allocation at {other:p}"
+			);
+		}
+
+		unsafe { ptr.write_bytes(fill, layout.size()) };
+		self.slots[self.len] = (ptr, layout, fill);
+		self.len += 1;
+	}
+
+	/// Checks that the allocation at index `i` still holds its contents, and forgets it.
+	fn remove(&mut self, i: usize) -> (NonNull<u8>, Layout) {
+		let (ptr, layout, fill) = self.slots[i];
+		check_contents(ptr, layout.size(), fill);
+		self.forget(i);
+		(ptr, layout)
+	}
+
+	/// Forgets the allocation at index `i`.
+	const fn forget(&mut self, i: usize) {
+		self.len -= 1;
+		self.slots.swap(i, self.len);
+	}
+}
+
+/// Checks that the first `size` bytes at `ptr` are all equal to `fill`.
+fn check_contents(ptr: NonNull<u8>, size: usize, fill: u8) {
+	let bytes = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), size) };
+	assert!(
+		bytes.iter().all(|&byte| byte == fill),
+		"the contents of the allocation at {ptr:p} were overwritten"
+	);
+}
+
+/// The operations that a `Harness` performs on an allocator.
+trait Backend {
+	unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+	/// `new_layout` always has the same alignment as `old_layout`.
+	unsafe fn resize(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Option<NonNull<u8>>;
+}
+
+struct Global<'a, A>(&'a A);
+
+impl<A: GlobalAlloc> Backend for Global<'_, A> {
+	unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+		NonNull::new(unsafe { self.0.alloc(layout) })
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		unsafe { self.0.dealloc(ptr.as_ptr(), layout) };
+	}
+
+	unsafe fn resize(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Option<NonNull<u8>> {
+		NonNull::new(unsafe { self.0.realloc(ptr.as_ptr(), old_layout, new_layout.size()) })
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+struct ByAllocator<A>(A);
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+impl<A: crate::Allocator> Backend for ByAllocator<A> {
+	unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+		self.0.allocate(layout).ok().map(NonNull::cast)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		unsafe { self.0.deallocate(ptr, layout) };
+	}
+
+	unsafe fn resize(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Option<NonNull<u8>> {
+		let res = if new_layout.size() >= old_layout.size() {
+			unsafe { self.0.grow(ptr, old_layout, new_layout) }
+		} else {
+			unsafe { self.0.shrink(ptr, old_layout, new_layout) }
+		};
+		res.ok().map(NonNull::cast)
+	}
+}
@@ -7,11 +7,29 @@ extern crate alloc;
 extern crate std;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
-use core::mem;
 use core::mem::MaybeUninit;
 #[allow(unused_imports)]
 use std::dbg;
 
+#[test]
+#[cfg(not(any(
+	feature = "stats",
+	feature = "canaries",
+	feature = "leak-check",
+	feature = "oom-handler",
+	feature = "abort-on-oom",
+	feature = "tracking",
+	feature = "quarantine",
+	feature = "free-cache",
+	feature = "randomize",
+	feature = "bounded-search",
+	feature = "hooks"
+)))]
+fn test_size() {
+	assert_eq!(size_of::<Stalloc<1000, 4>>(), 4008);
+	assert_eq!(size_of::<Stalloc<16, 16>>(), 272);
+}
+
 #[test]
 fn test_vec() {
 	let alloc = Stalloc::<1, 4>::new();
@@ -72,6 +90,27 @@ fn test_oom2() {
 	let _v: Vec<u32, _> = Vec::try_with_capacity_in(1, &alloc).unwrap();
 }
 
+#[test]
+#[cfg(not(feature = "quarantine"))]
+fn test_large_alloc_after_failure() {
+	let alloc = Stalloc::<12, 4>::new();
+	let ptrs: Vec<_> = (0..3)
+		.map(|_| unsafe { alloc.allocate_blocks(4, 1) }.unwrap())
+		.collect();
+
+	unsafe { alloc.deallocate_blocks(ptrs[1], 4) };
+	assert!(unsafe { alloc.allocate_blocks(8, 1) }.is_err());
+
+	// Freeing the first allocation creates a free chunk of 8 blocks.
+	unsafe { alloc.deallocate_blocks(ptrs[0], 4) };
+	assert_eq!(unsafe { alloc.allocate_blocks(8, 1) }, Ok(ptrs[0]));
+
+	unsafe {
+		alloc.deallocate_blocks(ptrs[0], 8);
+		alloc.deallocate_blocks(ptrs[2], 4);
+	}
+}
+
 #[test]
 fn test_free() {
 	let alloc = Stalloc::<4, 4>::new();
@@ -234,11 +273,9 @@ fn test_simple_push() {
 fn test_boxes() {
 	let alloc = Stalloc::<128, 4>::new();
 
-	for _ in 0..128 {
-		let b = Box::new_in(42, &alloc);
-		mem::forget(b);
-	}
+	let boxes: Vec<_> = (0..128).map(|_| Box::new_in(42, &alloc)).collect();
 	assert!(alloc.is_oom());
+	drop(boxes);
 }
 
 #[test]
@@ -384,13 +421,16 @@ fn test_boxes_vec_grow() {
 fn test_multiple_shrink() {
 	let alloc = Stalloc::<24, 4>::new();
 
-	for i in 0..24 {
-		let mut v: Vec<i32, _> = Vec::with_capacity_in(24 - i, &alloc);
-		v.shrink_to(1);
-		mem::forget(v);
-	}
+	let vecs: Vec<_> = (0..24)
+		.map(|i| {
+			let mut v: Vec<i32, _> = Vec::with_capacity_in(24 - i, &alloc);
+			v.shrink_to(1);
+			v
+		})
+		.collect();
 
 	assert!(alloc.is_oom());
+	drop(vecs);
 }
 
 #[test]
@@ -420,3 +460,1928 @@ fn test34() {
 	let _a = Stalloc::<34, 4>::new();
 	let _b = crate::SyncStalloc::<34, 4>::new();
 }
+
+#[test]
+#[cfg(feature = "tracking")]
+fn test_tracking() {
+	let alloc = Stalloc::<16, 4>::new();
+
+	let mut v1: Vec<u32, _> = Vec::with_capacity_in(2, &alloc);
+	let v2: Vec<u32, _> = Vec::with_capacity_in(3, &alloc);
+	let v3: Vec<u32, _> = Vec::with_capacity_in(4, &alloc);
+	assert!(alloc.iter_allocations().eq([(0, 2), (2, 3), (5, 4)]));
+
+	drop(v2);
+	#[cfg(feature = "quarantine")]
+	alloc.flush_quarantine();
+	#[cfg(feature = "free-cache")]
+	alloc.flush_free_cache();
+	v1.reserve_exact(5);
+	assert!(alloc.iter_allocations().eq([(0, 5), (5, 4)]));
+
+	v1.shrink_to(1);
+	assert!(alloc.iter_allocations().eq([(0, 1), (5, 4)]));
+	assert!(alloc.is_allocated(core::ptr::NonNull::new(v3.as_ptr().cast_mut().cast()).unwrap()));
+
+	drop(v1);
+	drop(v3);
+	assert_eq!(alloc.iter_allocations().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "stats")]
+fn test_stats() {
+	let alloc = Stalloc::<16, 4>::new();
+
+	let mut v1: Vec<u32, _> = Vec::with_capacity_in(2, &alloc);
+	let v2: Vec<u32, _> = Vec::with_capacity_in(2, &alloc);
+	v1.reserve_exact(4); // must move
+	drop(v2);
+	v1.reserve_exact(8); // grows in place
+	v1.shrink_to(1);
+	assert!(Vec::<u32, _>::try_with_capacity_in(16, &alloc).is_err());
+	drop(v1);
+
+	let stats = alloc.stats();
+	assert_eq!(stats.allocations, 3);
+	assert_eq!(stats.deallocations, 3);
+	assert_eq!(stats.reallocating_grows, 1);
+	assert_eq!(stats.grows_in_place, 1);
+	assert_eq!(stats.shrinks, 1);
+	assert_eq!(stats.failed_allocations, 1);
+	assert_eq!(stats.used_blocks, 0);
+	assert_eq!(stats.peak_used_blocks, 8);
+	assert_eq!(alloc.report().peak_used_blocks, Some(8));
+}
+
+#[test]
+#[cfg(feature = "stats")]
+fn test_stats_failed_allocate_blocks_at() {
+	let alloc = Stalloc::<16, 4>::new();
+
+	let ptr = alloc.allocate_blocks_at(4, 4).unwrap();
+	assert!(alloc.allocate_blocks_at(6, 2).is_err());
+	assert_eq!(alloc.stats().failed_allocations, 1);
+
+	// Out-of-bounds requests are not counted.
+	assert!(alloc.allocate_blocks_at(15, 2).is_err());
+	assert_eq!(alloc.stats().failed_allocations, 1);
+
+	unsafe { alloc.deallocate_blocks(ptr, 4) };
+}
+
+#[test]
+#[cfg(not(any(
+	feature = "quarantine",
+	feature = "free-cache",
+	all(feature = "strict-checks", feature = "tracking")
+)))]
+fn test_validate_detects_double_free() {
+	let alloc = Stalloc::<12, 4>::new();
+
+	unsafe {
+		let a = alloc.allocate_blocks(4, 1).unwrap();
+		let b = alloc.allocate_blocks(4, 1).unwrap();
+		let _c = alloc.allocate_blocks(4, 1).unwrap();
+		assert_eq!(alloc.validate(), Ok(()));
+
+		alloc.deallocate_blocks(b, 4);
+		alloc.deallocate_blocks(a, 4);
+		assert_eq!(alloc.validate(), Ok(()));
+
+		// Freeing `b` again corrupts the free list.
+		alloc.deallocate_blocks(b, 4);
+		assert!(alloc.validate().is_err());
+	}
+}
+
+#[test]
+#[cfg(feature = "strict-checks")]
+#[should_panic(expected = "safety precondition violated")]
+fn test_strict_checks() {
+	let alloc = Stalloc::<12, 4>::new();
+	let _ = unsafe { alloc.allocate_blocks(0, 1) };
+}
+
+#[test]
+#[cfg(feature = "poison-check")]
+fn test_poison_check_reuse() {
+	let alloc = Stalloc::<64, 4>::new();
+
+	// Merging, splitting and resizing chunks must never leave unpoisoned bytes behind.
+	let mut vecs: Vec<Vec<u32, _>> = Vec::new();
+	for i in 0..8 {
+		let mut v = Vec::with_capacity_in(i % 3 + 1, &alloc);
+		v.extend(0..i as u32);
+		vecs.push(v);
+	}
+	for i in (0..8).step_by(2) {
+		vecs[i] = Vec::new_in(&alloc);
+	}
+	vecs[1].shrink_to_fit();
+	vecs[3].reserve_exact(5);
+	drop(vecs);
+
+	let size = 64;
+	let ptr = unsafe { alloc.allocate_blocks(size, 1) }.unwrap();
+	unsafe { alloc.deallocate_blocks(ptr, size) };
+}
+
+#[test]
+#[cfg(feature = "poison-check")]
+#[should_panic(expected = "written to after being freed")]
+fn test_poison_check_use_after_free() {
+	let alloc = Stalloc::<12, 4>::new();
+
+	unsafe {
+		let ptr = alloc.allocate_blocks(4, 1).unwrap();
+		alloc.deallocate_blocks(ptr, 4);
+		ptr.add(8).write(1);
+		#[cfg(feature = "quarantine")]
+		alloc.flush_quarantine();
+		let _ = alloc.allocate_blocks(4, 1);
+	}
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn test_random_operations() {
+	use core::ptr::NonNull;
+
+	let alloc = Stalloc::<200, 8>::new();
+	let mut live: Vec<(NonNull<u8>, usize)> = Vec::new();
+
+	#[cfg(feature = "randomize")]
+	alloc.set_seed(0x9e37_79b9_7f4a_7c15);
+
+	// A simple xorshift generator, so that the test is deterministic.
+	let mut state = 12345u64;
+	let mut random = |n: usize| {
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		(state % n as u64) as usize
+	};
+
+	for _ in 0..10000 {
+		unsafe {
+			match random(13) {
+				0 | 1 => {
+					let size = random(12) + 1;
+					let align = 1 << random(3);
+					if let Ok(ptr) = alloc.allocate_blocks(size, align) {
+						assert!(ptr.addr().get().is_multiple_of(align * 8));
+						ptr.write_bytes(42, size * 8);
+						live.push((ptr, size));
+					}
+				}
+				2 if !live.is_empty() => {
+					let (ptr, size) = live.swap_remove(random(live.len()));
+					alloc.deallocate_blocks(ptr, size);
+				}
+				3 if !live.is_empty() => {
+					let i = random(live.len());
+					let (ptr, size) = live[i];
+					let new_size = size + random(5) + 1;
+					if alloc.grow_in_place(ptr, size, new_size).is_ok() {
+						ptr.write_bytes(42, new_size * 8);
+						live[i].1 = new_size;
+					}
+				}
+				4 if !live.is_empty() => {
+					let i = random(live.len());
+					let (ptr, size) = live[i];
+					if size > 1 {
+						let new_size = random(size - 1) + 1;
+						alloc.shrink_in_place(ptr, size, new_size);
+						live[i].1 = new_size;
+					}
+				}
+				12 if !live.is_empty() => {
+					let i = random(live.len());
+					let (ptr, size) = live[i];
+					if size > 1 {
+						let new_size = random(size - 1) + 1;
+						let new = alloc.shrink_in_place_front(ptr, size, new_size);
+						assert_eq!(new, ptr.add((size - new_size) * 8));
+						live[i] = (new, new_size);
+					}
+				}
+				11 if !live.is_empty() => {
+					let i = random(live.len());
+					let (ptr, size) = live[i];
+					let new_size = size + random(8) + 1;
+					let align = 1 << random(3);
+					ptr.write_bytes(7, size * 8);
+					if let Ok(new) = alloc.grow_in_place_front(ptr, size, new_size, align) {
+						assert!(new.addr().get().is_multiple_of(align * 8));
+						assert!((0..size * 8).all(|j| new.add(j).read() == 7));
+						new.write_bytes(42, new_size * 8);
+						live[i] = (new, new_size);
+					}
+				}
+				10 if live.len() >= 2 => {
+					// Merge an allocation with the one right after it, if there is one.
+					let i = random(live.len());
+					let (ptr, size) = live[i];
+					let next = ptr.as_ptr().wrapping_add(size * 8);
+					if let Some(j) = live.iter().position(|&(p, _)| p.as_ptr() == next) {
+						let (next, next_size) = live.swap_remove(j);
+						let i = live.iter().position(|&(p, _)| p == ptr).unwrap();
+						live[i].1 = alloc.merge_allocations(ptr, size, next, next_size);
+					}
+				}
+				9 if !live.is_empty() => {
+					let i = random(live.len());
+					let (ptr, size) = live[i];
+					if size > 1 {
+						let at = random(size - 1) + 1;
+						let (first, second) = alloc.split_allocation(ptr, size, at);
+						live[i] = (first, at);
+						live.push((second, size - at));
+					}
+				}
+				8 => {
+					let size = random(8) + 1;
+					let align = 1 << random(3);
+					let boundary = 64 << random(3);
+					let constraint = crate::AddrConstraint::new().no_crossing(boundary);
+					if let Ok(ptr) = alloc.allocate_blocks_constrained(size, align, constraint) {
+						let start = ptr.addr().get();
+						assert!(start.is_multiple_of(align * 8));
+						assert_eq!(start / boundary, (start + size * 8 - 1) / boundary);
+						ptr.write_bytes(42, size * 8);
+						live.push((ptr, size));
+					}
+				}
+				7 => {
+					let size = random(8) + 1;
+					if let Ok(ptr) = alloc.allocate_blocks_at(random(200), size) {
+						ptr.write_bytes(42, size * 8);
+						live.push((ptr, size));
+					}
+				}
+				6 if live.len() >= 3 => {
+					let mut allocs: Vec<_> = (0..3)
+						.map(|_| live.swap_remove(random(live.len())))
+						.collect();
+					alloc.deallocate_many(&mut allocs);
+				}
+				5 => {
+					let sizes = [random(6) + 1, random(6) + 1, random(6) + 1];
+					let align = 1 << random(3);
+					let mut out = [MaybeUninit::uninit(); 3];
+					if let Ok(ptrs) = alloc.allocate_many(&sizes, align, &mut out) {
+						for (&ptr, &size) in ptrs.iter().zip(&sizes) {
+							assert!(ptr.addr().get().is_multiple_of(align * 8));
+							ptr.write_bytes(42, size * 8);
+							live.push((ptr, size));
+						}
+					}
+				}
+				_ => {}
+			}
+		}
+
+		// Every block is either free, quarantined, cached, or part of a live allocation.
+		let used: usize = live.iter().map(|&(_, size)| size).sum();
+		let report = alloc.report();
+		assert_eq!(alloc.validate(), Ok(()));
+		assert_eq!(report.used_blocks, used);
+		assert_eq!(report.free_blocks + report.held_blocks + used, 200);
+		assert_eq!(alloc.live_allocations(), live.len());
+		assert_eq!(alloc.is_empty(), live.is_empty());
+	}
+
+	for (ptr, size) in live {
+		unsafe { alloc.deallocate_blocks(ptr, size) };
+	}
+	#[cfg(feature = "quarantine")]
+	alloc.flush_quarantine();
+	#[cfg(feature = "free-cache")]
+	alloc.flush_free_cache();
+	assert_eq!(alloc.report().free_blocks, 200);
+	assert!(alloc.try_clear());
+}
+
+#[test]
+#[cfg(feature = "canaries")]
+#[should_panic(expected = "guard blocks of the allocation at index 1 were overwritten")]
+fn test_canaries_overflow() {
+	let alloc = Stalloc::<16, 4>::new().with_canaries();
+
+	let mut v: Vec<u8, _> = Vec::with_capacity_in(8, &alloc);
+	v.extend_from_slice(b"overflow");
+	assert_eq!(alloc.check_canaries(), Ok(()));
+
+	// Write one byte past the end of the buffer.
+	unsafe { v.as_mut_ptr().add(8).write(0) };
+	assert!(alloc.check_canaries().is_err());
+	drop(v);
+}
+
+#[test]
+#[cfg(feature = "quarantine")]
+fn test_quarantine() {
+	use crate::QUARANTINE_CAPACITY;
+
+	let alloc = Stalloc::<64, 4>::new();
+
+	// Freed blocks are only released once the quarantine is full.
+	let boxes: Vec<_> = (0..=QUARANTINE_CAPACITY)
+		.map(|i| Box::new_in(i as u32, &alloc))
+		.collect();
+	drop(boxes);
+	assert_eq!(alloc.quarantined_blocks(), QUARANTINE_CAPACITY);
+
+	// The quarantine is flushed before an allocation fails.
+	let v: Vec<u32, _> = Vec::with_capacity_in(64, &alloc);
+	assert_eq!(alloc.quarantined_blocks(), 0);
+	drop(v);
+}
+
+#[test]
+#[cfg(all(feature = "bounded-search", feature = "quarantine"))]
+fn test_bounded_search_flushes_quarantine() {
+	let alloc = Stalloc::<12, 4>::new();
+
+	unsafe {
+		let ptrs: Vec<_> = [1, 1, 1, 1, 3, 5]
+			.into_iter()
+			.map(|size| alloc.allocate_blocks(size, 1).unwrap())
+			.collect();
+
+		// Leave free chunks of 1, 1 and 3 blocks, and quarantine the block between the first two.
+		alloc.deallocate_blocks(ptrs[0], 1);
+		alloc.deallocate_blocks(ptrs[2], 1);
+		alloc.deallocate_blocks(ptrs[4], 3);
+		alloc.flush_quarantine();
+		alloc.deallocate_blocks(ptrs[1], 1);
+
+		// The first free chunk is too small, but releasing the quarantine makes it large enough.
+		alloc.set_search_limit(core::num::NonZeroUsize::new(1));
+		assert_eq!(alloc.allocate_blocks(3, 1), Ok(ptrs[0]));
+
+		alloc.deallocate_blocks(ptrs[0], 3);
+		alloc.deallocate_blocks(ptrs[3], 1);
+		alloc.deallocate_blocks(ptrs[5], 5);
+	}
+}
+
+#[test]
+#[cfg(all(feature = "free-cache", not(feature = "quarantine")))]
+fn test_free_cache() {
+	use crate::FREE_CACHE_CAPACITY;
+
+	let alloc = Stalloc::<64, 4>::new();
+
+	// Only the most recent deallocations are cached.
+	let boxes: Vec<_> = (0..=FREE_CACHE_CAPACITY)
+		.map(|i| Box::new_in(i as u32, &alloc))
+		.collect();
+	drop(boxes);
+	assert_eq!(alloc.cached_blocks(), FREE_CACHE_CAPACITY);
+	assert_eq!(alloc.report().held_blocks, alloc.cached_blocks());
+	assert!(alloc.is_empty());
+
+	// The cache is flushed before an allocation fails.
+	let v: Vec<u32, _> = Vec::with_capacity_in(64, &alloc);
+	assert_eq!(alloc.cached_blocks(), 0);
+	drop(v);
+
+	// Lowering the depth releases the oldest cached regions.
+	alloc.set_free_cache_depth(0);
+	assert_eq!(alloc.cached_blocks(), 0);
+	assert!(alloc.is_empty());
+}
+
+#[test]
+#[cfg(all(feature = "free-cache", feature = "quarantine"))]
+fn test_free_cache_after_quarantine() {
+	use crate::QUARANTINE_CAPACITY;
+
+	let alloc = Stalloc::<{ QUARANTINE_CAPACITY + 1 }, 4>::new();
+
+	unsafe {
+		let ptrs: Vec<_> = (0..=QUARANTINE_CAPACITY)
+			.map(|_| alloc.allocate_blocks(1, 1).unwrap())
+			.collect();
+		for &ptr in &ptrs {
+			alloc.deallocate_blocks(ptr, 1);
+		}
+
+		// The oldest deallocation left the quarantine, and went to the cache instead of the free list.
+		assert_eq!(alloc.quarantined_blocks(), QUARANTINE_CAPACITY);
+		assert_eq!(alloc.cached_blocks(), 1);
+		assert_eq!(alloc.allocate_blocks(1, 1), Ok(ptrs[0]));
+		assert_eq!(alloc.cached_blocks(), 0);
+
+		alloc.deallocate_blocks(ptrs[0], 1);
+	}
+}
+
+#[test]
+#[cfg(all(feature = "zero-on-free", not(feature = "poison")))]
+fn test_zero_on_free() {
+	let alloc = Stalloc::<16, 4>::new();
+
+	unsafe {
+		let ptr = alloc.allocate_blocks(4, 1).unwrap();
+		ptr.write_bytes(0xff, 16);
+		let _keep = alloc.allocate_blocks(4, 1).unwrap();
+
+		// Everything except the header of the new free chunk is wiped.
+		alloc.deallocate_blocks(ptr, 4);
+		let bytes = core::slice::from_raw_parts(ptr.as_ptr(), 16);
+		assert!(bytes[4..].iter().all(|&b| b == 0));
+
+		let ptr = alloc.allocate_blocks(4, 1).unwrap();
+		ptr.write_bytes(0xff, 16);
+		alloc.clear();
+		let bytes = core::slice::from_raw_parts(ptr.as_ptr(), 16);
+		assert!(bytes[4..].iter().all(|&b| b == 0));
+	}
+}
+
+#[test]
+fn test_allocate_blocks_zeroed() {
+	let alloc = Stalloc::<32, 4>::new();
+
+	unsafe {
+		// Leave free chunk headers and garbage all over the buffer.
+		let ptrs: Vec<_> = (0..4)
+			.map(|_| alloc.allocate_blocks(4, 1).unwrap())
+			.collect();
+		for &ptr in &ptrs {
+			ptr.write_bytes(0xff, 16);
+		}
+		for &ptr in ptrs.iter().rev() {
+			alloc.deallocate_blocks(ptr, 4);
+		}
+
+		let ptr = alloc.allocate_blocks_zeroed(16, 1).unwrap();
+		let bytes = core::slice::from_raw_parts(ptr.as_ptr(), 64);
+		assert!(bytes.iter().all(|&b| b == 0));
+		alloc.deallocate_blocks(ptr, 16);
+	}
+}
+
+#[test]
+#[cfg(not(feature = "quarantine"))]
+fn test_grow_zeroed_in_place() {
+	let alloc = Stalloc::<32, 4>::new();
+
+	unsafe {
+		let ptr = alloc.allocate_blocks(4, 1).unwrap();
+		let garbage: Vec<_> = (0..3)
+			.map(|_| alloc.allocate_blocks(4, 1).unwrap())
+			.collect();
+		ptr.write_bytes(0xff, 16);
+		for &p in &garbage {
+			p.write_bytes(0xff, 16);
+			alloc.deallocate_blocks(p, 4);
+		}
+		#[cfg(feature = "free-cache")]
+		alloc.flush_free_cache();
+
+		alloc.grow_zeroed_in_place(ptr, 4, 16).unwrap();
+		let bytes = core::slice::from_raw_parts(ptr.as_ptr(), 64);
+		assert!(bytes[..16].iter().all(|&b| b == 0xff));
+		assert!(bytes[16..].iter().all(|&b| b == 0));
+		alloc.deallocate_blocks(ptr, 16);
+	}
+}
+
+#[test]
+fn test_with_reserved() {
+	for (idx, size) in [(0, 4), (4, 4), (12, 4), (0, 16)] {
+		let alloc = Stalloc::<16, 4>::with_reserved(idx, size);
+		assert_eq!(alloc.validate(), Ok(()));
+		assert_eq!(alloc.report().free_blocks, 16 - size);
+		assert!(alloc.reserve_blocks(idx, 1).is_err());
+
+		// The reserved blocks are not released by `try_clear()`.
+		assert!(!alloc.try_clear());
+		assert!(alloc.reserve_blocks(idx, 1).is_err());
+
+		unsafe { alloc.release_reserved(idx, size) };
+		assert_eq!(alloc.validate(), Ok(()));
+		assert!(alloc.is_empty());
+	}
+}
+
+#[test]
+fn test_run_scope_keeps_reserved() {
+	let alloc = Stalloc::<16, 4>::new();
+	alloc.reserve_blocks(4, 4).unwrap();
+
+	let result = alloc.run_scope(|| {
+		let ptr = unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+		unsafe { alloc.deallocate_blocks(ptr, 4) };
+	});
+	assert_eq!(result, Ok(()));
+
+	// The reserved blocks are still unavailable.
+	assert!(!alloc.is_empty());
+	assert!(alloc.reserve_blocks(4, 1).is_err());
+	unsafe { alloc.release_reserved(4, 4) };
+	assert!(alloc.is_empty());
+}
+
+#[test]
+#[cfg(feature = "fault-injection")]
+fn test_faulty_alloc() {
+	use crate::FaultyAlloc;
+
+	let alloc = FaultyAlloc::every_nth(Stalloc::<64, 4>::new(), 2);
+
+	let mut v: Vec<u32, _> = Vec::new_in(&alloc);
+	v.try_reserve_exact(4).unwrap();
+	assert!(v.try_reserve_exact(8).is_err()); // the second request fails
+	v.try_reserve_exact(8).unwrap();
+	assert_eq!(alloc.request_count(), 3);
+}
+
+#[cfg(feature = "testing")]
+mod harness {
+	use super::std::alloc::System;
+	use crate::testing::Harness;
+	use crate::{Stalloc, SyncStalloc};
+
+	/// Checks whether `alloc` is empty, after releasing any quarantined or cached blocks.
+	fn is_empty<const L: usize, const B: usize>(alloc: &Stalloc<L, B>) -> bool
+	where
+		crate::Align<B>: crate::Alignment,
+	{
+		#[cfg(feature = "quarantine")]
+		alloc.flush_quarantine();
+		#[cfg(feature = "free-cache")]
+		alloc.flush_free_cache();
+		alloc.is_empty()
+	}
+
+	#[test]
+	fn stalloc() {
+		let alloc = Stalloc::<512, 8>::new();
+		for seed in 0..8 {
+			let summary = Harness::new(seed).run_allocator(&alloc, |a| is_empty(a));
+			assert!(summary.failed_allocations > 0);
+		}
+	}
+
+	#[test]
+	fn sync_stalloc() {
+		let alloc = SyncStalloc::<512, 4>::new();
+		#[cfg(not(feature = "abort-on-oom"))]
+		Harness::new(1)
+			.max_align(64)
+			.run_global(&alloc, |a| is_empty(&a.acquire_locked()));
+		Harness::new(2).run_allocator(&alloc, |a| is_empty(&a.acquire_locked()));
+	}
+
+	#[test]
+	#[cfg(not(feature = "abort-on-oom"))]
+	fn unsafe_stalloc() {
+		use crate::UnsafeStalloc;
+
+		let alloc = unsafe { UnsafeStalloc::<512, 16>::new() };
+		Harness::new(3).run_global(&alloc, |a| is_empty(a));
+	}
+
+	#[test]
+	fn chain() {
+		let alloc = SyncStalloc::<64, 8>::new().chain(&System);
+		let summary = Harness::new(4).run_global(&alloc, |_| true);
+		assert_eq!(summary.failed_allocations, 0);
+	}
+}
+
+#[test]
+#[cfg(all(feature = "shadow", feature = "testing"))]
+fn test_shadow() {
+	use crate::testing::Harness;
+	use crate::{Shadow, SyncStalloc};
+
+	let alloc = Shadow::<_, 64>::new(SyncStalloc::<256, 4>::new());
+	for seed in 0..8 {
+		#[cfg(not(feature = "abort-on-oom"))]
+		Harness::new(seed)
+			.max_align(64)
+			.run_global(&alloc, |a| a.live_allocations() == 0);
+		Harness::new(seed).run_allocator(&alloc, |a| a.live_allocations() == 0);
+	}
+}
+
+#[test]
+#[cfg(feature = "shadow")]
+#[should_panic = "is not a live allocation"]
+fn test_shadow_double_free() {
+	use crate::{Shadow, Stalloc};
+	use core::alloc::{Allocator, Layout};
+
+	let alloc = Shadow::<_, 8>::new(Stalloc::<16, 4>::new());
+	let layout = Layout::new::<u32>();
+	let ptr = (&alloc).allocate(layout).unwrap().cast::<u8>();
+	unsafe {
+		(&alloc).deallocate(ptr, layout);
+		(&alloc).deallocate(ptr, layout);
+	}
+}
+
+#[test]
+#[cfg(feature = "tracking")]
+fn test_compact() {
+	use core::ptr::NonNull;
+
+	let alloc = Stalloc::<256, 4>::new();
+	let mut live: Vec<(NonNull<u8>, usize, u8)> = Vec::new();
+
+	unsafe {
+		for i in 0..40u8 {
+			let size = usize::from(i % 5) + 1;
+			let align = 1 << (i % 3);
+			let ptr = alloc.allocate_blocks(size, align).unwrap();
+			ptr.write_bytes(i, size * 4);
+			live.push((ptr, size, i));
+		}
+
+		// Free every other allocation to fragment the buffer.
+		let mut i = 0;
+		live.retain(|&(ptr, size, _)| {
+			i += 1;
+			if i % 2 == 0 {
+				alloc.deallocate_blocks(ptr, size);
+			}
+			i % 2 == 1
+		});
+		let before = alloc.free_chunks().count();
+
+		alloc.compact(|old, new, size| {
+			let entry = live.iter_mut().find(|e| e.0 == old).unwrap();
+			assert_eq!(entry.1, size);
+			assert!(new < old);
+			entry.0 = new;
+		});
+
+		assert!(alloc.free_chunks().count() < before);
+		for &(ptr, size, byte) in &live {
+			assert!(alloc.is_allocated(ptr));
+			assert!((0..size * 4).all(|i| ptr.add(i).read() == byte));
+		}
+
+		for (ptr, size, _) in live {
+			alloc.deallocate_blocks(ptr, size);
+		}
+	}
+
+	#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+	assert!(alloc.is_empty());
+}
+
+#[test]
+fn test_substalloc() {
+	use core::ptr::NonNull;
+
+	let parent = Stalloc::<128, 4>::new();
+	let sub = parent.carve(64).unwrap();
+	let mut live: Vec<(NonNull<u8>, usize)> = Vec::new();
+
+	let mut state = 987_654_321u64;
+	let mut random = |n: usize| {
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		(state % n as u64) as usize
+	};
+
+	for _ in 0..5000 {
+		unsafe {
+			if random(2) == 0 {
+				let size = random(8) + 1;
+				let align = 1 << random(3);
+				if let Ok(ptr) = sub.allocate_blocks(size, align) {
+					assert!(ptr.addr().get().is_multiple_of(align * 4));
+					ptr.write_bytes(42, size * 4);
+					live.push((ptr, size));
+				}
+			} else if !live.is_empty() {
+				let (ptr, size) = live.swap_remove(random(live.len()));
+				sub.deallocate_blocks(ptr, size);
+			}
+		}
+	}
+
+	for (ptr, size) in live {
+		unsafe { sub.deallocate_blocks(ptr, size) };
+	}
+	assert!(sub.is_empty());
+
+	drop(sub);
+	#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+	assert!(parent.is_empty());
+}
+
+#[test]
+#[cfg(feature = "tags")]
+fn test_tags() {
+	let alloc = Stalloc::<64, 4>::new();
+
+	unsafe {
+		let a = alloc.allocate_blocks_tagged(4, 1, 1).unwrap();
+		let b = alloc.allocate_blocks_tagged(8, 1, 2).unwrap();
+		let c = alloc.allocate_blocks(2, 1).unwrap();
+		assert_eq!(alloc.tag_usage(1), 4);
+		assert_eq!(alloc.tag_usage(2), 8);
+		assert_eq!(alloc.tag(c), Some(0));
+
+		// Both halves of a split keep the tag.
+		let (b1, b2) = alloc.split_allocation(b, 8, 3);
+		assert_eq!(alloc.tag(b1), Some(2));
+		assert_eq!(alloc.tag(b2), Some(2));
+
+		// Moving an allocation keeps its tag.
+		let a = alloc.reallocate_blocks(a, 4, 20, 1).unwrap();
+		assert_eq!(alloc.tag(a), Some(1));
+		assert_eq!(alloc.tag_usage(1), 20);
+
+		alloc.deallocate_blocks(a, 20);
+		assert_eq!(alloc.tag_usage(1), 0);
+		assert_eq!(alloc.tag(a), None);
+
+		// A new allocation in the same place starts out untagged.
+		let d = alloc.allocate_blocks(4, 1).unwrap();
+		assert_eq!(alloc.tag(d), Some(0));
+
+		assert_eq!(alloc.free_all_with_tag(2), 2);
+		assert_eq!(alloc.free_all_with_tag(0), 2);
+		assert_eq!(alloc.validate(), Ok(()));
+	}
+
+	#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+	assert!(alloc.is_empty());
+}
+
+#[test]
+fn test_arena_writer() {
+	use crate::ArenaWriter;
+	use core::fmt::Write;
+
+	let alloc = Stalloc::<32, 4>::new();
+
+	// Many small writes grow the buffer until the allocator is full.
+	let mut w = ArenaWriter::new(&alloc);
+	for i in 0..16 {
+		write!(w, "{i:x}").unwrap();
+	}
+	assert_eq!(w.as_str(), "0123456789abcdef");
+	assert!(write!(w, "{}", "x".repeat(200)).is_err());
+
+	// Finishing shrinks the buffer, so the rest of the allocator is usable again.
+	let s = w.finish();
+	assert_eq!(s, "0123456789abcdef");
+	let ptr = unsafe { alloc.allocate_blocks(20, 1) }.unwrap();
+	unsafe {
+		alloc.deallocate_blocks(ptr, 20);
+		alloc.deallocate_blocks(core::ptr::NonNull::from(s).cast(), 4);
+	}
+
+	// An unfinished writer frees its buffer.
+	let alloc = Stalloc::<8, 4>::new();
+	let mut w = ArenaWriter::new(&alloc);
+	w.write_str("hello").unwrap();
+	drop(w);
+	#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+	assert!(alloc.is_empty());
+}
+
+#[test]
+fn test_collections() {
+	use crate::collections::{Box, RingBuffer, SmallVec, String, Vec};
+	use core::fmt::Write;
+
+	let alloc = Stalloc::<64, 4>::new();
+
+	{
+		let mut v = Vec::new_in(&alloc);
+		for i in 0..32u32 {
+			v.push(i);
+		}
+		assert_eq!(v.len(), 32);
+		assert_eq!(v.remove(0), 0);
+		v.insert(5, 100);
+		assert_eq!(v[5], 100);
+		assert_eq!(v.swap_remove(5), 100);
+		v.truncate(4);
+		v.shrink_to_fit();
+		assert_eq!(v, [1, 2, 3, 4]);
+
+		let mut s = String::from_str_in("héllo", &alloc);
+		write!(s, " {}", 42).unwrap();
+		assert_eq!(s, "héllo 42");
+		assert_eq!(s.pop(), Some('2'));
+
+		// Values with drop glue are dropped exactly once.
+		let counter = std::rc::Rc::new(());
+		let b = Box::new_in(counter.clone(), &alloc);
+		let mut v2 = Vec::new_in(&alloc);
+		v2.resize(3, counter.clone());
+		assert_eq!(std::rc::Rc::strong_count(&counter), 5);
+		drop(b);
+		drop(v2);
+		assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+
+		// A `SmallVec` drops its elements both before and after spilling.
+		let mut small = SmallVec::<_, _, 2>::new_in(&alloc);
+		small.extend([counter.clone(), counter.clone()]);
+		assert!(!small.spilled());
+		small.push(counter.clone());
+		assert!(small.spilled());
+		assert_eq!(std::rc::Rc::strong_count(&counter), 4);
+		drop(small);
+		assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+
+		// A `RingBuffer` wraps around, and drops the elements that are still queued.
+		let mut queue = RingBuffer::with_capacity_in(3, &alloc);
+		for _ in 0..10 {
+			queue.push(counter.clone()).unwrap();
+			queue.push(counter.clone()).unwrap();
+			queue.pop().unwrap();
+			queue.pop().unwrap();
+		}
+		queue.push(counter.clone()).unwrap();
+		assert_eq!(std::rc::Rc::strong_count(&counter), 2);
+		drop(queue);
+		assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+
+		// Zero-sized types never allocate.
+		let mut zst = Vec::new_in(&alloc);
+		zst.extend([(); 1000]);
+		assert_eq!(zst.len(), 1000);
+
+		assert_eq!(alloc.validate(), Ok(()));
+	}
+
+	#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+	assert!(alloc.is_empty());
+}
+
+#[test]
+#[cfg(feature = "leak-check")]
+fn test_leak_check() {
+	use crate::LeakError;
+
+	let alloc = Stalloc::<16, 4>::new();
+
+	// Freed and permanent allocations are not leaks.
+	unsafe {
+		let ptr = alloc.allocate_blocks(4, 1).unwrap();
+		alloc.deallocate_blocks(ptr, 4);
+		alloc.allocate_blocks_permanent(4, 1).unwrap();
+		assert_eq!(alloc.check_leaks(), Ok(()));
+		alloc.debug_assert_no_leaks();
+
+		alloc.allocate_blocks(4, 1).unwrap();
+	}
+	assert_eq!(alloc.check_leaks(), Err(LeakError { leaked: 1 }));
+}
+
+#[test]
+#[cfg(all(feature = "leak-check", debug_assertions))]
+#[should_panic(expected = "Stalloc has 1 live allocation(s)")]
+fn test_debug_assert_no_leaks() {
+	let alloc = Stalloc::<16, 4>::new();
+
+	unsafe { alloc.allocate_blocks(4, 1) }.unwrap();
+	alloc.debug_assert_no_leaks();
+}
+
+#[test]
+#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+fn test_allocator_stats() {
+	use crate::AllocatorStats;
+
+	let first = Stalloc::<4, 4>::new();
+	let second = Stalloc::<8, 4>::new();
+	let chain = first.chain(&second);
+	assert_eq!(chain.capacity_bytes(), 48);
+
+	{
+		// The first vector doesn't fit in `first`, so it falls back to `second`.
+		let a: Vec<u8, _> = Vec::with_capacity_in(20, &chain);
+		let b: Vec<u8, _> = Vec::with_capacity_in(8, &chain);
+		assert_eq!(chain.used_bytes(), a.capacity() + b.capacity());
+		assert_eq!(chain.free_bytes(), 48 - 28);
+
+		#[cfg(feature = "stats")]
+		{
+			assert_eq!(chain.allocations(), Some(2));
+			assert_eq!(chain.failed_allocations(), Some(1));
+		}
+	}
+
+	assert_eq!(chain.used_bytes(), 0);
+}
+
+#[test]
+fn test_alloc_counter() {
+	use crate::{AllocCounter, AllocatorStats};
+
+	let head = Stalloc::<4, 4>::new();
+	let tail = AllocCounter::new(std::alloc::System);
+	let chain = head.chain(&tail);
+
+	{
+		let mut v: Vec<u8, _> = Vec::with_capacity_in(32, &chain);
+		assert_eq!(tail.live_bytes(), 32);
+
+		v.reserve_exact(64);
+		assert_eq!(tail.live_bytes(), 64);
+		assert_eq!(tail.peak_bytes(), 64);
+		assert_eq!(tail.total_bytes(), 64);
+	}
+
+	assert_eq!(tail.live_allocations(), 0);
+	assert_eq!(tail.total_allocations(), 1);
+	assert_eq!(tail.deallocations(), Some(1));
+}
+
+#[test]
+#[cfg(feature = "latency")]
+fn test_latency_histogram() {
+	use crate::LatencyHistogram;
+	use core::time::Duration;
+
+	let histogram = LatencyHistogram::new();
+	assert_eq!(histogram.percentile(0.5), None);
+
+	for _ in 0..99 {
+		histogram.record(Duration::from_nanos(100));
+	}
+	histogram.record(Duration::from_micros(50));
+
+	assert_eq!(histogram.count(), 100);
+	assert_eq!(histogram.percentile(0.5), Some(Duration::from_nanos(128)));
+	assert_eq!(histogram.percentile(0.99), Some(Duration::from_nanos(128)));
+	assert_eq!(histogram.percentile(1.0), Some(Duration::from_nanos(65536)));
+}
+
+#[test]
+fn test_spin_stalloc() {
+	use crate::SpinStalloc;
+
+	let alloc = SpinStalloc::<1024, 8>::new();
+
+	std::thread::scope(|s| {
+		for _ in 0..4 {
+			s.spawn(|| {
+				for i in 0..200 {
+					let v: Vec<usize, _> = Vec::with_capacity_in(i % 16 + 1, &alloc);
+					drop(v);
+				}
+			});
+		}
+	});
+
+	assert_eq!(alloc.live_allocations(), 0);
+	assert_eq!(alloc.validate(), Ok(()));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_try_acquire_locked_for_waits() {
+	use crate::SyncStalloc;
+	use core::time::Duration;
+
+	let alloc = SyncStalloc::<16, 4>::new();
+
+	let lock = alloc.acquire_locked();
+	std::thread::scope(|s| {
+		let waiter = s.spawn(|| {
+			alloc
+				.try_acquire_locked_for(Duration::from_secs(10))
+				.is_some()
+		});
+
+		// The waiter backs off while the lock is held, and gets it once it is released.
+		std::thread::sleep(Duration::from_millis(20));
+		drop(lock);
+		assert!(waiter.join().unwrap());
+	});
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_thread_cache() {
+	use crate::{SyncStalloc, THREAD_CACHE_BATCH};
+
+	let alloc = SyncStalloc::<1024, 8>::new();
+
+	std::thread::scope(|s| {
+		for _ in 0..4 {
+			s.spawn(|| {
+				let cache = alloc.thread_cache();
+				let mut boxes = Vec::new();
+				for i in 0..200 {
+					boxes.push(Box::new_in(i, &cache));
+				}
+				assert!(cache.cached_blocks() < THREAD_CACHE_BATCH);
+
+				// Larger allocations bypass the cache.
+				let v: Vec<u64, _> = Vec::with_capacity_in(100, &cache);
+				drop(v);
+			});
+		}
+	});
+
+	assert_eq!(alloc.live_allocations(), 0);
+	assert_eq!(alloc.validate(), Ok(()));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_thread_local_stalloc() {
+	use crate::{ChainableAlloc, ThreadLocalStalloc};
+
+	let alloc = ThreadLocalStalloc::<256, 8, 4>::new();
+
+	// Allocate on worker threads, and free on the main thread.
+	let vecs: Vec<_> = std::thread::scope(|s| {
+		let handles: Vec<_> = (0..4)
+			.map(|_| s.spawn(|| Vec::<u64, _>::with_capacity_in(8, &alloc)))
+			.collect();
+		handles.into_iter().map(|h| h.join().unwrap()).collect()
+	});
+
+	// Every thread allocated from its own arena.
+	let mut owners: Vec<_> = vecs
+		.iter()
+		.map(|v| {
+			let addr = v.as_ptr().addr();
+			alloc.arenas().iter().position(|a| a.addr_in_bounds(addr))
+		})
+		.collect();
+	owners.sort_unstable();
+	assert_eq!(owners, [Some(0), Some(1), Some(2), Some(3)]);
+
+	drop(vecs);
+	assert_eq!(alloc.live_allocations(), 0);
+	assert!(alloc.is_empty());
+}
+
+#[test]
+fn test_balanced_stalloc() {
+	use crate::{Balance, BalancedStalloc, ChainableAlloc};
+
+	let position = |alloc: &BalancedStalloc<64, 8, 3>, v: &Vec<u64, _>| {
+		let addr = v.as_ptr().addr();
+		alloc.arenas().iter().position(|a| a.addr_in_bounds(addr))
+	};
+
+	// Round-robin: consecutive allocations land in consecutive arenas.
+	let alloc = BalancedStalloc::<64, 8, 3>::new();
+	let vecs: Vec<_> = (0..3)
+		.map(|_| Vec::<u64, _>::with_capacity_in(4, &alloc))
+		.collect();
+	let owners: Vec<_> = vecs.iter().map(|v| position(&alloc, v)).collect();
+	assert_eq!(owners, [Some(0), Some(1), Some(2)]);
+	drop(vecs);
+	assert!(alloc.is_empty());
+
+	// Least-used: a large allocation in one arena steers the next ones elsewhere.
+	let alloc = BalancedStalloc::<64, 8, 3>::new().with_balance(Balance::LeastUsed);
+	let big = Vec::<u64, _>::with_capacity_in(32, &alloc);
+	let small1 = Vec::<u64, _>::with_capacity_in(4, &alloc);
+	let small2 = Vec::<u64, _>::with_capacity_in(4, &alloc);
+	assert_eq!(position(&alloc, &big), Some(0));
+	assert_eq!(position(&alloc, &small1), Some(1));
+	assert_eq!(position(&alloc, &small2), Some(2));
+
+	// When every other arena is full, allocations still succeed in the remaining one.
+	let rest = Vec::<u64, _>::with_capacity_in(60, &alloc);
+	assert!(position(&alloc, &rest).is_some());
+	drop((big, small1, small2, rest));
+	assert_eq!(alloc.live_allocations(), 0);
+}
+
+#[test]
+fn test_async_stalloc() {
+	use crate::AsyncStalloc;
+	use alloc::sync::Arc;
+	use core::alloc::Layout;
+	use core::pin::pin;
+	use core::task::{Context, Poll};
+	use std::task::Wake;
+	use std::thread::{self, Thread};
+
+	struct Unpark(Thread);
+
+	impl Wake for Unpark {
+		fn wake(self: Arc<Self>) {
+			self.0.unpark();
+		}
+	}
+
+	let alloc = AsyncStalloc::<8, 8>::new();
+	let layout = Layout::new::<[u64; 6]>();
+	let held = alloc.try_allocate(layout).unwrap();
+
+	thread::scope(|s| {
+		let waiter = s.spawn(|| {
+			let waker = Arc::new(Unpark(thread::current())).into();
+			let mut cx = Context::from_waker(&waker);
+			let mut fut = pin!(alloc.allocate(layout));
+			let ptr = loop {
+				match fut.as_mut().poll(&mut cx) {
+					Poll::Ready(res) => break res.unwrap(),
+					Poll::Pending => thread::park(),
+				}
+			};
+			unsafe { alloc.deallocate(ptr.cast(), layout) };
+		});
+
+		while alloc.waiting_tasks() == 0 {
+			thread::yield_now();
+		}
+		unsafe { alloc.deallocate(held.cast(), layout) };
+
+		waiter.join().unwrap();
+	});
+
+	assert_eq!(alloc.waiting_tasks(), 0);
+	assert!(alloc.inner().is_empty());
+
+	// An allocation larger than the allocator fails right away.
+	let mut cx = Context::from_waker(core::task::Waker::noop());
+	let huge = pin!(alloc.allocate(Layout::new::<[u64; 9]>()));
+	assert!(matches!(huge.poll(&mut cx), Poll::Ready(Err(_))));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_guard_allocator() {
+	use crate::SyncStalloc;
+
+	let alloc = SyncStalloc::<100, 4>::new();
+	let lock = alloc.acquire_locked();
+
+	let mut v = Vec::new_in(&lock);
+	v.extend(0..20u32);
+	assert_eq!(lock.live_allocations(), 1);
+
+	drop(v);
+	assert!(lock.is_empty());
+}
+
+#[test]
+fn test_block_alloc() {
+	use crate::{BlockAlloc, SpinStalloc};
+	use core::alloc::Layout;
+
+	let allocs: [&dyn BlockAlloc; 2] = [&Stalloc::<8, 8>::new(), &SpinStalloc::<8, 8>::new()];
+
+	for alloc in allocs {
+		let small = Layout::new::<[u64; 2]>();
+		let large = Layout::new::<[u64; 6]>();
+
+		let ptr = alloc.allocate(small).unwrap().cast::<u64>();
+		unsafe { ptr.write(7) };
+
+		let grown = unsafe { alloc.grow(ptr.cast(), small, large) }.unwrap();
+		assert_eq!(grown.len(), 48);
+		assert!(alloc.allocate(large).is_err());
+
+		let shrunk = unsafe { alloc.shrink(grown.cast(), large, small) }.unwrap();
+		assert_eq!(unsafe { shrunk.cast::<u64>().read() }, 7);
+		unsafe { alloc.deallocate(shrunk.cast(), small) };
+	}
+}
+
+#[test]
+fn test_lock_free_stalloc() {
+	use crate::LockFreeStalloc;
+
+	let alloc = LockFreeStalloc::<1024, 8>::new();
+
+	std::thread::scope(|s| {
+		for _ in 0..4 {
+			s.spawn(|| {
+				for i in 0..200 {
+					let a: Vec<usize, _> = Vec::with_capacity_in(i % 4 + 1, &alloc);
+					let b: Vec<usize, _> = Vec::with_capacity_in(i % 16 + 1, &alloc);
+					drop(a);
+					drop(b);
+				}
+			});
+		}
+	});
+
+	// Every allocation is either cached or returned to the inner allocator.
+	assert!(alloc.flush() > 0);
+	assert_eq!(alloc.inner().live_allocations(), 0);
+	assert_eq!(alloc.inner().validate(), Ok(()));
+}
+
+#[test]
+fn test_stalloc_dyn() {
+	use crate::{AllocatorStats, SpinStalloc, StallocDyn};
+
+	let stalloc = Stalloc::<16, 8>::new();
+	let spin = SpinStalloc::<32, 4>::new();
+	let handles = [StallocDyn::from(&stalloc), StallocDyn::from(&spin)];
+
+	for alloc in handles {
+		let mut v: Vec<u64, _> = Vec::with_capacity_in(4, alloc);
+		v.extend([1, 2, 3, 4]);
+		assert_eq!(alloc.used_bytes(), 32);
+		assert_eq!(alloc.live_allocations(), 1);
+		assert!(alloc.validate().is_ok());
+
+		v.push(5);
+		assert_eq!(v.iter().sum::<u64>(), 15);
+		drop(v);
+		assert!(alloc.is_empty());
+	}
+}
+
+#[test]
+fn test_raw_block_allocator() {
+	use crate::{RawBlockAllocator, SyncStalloc, UnsafeStalloc};
+
+	fn exercise<A: RawBlockAllocator>(alloc: &A) {
+		unsafe {
+			let ptr = alloc.allocate_blocks_zeroed(2, 1).unwrap();
+			alloc.grow_zeroed_in_place(ptr, 2, 4).unwrap();
+			assert!(
+				ptr.as_ptr()
+					.cast::<[u8; 16]>()
+					.read()
+					.iter()
+					.all(|&b| b == 0)
+			);
+
+			let max = A::BLOCK_COUNT;
+			assert_eq!(alloc.grow_up_to(ptr, 4, A::BLOCK_COUNT + 1), max);
+			assert!(alloc.is_oom());
+
+			alloc.shrink_in_place(ptr, max, 1);
+			let ptr = alloc.reallocate_blocks(ptr, 1, 3, 1).unwrap();
+			alloc.deallocate_blocks(ptr, 3);
+		}
+		assert!(alloc.is_empty());
+	}
+
+	exercise(&Stalloc::<8, 4>::new());
+	exercise(&unsafe { UnsafeStalloc::<8, 4>::new() });
+	exercise(&SyncStalloc::<8, 4>::new());
+}
+
+#[test]
+#[cfg(feature = "c-abi")]
+fn test_c_abi_chain() {
+	use crate::cabi::{self, EINVAL};
+	use crate::{MAX_ALIGN, SyncStalloc};
+
+	let first = SyncStalloc::<4, 16>::new();
+	let second = SyncStalloc::<16, 16>::new();
+	let chain = first.chain(&second);
+
+	unsafe {
+		let a = cabi::malloc(&chain, 64);
+		let b = cabi::malloc(&chain, 16);
+		assert!(chain.0.is_oom());
+		assert_eq!(second.live_allocations(), 1);
+
+		// `a` can't grow in the first allocator, so it moves to the second one.
+		a.cast::<u64>().write(42);
+		let a = cabi::realloc(&chain, a, 128);
+		assert_eq!(a.cast::<u64>().read(), 42);
+		assert_eq!(second.live_allocations(), 2);
+
+		let mut c = core::ptr::null_mut();
+		assert_eq!(cabi::posix_memalign(&chain, &raw mut c, 3, 8), EINVAL);
+		assert_eq!(
+			cabi::posix_memalign(&chain, &raw mut c, MAX_ALIGN * 2, 8),
+			EINVAL
+		);
+		assert_eq!(cabi::posix_memalign(&chain, &raw mut c, 64, 8), 0);
+		assert!(c.addr().is_multiple_of(64));
+
+		assert!(cabi::aligned_alloc(&chain, MAX_ALIGN * 2, 8).is_null());
+		let d = cabi::aligned_alloc(&chain, 32, 8);
+		assert!(d.addr().is_multiple_of(32));
+
+		for ptr in [a, b, c, d] {
+			cabi::free(&chain, ptr);
+		}
+	}
+
+	assert!(chain.0.is_empty() && second.is_empty());
+}
+
+#[test]
+#[cfg(feature = "c-abi")]
+fn test_c_handles() {
+	use crate::cabi::{stalloc_alloc, stalloc_create, stalloc_destroy, stalloc_free};
+	use core::mem::MaybeUninit;
+
+	let mut buf = [MaybeUninit::<u8>::uninit(); 1000];
+	assert!(unsafe { stalloc_create(buf.as_mut_ptr().cast(), 40) }.is_null());
+
+	unsafe {
+		let handle = stalloc_create(buf.as_mut_ptr().cast(), buf.len());
+
+		let a = stalloc_alloc(handle, 100, 0);
+		let b = stalloc_alloc(handle, 8, 128);
+		assert!(!a.is_null() && !b.is_null());
+		assert!(b.addr().is_multiple_of(128));
+		assert!(stalloc_alloc(handle, 1000, 0).is_null());
+
+		a.cast::<u8>().write_bytes(0xff, 100);
+		assert!(stalloc_alloc(handle, 800, 0).is_null());
+		stalloc_free(handle, a);
+		stalloc_free(handle, b);
+
+		// Everything was freed, so most of the arena can be allocated at once.
+		let c = stalloc_alloc(handle, 800, 0);
+		assert!(!c.is_null());
+		stalloc_free(handle, c);
+
+		stalloc_destroy(handle);
+	}
+}
+
+#[test]
+#[cfg(feature = "c-abi")]
+fn test_arena_registry() {
+	use crate::cabi::{ArenaRegistry, stalloc_alloc, stalloc_create, stalloc_destroy};
+	use core::ffi::c_void;
+	use core::mem::MaybeUninit;
+	use core::sync::atomic::{AtomicUsize, Ordering};
+
+	static FALLBACK_FREES: AtomicUsize = AtomicUsize::new(0);
+	unsafe extern "C" fn count_free(_ptr: *mut c_void) {
+		FALLBACK_FREES.fetch_add(1, Ordering::Relaxed);
+	}
+
+	let registry = ArenaRegistry::<1>::new();
+	registry.set_fallback(Some(count_free));
+	let mut bufs = [[MaybeUninit::<u8>::uninit(); 256]; 2];
+
+	unsafe {
+		let [a, b] = bufs
+			.each_mut()
+			.map(|buf| stalloc_create(buf.as_mut_ptr().cast(), buf.len()));
+		assert!(registry.register(a));
+		assert!(!registry.register(b));
+
+		let in_a = stalloc_alloc(a, 16, 0);
+		let in_b = stalloc_alloc(b, 16, 0);
+		assert_eq!(registry.owner(in_a), Some(a));
+		assert_eq!(registry.owner(in_b), None);
+
+		registry.free(in_a);
+		registry.free(in_b);
+		registry.free(core::ptr::null_mut());
+		assert_eq!(FALLBACK_FREES.load(Ordering::Relaxed), 1);
+
+		assert!(registry.unregister(a));
+		assert!(!registry.unregister(a));
+		stalloc_destroy(a);
+		stalloc_destroy(b);
+	}
+}
+
+#[test]
+fn test_owned_alloc_chain() {
+	use crate::{ChainableAlloc, OwnedAllocChain, SyncStalloc};
+	use std::alloc::System;
+
+	static CHAIN: OwnedAllocChain<SyncStalloc<4, 4>, System> =
+		SyncStalloc::new().chain_owned(System);
+
+	{
+		let small: Vec<u8, _> = Vec::with_capacity_in(8, &CHAIN);
+		let large: Vec<u8, _> = Vec::with_capacity_in(64, &CHAIN);
+		assert!(CHAIN.0.addr_in_bounds(small.as_ptr().addr()));
+		assert!(!CHAIN.0.addr_in_bounds(large.as_ptr().addr()));
+	}
+
+	assert!(CHAIN.0.is_empty());
+}
+
+#[test]
+#[cfg(feature = "stats")]
+fn test_chain_stats() {
+	use crate::LinkStats;
+
+	let second = Stalloc::<16, 4>::new();
+	let chain = Stalloc::<4, 4>::new().chain(&second);
+
+	{
+		let mut v: Vec<u8, _> = Vec::with_capacity_in(8, &chain);
+		let _w: Vec<u8, _> = Vec::with_capacity_in(20, &chain);
+
+		// Growing `v` past the first allocator moves it to the second one.
+		v.reserve_exact(32);
+		let stats = chain.chain_stats();
+		assert_eq!(
+			stats.first,
+			LinkStats {
+				allocations: 1,
+				live_bytes: 0
+			}
+		);
+		assert_eq!(
+			stats.second,
+			LinkStats {
+				allocations: 2,
+				live_bytes: 52
+			}
+		);
+	}
+
+	assert_eq!(chain.chain_stats().second.live_bytes, 0);
+}
+
+#[test]
+fn test_chain_migrate_back() {
+	let second = Stalloc::<16, 4>::new();
+	let chain = Stalloc::<4, 4>::new().chain(&second).migrate_back(true);
+
+	let mut v: Vec<u8, _> = Vec::with_capacity_in(32, &chain);
+	v.extend_from_slice(b"spilled");
+	assert!(!second.is_empty());
+	#[cfg(feature = "stats")]
+	assert_eq!(chain.chain_stats().second.live_bytes, 32);
+
+	// Once the vector fits in the first allocator, shrinking moves it back.
+	v.shrink_to_fit();
+	assert_eq!(v, b"spilled");
+	#[cfg(feature = "stats")]
+	assert_eq!(chain.chain_stats().first.live_bytes, 7);
+	assert!(second.is_empty());
+}
+
+#[test]
+fn test_nested_chain() {
+	let second = Stalloc::<4, 4>::new();
+	let third = Stalloc::<16, 4>::new();
+	let chain = Stalloc::<4, 4>::new().chain(&second).chain(&third);
+
+	{
+		let a: Vec<u8, _> = Vec::with_capacity_in(16, &chain);
+		let b: Vec<u8, _> = Vec::with_capacity_in(16, &chain);
+		let c: Vec<u8, _> = Vec::with_capacity_in(16, &chain);
+		assert!(chain.0.0.is_oom() && second.is_oom());
+		#[cfg(feature = "stats")]
+		assert_eq!(chain.chain_stats().second.live_bytes, 16);
+		drop((a, b, c));
+	}
+
+	assert!(chain.0.0.is_empty() && second.is_empty() && third.is_empty());
+}
+
+#[test]
+fn test_chain_fallback_handler() {
+	use core::alloc::Layout;
+	use core::sync::atomic::{AtomicUsize, Ordering};
+
+	static CALLS: AtomicUsize = AtomicUsize::new(0);
+	fn on_fallback(layout: Layout) {
+		assert_eq!(layout.size(), 32);
+		CALLS.fetch_add(1, Ordering::Relaxed);
+	}
+
+	let second = Stalloc::<32, 4>::new();
+	let chain = Stalloc::<4, 4>::new()
+		.chain(&second)
+		.with_fallback_handler(on_fallback, false);
+
+	// Only the first fallback is reported.
+	let a: Vec<u8, _> = Vec::with_capacity_in(32, &chain);
+	let b: Vec<u8, _> = Vec::with_capacity_in(32, &chain);
+	assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+	drop((a, b));
+}
+
+#[test]
+fn test_chain_zeroed() {
+	use core::alloc::{Allocator, Layout};
+
+	let second = Stalloc::<16, 4>::new();
+	let chain = Stalloc::<4, 4>::new().chain(&second);
+
+	// Leave garbage behind in both links.
+	let mut dirty: Vec<u8, _> = Vec::with_capacity_in(16, &chain);
+	dirty.resize(16, 0xaa);
+	drop(dirty);
+	let mut dirty: Vec<u8, _> = Vec::with_capacity_in(64, &second);
+	dirty.resize(64, 0xaa);
+	drop(dirty);
+
+	let small = Layout::new::<[u8; 8]>();
+	let large = Layout::new::<[u8; 48]>();
+	unsafe {
+		let ptr = (&chain).allocate_zeroed(small).unwrap();
+		assert!(ptr.as_ref().iter().all(|&b| b == 0));
+
+		// Growing moves the allocation to `second`, where the new memory must be zeroed too.
+		let ptr = (&chain).grow_zeroed(ptr.cast(), small, large).unwrap();
+		assert!(ptr.as_ref().iter().all(|&b| b == 0));
+		#[cfg(feature = "stats")]
+		assert_eq!(chain.chain_stats().second.live_bytes, 48);
+
+		(&chain).deallocate(ptr.cast(), large);
+	}
+}
+
+#[test]
+#[cfg(feature = "oom-handler")]
+fn test_chain_skips_oom_handler_of_first_link() {
+	use crate::{AllocReport, SyncStalloc};
+	use core::alloc::{GlobalAlloc, Layout};
+
+	fn on_oom(_: Layout, _: &AllocReport) {
+		panic!("the first link of a chain ran its OOM handler");
+	}
+
+	let second = SyncStalloc::<32, 4>::new();
+	let chain = SyncStalloc::<4, 4>::new()
+		.with_oom_handler(on_oom)
+		.chain(&second);
+
+	let small = Layout::new::<[u8; 8]>();
+	let large = Layout::new::<[u8; 64]>();
+	unsafe {
+		// Both of these fall back to `second` instead of reporting an OOM.
+		let a = chain.alloc(large);
+		let b = chain.alloc(small);
+		let b = chain.realloc(b, small, 48);
+		assert!(!a.is_null() && !b.is_null());
+		assert!(chain.0.is_empty());
+
+		chain.dealloc(a, large);
+		chain.dealloc(b, Layout::new::<[u8; 48]>());
+	}
+}
+
+#[test]
+#[cfg(feature = "oom-handler")]
+fn test_counted_chain_link_skips_oom_handler() {
+	use crate::{AllocCounter, AllocReport, SyncStalloc};
+	use core::alloc::{GlobalAlloc, Layout};
+	use std::alloc::System;
+
+	fn on_oom(_: Layout, _: &AllocReport) {
+		panic!("the counted link of a chain ran its OOM handler");
+	}
+
+	let chain =
+		AllocCounter::new(SyncStalloc::<8, 4>::new().with_oom_handler(on_oom)).chain(&System);
+
+	let small = Layout::new::<[u8; 8]>();
+	let large = Layout::new::<[u8; 64]>();
+	unsafe {
+		let a = chain.alloc(large);
+		let b = chain.alloc(small);
+		let b = chain.realloc(b, small, 48);
+		assert!(!a.is_null() && !b.is_null());
+
+		// The allocation of `b` succeeded, but the two others fell back to `System`.
+		assert_eq!(chain.0.total_allocations(), 1);
+		assert_eq!(chain.0.failed_allocations(), 2);
+		assert_eq!(chain.0.live_allocations(), 0);
+
+		chain.dealloc(a, large);
+		chain.dealloc(b, Layout::new::<[u8; 48]>());
+	}
+}
+
+#[test]
+#[cfg(all(feature = "latency", feature = "oom-handler"))]
+fn test_timed_chain_link_skips_oom_handler() {
+	use crate::{AllocReport, SyncStalloc, Timed};
+	use core::alloc::{GlobalAlloc, Layout};
+	use std::alloc::System;
+
+	fn on_oom(_: Layout, _: &AllocReport) {
+		panic!("the timed link of a chain ran its OOM handler");
+	}
+
+	let chain = Timed::new(SyncStalloc::<8, 4>::new().with_oom_handler(on_oom)).chain(&System);
+
+	let layout = Layout::new::<[u8; 64]>();
+	unsafe {
+		let ptr = chain.alloc(layout);
+		assert!(!ptr.is_null());
+		assert_eq!(chain.0.alloc_latencies().count(), 1);
+
+		chain.dealloc(ptr, layout);
+	}
+}
+
+#[test]
+#[cfg(all(feature = "leak-debug", feature = "oom-handler"))]
+fn test_leak_debug_chain_link_skips_oom_handler() {
+	use crate::{AllocReport, LeakDebug, SyncStalloc};
+	use core::alloc::{GlobalAlloc, Layout};
+	use std::alloc::System;
+
+	fn on_oom(_: Layout, _: &AllocReport) {
+		panic!("the leak-debugged link of a chain ran its OOM handler");
+	}
+
+	let chain = LeakDebug::new(SyncStalloc::<8, 4>::new().with_oom_handler(on_oom)).chain(&System);
+
+	let small = Layout::new::<[u8; 8]>();
+	let large = Layout::new::<[u8; 64]>();
+	unsafe {
+		let a = chain.alloc(large);
+		let b = chain.alloc(small);
+		assert!(!a.is_null() && !b.is_null());
+
+		// Only `b` was allocated by the wrapped allocator, so the failed allocation isn't recorded.
+		assert_eq!(chain.0.leak_count(), 1);
+
+		let b = chain.realloc(b, small, 48);
+		assert!(!b.is_null());
+		assert_eq!(chain.0.leak_count(), 0);
+
+		chain.dealloc(a, large);
+		chain.dealloc(b, Layout::new::<[u8; 48]>());
+	}
+}
+
+#[test]
+#[cfg(any(feature = "abort-on-oom", feature = "oom-handler"))]
+fn test_nested_chain_skips_oom_handler_of_inner_links() {
+	use crate::{SyncStalloc, alloc_chain};
+	use core::alloc::{GlobalAlloc, Layout};
+	use std::alloc::System;
+
+	#[cfg(feature = "oom-handler")]
+	fn on_oom(_: Layout, _: &crate::AllocReport) {
+		panic!("an inner link of a chain ran its OOM handler");
+	}
+
+	// With `abort-on-oom`, a failure in either `SyncStalloc` would abort instead of reaching `System`.
+	#[cfg(feature = "oom-handler")]
+	let chain = alloc_chain!(
+		SyncStalloc::<4, 8>::new().with_oom_handler(on_oom),
+		SyncStalloc::<4, 8>::new().with_oom_handler(on_oom),
+		System
+	);
+	#[cfg(not(feature = "oom-handler"))]
+	let chain = alloc_chain!(
+		SyncStalloc::<4, 8>::new(),
+		SyncStalloc::<4, 8>::new(),
+		System
+	);
+
+	let small = Layout::new::<[u8; 8]>();
+	let large = Layout::new::<[u8; 1000]>();
+	unsafe {
+		// Both of these fall through the inner chain to `System`.
+		let a = chain.alloc(large);
+		let b = chain.alloc(small);
+		let b = chain.realloc(b, small, 1000);
+		assert!(!a.is_null() && !b.is_null());
+		assert!(chain.0.0.is_empty() && chain.0.1.is_empty());
+		#[cfg(feature = "stats")]
+		assert_eq!(chain.chain_stats().second.live_bytes, 2000);
+
+		chain.dealloc(a, large);
+		chain.dealloc(b, large);
+	}
+}
+
+#[test]
+fn test_chain_of_plain_global_alloc_links() {
+	use crate::{AllocChain, ChainableAlloc, SyncStalloc};
+	use core::alloc::{GlobalAlloc, Layout};
+	use std::alloc::System;
+
+	// A link that only implements `GlobalAlloc` and `addr_in_bounds()`, like any link written before
+	// chains could skip OOM handlers.
+	struct Plain(SyncStalloc<4, 8>);
+
+	unsafe impl GlobalAlloc for Plain {
+		unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+			unsafe { self.0.alloc(layout) }
+		}
+
+		unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+			unsafe { self.0.dealloc(ptr, layout) };
+		}
+	}
+
+	unsafe impl ChainableAlloc for Plain {
+		fn addr_in_bounds(&self, addr: usize) -> bool {
+			self.0.addr_in_bounds(addr)
+		}
+	}
+
+	let chain = AllocChain::new(Plain(SyncStalloc::new()), &System);
+	let layout = Layout::new::<[u8; 8]>();
+	unsafe {
+		let a = chain.alloc(layout);
+		assert!(!a.is_null());
+		assert!(!chain.0.0.is_empty());
+		chain.dealloc(a, layout);
+	}
+	assert!(chain.0.0.is_empty());
+}
+
+#[test]
+fn test_tracked() {
+	use crate::Tracked;
+	use std::alloc::System;
+
+	let tracked = Tracked::<_, 2>::new(System);
+	let tail = Stalloc::<16, 4>::new();
+	let chain = Stalloc::<4, 4>::new().chain(&tracked).chain(&tail);
+
+	{
+		let mut a: Vec<u8, _> = Vec::with_capacity_in(32, &chain);
+		let b: Vec<u8, _> = Vec::with_capacity_in(32, &chain);
+
+		// The table of `tracked` is full, so this falls through to `tail`.
+		let c: Vec<u8, _> = Vec::with_capacity_in(32, &chain);
+		assert_eq!(tracked.live_allocations(), 2);
+		assert!(!tail.is_empty());
+
+		a.reserve_exact(1000);
+		assert_eq!(tracked.live_allocations(), 2);
+		drop((a, b, c));
+	}
+
+	assert_eq!(tracked.live_allocations(), 0);
+	assert!(tail.is_empty());
+}
+
+#[test]
+fn test_tracked_failed_resize() {
+	use crate::{ChainableAlloc, Tracked};
+	use core::alloc::{Allocator, Layout};
+
+	let tracked = Tracked::<_, 1>::new(Stalloc::<8, 4>::new());
+	let small = Layout::new::<[u8; 8]>();
+	let large = Layout::new::<[u8; 64]>();
+
+	unsafe {
+		let ptr = (&tracked).allocate(small).unwrap().cast::<u8>();
+		assert!((&tracked).grow(ptr, small, large).is_err());
+
+		// The allocation is still recorded after the failed resize.
+		assert_eq!(tracked.live_allocations(), 1);
+		assert!(tracked.addr_in_bounds(ptr.addr().get()));
+
+		(&tracked).deallocate(ptr, small);
+	}
+
+	assert_eq!(tracked.live_allocations(), 0);
+	assert!(tracked.is_empty());
+}
+
+#[test]
+fn test_tagged_alloc_chain() {
+	use crate::TaggedAllocChain;
+
+	let chain = TaggedAllocChain::new(Stalloc::<8, 8>::new(), Stalloc::<32, 8>::new());
+
+	{
+		// Zero-sized allocations are real allocations, so they are freed by the right allocator.
+		let empty: Vec<(), _> = Vec::with_capacity_in(1, &chain);
+		let mut v: Vec<u8, _> = Vec::with_capacity_in(16, &chain);
+		v.extend_from_slice(b"tagged");
+		assert!(chain.1.is_empty());
+
+		// Growing `v` moves it to the second allocator.
+		v.reserve_exact(100);
+		assert_eq!(v, b"tagged");
+		assert!(!chain.1.is_empty());
+		drop((empty, v));
+	}
+
+	assert!(chain.0.is_empty() && chain.1.is_empty());
+}
+
+#[test]
+#[cfg(feature = "oom-handler")]
+fn test_tagged_alloc_chain_skips_oom_handler_of_first_link() {
+	use crate::{AllocReport, SyncStalloc, TaggedAllocChain};
+	use core::alloc::{GlobalAlloc, Layout};
+	use std::alloc::System;
+
+	fn on_oom(_: Layout, _: &AllocReport) {
+		panic!("the first link of a TaggedAllocChain ran its OOM handler");
+	}
+
+	let chain = TaggedAllocChain::new(SyncStalloc::<8, 8>::new().with_oom_handler(on_oom), System);
+
+	let small = Layout::new::<[u8; 8]>();
+	let large = Layout::new::<[u8; 256]>();
+	unsafe {
+		// Both of these fall back to `System` instead of reporting an OOM.
+		let a = chain.alloc(large);
+		let b = chain.alloc(small);
+		let b = chain.realloc(b, small, 256);
+		assert!(!a.is_null() && !b.is_null());
+		assert!(chain.0.is_empty());
+
+		chain.dealloc(a, large);
+		chain.dealloc(b, large);
+	}
+}
+
+#[test]
+#[cfg(not(any(feature = "quarantine", feature = "free-cache")))]
+fn test_strategies() {
+	use crate::{BestFit, NextFit, Strategy};
+	use core::ptr::NonNull;
+
+	const L: usize = 16;
+
+	// Leaves a hole of 4 blocks and a hole of 2 blocks, and returns the allocation that made the second
+	// hole, the last allocation, and a new allocation of 2 blocks.
+	fn place_in_holes<S: Strategy>(alloc: &Stalloc<L, 4, S>) -> [NonNull<u8>; 3] {
+		unsafe {
+			let big = alloc.allocate_blocks(4, 1).unwrap();
+			let sep = alloc.allocate_blocks(1, 1).unwrap();
+			let small = alloc.allocate_blocks(2, 1).unwrap();
+			let last = alloc.allocate_blocks(1, 1).unwrap();
+			alloc.deallocate_blocks(big, 4);
+			alloc.deallocate_blocks(small, 2);
+
+			let new = alloc.allocate_blocks(2, 1).unwrap();
+			alloc.deallocate_blocks(sep, 1);
+			[small, last, new]
+		}
+	}
+
+	// First-fit uses the hole of 4 blocks.
+	let alloc = Stalloc::<L, 4>::new();
+	let [small, last, new] = place_in_holes(&alloc);
+	assert!(new < small);
+	unsafe {
+		alloc.deallocate_blocks(new, 2);
+		alloc.deallocate_blocks(last, 1);
+	}
+	assert!(alloc.is_empty());
+
+	// Best-fit uses the hole of 2 blocks.
+	let alloc = Stalloc::<L, 4, BestFit>::new();
+	let [small, last, new] = place_in_holes(&alloc);
+	assert_eq!(new, small);
+	unsafe {
+		alloc.deallocate_blocks(new, 2);
+		alloc.deallocate_blocks(last, 1);
+	}
+	assert!(alloc.is_empty());
+
+	// Next-fit continues after the last allocation.
+	let alloc = Stalloc::<L, 4, NextFit>::new();
+	let [_, last, new] = place_in_holes(&alloc);
+	assert!(new > last);
+	unsafe {
+		alloc.deallocate_blocks(new, 2);
+		alloc.deallocate_blocks(last, 1);
+	}
+	assert!(alloc.is_empty());
+}
+
+#[test]
+#[cfg(not(feature = "randomize"))]
+fn test_next_fit_clear() {
+	use crate::NextFit;
+
+	let alloc = Stalloc::<4, 4, NextFit>::new();
+
+	unsafe {
+		let first = alloc.allocate_blocks(1, 1).unwrap();
+		alloc.clear();
+
+		// Leave a free chunk at block 0, before where the previous allocation ended, and one after it.
+		alloc.allocate_blocks_at(1, 1).unwrap();
+
+		// The search starts over from block 0 after a clear.
+		assert_eq!(alloc.allocate_blocks(1, 1), Ok(first));
+		alloc.clear();
+	}
+}
+
+#[test]
+fn test_huge_alignment() {
+	use crate::{AllocErrorKind, MAX_ALIGN};
+	use core::alloc::{Allocator, Layout};
+
+	let alloc = Stalloc::<64, 8>::new();
+
+	// A 1 GiB alignment is accepted, and fails like any other allocation that doesn't fit.
+	let layout = Layout::from_size_align(8, 1 << 30).unwrap();
+	assert!((&alloc).allocate(layout).is_err());
+
+	let err = unsafe { alloc.allocate_blocks_detailed(1, MAX_ALIGN / 8) }.unwrap_err();
+	assert_eq!(err.kind, AllocErrorKind::AlignTooLarge);
+	assert!(alloc.is_empty());
+}
@@ -0,0 +1,278 @@
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, SyncStalloc};
+
+/// The number of size classes that a `ThreadCache` caches. Allocations of up to this many blocks
+/// (with an alignment of at most `B`) are served from the cache. This requires the `std` feature.
+pub const THREAD_CACHE_CLASSES: usize = 4;
+
+/// The number of allocations that a `ThreadCache` takes from, or returns to, the shared allocator
+/// at once. This requires the `std` feature.
+pub const THREAD_CACHE_BATCH: usize = 32;
+
+/// The number of allocations that a `ThreadCache` can hold in each size class.
+const CAPACITY: usize = 2 * THREAD_CACHE_BATCH;
+
+/// A per-thread front end for a `SyncStalloc`, which only takes its lock once every `THREAD_CACHE_BATCH`
+/// allocations or deallocations. This requires the `std` feature.
+///
+/// Each thread creates its own `ThreadCache` with `SyncStalloc::thread_cache()`. When the cache has no
+/// allocation of the requested size, it locks the shared allocator once and takes `THREAD_CACHE_BATCH`
+/// allocations of that size. Freed allocations are kept in the cache, and once it is full, half of them are
+/// returned under a single lock. Allocations that are too large or too aligned to be cached go directly
+/// to the shared allocator.
+///
+/// Cached allocations are counted as allocated by the shared allocator, and the cache is flushed when
+/// it is dropped. Memory may be freed through a different cache than the one it was allocated from.
+///
+/// # Examples
+/// ```
+/// use stalloc::SyncStalloc;
+///
+/// let alloc = SyncStalloc::<1024, 16>::new();
+///
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| {
+///             let cache = alloc.thread_cache();
+///             for _ in 0..1000 {
+///                 // Only one in every 32 of these allocations takes the lock.
+///                 let ptr = unsafe { cache.allocate_blocks(1, 1) }.unwrap();
+///                 unsafe { cache.deallocate_blocks(ptr, 1) };
+///             }
+///         });
+///     }
+/// });
+///
+/// assert!(alloc.is_empty());
+/// ```
+pub struct ThreadCache<'a, const L: usize, const B: usize>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a SyncStalloc<L, B>,
+	/// The cached allocations of `i + 1` blocks are stored in `slots[i][..lens[i]]`.
+	slots: [[Cell<Option<NonNull<u8>>>; CAPACITY]; THREAD_CACHE_CLASSES],
+	lens: [Cell<usize>; THREAD_CACHE_CLASSES],
+}
+
+// SAFETY: The cached pointers are owned by the cache, and the shared allocator is `Sync`.
+unsafe impl<const L: usize, const B: usize> Send for ThreadCache<'_, L, B> where Align<B>: Alignment {}
+
+impl<'a, const L: usize, const B: usize> ThreadCache<'a, L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Creates an empty cache in front of `alloc`.
+	pub const fn new(alloc: &'a SyncStalloc<L, B>) -> Self {
+		Self {
+			alloc,
+			slots: [const { [const { Cell::new(None) }; CAPACITY] }; THREAD_CACHE_CLASSES],
+			lens: [const { Cell::new(0) }; THREAD_CACHE_CLASSES],
+		}
+	}
+
+	/// Returns the shared allocator.
+	pub const fn allocator(&self) -> &'a SyncStalloc<L, B> {
+		self.alloc
+	}
+
+	/// Returns the number of blocks that are currently cached.
+	pub fn cached_blocks(&self) -> usize {
+		self.lens
+			.iter()
+			.enumerate()
+			.map(|(class, len)| (class + 1) * len.get())
+			.sum()
+	}
+
+	/// Tries to allocate `size` blocks aligned to `align` blocks, using a cached allocation if possible.
+	///
+	/// # Safety
+	///
+	/// `size` must be nonzero, and `align` must be a power of 2 in the range `1..=MAX_ALIGN / B`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	pub unsafe fn allocate_blocks(
+		&self,
+		size: usize,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		if align == 1 && size <= THREAD_CACHE_CLASSES {
+			let class = size - 1;
+			if self.lens[class].get() == 0 {
+				self.refill(class);
+			}
+			if let Some(ptr) = self.pop(class) {
+				return Ok(ptr);
+			}
+		}
+
+		// SAFETY: Upheld by the caller.
+		unsafe { self.alloc.allocate_blocks(size, align) }.or_else(|_| {
+			// Other size classes might be hoarding the memory we need.
+			if self.flush() == 0 {
+				return Err(AllocError);
+			}
+
+			// SAFETY: Upheld by the caller.
+			unsafe { self.alloc.allocate_blocks(size, align) }
+		})
+	}
+
+	/// Deallocates a pointer. Allocations of up to `THREAD_CACHE_CLASSES` blocks are cached for reuse.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to an allocation of the shared allocator, and `size` must be the number of blocks
+	/// in the allocation. That is, `size` is always in `1..=L`.
+	pub unsafe fn deallocate_blocks(&self, ptr: NonNull<u8>, size: usize) {
+		if size > THREAD_CACHE_CLASSES {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.alloc.deallocate_blocks(ptr, size) };
+			return;
+		}
+
+		let class = size - 1;
+		if self.lens[class].get() == CAPACITY {
+			self.release(class, THREAD_CACHE_BATCH);
+		}
+
+		let len = self.lens[class].get();
+		self.slots[class][len].set(Some(ptr));
+		self.lens[class].set(len + 1);
+	}
+
+	/// Returns every cached allocation to the shared allocator. Returns the number of blocks that were released.
+	pub fn flush(&self) -> usize {
+		let cached = self.cached_blocks();
+		if cached == 0 {
+			return 0;
+		}
+
+		let lock = self.alloc.acquire_locked();
+		for class in 0..THREAD_CACHE_CLASSES {
+			while let Some(ptr) = self.pop(class) {
+				// SAFETY: Cached pointers are allocations of `class + 1` blocks.
+				unsafe { lock.deallocate_blocks(ptr, class + 1) };
+			}
+		}
+
+		cached
+	}
+
+	/// Takes up to `THREAD_CACHE_BATCH` allocations for `class` from the shared allocator.
+	fn refill(&self, class: usize) {
+		let lock = self.alloc.acquire_locked();
+		for _ in 0..THREAD_CACHE_BATCH {
+			// SAFETY: `class + 1` is nonzero.
+			let Ok(ptr) = (unsafe { lock.allocate_blocks(class + 1, 1) }) else {
+				break;
+			};
+
+			let len = self.lens[class].get();
+			self.slots[class][len].set(Some(ptr));
+			self.lens[class].set(len + 1);
+		}
+	}
+
+	/// Returns `count` allocations from `class` to the shared allocator.
+	fn release(&self, class: usize, count: usize) {
+		let lock = self.alloc.acquire_locked();
+		for _ in 0..count {
+			let Some(ptr) = self.pop(class) else {
+				break;
+			};
+
+			// SAFETY: Cached pointers are allocations of `class + 1` blocks.
+			unsafe { lock.deallocate_blocks(ptr, class + 1) };
+		}
+	}
+
+	/// Removes the most recently cached allocation from `class`.
+	fn pop(&self, class: usize) -> Option<NonNull<u8>> {
+		let len = self.lens[class].get().checked_sub(1)?;
+		self.lens[class].set(len);
+		self.slots[class][len].take()
+	}
+}
+
+impl<const L: usize, const B: usize> Drop for ThreadCache<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		self.flush();
+	}
+}
+
+impl<const L: usize, const B: usize> Debug for ThreadCache<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let lens: [usize; THREAD_CACHE_CLASSES] = core::array::from_fn(|i| self.lens[i].get());
+		f.debug_struct("ThreadCache")
+			.field("cached", &lens)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<const L: usize, const B: usize> SyncStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	/// Creates a per-thread cache in front of this allocator, which only takes the lock once every
+	/// `THREAD_CACHE_BATCH` small allocations. See `ThreadCache` for details.
+	pub const fn thread_cache(&self) -> ThreadCache<'_, L, B> {
+		ThreadCache::new(self)
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {crate::Allocator, core::alloc::Layout};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<const L: usize, const B: usize> Allocator for &ThreadCache<'_, L, B>
+where
+	Align<B>: Alignment,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// We can only allocate memory in units of `B`, so round up.
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// If `size` is zero, give away a dangling pointer.
+		if size == 0 {
+			let dangling = NonNull::new(layout.align() as _).unwrap();
+			return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+		}
+
+		// SAFETY: We have made sure that `size` and `align` are valid.
+		unsafe { self.allocate_blocks(size, align) }
+			.map(|p| NonNull::slice_from_raw_parts(p, size * B))
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size == 0 {
+			return;
+		}
+
+		// SAFETY: We just made sure that size != 0. Everything else is upheld by the caller.
+		unsafe { self.deallocate_blocks(ptr, size) };
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
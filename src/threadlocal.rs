@@ -0,0 +1,95 @@
+use core::cell::Cell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+extern crate std;
+
+use crate::SpinStalloc;
+use crate::align::{Align, Alignment};
+use crate::multiarena::multi_arena_api;
+
+/// The number of threads that have used a `ThreadLocalStalloc` so far.
+static THREAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+std::thread_local! {
+	/// The number of the current thread, which decides which arena it allocates from.
+	/// This is `usize::MAX` until the thread first allocates.
+	static THREAD_NUMBER: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// Returns the number of the current thread, assigning one if necessary.
+fn thread_number() -> usize {
+	THREAD_NUMBER
+		.try_with(|n| {
+			if n.get() == usize::MAX {
+				n.set(THREAD_COUNT.fetch_add(1, Ordering::Relaxed));
+			}
+			n.get()
+		})
+		// The thread-local has already been destroyed, so any arena will do.
+		.unwrap_or(0)
+}
+
+/// A global allocator that gives each thread its own arena of `L` blocks, out of `N` arenas.
+/// This requires the `std` feature.
+///
+/// The `i`th thread to allocate is assigned arena `i % N`, so as long as there are at most `N` threads,
+/// no two threads ever allocate from the same arena. Memory can be freed from any thread: the arena that
+/// owns a pointer is found by its address, and the pointer is returned to that arena. If a thread's arena
+/// runs out of memory, it borrows memory from the other arenas.
+///
+/// Each arena is a `SpinStalloc`, but its spinlock is only contended if memory is freed by a different
+/// thread at the same time, or if there are more than `N` threads. For thread-confined allocation
+/// patterns, every operation takes an uncontended lock, which is much cheaper than sharing one `SyncStalloc`.
+///
+/// # Examples
+/// ```
+/// use stalloc::ThreadLocalStalloc;
+///
+/// #[global_allocator]
+/// static GLOBAL: ThreadLocalStalloc<1000, 16, 4> = ThreadLocalStalloc::new();
+///
+/// fn main() {
+///     let v = std::thread::spawn(|| vec![1, 2, 3]).join().unwrap();
+///
+///     // Memory allocated by another thread can be freed here.
+///     drop(v);
+/// }
+/// ```
+pub struct ThreadLocalStalloc<const L: usize, const B: usize, const N: usize>
+where
+	Align<B>: Alignment,
+{
+	arenas: [SpinStalloc<L, B>; N],
+}
+
+impl<const L: usize, const B: usize, const N: usize> ThreadLocalStalloc<L, B, N>
+where
+	Align<B>: Alignment,
+{
+	/// Initializes a new `ThreadLocalStalloc` with `N` empty arenas.
+	///
+	/// # Panics
+	///
+	/// Panics if `N` is zero.
+	#[must_use]
+	pub const fn new() -> Self {
+		assert!(N > 0, "a `ThreadLocalStalloc` needs at least one arena");
+
+		Self {
+			arenas: [const { SpinStalloc::new() }; N],
+		}
+	}
+
+	/// Returns the arena that the current thread allocates from.
+	pub fn current_arena(&self) -> &SpinStalloc<L, B> {
+		&self.arenas[self.first_arena()]
+	}
+
+	/// Returns the index of the arena of the current thread.
+	#[allow(clippy::unused_self)]
+	fn first_arena(&self) -> usize {
+		thread_number() % N
+	}
+}
+
+multi_arena_api!(ThreadLocalStalloc, "the arena of the current thread");
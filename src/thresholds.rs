@@ -0,0 +1,156 @@
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+/// The maximum number of usage thresholds that can be registered. This requires the `thresholds` feature.
+pub const MAX_THRESHOLDS: usize = 8;
+
+/// Whether memory usage rose above a threshold, or fell back below it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Crossing {
+	/// The usage reached the threshold, coming from below it.
+	Above,
+	/// The usage fell below the threshold, coming from above it.
+	Below,
+}
+
+/// Describes a usage threshold that was crossed. This requires the `thresholds` feature.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ThresholdEvent {
+	/// The threshold that was crossed, as a percentage of the allocator's blocks.
+	pub percent: u8,
+	/// The direction in which the threshold was crossed.
+	pub crossing: Crossing,
+	/// The number of blocks in use after the change that crossed the threshold.
+	pub used_blocks: usize,
+	/// The total number of blocks managed by the allocator.
+	pub total_blocks: usize,
+}
+
+/// A function that is called when memory usage crosses a registered threshold.
+///
+/// It runs while the allocator is being modified (and, for `SyncStalloc`, while its lock is held),
+/// so it must not panic or use the allocator it is attached to.
+pub type ThresholdCallback = fn(ThresholdEvent);
+
+/// The registered thresholds, in increasing order, and how many of them the usage currently reaches.
+#[derive(Clone, Copy)]
+pub struct Thresholds {
+	percents: [u8; MAX_THRESHOLDS],
+	len: usize,
+	reached: usize,
+	callback: Option<ThresholdCallback>,
+}
+
+impl Thresholds {
+	pub const fn new() -> Self {
+		Self {
+			percents: [0; MAX_THRESHOLDS],
+			len: 0,
+			reached: 0,
+			callback: None,
+		}
+	}
+
+	/// Returns how many thresholds are reached by a usage of `used` out of `total` blocks.
+	fn count_reached(&self, used: usize, total: usize) -> usize {
+		self.percents[..self.len]
+			.iter()
+			.take_while(|&&percent| used * 100 >= usize::from(percent) * total)
+			.count()
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Registers a callback that is called whenever the memory usage reaches one of `percents` (as a
+	/// percentage of `L`), or falls back below it. This replaces any previously registered thresholds.
+	/// This requires the `thresholds` feature.
+	///
+	/// A threshold is reached when at least that percentage of blocks is in use, as counted by `stats()`.
+	/// Thresholds that are already reached when this function is called don't trigger the callback.
+	/// If a single operation crosses several thresholds, the callback is called once for each of them.
+	///
+	/// # Panics
+	///
+	/// Panics if more than `MAX_THRESHOLDS` thresholds are given, or if a threshold is greater than 100.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{Crossing, Stalloc, ThresholdEvent};
+	/// use std::sync::atomic::{AtomicBool, Ordering};
+	///
+	/// static SHED_CACHES: AtomicBool = AtomicBool::new(false);
+	///
+	/// fn on_threshold(event: ThresholdEvent) {
+	///     SHED_CACHES.store(event.crossing == Crossing::Above, Ordering::Relaxed);
+	/// }
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	/// alloc.set_thresholds(&[75, 90], on_threshold);
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(15, 1) }.unwrap();
+	/// assert!(SHED_CACHES.load(Ordering::Relaxed));
+	///
+	/// unsafe { alloc.deallocate_blocks(ptr, 15) };
+	/// assert!(!SHED_CACHES.load(Ordering::Relaxed));
+	/// ```
+	pub fn set_thresholds(&self, percents: &[u8], callback: ThresholdCallback) {
+		assert!(
+			percents.len() <= MAX_THRESHOLDS,
+			"at most `MAX_THRESHOLDS` thresholds can be registered"
+		);
+		assert!(
+			percents.iter().all(|&percent| percent <= 100),
+			"thresholds must be percentages"
+		);
+
+		let mut thresholds = Thresholds::new();
+		thresholds.percents[..percents.len()].copy_from_slice(percents);
+		thresholds.percents[..percents.len()].sort_unstable();
+		thresholds.len = percents.len();
+		thresholds.reached = thresholds.count_reached(self.stats().used_blocks, L);
+		thresholds.callback = Some(callback);
+
+		unsafe { *self.thresholds.get() = thresholds };
+	}
+
+	/// Removes the registered thresholds and their callback. This requires the `thresholds` feature.
+	pub const fn clear_thresholds(&self) {
+		unsafe { *self.thresholds.get() = Thresholds::new() };
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Calls the threshold callback for every threshold that was crossed since the last check.
+	pub(crate) fn check_thresholds(&self) {
+		let thresholds = unsafe { &mut *self.thresholds.get() };
+		let Some(callback) = thresholds.callback else {
+			return;
+		};
+
+		let used_blocks = self.stats().used_blocks;
+		let reached = thresholds.count_reached(used_blocks, L);
+		let previous = core::mem::replace(&mut thresholds.reached, reached);
+		let percents = thresholds.percents;
+
+		let event = |percent, crossing| ThresholdEvent {
+			percent,
+			crossing,
+			used_blocks,
+			total_blocks: L,
+		};
+
+		for &percent in percents[reached.min(previous)..previous].iter().rev() {
+			callback(event(percent, Crossing::Below));
+		}
+		for &percent in &percents[previous..reached.max(previous)] {
+			callback(event(percent, Crossing::Above));
+		}
+	}
+}
@@ -0,0 +1,372 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+use core::fmt::{self, Debug, Formatter};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{AllocChain, AllocatorStats, ChainableAlloc, OwnedAllocChain};
+
+/// The address ranges that a `Tracked` has handed out, sorted by start address.
+struct Ranges<const N: usize> {
+	entries: [(usize, usize); N],
+	len: usize,
+	/// The number of entries that are held back for resizes in progress, so other allocations can't take them.
+	reserved: usize,
+}
+
+impl<const N: usize> Ranges<N> {
+	/// Records the range `start..end`. Returns false if the table is full.
+	fn insert(&mut self, start: usize, end: usize) -> bool {
+		if self.len + self.reserved == N {
+			return false;
+		}
+
+		let idx = self.entries[..self.len].partition_point(|r| r.0 < start);
+		self.entries.copy_within(idx..self.len, idx + 1);
+		self.entries[idx] = (start, end);
+		self.len += 1;
+		true
+	}
+
+	/// Forgets the range that starts at `start`.
+	fn remove(&mut self, start: usize) {
+		let idx = self.entries[..self.len].partition_point(|r| r.0 < start);
+		if idx < self.len && self.entries[idx].0 == start {
+			self.entries.copy_within(idx + 1..self.len, idx);
+			self.len -= 1;
+		}
+	}
+
+	/// Checks if `addr` is inside one of the ranges.
+	fn contains(&self, addr: usize) -> bool {
+		let idx = self.entries[..self.len].partition_point(|r| r.0 <= addr);
+		idx > 0 && addr < self.entries[idx - 1].1
+	}
+}
+
+/// A wrapper around any allocator that remembers the address range of every live allocation, so that it
+/// can be a non-terminal link of an `AllocChain`.
+///
+/// A chain needs to know which of its links owns a pointer, which allocators like the system allocator
+/// can't tell. `Tracked` keeps the ranges in a sorted table with room for `N` live allocations. Once the
+/// table is full, further allocations fail, so a chain falls through to its next link.
+///
+/// A chain always uses the inner allocator through `GlobalAlloc`, since it doesn't have to be chainable. If it
+/// reports failures in some other way than returning a null pointer, like a `SyncStalloc` with an OOM handler,
+/// it does so before the chain falls through. Such an allocator can already be a link by itself.
+///
+/// # Examples
+/// ```
+/// use stalloc::{SyncStalloc, Tracked};
+/// use std::alloc::{GlobalAlloc, Layout, System};
+///
+/// // A fast arena, then up to 16 allocations from the system allocator, and then the system allocator again.
+/// let tracked = Tracked::<_, 16>::new(System);
+/// let chain = SyncStalloc::<4, 4>::new().chain(&tracked).chain(&System);
+///
+/// let layout = Layout::new::<[u8; 64]>();
+/// unsafe {
+///     let ptr = chain.alloc(layout);
+///     assert_eq!(tracked.live_allocations(), 1);
+///
+///     chain.dealloc(ptr, layout);
+///     assert_eq!(tracked.live_allocations(), 0);
+/// }
+/// ```
+pub struct Tracked<A, const N: usize = 64> {
+	inner: A,
+	locked: AtomicBool,
+	ranges: UnsafeCell<Ranges<N>>,
+}
+
+// SAFETY: `ranges` is only accessed while the spinlock is held.
+unsafe impl<A: Sync, const N: usize> Sync for Tracked<A, N> {}
+
+impl<A, const N: usize> Tracked<A, N> {
+	/// Wraps `inner`, without any live allocations.
+	pub const fn new(inner: A) -> Self {
+		Self {
+			inner,
+			locked: AtomicBool::new(false),
+			ranges: UnsafeCell::new(Ranges {
+				entries: [(0, 0); N],
+				len: 0,
+				reserved: 0,
+			}),
+		}
+	}
+
+	/// Returns the number of allocations that are currently live.
+	pub fn live_allocations(&self) -> usize {
+		self.with_ranges(|ranges| ranges.len + ranges.reserved)
+	}
+
+	/// Returns the wrapped allocator.
+	pub fn into_inner(self) -> A {
+		self.inner
+	}
+
+	/// Creates a new `AllocChain` containing this allocator and `next`.
+	pub const fn chain<T>(self, next: &T) -> AllocChain<'_, Self, T>
+	where
+		Self: Sized,
+	{
+		AllocChain::new(self, next)
+	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
+
+	/// Runs `f` while holding the spinlock.
+	fn with_ranges<R>(&self, f: impl FnOnce(&mut Ranges<N>) -> R) -> R {
+		while self
+			.locked
+			.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+			.is_err()
+		{
+			core::hint::spin_loop();
+		}
+
+		// SAFETY: We hold the lock.
+		let result = f(unsafe { &mut *self.ranges.get() });
+		self.locked.store(false, Ordering::Release);
+		result
+	}
+
+	/// Records an allocation of `size` bytes at `ptr`. Returns false if the table is full.
+	/// A zero-sized allocation is recorded as a single byte, so that it can still be found.
+	fn track(&self, ptr: *mut u8, size: usize) -> bool {
+		let start = ptr.addr();
+		self.with_ranges(|ranges| ranges.insert(start, start + size.max(1)))
+	}
+
+	/// Forgets the allocation at `ptr`.
+	fn untrack(&self, ptr: *mut u8) {
+		self.with_ranges(|ranges| ranges.remove(ptr.addr()));
+	}
+
+	/// Forgets the allocation at `ptr` before it is resized, holding back its entry for `retrack()`.
+	///
+	/// This must happen before the inner allocator frees the old memory, since another thread could be
+	/// handed the same address right away and record it.
+	fn untrack_for_resize(&self, ptr: *mut u8) {
+		self.with_ranges(|ranges| {
+			ranges.remove(ptr.addr());
+			ranges.reserved += 1;
+		});
+	}
+
+	/// Records the allocation of `size` bytes at `ptr` after a resize, in the entry held back by
+	/// `untrack_for_resize()`. If the resize failed, this is the old allocation.
+	fn retrack(&self, ptr: *mut u8, size: usize) {
+		let start = ptr.addr();
+		self.with_ranges(|ranges| {
+			ranges.reserved -= 1;
+			// This can't fail, since an entry was held back.
+			ranges.insert(start, start + size.max(1));
+		});
+	}
+}
+
+impl<A, const N: usize> Deref for Tracked<A, N> {
+	type Target = A;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl<A, const N: usize> Debug for Tracked<A, N> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Tracked")
+			.field("live_allocations", &self.live_allocations())
+			.field("capacity", &N)
+			.finish_non_exhaustive()
+	}
+}
+
+unsafe impl<A: GlobalAlloc, const N: usize> GlobalAlloc for Tracked<A, N> {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.inner.alloc(layout) };
+		if !ptr.is_null() && !self.track(ptr, layout.size()) {
+			// SAFETY: The allocation was just made with `layout`.
+			unsafe { self.inner.dealloc(ptr, layout) };
+			return core::ptr::null_mut();
+		}
+		ptr
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.inner.alloc_zeroed(layout) };
+		if !ptr.is_null() && !self.track(ptr, layout.size()) {
+			// SAFETY: The allocation was just made with `layout`.
+			unsafe { self.inner.dealloc(ptr, layout) };
+			return core::ptr::null_mut();
+		}
+		ptr
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		self.untrack(ptr);
+		// SAFETY: Upheld by the caller.
+		unsafe { self.inner.dealloc(ptr, layout) };
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		self.untrack_for_resize(ptr);
+		// SAFETY: Upheld by the caller.
+		let new_ptr = unsafe { self.inner.realloc(ptr, layout, new_size) };
+		if new_ptr.is_null() {
+			self.retrack(ptr, layout.size());
+		} else {
+			self.retrack(new_ptr, new_size);
+		}
+		new_ptr
+	}
+}
+
+unsafe impl<A, const N: usize> ChainableAlloc for Tracked<A, N> {
+	fn addr_in_bounds(&self, addr: usize) -> bool {
+		self.with_ranges(|ranges| ranges.contains(addr))
+	}
+}
+
+impl<A: AllocatorStats, const N: usize> AllocatorStats for Tracked<A, N> {
+	fn capacity_bytes(&self) -> usize {
+		self.inner.capacity_bytes()
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.inner.used_bytes()
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		self.inner.peak_used_bytes()
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		self.inner.allocations()
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		self.inner.deallocations()
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		self.inner.failed_allocations()
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+impl<A, const N: usize> Tracked<A, N>
+where
+	for<'a> &'a A: Allocator,
+{
+	/// Records a new allocation of `layout`, and frees it if the table is full.
+	fn track_result(
+		&self,
+		res: Result<NonNull<[u8]>, AllocError>,
+		layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		let ptr = res?;
+		if self.track(ptr.cast().as_ptr(), layout.size()) {
+			Ok(ptr)
+		} else {
+			// SAFETY: The allocation was just made with `layout`.
+			unsafe { (&self.inner).deallocate(ptr.cast(), layout) };
+			Err(AllocError)
+		}
+	}
+
+	/// Records the result of resizing the allocation at `old_ptr` from `old_layout` to `new_layout`, which
+	/// was passed to `untrack_for_resize()` beforehand. If the resize failed, the old allocation is recorded again.
+	fn retrack_result(
+		&self,
+		res: Result<NonNull<[u8]>, AllocError>,
+		old_ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		match res {
+			Ok(new_ptr) => self.retrack(new_ptr.cast().as_ptr(), new_layout.size()),
+			Err(_) => self.retrack(old_ptr.as_ptr(), old_layout.size()),
+		}
+		res
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl<A, const N: usize> Allocator for &Tracked<A, N>
+where
+	for<'a> &'a A: Allocator,
+{
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.track_result((&self.inner).allocate(layout), layout)
+	}
+
+	fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		self.track_result((&self.inner).allocate_zeroed(layout), layout)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		self.untrack(ptr.as_ptr());
+		// SAFETY: Upheld by the caller.
+		unsafe { (&self.inner).deallocate(ptr, layout) };
+	}
+
+	unsafe fn grow(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.untrack_for_resize(ptr.as_ptr());
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow(ptr, old_layout, new_layout) };
+		self.retrack_result(res, ptr, old_layout, new_layout)
+	}
+
+	unsafe fn grow_zeroed(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.untrack_for_resize(ptr.as_ptr());
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).grow_zeroed(ptr, old_layout, new_layout) };
+		self.retrack_result(res, ptr, old_layout, new_layout)
+	}
+
+	unsafe fn shrink(
+		&self,
+		ptr: NonNull<u8>,
+		old_layout: Layout,
+		new_layout: Layout,
+	) -> Result<NonNull<[u8]>, AllocError> {
+		self.untrack_for_resize(ptr.as_ptr());
+		// SAFETY: Upheld by the caller.
+		let res = unsafe { (&self.inner).shrink(ptr, old_layout, new_layout) };
+		self.retrack_result(res, ptr, old_layout, new_layout)
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
@@ -0,0 +1,175 @@
+use core::iter::FusedIterator;
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{FirstFit, Stalloc, Strategy, as_u16, precondition};
+
+/// An iterator over the live allocations of a `Stalloc`, created by `iter_allocations()`.
+///
+/// Each item is an `(index, length)` pair, both measured in blocks. Allocations are
+/// yielded in order of increasing address.
+pub struct Allocations<'a, const L: usize, const B: usize, S: Strategy = FirstFit>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a Stalloc<L, B, S>,
+	idx: usize,
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Iterator for Allocations<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	type Item = (usize, usize);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while self.idx < L {
+			let idx = self.idx;
+			let length = self.alloc.tracked_length(idx);
+
+			if length > 0 {
+				self.idx += length;
+				return Some((idx, length));
+			}
+
+			self.idx += 1;
+		}
+
+		None
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> FusedIterator for Allocations<'_, L, B, S> where
+	Align<B>: Alignment
+{
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns an iterator over all live allocations. Each item is an `(index, length)` pair,
+	/// measured in blocks. This requires the `tracking` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr1 = unsafe { alloc.allocate_blocks(3, 1) }.unwrap();
+	/// let ptr2 = unsafe { alloc.allocate_blocks(5, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(ptr1, 3) };
+	///
+	/// let allocations: Vec<_> = alloc.iter_allocations().collect();
+	/// assert_eq!(allocations, [(3, 5)]);
+	/// ```
+	pub const fn iter_allocations(&self) -> Allocations<'_, L, B, S> {
+		Allocations {
+			alloc: self,
+			idx: 0,
+		}
+	}
+
+	/// Checks whether `ptr` points to the start of a live allocation.
+	/// This runs in O(1) and requires the `tracking` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(3, 1) }.unwrap();
+	/// assert!(alloc.is_allocated(ptr));
+	///
+	/// unsafe { alloc.deallocate_blocks(ptr, 3) };
+	/// assert!(!alloc.is_allocated(ptr));
+	/// ```
+	pub fn is_allocated(&self, ptr: NonNull<u8>) -> bool {
+		self.allocation_size(ptr).is_some()
+	}
+
+	/// Returns the length in blocks of the live allocation that starts at `ptr`, or `None` if there is none.
+	/// This runs in O(1) and requires the `tracking` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(3, 1) }.unwrap();
+	/// assert_eq!(alloc.allocation_size(ptr), Some(3));
+	/// assert_eq!(alloc.allocation_size(unsafe { ptr.add(4) }), None);
+	/// ```
+	pub fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+		let addr = ptr.addr().get();
+		let start = self.data.get().addr();
+
+		if addr < start || addr >= start + B * L || !(addr - start).is_multiple_of(B) {
+			return None;
+		}
+
+		match self.tracked_length((addr - start) / B) {
+			0 => None,
+			length => Some(length),
+		}
+	}
+
+	/// Deallocates a pointer without knowing its size, which is looked up instead.
+	/// This runs in O(1) and requires the `tracking` feature.
+	///
+	/// This makes it possible to free memory through an interface like C's `free()`,
+	/// which doesn't pass the size of the allocation.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(7, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_tracked(ptr) };
+	/// assert!(alloc.is_empty());
+	/// ```
+	pub unsafe fn deallocate_tracked(&self, ptr: NonNull<u8>) {
+		let size = self.allocation_size(ptr);
+
+		// Assert unsafe preconditions.
+		precondition!(size.is_some(), "`ptr` must point to a live allocation");
+
+		// SAFETY: The size was looked up, and `ptr` is upheld by the caller.
+		unsafe { self.deallocate_blocks(ptr, size.unwrap_unchecked()) };
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Returns the length of the allocation starting at `idx`, or 0 if there is none.
+	pub(crate) fn tracked_length(&self, idx: usize) -> usize {
+		unsafe { (*self.lengths.get())[idx].into() }
+	}
+
+	/// Records an allocation of `size` blocks at `idx`.
+	pub(crate) fn track_alloc(&self, idx: usize, size: usize) {
+		unsafe { (*self.lengths.get())[idx] = as_u16(size) };
+	}
+
+	/// Forgets the allocation at `idx`.
+	pub(crate) fn track_dealloc(&self, idx: usize) {
+		unsafe { (*self.lengths.get())[idx] = 0 };
+	}
+
+	/// Forgets every allocation.
+	pub(crate) fn track_clear(&self) {
+		unsafe { (*self.lengths.get()).fill(0) };
+	}
+}
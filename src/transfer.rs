@@ -0,0 +1,104 @@
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy, precondition};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Moves an allocation of `size` blocks from this allocator into `dest`, where it is aligned to
+	/// `align` blocks of `dest`. The bytes are copied to a new allocation of `(size * B).div_ceil(B2)`
+	/// blocks in `dest`, and the old allocation is freed. Returns a pointer to the new allocation.
+	///
+	/// `dest` may be the same allocator as `self`, in which case the allocation is simply moved.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks, and `align` must be a power of 2
+	/// in the range `1..=MAX_ALIGN / B2`.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if `dest` couldn't make the new allocation, in which case this function
+	/// was a no-op and `ptr` is still valid.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let scratch = Stalloc::<16, 4>::new();
+	/// let long_lived = Stalloc::<64, 8>::new();
+	///
+	/// let ptr = unsafe { scratch.allocate_blocks(3, 1) }.unwrap();
+	/// unsafe { ptr.cast::<[u32; 3]>().write([1, 2, 3]) };
+	///
+	/// // 3 blocks of 4 bytes fit in 2 blocks of 8 bytes.
+	/// let moved = unsafe { scratch.transfer_to(ptr, 3, &long_lived, 1) }.unwrap();
+	/// assert_eq!(unsafe { moved.cast::<[u32; 3]>().read() }, [1, 2, 3]);
+	/// assert!(scratch.is_empty());
+	///
+	/// unsafe { long_lived.deallocate_blocks(moved, 2) };
+	/// ```
+	pub unsafe fn transfer_to<const L2: usize, const B2: usize>(
+		&self,
+		ptr: NonNull<u8>,
+		size: usize,
+		dest: &Stalloc<L2, B2>,
+		align: usize,
+	) -> Result<NonNull<u8>, AllocError>
+	where
+		Align<B2>: Alignment,
+	{
+		unsafe {
+			let new_ptr = dest.allocate_blocks((size * B).div_ceil(B2), align)?;
+			ptr.copy_to_nonoverlapping(new_ptr, size * B);
+			self.deallocate_blocks(ptr, size);
+			Ok(new_ptr)
+		}
+	}
+
+	/// Makes a copy of an allocation of `size` blocks in this allocator, and returns a pointer to it.
+	///
+	/// The copy is at least as aligned as the original, up to an alignment of `size.next_power_of_two()`
+	/// blocks. This covers every type that could be stored in the original, since the size of a type
+	/// is always a multiple of its alignment.
+	///
+	/// # Safety
+	///
+	/// `ptr` must point to a valid allocation of `size` blocks.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the copy couldn't be allocated, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<16, 4>::new();
+	///
+	/// let ptr = unsafe { alloc.allocate_blocks(2, 2) }.unwrap();
+	/// unsafe { ptr.cast::<u64>().write(42) };
+	///
+	/// let copy = unsafe { alloc.duplicate(ptr, 2) }.unwrap();
+	/// assert_ne!(copy, ptr);
+	/// assert!(copy.cast::<u64>().is_aligned());
+	/// assert_eq!(unsafe { copy.cast::<u64>().read() }, 42);
+	/// ```
+	pub unsafe fn duplicate(
+		&self,
+		ptr: NonNull<u8>,
+		size: usize,
+	) -> Result<NonNull<u8>, AllocError> {
+		// Assert unsafe preconditions.
+		precondition!(size >= 1 && size <= L, "`size` must be in `1..=L`");
+		self.check_allocation(ptr, size);
+
+		unsafe {
+			let new_ptr = self.allocate_blocks(size, Self::natural_align(ptr, size))?;
+			ptr.copy_to_nonoverlapping(new_ptr, size * B);
+			Ok(new_ptr)
+		}
+	}
+}
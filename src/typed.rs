@@ -0,0 +1,116 @@
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{AllocError, Stalloc, Strategy};
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Tries to allocate uninitialized memory for a value of type `T`. The number of blocks and their
+	/// alignment are computed from the layout of `T`. Zero-sized types get a dangling pointer.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the allocation was unsuccessful, in which case this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let ptr = alloc.allocate_value::<u64>().unwrap();
+	/// assert!(ptr.is_aligned());
+	/// unsafe { ptr.write(42) };
+	///
+	/// unsafe { alloc.deallocate_value(ptr) };
+	/// assert!(alloc.is_empty());
+	/// ```
+	pub fn allocate_value<T>(&self) -> Result<NonNull<T>, AllocError> {
+		self.allocate_layout(Layout::new::<T>()).map(NonNull::cast)
+	}
+
+	/// Tries to allocate uninitialized memory for `len` values of type `T`. The number of blocks and their
+	/// alignment are computed from the layout of `[T; len]`. Empty slices get a dangling pointer.
+	///
+	/// # Errors
+	///
+	/// Will return `AllocError` if the size of the slice overflows, or if the allocation was unsuccessful.
+	/// In that case, this function was a no-op.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<20, 4>::new();
+	///
+	/// let slice = alloc.allocate_slice::<u16>(10).unwrap();
+	/// assert_eq!(slice.len(), 10);
+	/// unsafe { slice.cast::<u16>().write_bytes(0, 10) };
+	///
+	/// unsafe { alloc.deallocate_slice(slice) };
+	/// assert!(alloc.is_empty());
+	/// ```
+	pub fn allocate_slice<T>(&self, len: usize) -> Result<NonNull<[T]>, AllocError> {
+		let layout = Layout::array::<T>(len).map_err(|_| AllocError)?;
+		self.allocate_layout(layout)
+			.map(|ptr| NonNull::slice_from_raw_parts(ptr.cast(), len))
+	}
+
+	/// Deallocates memory that was allocated with `allocate_value::<T>()`.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been returned by `allocate_value::<T>()` on this allocator, and must not have been freed.
+	/// The value is not dropped.
+	pub unsafe fn deallocate_value<T>(&self, ptr: NonNull<T>) {
+		unsafe { self.deallocate_layout(ptr.cast(), Layout::new::<T>()) };
+	}
+
+	/// Deallocates memory that was allocated with `allocate_slice::<T>()`.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been returned by `allocate_slice::<T>()` on this allocator, and must not have been freed.
+	/// The values are not dropped.
+	pub unsafe fn deallocate_slice<T>(&self, ptr: NonNull<[T]>) {
+		// SAFETY: The layout was already computed successfully when the slice was allocated.
+		unsafe {
+			let layout = Layout::array::<T>(ptr.len()).unwrap_unchecked();
+			self.deallocate_layout(ptr.cast(), layout);
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Allocates memory for `layout`, rounding its size and alignment up to whole blocks.
+	fn allocate_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// If `size` is zero, give away a dangling pointer.
+		if size == 0 {
+			// SAFETY: Alignment is always nonzero.
+			return Ok(unsafe { NonNull::new_unchecked(layout.align() as _) });
+		}
+
+		// SAFETY: We have made sure that `size` and `align` are valid.
+		unsafe { self.allocate_blocks(size, align) }
+	}
+
+	/// Deallocates memory that was allocated with `allocate_layout()`.
+	unsafe fn deallocate_layout(&self, ptr: NonNull<u8>, layout: Layout) {
+		let size = layout.size().div_ceil(B);
+
+		if size != 0 {
+			// SAFETY: We just made sure that size != 0. Everything else is upheld by the caller.
+			unsafe { self.deallocate_blocks(ptr, size) };
+		}
+	}
+}
@@ -1,18 +1,17 @@
 use core::alloc::{GlobalAlloc, Layout};
-use core::fmt::{self, Debug, Formatter};
-use core::hint::assert_unchecked;
+use core::fmt::{self, Debug, Display, Formatter};
 use core::ops::Deref;
 use core::ptr::{self, NonNull};
 
 use crate::align::{Align, Alignment};
-use crate::{AllocChain, ChainableAlloc, Stalloc};
+use crate::{AllocChain, ChainableAlloc, OwnedAllocChain, Stalloc, precondition};
 
 /// A wrapper around `Stalloc` that implements both `Sync` and `GlobalAlloc`.
 ///
 /// This type is unsafe to create, because it does not prevent data races.
 /// Therefore, it is encouraged to only use it in single-threaded environments.
 #[repr(transparent)]
-pub struct UnsafeStalloc<const L: usize, const B: usize>(Stalloc<L, B>)
+pub struct UnsafeStalloc<const L: usize, const B: usize>(pub(crate) Stalloc<L, B>)
 where
 	Align<B>: Alignment;
 
@@ -36,6 +35,15 @@ where
 	}
 }
 
+impl<const L: usize, const B: usize> Display for UnsafeStalloc<L, B>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
 impl<const L: usize, const B: usize> UnsafeStalloc<L, B>
 where
 	Align<B>: Alignment,
@@ -55,6 +63,65 @@ where
 	pub const unsafe fn new() -> Self {
 		Self(Stalloc::<L, B>::new())
 	}
+
+	/// Initializes a new `UnsafeStalloc` instance in which the `size` blocks starting at block index `idx`
+	/// are reserved. See `Stalloc::with_reserved()` for details.
+	///
+	/// # Safety
+	///
+	/// `UnsafeStalloc` does not prevent data races. It is strongly recommend
+	/// to only use it in a single-threaded environment.
+	///
+	/// # Panics
+	///
+	/// Panics if `size` is zero or the reserved blocks are out of bounds.
+	#[must_use]
+	pub const unsafe fn with_reserved(idx: usize, size: usize) -> Self {
+		Self(Stalloc::<L, B>::with_reserved(idx, size))
+	}
+
+	/// Returns this allocator with `handler` registered as its OOM handler, which is called when
+	/// `GlobalAlloc` is about to return a null pointer. This can be used to initialize a `static`.
+	/// This requires the `oom-handler` feature.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::{AllocReport, UnsafeStalloc};
+	/// use std::alloc::Layout;
+	///
+	/// fn on_oom(layout: Layout, report: &AllocReport) {
+	///     // Log the state of the allocator, without allocating.
+	/// }
+	///
+	/// // SAFETY: The program is single-threaded.
+	/// #[global_allocator]
+	/// static GLOBAL: UnsafeStalloc<1000, 4> = unsafe { UnsafeStalloc::new() }.with_oom_handler(on_oom);
+	/// # fn main() {}
+	/// ```
+	#[cfg(feature = "oom-handler")]
+	#[must_use]
+	pub const fn with_oom_handler(mut self, handler: crate::OomHandler) -> Self {
+		self.0.oom_handler = core::cell::Cell::new(Some(handler));
+		self
+	}
+
+	/// Returns this allocator with `sink` registered as its OOM sink. This requires the `abort-on-oom` feature.
+	/// See `Stalloc::set_oom_sink()` for details.
+	#[cfg(feature = "abort-on-oom")]
+	#[must_use]
+	pub const fn with_oom_sink(mut self, sink: crate::OomSink) -> Self {
+		self.0.oom_sink = core::cell::Cell::new(Some(sink));
+		self
+	}
+
+	/// Returns this allocator with every allocation surrounded by guard blocks. This requires the `canaries`
+	/// feature. See `Stalloc::with_canaries()` for details.
+	#[cfg(feature = "canaries")]
+	#[must_use]
+	pub const fn with_canaries(mut self) -> Self {
+		self.0.canaries = true;
+		self
+	}
 }
 
 unsafe impl<const L: usize, const B: usize> Sync for UnsafeStalloc<L, B> where Align<B>: Alignment {}
@@ -125,27 +192,21 @@ where
 	Align<B>: Alignment,
 {
 	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-		let size = layout.size().div_ceil(B);
-		let align = layout.align().div_ceil(B);
-
-		// SAFETY: `size` and `align` are valid.
-		unsafe {
-			self.allocate_blocks(size, align)
-				.map(|p| p.as_ptr().cast())
-				.unwrap_or(ptr::null_mut())
+		// SAFETY: Upheld by the caller.
+		let ptr = unsafe { self.alloc_in_chain(layout, false) };
+		if ptr.is_null() {
+			self.on_global_oom(layout);
 		}
+		ptr
 	}
 
 	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
-		let size = layout.size().div_ceil(B);
-
 		// SAFETY: Upheld by the caller.
-		let new = unsafe { self.alloc(layout) };
-		if !new.is_null() {
-			// SAFETY: `new` points to a valid allocation of `size * B` bytes.
-			unsafe { ptr::write_bytes(new, 0, size * B) };
+		let ptr = unsafe { self.alloc_in_chain(layout, true) };
+		if ptr.is_null() {
+			self.on_global_oom(layout);
 		}
-		new
+		ptr
 	}
 
 	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
@@ -158,43 +219,15 @@ where
 	}
 
 	unsafe fn realloc(&self, ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
-		// Assert unsafe precondition.
-		unsafe {
-			assert_unchecked(new_size > 0);
-		}
-
-		let old_size = old_layout.size() / B;
-		let new_size = new_size.div_ceil(B);
-
-		unsafe {
-			// SAFETY: Upheld by the caller.
-			let ptr: NonNull<u8> = NonNull::new_unchecked(ptr);
-
-			// SAFETY: Upheld by the caller.
-			if new_size > old_size && self.grow_in_place(ptr, old_size, new_size).is_ok() {
-				return ptr.as_ptr();
-			} else if new_size > old_size {
-				// Reallocate and copy.
-				// SAFETY: We have made sure that `new_size > 0` and that `align` is valid.
-				let Ok(new) = self.allocate_blocks(new_size, old_layout.align()) else {
-					return ptr::null_mut();
-				};
-
-				// SAFETY: We are copying all the necessary bytes from `ptr` into `new`.
-				// `ptr` and `new` both point to an allocation of at least `old_layout.size()` bytes.
-				ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr(), old_layout.size());
-
-				// SAFETY: The caller upholds that old_size > 0.
-				self.deallocate_blocks(ptr, old_size);
-
-				return new.as_ptr();
-			} else if old_size > new_size {
-				// SAFETY: Upheld by the caller.
-				self.shrink_in_place(ptr, old_size, new_size);
-			}
-
-			ptr.as_ptr()
+		// SAFETY: Upheld by the caller.
+		let new_ptr = unsafe { self.realloc_in_chain(ptr, old_layout, new_size) };
+		if new_ptr.is_null() {
+			// SAFETY: The caller upholds that `new_size` is valid for the alignment.
+			self.on_global_oom(unsafe {
+				Layout::from_size_align_unchecked(new_size, old_layout.align())
+			});
 		}
+		new_ptr
 	}
 }
 
@@ -205,6 +238,48 @@ where
 	fn addr_in_bounds(&self, addr: usize) -> bool {
 		self.0.addr_in_bounds(addr)
 	}
+
+	fn chain_aware(&self) -> bool {
+		true
+	}
+
+	unsafe fn alloc_in_chain(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+		let size = layout.size().div_ceil(B);
+		let align = layout.align().div_ceil(B);
+
+		// SAFETY: `size` and `align` are valid.
+		unsafe {
+			if zeroed {
+				self.allocate_blocks_zeroed(size, align)
+			} else {
+				self.allocate_blocks(size, align)
+			}
+		}
+		.map_or(ptr::null_mut(), NonNull::as_ptr)
+	}
+
+	unsafe fn realloc_in_chain(
+		&self,
+		ptr: *mut u8,
+		old_layout: Layout,
+		new_size: usize,
+	) -> *mut u8 {
+		// Assert unsafe precondition.
+		precondition!(new_size > 0, "`new_size` must be nonzero");
+
+		let old_size = old_layout.size().div_ceil(B);
+		let new_size = new_size.div_ceil(B);
+		let align = old_layout.align().div_ceil(B);
+
+		// SAFETY: Upheld by the caller.
+		unsafe { self.reallocate_blocks(NonNull::new_unchecked(ptr), old_size, new_size, align) }
+			.map_or(ptr::null_mut(), NonNull::as_ptr)
+	}
+
+	unsafe fn dealloc_in_chain(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: Upheld by the caller.
+		unsafe { self.dealloc(ptr, layout) };
+	}
 }
 
 impl<const L: usize, const B: usize> UnsafeStalloc<L, B>
@@ -218,4 +293,12 @@ where
 	{
 		AllocChain::new(self, next)
 	}
+
+	/// Creates a new `OwnedAllocChain` containing this allocator and `next`, which it takes ownership of.
+	pub const fn chain_owned<T>(self, next: T) -> OwnedAllocChain<Self, T>
+	where
+		Self: Sized,
+	{
+		OwnedAllocChain::new(self, next)
+	}
 }
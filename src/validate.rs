@@ -0,0 +1,209 @@
+use core::fmt::{self, Display, Formatter};
+
+use crate::align::{Align, Alignment};
+use crate::{OOM_MARKER, Stalloc, Strategy};
+
+/// An error describing how the internal state of a `Stalloc` was found to be corrupted, as returned
+/// by `validate()`. Every `index` refers to the free chunk (or allocation) where the problem was detected.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CorruptionError {
+	/// The OOM marker has an invalid value, or the bound on the largest free chunk that it holds is too small.
+	InvalidMarker,
+	/// A free chunk extends past the end of the buffer.
+	OutOfBounds {
+		/// The index of the free chunk.
+		index: usize,
+	},
+	/// A free chunk has a length of zero.
+	EmptyChunk {
+		/// The index of the free chunk.
+		index: usize,
+	},
+	/// The free list is not sorted by address, or it contains a cycle.
+	Unordered {
+		/// The index of the free chunk.
+		index: usize,
+	},
+	/// A free chunk overlaps the next free chunk.
+	Overlapping {
+		/// The index of the free chunk.
+		index: usize,
+	},
+	/// A free chunk is directly followed by another free chunk, which should have been merged into it.
+	Unmerged {
+		/// The index of the free chunk.
+		index: usize,
+	},
+	/// A live allocation overlaps a free chunk or another allocation. This is only detected with the
+	/// `tracking` feature.
+	AllocationOverlap {
+		/// The index of the allocation.
+		index: usize,
+	},
+	/// The guard blocks around a live allocation were overwritten. This is only detected in an allocator
+	/// created with `with_canaries()`.
+	BrokenCanary {
+		/// The index of the allocation.
+		index: usize,
+	},
+}
+
+impl core::error::Error for CorruptionError {}
+
+impl Display for CorruptionError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match *self {
+			Self::InvalidMarker => f.write_str("the OOM marker is invalid"),
+			Self::OutOfBounds { index } => {
+				write!(f, "free chunk at index {index} is out of bounds")
+			}
+			Self::EmptyChunk { index } => write!(f, "free chunk at index {index} is empty"),
+			Self::Unordered { index } => write!(f, "free list is out of order at index {index}"),
+			Self::Overlapping { index } => {
+				write!(
+					f,
+					"free chunk at index {index} overlaps the next free chunk"
+				)
+			}
+			Self::Unmerged { index } => {
+				write!(
+					f,
+					"free chunk at index {index} was not merged with the next free chunk"
+				)
+			}
+			Self::AllocationOverlap { index } => {
+				write!(f, "allocation at index {index} overlaps other memory")
+			}
+			Self::BrokenCanary { index } => {
+				write!(
+					f,
+					"guard blocks of allocation at index {index} were overwritten"
+				)
+			}
+		}
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Walks the free list and checks that it is consistent: every free chunk must be nonempty and in bounds,
+	/// and the chunks must be sorted by address, must not overlap, and must not be directly adjacent (since
+	/// adjacent chunks are always merged). With the `tracking` feature, this also checks that no live
+	/// allocation overlaps a free chunk or another allocation, and with canaries, that the
+	/// guard blocks of every allocation are intact.
+	///
+	/// This never follows an invalid index, so it is safe to call on a corrupted allocator.
+	/// It runs in O(n), where n is the number of free chunks (or O(L) with the `tracking` feature).
+	///
+	/// # Errors
+	///
+	/// Returns a `CorruptionError` describing the first inconsistency that was found.
+	///
+	/// # Examples
+	/// ```
+	/// use stalloc::Stalloc;
+	///
+	/// let alloc = Stalloc::<100, 4>::new();
+	///
+	/// let ptr1 = unsafe { alloc.allocate_blocks(10, 1) }.unwrap();
+	/// let ptr2 = unsafe { alloc.allocate_blocks(20, 1) }.unwrap();
+	/// unsafe { alloc.deallocate_blocks(ptr1, 10) };
+	///
+	/// assert_eq!(alloc.validate(), Ok(()));
+	/// ```
+	pub fn validate(&self) -> Result<(), CorruptionError> {
+		let base = unsafe { *self.base.get() };
+
+		if base.length == OOM_MARKER {
+			return self.validate_allocations();
+		}
+
+		let mut idx = usize::from(base.next);
+		loop {
+			if idx >= L {
+				return Err(CorruptionError::OutOfBounds { index: idx });
+			}
+
+			// SAFETY: We just made sure that `idx` is in bounds.
+			let header = unsafe { *self.header_at(idx) };
+			let length = usize::from(header.length);
+			let next = usize::from(header.next);
+
+			if length == 0 {
+				return Err(CorruptionError::EmptyChunk { index: idx });
+			}
+
+			if length > self.largest_free() {
+				return Err(CorruptionError::InvalidMarker);
+			}
+
+			if idx + length > L {
+				return Err(CorruptionError::OutOfBounds { index: idx });
+			}
+
+			// The last chunk in the free list points back to index 0.
+			if next == 0 {
+				return self.validate_allocations();
+			}
+
+			if next <= idx {
+				return Err(CorruptionError::Unordered { index: idx });
+			}
+
+			if idx + length > next {
+				return Err(CorruptionError::Overlapping { index: idx });
+			}
+
+			if idx + length == next {
+				return Err(CorruptionError::Unmerged { index: idx });
+			}
+
+			idx = next;
+		}
+	}
+}
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Checks that the live allocations don't overlap the free list or each other.
+	/// This must only be called once the free list has been validated.
+	#[allow(
+		clippy::unused_self,
+		clippy::unnecessary_wraps,
+		clippy::missing_const_for_fn
+	)]
+	fn validate_allocations(&self) -> Result<(), CorruptionError> {
+		#[cfg(feature = "tracking")]
+		{
+			let mut free_chunks = self.free_chunks().peekable();
+			let mut end_of_prev = 0;
+
+			for (idx, length) in self.iter_allocations() {
+				// Skip the free chunks that end before this allocation.
+				while free_chunks
+					.next_if(|&(free_idx, free_length)| free_idx + free_length <= idx)
+					.is_some()
+				{}
+
+				let overlaps_free = free_chunks
+					.peek()
+					.is_some_and(|&(free_idx, _)| free_idx < idx + length);
+				if idx < end_of_prev || idx + length > L || overlaps_free {
+					return Err(CorruptionError::AllocationOverlap { index: idx });
+				}
+
+				end_of_prev = idx + length;
+			}
+		}
+
+		#[cfg(feature = "canaries")]
+		self.check_canaries()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,311 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+use core::fmt::{self, Debug, Formatter};
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::AllocatorStats;
+
+/// The size of a page of WebAssembly linear memory.
+pub const WASM_PAGE_SIZE: usize = 65536;
+
+/// The memory that a `WasmPageAlloc` has obtained so far.
+struct Pages {
+	/// The address of the first byte that hasn't been handed out yet.
+	next: usize,
+	/// The address right after the last page that was obtained, which is 0 before the first page.
+	end: usize,
+	/// The total number of bytes that were obtained with `memory.grow`.
+	grown: usize,
+	/// The number of bytes that are currently allocated.
+	used: usize,
+	/// The highest number of bytes that were ever allocated at the same time.
+	peak_used: usize,
+}
+
+/// An allocator that gets its memory by growing WebAssembly linear memory with `memory.grow`. It is meant
+/// to be the last link of an `AllocChain`, after a `Stalloc` that handles most allocations.
+///
+/// Memory is handed out from the grown pages one allocation after another, and can't be returned to the
+/// host. Freeing or resizing the most recent allocation happens in place, but the memory of any other freed
+/// allocation is only reused once everything after it has been freed. If another allocator grows the memory
+/// as well, the pages in between are skipped.
+///
+/// On targets other than `wasm32`, `memory.grow` doesn't exist, so every allocation fails.
+///
+/// # Examples
+/// ```no_run
+/// use stalloc::{AllocChain, SpinStalloc, WasmPageAlloc};
+///
+/// static PAGES: WasmPageAlloc = WasmPageAlloc::new();
+///
+/// #[global_allocator]
+/// static GLOBAL: AllocChain<SpinStalloc<1024, 8>, WasmPageAlloc> = SpinStalloc::new().chain(&PAGES);
+///
+/// fn main() {
+///     // Small allocations come from the `SpinStalloc`, and large ones from new pages.
+///     let small = vec![0u8; 100];
+///     let large = vec![0u8; 100_000];
+///     assert!(PAGES.grown_bytes() >= 100_000);
+/// }
+/// ```
+pub struct WasmPageAlloc {
+	locked: AtomicBool,
+	pages: UnsafeCell<Pages>,
+}
+
+// SAFETY: `pages` is only accessed while the spinlock is held.
+unsafe impl Sync for WasmPageAlloc {}
+
+impl WasmPageAlloc {
+	/// Creates an allocator that hasn't obtained any pages yet.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self {
+			locked: AtomicBool::new(false),
+			pages: UnsafeCell::new(Pages {
+				next: 0,
+				end: 0,
+				grown: 0,
+				used: 0,
+				peak_used: 0,
+			}),
+		}
+	}
+
+	/// Returns the number of bytes that were obtained by growing the linear memory.
+	pub fn grown_bytes(&self) -> usize {
+		self.with_pages(|pages| pages.grown)
+	}
+
+	/// Runs `f` while holding the spinlock.
+	fn with_pages<R>(&self, f: impl FnOnce(&mut Pages) -> R) -> R {
+		while self
+			.locked
+			.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+			.is_err()
+		{
+			core::hint::spin_loop();
+		}
+
+		// SAFETY: We hold the lock.
+		let result = f(unsafe { &mut *self.pages.get() });
+		self.locked.store(false, Ordering::Release);
+		result
+	}
+}
+
+impl Pages {
+	/// Hands out memory for `layout`, growing the linear memory if necessary. Returns null on failure.
+	fn alloc(&mut self, layout: Layout) -> *mut u8 {
+		loop {
+			let Some((start, end)) = self
+				.next
+				.checked_next_multiple_of(layout.align())
+				.and_then(|start| Some((start, start.checked_add(layout.size())?)))
+			else {
+				return ptr::null_mut();
+			};
+
+			if start != 0 && end <= self.end {
+				self.next = end;
+				self.used += layout.size();
+				self.peak_used = self.peak_used.max(self.used);
+				// Linear memory is addressed by plain integers, so the address has no provenance to come from.
+				return ptr::with_exposed_provenance_mut(start);
+			}
+
+			let pages = (end - self.end).max(1).div_ceil(WASM_PAGE_SIZE);
+			let Some(old_end) = grow_memory(pages) else {
+				return ptr::null_mut();
+			};
+
+			// If somebody else grew the memory in the meantime, start over in the new pages.
+			if old_end != self.end {
+				self.next = old_end;
+			}
+			self.end = old_end + pages * WASM_PAGE_SIZE;
+			self.grown += pages * WASM_PAGE_SIZE;
+		}
+	}
+
+	/// Frees the allocation of `layout` at `addr`, which can only be reused if it is the most recent one.
+	const fn dealloc(&mut self, addr: usize, layout: Layout) {
+		self.used -= layout.size();
+
+		if addr + layout.size() == self.next {
+			self.next = addr;
+		}
+	}
+
+	/// Resizes the allocation at `addr` in place, which is only possible if it is the most recent one.
+	fn resize_in_place(&mut self, addr: usize, layout: Layout, new_size: usize) -> bool {
+		if addr + layout.size() != self.next {
+			return new_size <= layout.size() && {
+				self.used -= layout.size() - new_size;
+				true
+			};
+		}
+
+		let Some(end) = addr.checked_add(new_size) else {
+			return false;
+		};
+
+		// Grow the linear memory if the allocation would extend past it. This only helps if the new pages
+		// come right after the current ones.
+		if end > self.end {
+			let pages = (end - self.end).div_ceil(WASM_PAGE_SIZE);
+			match grow_memory(pages) {
+				Some(old_end) if old_end == self.end => {
+					self.end += pages * WASM_PAGE_SIZE;
+					self.grown += pages * WASM_PAGE_SIZE;
+				}
+				Some(old_end) => {
+					// The pages can still be used for future allocations.
+					self.next = old_end;
+					self.end = old_end + pages * WASM_PAGE_SIZE;
+					self.grown += pages * WASM_PAGE_SIZE;
+					return false;
+				}
+				None => return false,
+			}
+		}
+
+		self.next = end;
+		self.used = self.used - layout.size() + new_size;
+		self.peak_used = self.peak_used.max(self.used);
+		true
+	}
+}
+
+/// Grows the linear memory by `pages` pages, and returns the address where the new pages start.
+#[allow(clippy::missing_const_for_fn)]
+fn grow_memory(pages: usize) -> Option<usize> {
+	#[cfg(target_arch = "wasm32")]
+	return match core::arch::wasm32::memory_grow(0, pages) {
+		usize::MAX => None,
+		old_pages => Some(old_pages * WASM_PAGE_SIZE),
+	};
+	#[cfg(not(target_arch = "wasm32"))]
+	{
+		let _ = pages;
+		None
+	}
+}
+
+impl Default for WasmPageAlloc {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Debug for WasmPageAlloc {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		self.with_pages(|pages| {
+			f.debug_struct("WasmPageAlloc")
+				.field("grown_bytes", &pages.grown)
+				.field("used_bytes", &pages.used)
+				.finish_non_exhaustive()
+		})
+	}
+}
+
+unsafe impl GlobalAlloc for WasmPageAlloc {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		self.with_pages(|pages| pages.alloc(layout))
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		let ptr = unsafe { self.alloc(layout) };
+
+		// Freed memory can be handed out again, so it isn't necessarily zeroed.
+		if !ptr.is_null() {
+			unsafe { ptr.write_bytes(0, layout.size()) };
+		}
+		ptr
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		self.with_pages(|pages| pages.dealloc(ptr.addr(), layout));
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		self.with_pages(|pages| {
+			if pages.resize_in_place(ptr.addr(), layout, new_size) {
+				return ptr;
+			}
+
+			// SAFETY: The caller guarantees that the new layout is valid.
+			let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+			let new_ptr = pages.alloc(new_layout);
+			if !new_ptr.is_null() {
+				// SAFETY: The new allocation is disjoint from the old one, and at least as large.
+				unsafe { ptr.copy_to_nonoverlapping(new_ptr, layout.size()) };
+				pages.dealloc(ptr.addr(), layout);
+			}
+			new_ptr
+		})
+	}
+}
+
+impl AllocatorStats for WasmPageAlloc {
+	fn capacity_bytes(&self) -> usize {
+		self.grown_bytes()
+	}
+
+	fn used_bytes(&self) -> usize {
+		self.with_pages(|pages| pages.used)
+	}
+
+	fn peak_used_bytes(&self) -> Option<usize> {
+		Some(self.with_pages(|pages| pages.peak_used))
+	}
+
+	fn allocations(&self) -> Option<usize> {
+		None
+	}
+
+	fn deallocations(&self) -> Option<usize> {
+		None
+	}
+
+	fn failed_allocations(&self) -> Option<usize> {
+		None
+	}
+}
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+use {
+	crate::{AllocError, Allocator},
+	core::ptr::NonNull,
+};
+
+#[cfg(any(feature = "allocator-api", feature = "allocator-api2"))]
+unsafe impl Allocator for &WasmPageAlloc {
+	fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+		// If the size is zero, give away a dangling pointer.
+		if layout.size() == 0 {
+			let dangling = NonNull::new(layout.align() as _).unwrap();
+			return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+		}
+
+		// SAFETY: `layout` has a nonzero size.
+		NonNull::new(unsafe { self.alloc(layout) })
+			.map(|p| NonNull::slice_from_raw_parts(p, layout.size()))
+			.ok_or(AllocError)
+	}
+
+	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+		if layout.size() != 0 {
+			// SAFETY: Upheld by the caller.
+			unsafe { self.dealloc(ptr.as_ptr(), layout) };
+		}
+	}
+
+	fn by_ref(&self) -> &Self
+	where
+		Self: Sized,
+	{
+		self
+	}
+}
@@ -0,0 +1,23 @@
+use core::sync::atomic::{Ordering, compiler_fence};
+
+use crate::align::{Align, Alignment};
+use crate::{Stalloc, Strategy};
+
+// Internal functions.
+impl<const L: usize, const B: usize, S: Strategy> Stalloc<L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Overwrites `size` blocks starting at index `idx` with zeros. The writes are volatile,
+	/// so the compiler can't remove them even though the memory is about to be freed.
+	pub(crate) fn wipe_blocks(&self, idx: usize, size: usize) {
+		// Every block is at least 4-byte aligned, so the blocks can be wiped one `u32` at a time.
+		let words = unsafe { self.block_at(idx).cast::<u32>() };
+		for i in 0..size * B / 4 {
+			unsafe { words.add(i).write_volatile(0) };
+		}
+
+		// Make sure that the wipe isn't reordered with the writes that free the blocks.
+		compiler_fence(Ordering::SeqCst);
+	}
+}
@@ -0,0 +1,181 @@
+use core::fmt::{self, Debug, Formatter, Write};
+use core::ptr::NonNull;
+
+use crate::align::{Align, Alignment};
+use crate::{FirstFit, Stalloc, Strategy};
+
+/// A `fmt::Write` implementation that formats text into a buffer inside a `Stalloc`, so that strings
+/// can be formatted without `std`. The buffer is grown in place when possible, and moved otherwise.
+///
+/// `finish()` turns the text into a `&mut str` that lives as long as the allocator is borrowed, like
+/// the strings returned by `alloc_str()`. If the writer is dropped instead, its buffer is freed.
+///
+/// # Examples
+/// ```
+/// use core::fmt::Write;
+/// use stalloc::{ArenaWriter, Stalloc};
+///
+/// let alloc = Stalloc::<64, 4>::new();
+///
+/// let mut w = ArenaWriter::new(&alloc);
+/// write!(w, "{} + {} = {}", 1, 2, 1 + 2).unwrap();
+/// assert_eq!(w.as_str(), "1 + 2 = 3");
+///
+/// let s: &mut str = w.finish();
+/// assert_eq!(s, "1 + 2 = 3");
+/// ```
+pub struct ArenaWriter<'a, const L: usize, const B: usize, S: Strategy = FirstFit>
+where
+	Align<B>: Alignment,
+{
+	alloc: &'a Stalloc<L, B, S>,
+	/// The buffer, which is dangling if nothing has been allocated yet.
+	buf: NonNull<u8>,
+	/// The length of the text in bytes.
+	len: usize,
+	/// The capacity of the buffer in blocks, or zero if nothing has been allocated yet.
+	blocks: usize,
+}
+
+impl<'a, const L: usize, const B: usize, S: Strategy> ArenaWriter<'a, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	/// Creates an empty writer. Nothing is allocated until the first write.
+	#[must_use]
+	pub const fn new(alloc: &'a Stalloc<L, B, S>) -> Self {
+		Self {
+			alloc,
+			buf: NonNull::dangling(),
+			len: 0,
+			blocks: 0,
+		}
+	}
+
+	/// Returns the text that has been written so far.
+	#[must_use]
+	pub const fn as_str(&self) -> &str {
+		// SAFETY: The first `len` bytes of the buffer were copied from `str`s, so they are valid UTF-8.
+		unsafe {
+			core::str::from_utf8_unchecked(core::slice::from_raw_parts(self.buf.as_ptr(), self.len))
+		}
+	}
+
+	/// Returns the number of bytes that have been written so far.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Checks whether nothing has been written yet.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Shrinks the buffer to fit the text, and returns it as a string that lives as long as the allocator
+	/// is borrowed. The memory is only reclaimed when the allocator is cleared or dropped.
+	#[must_use]
+	pub fn finish(mut self) -> &'a mut str {
+		let buf = self.buf;
+		let blocks = core::mem::take(&mut self.blocks);
+		let needed = self.len.div_ceil(B);
+
+		// SAFETY: `buf` is an allocation of `blocks` blocks, and its first `len` bytes are valid UTF-8.
+		unsafe {
+			if needed == 0 {
+				if blocks > 0 {
+					self.alloc.deallocate_blocks(buf, blocks);
+				}
+				return Default::default();
+			}
+
+			if needed < blocks {
+				self.alloc.shrink_in_place(buf, blocks, needed);
+			}
+
+			core::str::from_utf8_unchecked_mut(core::slice::from_raw_parts_mut(
+				buf.as_ptr(),
+				self.len,
+			))
+		}
+	}
+
+	/// Makes sure that the buffer can hold `additional` more bytes.
+	fn reserve(&mut self, additional: usize) -> Result<(), fmt::Error> {
+		let needed = (self.len + additional).div_ceil(B);
+		if needed <= self.blocks {
+			return Ok(());
+		}
+
+		// SAFETY: `needed` is nonzero, and `buf` is an allocation of `blocks` blocks if `blocks` is nonzero.
+		unsafe {
+			if self.blocks == 0 {
+				self.buf = self
+					.alloc
+					.allocate_blocks(needed, 1)
+					.map_err(|_| fmt::Error)?;
+				self.blocks = needed;
+				return Ok(());
+			}
+
+			// Try to double the capacity first, so that many small writes don't each have to grow.
+			let doubled = (self.blocks * 2).max(needed);
+			for new_size in [doubled, needed] {
+				if let Ok(buf) = self
+					.alloc
+					.reallocate_blocks(self.buf, self.blocks, new_size, 1)
+				{
+					self.buf = buf;
+					self.blocks = new_size;
+					return Ok(());
+				}
+			}
+		}
+
+		Err(fmt::Error)
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Write for ArenaWriter<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		if s.is_empty() {
+			return Ok(());
+		}
+
+		self.reserve(s.len())?;
+
+		// SAFETY: `reserve()` made sure that the buffer has room for `s`.
+		unsafe {
+			self.buf
+				.add(self.len)
+				.copy_from_nonoverlapping(NonNull::from(s).cast(), s.len());
+		}
+		self.len += s.len();
+		Ok(())
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Drop for ArenaWriter<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn drop(&mut self) {
+		if self.blocks > 0 {
+			// SAFETY: `buf` is an allocation of `blocks` blocks.
+			unsafe { self.alloc.deallocate_blocks(self.buf, self.blocks) };
+		}
+	}
+}
+
+impl<const L: usize, const B: usize, S: Strategy> Debug for ArenaWriter<'_, L, B, S>
+where
+	Align<B>: Alignment,
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Debug::fmt(self.as_str(), f)
+	}
+}